@@ -18,12 +18,43 @@ pub struct Args {
     #[arg(long, help = "List all available sessions")]
     pub list_sessions: Option<bool>,
 
+    #[arg(long, help = "Directory sessions are kept in")]
+    pub sessions_dir: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SESSION",
+        help = "Print the transcript of a session in --format and exit"
+    )]
+    pub export_session: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "md",
+        help = "Format of --export-session"
+    )]
+    pub format: ExportFormat,
+
+    #[arg(
+        long,
+        value_name = "JSONL_FILE",
+        help = "Create a session from a JSON Lines transcript and exit; named by --session or the file name"
+    )]
+    pub import_session: Option<std::path::PathBuf>,
+
     #[arg(long, help = "URL of an MCP server to connect to")]
     pub mcp_server: Option<String>,
 
     #[arg(long, help = "Authentication token for the MCP server")]
     pub mcp_auth_token: Option<String>,
 
+    #[arg(
+        long,
+        help = "File with MCP servers (OxideAgent format or the mcpServers format)"
+    )]
+    pub mcp_config_file: Option<std::path::PathBuf>,
+
     #[arg(long, value_enum, help = "Interface type to use")]
     pub interface: Option<InterfaceType>,
 
@@ -34,6 +65,9 @@ pub struct Args {
     )]
     pub config: Option<String>,
 
+    #[arg(long, help = "The LLM provider to use (ollama, openai, anthropic)")]
+    pub llm_provider: Option<String>,
+
     #[arg(long, help = "The base URL for the LLM API")]
     pub llm_api_base: Option<String>,
 
@@ -51,6 +85,16 @@ pub enum AgentType {
     Granite,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// Markdown
+    Md,
+    /// JSON Lines, which --import-session reads back
+    Jsonl,
+    /// A standalone HTML page
+    Html,
+}
+
 #[derive(ValueEnum, Debug, Clone, PartialEq)]
 pub enum InterfaceType {
     Tui,
@@ -3,9 +3,13 @@
 //! This module handles configuration parsing from command line arguments,
 //! environment variables, and configuration files.
 
+use crate::core::mcp::config::{McpConfigFile, McpServerConfig, McpServerType};
+use crate::core::session::validate_session_name;
+use crate::core::tools::ToolProfile;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -33,6 +37,11 @@ pub struct OxideConfig {
     #[serde(default)]
     pub list_sessions: bool,
 
+    /// Directory sessions are kept in (defaults to `oxideagent/sessions` in
+    /// the XDG data directory)
+    #[serde(default)]
+    pub sessions_dir: Option<PathBuf>,
+
     /// Interface type to use
     #[serde(default)]
     pub interface: InterfaceType,
@@ -40,6 +49,10 @@ pub struct OxideConfig {
     /// LLM provider configuration
     #[serde(default)]
     pub llm: LLMConfig,
+
+    /// Built-in tool configuration
+    #[serde(default)]
+    pub tools: ToolsConfig,
 }
 
 /// Agent configuration
@@ -60,6 +73,51 @@ pub struct AgentConfig {
     /// The system prompt
     #[serde(default = "default_system_prompt")]
     pub system_prompt: String,
+
+    /// Which tools the agent can see and call
+    #[serde(flatten)]
+    pub tool_scope: ToolScope,
+}
+
+/// Restricts the tools offered to an agent.
+///
+/// With no profiles and no included tools the agent sees every tool. Otherwise
+/// a tool is visible when its profile is allowed or it is included by name.
+/// Excluded tools are never visible.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ToolScope {
+    /// Tool profiles the agent may use, e.g. `["file"]`
+    #[serde(default)]
+    pub allowed_profiles: Vec<ToolProfile>,
+
+    /// Tools the agent may use regardless of their profile
+    #[serde(default)]
+    pub include_tools: Vec<String>,
+
+    /// Tools the agent may never use
+    #[serde(default)]
+    pub exclude_tools: Vec<String>,
+}
+
+impl ToolScope {
+    /// Whether a tool with the given name and profile is visible to the agent
+    pub fn allows(&self, tool_name: &str, profile: ToolProfile) -> bool {
+        if self.exclude_tools.iter().any(|name| name == tool_name) {
+            return false;
+        }
+        if self.allowed_profiles.is_empty() && self.include_tools.is_empty() {
+            return true;
+        }
+        self.allowed_profiles.contains(&profile)
+            || self.include_tools.iter().any(|name| name == tool_name)
+    }
+
+    /// Whether the scope lets every tool through
+    pub fn is_unrestricted(&self) -> bool {
+        self.allowed_profiles.is_empty()
+            && self.include_tools.is_empty()
+            && self.exclude_tools.is_empty()
+    }
 }
 
 /// MCP (Model Context Protocol) configuration
@@ -76,6 +134,97 @@ pub struct MCPConfig {
     /// MCP tools configuration
     #[serde(default)]
     pub tools: Vec<MCPToolConfig>,
+
+    /// MCP servers of any type: remote, docker, npm or command
+    #[serde(default)]
+    pub servers: Vec<McpServerConfig>,
+
+    /// File with more MCP servers, in OxideAgent's format or the `mcpServers`
+    /// format used by other MCP clients
+    #[serde(default)]
+    pub config_file: Option<PathBuf>,
+
+    /// Names to offer MCP tools under, keyed by qualified tool name
+    /// (`server__tool`)
+    #[serde(default)]
+    pub tool_aliases: HashMap<String, String>,
+
+    /// OAuth for remote MCP servers configured without a token or API key
+    #[serde(default)]
+    pub oauth: McpOAuthConfig,
+}
+
+impl MCPConfig {
+    /// Every configured server: the `tools` entries as command servers, then
+    /// `servers`, then the servers of `config_file`
+    pub fn server_configs(&self) -> anyhow::Result<Vec<McpServerConfig>> {
+        let mut servers: Vec<McpServerConfig> = self
+            .tools
+            .iter()
+            .map(MCPToolConfig::server_config)
+            .collect();
+        servers.extend(self.servers.iter().cloned());
+        if let Some(config_file) = &self.config_file {
+            let file = McpConfigFile::load_from_file(config_file).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to load MCP config file '{}': {}",
+                    config_file.display(),
+                    e
+                )
+            })?;
+            servers.extend(file.servers);
+        }
+
+        for (index, server) in servers.iter().enumerate() {
+            if servers[..index]
+                .iter()
+                .any(|other| other.name == server.name)
+            {
+                return Err(anyhow::anyhow!(
+                    "MCP server '{}' is configured more than once",
+                    server.name
+                ));
+            }
+        }
+        Ok(servers)
+    }
+}
+
+/// OAuth settings for remote MCP servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpOAuthConfig {
+    /// File the OAuth clients and tokens of every server are kept in
+    #[serde(default = "default_oauth_token_cache")]
+    pub token_cache: PathBuf,
+
+    /// Port of the localhost listener receiving the authorization callback
+    /// (0 picks a free port)
+    #[serde(default)]
+    pub callback_port: u16,
+
+    /// Client id for authorization servers without dynamic client registration
+    #[serde(default)]
+    pub client_id: Option<String>,
+
+    /// Scopes to request; defaults to the scopes the server advertises
+    #[serde(default)]
+    pub scope: Option<String>,
+
+    /// Open the authorization URL in the browser besides printing it
+    #[serde(default = "default_oauth_open_browser")]
+    pub open_browser: bool,
+}
+
+impl Default for McpOAuthConfig {
+    fn default() -> Self {
+        Self {
+            token_cache: default_oauth_token_cache(),
+            callback_port: 0,
+            client_id: None,
+            scope: None,
+            open_browser: default_oauth_open_browser(),
+        }
+    }
 }
 
 /// MCP Tool configuration
@@ -96,6 +245,24 @@ pub struct MCPToolConfig {
     pub requires_approval: bool,
 }
 
+impl MCPToolConfig {
+    /// The command server this entry describes
+    pub fn server_config(&self) -> McpServerConfig {
+        McpServerConfig {
+            name: self.name.clone(),
+            description: Some(format!("MCP server for {}", self.name)),
+            server_type: McpServerType::Command {
+                command: self.command.clone(),
+                args: Some(self.args.clone()),
+                environment: None,
+                working_directory: None,
+            },
+            auto_start: Some(true),
+            environment: None,
+        }
+    }
+}
+
 /// Agent types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub enum AgentType {
@@ -105,6 +272,17 @@ pub enum AgentType {
     Granite,
 }
 
+impl AgentType {
+    /// Model used when no LLM model is configured
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            AgentType::Qwen => "qwen3:4b",
+            AgentType::Llama => "llama3.2",
+            AgentType::Granite => "granite3.3",
+        }
+    }
+}
+
 /// Interface types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub enum InterfaceType {
@@ -116,7 +294,7 @@ pub enum InterfaceType {
 }
 
 /// Multi-agent mode configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultiAgentConfig {
     /// Whether multi-agent mode is enabled
     #[serde(default)]
@@ -129,18 +307,168 @@ pub struct MultiAgentConfig {
     /// Default agents to initialize
     #[serde(default)]
     pub default_agents: Vec<AgentConfig>,
+
+    /// Max number of LLM round-trips an agent makes on its own while auto-approved
+    /// tool calls keep coming back
+    #[serde(default = "default_max_tool_iterations")]
+    pub max_tool_iterations: usize,
+
+    /// How agents keep long conversations within the model's context window
+    #[serde(default)]
+    pub context: ContextConfig,
+}
+
+impl Default for MultiAgentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_agents: default_max_agents(),
+            default_agents: Vec::new(),
+            max_tool_iterations: default_max_tool_iterations(),
+            context: ContextConfig::default(),
+        }
+    }
+}
+
+/// Context window management for agents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// What to do once the conversation nears the model's context length
+    #[serde(default)]
+    pub strategy: CompactionStrategy,
+
+    /// Share of the context length the conversation may fill before it is compacted
+    #[serde(default = "default_context_threshold")]
+    pub threshold: f32,
+
+    /// Context length to assume instead of asking the provider (in tokens)
+    #[serde(default)]
+    pub context_length: Option<usize>,
+
+    /// Context window requested for models that don't set their own, such as
+    /// Ollama models without `num_ctx` (in tokens). Never more than the model's
+    /// trained length, which would need a lot of memory for the KV cache.
+    #[serde(default = "default_context_window")]
+    pub default_window: usize,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            strategy: CompactionStrategy::default(),
+            threshold: default_context_threshold(),
+            context_length: None,
+            default_window: default_context_window(),
+        }
+    }
+}
+
+/// How a conversation that no longer fits the context window is shortened
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStrategy {
+    /// Leave out old tool outputs, then the oldest turns
+    DropToolOutputs,
+    /// Summarize older turns with the LLM into a pinned summary message
+    #[default]
+    Summarize,
+}
+
+/// Built-in tool configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ToolsConfig {
+    /// Directory the built-in tools work in (defaults to the current directory).
+    /// File tools can't access paths outside it or the extra roots.
+    #[serde(default)]
+    pub workspace_root: Option<PathBuf>,
+
+    /// Whether file tools may write inside the workspace root
+    #[serde(default)]
+    pub file_access: FileAccessMode,
+
+    /// Additional directories file tools may access
+    #[serde(default)]
+    pub extra_roots: Vec<ExtraRootConfig>,
+
+    /// Shell tool configuration
+    #[serde(default)]
+    pub shell: ShellToolConfig,
+
+    /// How the workspace is checkpointed before file and shell tools run
+    #[serde(default)]
+    pub checkpoints: CheckpointMode,
+}
+
+/// Checkpoint strategy for workspace changes made by tools
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointMode {
+    /// Snapshot the working tree through a shadow git index inside a git
+    /// repository, and the touched files elsewhere
+    #[default]
+    Auto,
+    /// Only snapshot the files a tool reports it will change
+    Files,
+    /// Don't take checkpoints
+    Off,
+}
+
+/// File access mode for a workspace root
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAccessMode {
+    ReadOnly,
+    #[default]
+    ReadWrite,
+}
+
+/// An additional directory file tools may access
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtraRootConfig {
+    /// Directory path
+    pub path: PathBuf,
+
+    /// Access mode (read-only unless stated otherwise)
+    #[serde(default = "default_extra_root_mode")]
+    pub mode: FileAccessMode,
+}
+
+/// Shell tool configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellToolConfig {
+    /// Seconds a command may run before it is killed (0 disables the timeout)
+    #[serde(default = "default_shell_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Environment variables passed through to commands; everything else is removed
+    #[serde(default = "default_shell_env_allowlist")]
+    pub env_allowlist: Vec<String>,
+
+    /// Max bytes of stdout and of stderr kept in the result
+    #[serde(default = "default_shell_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_shell_timeout_secs(),
+            env_allowlist: default_shell_env_allowlist(),
+            max_output_bytes: default_shell_max_output_bytes(),
+        }
+    }
 }
 
 /// LLM provider configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct LLMConfig {
-    /// Provider type (ollama, openai, etc.)
+    /// Provider type (ollama, openai, anthropic)
     #[serde(default = "default_provider")]
     pub provider: String,
 
-    /// API base URL (for providers like OpenAI)
-    #[serde(default = "default_api_base")]
-    pub api_base: String,
+    /// API base URL; the provider's own when unset (see [`default_api_base`])
+    #[serde(default)]
+    pub api_base: Option<String>,
 
     /// API key (for providers like OpenAI)
     #[serde(default)]
@@ -151,6 +479,16 @@ pub struct LLMConfig {
     pub model: Option<String>,
 }
 
+impl LLMConfig {
+    /// The configured API base URL, else the provider's default
+    pub fn base_url(&self) -> String {
+        self.api_base
+            .clone()
+            .filter(|api_base| !api_base.is_empty())
+            .unwrap_or_else(|| default_api_base(&self.provider))
+    }
+}
+
 impl From<crate::cli::InterfaceType> for InterfaceType {
     fn from(cli_type: crate::cli::InterfaceType) -> Self {
         match cli_type {
@@ -178,15 +516,91 @@ pub fn default_provider() -> String {
     "ollama".to_string()
 }
 
-pub fn default_api_base() -> String {
-    "http://localhost:11434".to_string()
+/// API base URL of `provider`: the hosted API, or a local Ollama server
+pub fn default_api_base(provider: &str) -> String {
+    match provider {
+        "anthropic" => "https://api.anthropic.com",
+        "openai" | "openai-compatible" => "https://api.openai.com",
+        _ => "http://localhost:11434",
+    }
+    .to_string()
 }
 
 pub fn default_max_agents() -> usize {
     5
 }
 
+pub fn default_max_tool_iterations() -> usize {
+    10
+}
+
+pub fn default_context_threshold() -> f32 {
+    0.8
+}
+
+pub fn default_context_window() -> usize {
+    8192
+}
+
+pub fn default_extra_root_mode() -> FileAccessMode {
+    FileAccessMode::ReadOnly
+}
+
+pub fn default_shell_timeout_secs() -> u64 {
+    60
+}
+
+pub fn default_shell_env_allowlist() -> Vec<String> {
+    ["PATH", "HOME", "USER", "LANG", "LC_ALL", "TERM", "TMPDIR"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+pub fn default_shell_max_output_bytes() -> usize {
+    64 * 1024
+}
+
+pub fn default_oauth_token_cache() -> PathBuf {
+    PathBuf::from("mcp_oauth_tokens.json")
+}
+
+pub fn default_oauth_open_browser() -> bool {
+    true
+}
+
+/// `oxideagent/sessions` in `$XDG_DATA_HOME`, falling back to
+/// `~/.local/share` and then to the current directory
+pub fn default_sessions_dir() -> PathBuf {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .unwrap_or_else(|| PathBuf::from("."));
+    data_home.join("oxideagent").join("sessions")
+}
+
 impl OxideConfig {
+    /// Model the default agent chats with: the LLM model, or the default
+    /// model of the agent type
+    pub fn chat_model(&self) -> String {
+        self.llm
+            .model
+            .clone()
+            .unwrap_or_else(|| self.agent.agent_type.default_model().to_string())
+    }
+
+    /// Directory sessions are kept in
+    pub fn sessions_dir(&self) -> PathBuf {
+        self.sessions_dir
+            .clone()
+            .unwrap_or_else(default_sessions_dir)
+    }
+
     /// Create a new configuration from a file path (auto-detect format by extension)
     pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
         let path = path.as_ref();
@@ -236,23 +650,9 @@ impl OxideConfig {
 
     /// Validate the configuration
     pub fn validate(&self) -> anyhow::Result<()> {
-        // Validate that if an MCP server is specified, an auth token is also provided
-        if self.mcp.server.is_some() && self.mcp.auth_token.is_none() {
-            return Err(anyhow::anyhow!(
-                "MCP server specified but no auth token provided"
-            ));
-        }
-
-        // Validate that the session name doesn't contain invalid characters
+        // The session name becomes part of a file name
         if let Some(session) = &self.session {
-            if session.is_empty() {
-                return Err(anyhow::anyhow!("Session name cannot be empty"));
-            }
-
-            // Check for invalid characters in session name
-            if session.contains('/') || session.contains('\\') || session.contains(':') {
-                return Err(anyhow::anyhow!("Session name contains invalid characters"));
-            }
+            validate_session_name(session)?;
         }
 
         Ok(())
@@ -0,0 +1,343 @@
+//! Keeping conversations within the model's context window.
+//!
+//! An agent's history is kept (and persisted) whole; what goes to the LLM is
+//! a view of it. Once the history nears the model's context length, outputs
+//! of tools from earlier turns are left out of the view. If that is not
+//! enough, older turns are either summarized by the LLM into a summary
+//! message pinned after the system prompt, or dropped, depending on the
+//! configured [`CompactionStrategy`]. Turns are only ever cut at user
+//! messages, so tool results are never separated from their calls.
+
+use crate::config::{CompactionStrategy, ContextConfig};
+use crate::core::llm::client::LlmClient;
+use crate::types::ChatMessage;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Context length assumed for models nothing is known about
+pub const DEFAULT_CONTEXT_LENGTH: usize = 8192;
+
+/// Context lengths of hosted models by name prefix; more specific prefixes first
+const KNOWN_CONTEXT_LENGTHS: &[(&str, usize)] = &[
+    ("claude", 200_000),
+    ("gpt-5", 400_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+];
+
+/// Most characters of a message quoted in a summarization request
+const SUMMARY_EXCERPT_CHARS: usize = 2_000;
+
+const SUMMARY_PROMPT: &str = "Summarize the conversation below for the assistant that will continue it. \
+Keep the user's goals and requests, decisions made, facts learned from tool results (file names, paths, \
+errors, values) and work still outstanding. Write concise notes and add nothing that is not in the conversation.";
+
+/// Rough token count of a message: about four characters per token
+pub fn message_tokens(message: &ChatMessage) -> usize {
+    let arguments: usize = message
+        .tool_calls
+        .iter()
+        .flatten()
+        .map(|call| call.function.name.len() + call.function.arguments.to_string().len())
+        .sum();
+    (message.content.chars().count() + arguments).div_ceil(4)
+}
+
+/// Context length of a hosted model known by name
+pub fn known_context_length(model: &str) -> Option<usize> {
+    let model = model.to_lowercase();
+    KNOWN_CONTEXT_LENGTHS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, length)| *length)
+}
+
+/// Summary of older turns sent in their place
+#[derive(Debug, Clone)]
+struct PinnedSummary {
+    /// Number of history messages, system prompt included, the summary stands in for
+    covered: usize,
+    /// Fingerprint of the messages covered, to notice when the history is replaced
+    fingerprint: u64,
+    message: ChatMessage,
+}
+
+impl PinnedSummary {
+    fn still_covers(&self, history: &[ChatMessage]) -> bool {
+        self.covered <= history.len() && fingerprint(&history[..self.covered]) == self.fingerprint
+    }
+}
+
+/// Fits an agent's history into the context window of the model it talks to
+#[derive(Debug, Default)]
+pub struct ContextManager {
+    config: ContextConfig,
+    /// Context lengths already looked up, by model
+    context_lengths: HashMap<String, usize>,
+    summary: Option<PinnedSummary>,
+}
+
+impl ContextManager {
+    pub fn new(config: ContextConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// The pinned summary of older turns, if one is in use
+    pub fn summary(&self) -> Option<&ChatMessage> {
+        self.summary.as_ref().map(|summary| &summary.message)
+    }
+
+    /// Context length of `model`: the configured one, else what the provider
+    /// reports, else a known or default length. Looked up once per model.
+    /// For providers that size the window per request this is the window
+    /// requested, so the budget matches what the model is actually given.
+    pub async fn context_length(&mut self, model: &str, client: &dyn LlmClient) -> usize {
+        if let Some(length) = self.config.context_length {
+            return length;
+        }
+        if let Some(length) = self.context_lengths.get(model) {
+            return *length;
+        }
+        let length = match client
+            .context_length(model, self.config.default_window)
+            .await
+        {
+            Some(length) => length,
+            None => known_context_length(model).unwrap_or(DEFAULT_CONTEXT_LENGTH),
+        };
+        info!("Context length of {}: {} tokens", model, length);
+        self.context_lengths.insert(model.to_string(), length);
+        length
+    }
+
+    /// Messages of `history` to send to `model`, compacted if they would
+    /// not fit its context window. The context length is passed on to
+    /// `client`, for providers that need to be told how much to use.
+    pub async fn prepare(
+        &mut self,
+        model: &str,
+        history: &[ChatMessage],
+        client: &dyn LlmClient,
+    ) -> Vec<ChatMessage> {
+        let context_length = self.context_length(model, client).await;
+        client.set_context_length(model, context_length);
+        let budget = (context_length as f32 * self.config.threshold.clamp(0.1, 1.0)) as usize;
+        if tokens(history) <= budget {
+            return history.to_vec();
+        }
+
+        // The system prompt always goes first and the current turn is sent whole
+        let head = usize::from(history.first().is_some_and(|m| m.role == "system"));
+        self.summary = self
+            .summary
+            .take()
+            .filter(|summary| summary.still_covers(history));
+        let mut start = self.summary.as_ref().map_or(head, |s| s.covered);
+        let turns: Vec<usize> = (start..history.len())
+            .filter(|&i| history[i].role == "user")
+            .collect();
+        let current_turn = turns.last().copied().unwrap_or(start);
+
+        let view = self.view(history, head, start, current_turn);
+        if tokens(&view) <= budget {
+            info!("Left out old tool outputs to fit {} tokens", budget);
+            return view;
+        }
+
+        if self.config.strategy == CompactionStrategy::Summarize {
+            // Keep the recent turns that fit in half the budget; the summary gets the rest
+            let split = turns
+                .iter()
+                .copied()
+                .find(|&turn| tokens(&recent(history, turn, current_turn)) <= budget / 2)
+                .unwrap_or(current_turn);
+            if split > start {
+                match self
+                    .summarize(model, client, &history[start..split], budget)
+                    .await
+                {
+                    Ok(text) => {
+                        info!("Summarized messages {}..{} of the history", start, split);
+                        self.summary = Some(PinnedSummary {
+                            covered: split,
+                            fingerprint: fingerprint(&history[..split]),
+                            message: ChatMessage::system(&format!(
+                                "Summary of the earlier conversation, which is no longer shown:\n\n{}",
+                                text
+                            )),
+                        });
+                        start = split;
+                        let view = self.view(history, head, start, current_turn);
+                        if tokens(&view) <= budget {
+                            return view;
+                        }
+                    }
+                    Err(e) => warn!("Failed to summarize older turns, dropping them: {}", e),
+                }
+            }
+        }
+
+        let keep_from = turns
+            .iter()
+            .copied()
+            .find(|&turn| tokens(&self.view(history, head, turn, current_turn)) <= budget)
+            .unwrap_or(current_turn);
+        if keep_from == current_turn
+            && tokens(&self.view(history, head, keep_from, current_turn)) > budget
+        {
+            warn!(
+                "The current turn alone exceeds {} tokens of context",
+                budget
+            );
+        }
+        info!(
+            "Dropped messages {}..{} of the history to fit {} tokens",
+            start, keep_from, budget
+        );
+        self.view(history, head, keep_from, current_turn)
+    }
+
+    /// The first `head` messages, the pinned summary and the recent messages
+    /// from `start` on
+    fn view(
+        &self,
+        history: &[ChatMessage],
+        head: usize,
+        start: usize,
+        current_turn: usize,
+    ) -> Vec<ChatMessage> {
+        let mut view: Vec<ChatMessage> = history[..head].to_vec();
+        view.extend(self.summary().cloned());
+        view.extend(recent(history, start, current_turn));
+        view
+    }
+
+    /// Ask the LLM for a summary of `messages` (and of the summary before them)
+    async fn summarize(
+        &self,
+        model: &str,
+        client: &dyn LlmClient,
+        messages: &[ChatMessage],
+        budget: usize,
+    ) -> anyhow::Result<String> {
+        // The request itself has to fit: quote less of tool inputs and outputs,
+        // then of every message, until it does
+        let max_chars = budget.saturating_mul(4);
+        let (mut tool_share, mut share) = (SUMMARY_EXCERPT_CHARS, SUMMARY_EXCERPT_CHARS);
+        let mut transcript = self.transcript(messages, tool_share, share);
+        while transcript.chars().count() > max_chars && share > 1 {
+            if tool_share > 1 {
+                tool_share /= 2;
+            } else {
+                share /= 2;
+            }
+            transcript = self.transcript(messages, tool_share, share);
+        }
+
+        let request = [
+            ChatMessage::system(SUMMARY_PROMPT),
+            ChatMessage::user(&transcript),
+        ];
+        let (tx, _rx) = mpsc::channel(1);
+        let summary = client
+            .chat(model, &request, &[], false, tx)
+            .await?
+            .map(|message| message.content.trim().to_string())
+            .unwrap_or_default();
+        if summary.is_empty() {
+            return Err(anyhow::anyhow!("The LLM returned an empty summary"));
+        }
+        Ok(summary)
+    }
+
+    /// The pinned summary and `messages` as text, quoting at most
+    /// `tool_share` characters of tool inputs and outputs and `share`
+    /// characters of other messages
+    fn transcript(&self, messages: &[ChatMessage], tool_share: usize, share: usize) -> String {
+        let mut transcript = String::new();
+        if let Some(summary) = self.summary() {
+            transcript.push_str(&summary.content);
+            transcript.push_str("\n\n");
+        }
+        for message in messages {
+            let content_share = if message.role == "tool" {
+                tool_share
+            } else {
+                share
+            };
+            if !message.content.is_empty() || message.tool_calls.is_none() {
+                transcript.push_str(&format!(
+                    "{}: {}\n",
+                    message.role,
+                    excerpt(&message.content, content_share)
+                ));
+            }
+            for call in message.tool_calls.iter().flatten() {
+                transcript.push_str(&format!(
+                    "{} called {} with {}\n",
+                    message.role,
+                    call.function.name,
+                    excerpt(&call.function.arguments.to_string(), tool_share)
+                ));
+            }
+        }
+        transcript
+    }
+}
+
+/// Messages of `history` from `start` on, with outputs of tools run before
+/// `current_turn` left out
+fn recent(history: &[ChatMessage], start: usize, current_turn: usize) -> Vec<ChatMessage> {
+    history
+        .iter()
+        .enumerate()
+        .skip(start)
+        .map(|(index, message)| {
+            let mut message = message.clone();
+            if message.role == "tool" && index < current_turn {
+                message.content = format!(
+                    "[Output of {} left out to save context]",
+                    message.tool_name.as_deref().unwrap_or("tool")
+                );
+            }
+            message
+        })
+        .collect()
+}
+
+fn fingerprint(messages: &[ChatMessage]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for message in messages {
+        message.role.hash(&mut hasher);
+        message.content.hash(&mut hasher);
+        message.tool_call_id.hash(&mut hasher);
+        message.timestamp.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn tokens(messages: &[ChatMessage]) -> usize {
+    messages.iter().map(message_tokens).sum()
+}
+
+/// At most `max_chars` characters of `text`
+fn excerpt(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        let mut excerpt: String = text.chars().take(max_chars).collect();
+        excerpt.push_str(" […]");
+        excerpt
+    } else {
+        text.to_string()
+    }
+}
@@ -1,7 +1,11 @@
+pub mod context;
+
 use crate::{
+    config::ContextConfig,
     core::llm::client::LlmClient,
-    types::{AppEvent, ChatMessage, Tool},
+    types::{AppEvent, ChatMessage, Tool, ToolCall},
 };
+use context::ContextManager;
 use std::fmt::Debug; // Added Debug import
 use tokio::sync::mpsc;
 use tracing::info;
@@ -22,6 +26,8 @@ impl std::fmt::Display for AgentId {
 pub struct Agent {
     pub history: Vec<ChatMessage>,
     pub llm_client: Box<dyn LlmClient>,
+    /// Fits the history into the model's context window on each request
+    pub context: ContextManager,
 }
 
 impl Debug for Agent {
@@ -37,9 +43,15 @@ impl Agent {
         Self {
             history: vec![ChatMessage::system(system_prompt)],
             llm_client,
+            context: ContextManager::default(),
         }
     }
 
+    /// Choose how the history is kept within the model's context window.
+    pub fn set_context_config(&mut self, config: ContextConfig) {
+        self.context = ContextManager::new(config);
+    }
+
     pub fn add_user_message(&mut self, content: &str) {
         self.history.push(ChatMessage::user(content));
     }
@@ -48,6 +60,10 @@ impl Agent {
         self.history.push(message);
     }
 
+    pub fn add_tool_message(&mut self, tool_call: &ToolCall, content: &str) {
+        self.history.push(ChatMessage::tool(tool_call, content));
+    }
+
     #[allow(dead_code)]
     pub fn update_system_prompt(&mut self, new_system_prompt: &str) {
         if !self.history.is_empty() && self.history[0].role == "system" {
@@ -79,12 +95,26 @@ impl Agent {
         }
         info!("Streaming: {}", stream);
 
-        let response = self
+        // The history stays whole; only the request is compacted
+        let messages = self
+            .context
+            .prepare(model, &self.history, self.llm_client.as_ref())
+            .await;
+        if messages.len() != self.history.len() {
+            info!(
+                "Sending {} of {} messages after compaction",
+                messages.len(),
+                self.history.len()
+            );
+        }
+
+        let mut response = self
             .llm_client
-            .chat(model, &self.history, tools, stream, tx)
+            .chat(model, &messages, tools, stream, tx)
             .await?;
 
-        if let Some(message) = response.clone() {
+        if let Some(message) = response.as_mut() {
+            assign_tool_call_ids(message);
             self.add_assistant_message(message.clone());
         }
 
@@ -93,3 +123,14 @@ impl Agent {
         Ok(response)
     }
 }
+
+/// Give every tool call an id so its result can be linked back to it.
+///
+/// Some providers (e.g. Ollama) do not assign ids themselves.
+fn assign_tool_call_ids(message: &mut ChatMessage) {
+    for tool_call in message.tool_calls.iter_mut().flatten() {
+        if tool_call.id.is_none() {
+            tool_call.id = Some(format!("call_{}", nanoid::nanoid!(12)));
+        }
+    }
+}
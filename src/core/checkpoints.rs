@@ -0,0 +1,309 @@
+//! Workspace checkpoints taken before tools change files.
+//!
+//! Before a `File` or `Shell` profile tool runs, the agent records a
+//! checkpoint tied to the position in the session history of the message that
+//! requested the call. Inside a git repository the whole working tree is
+//! written to a tree object through a shadow index, leaving the repository's
+//! own index, refs and working tree untouched. Elsewhere only the files the
+//! tool reports through `Tool::affected_paths` are snapshotted, so shell
+//! commands outside a git repository can't be checkpointed.
+//!
+//! Restoring a checkpoint rolls back every checkpoint taken from the same
+//! message onwards, newest first, and drops them. Files ignored by git are
+//! not part of tree checkpoints.
+
+use crate::config::CheckpointMode;
+use crate::core::tools::ToolProfile;
+use crate::core::undo::FileSnapshot;
+use crate::types::CheckpointInfo;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Name of the shadow index file, kept in the repository's git directory
+const SHADOW_INDEX: &str = "oxideagent-checkpoint-index";
+
+/// What a checkpoint captured
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckpointState {
+    /// Before-images of the files a tool was about to change
+    Files(Vec<FileSnapshot>),
+    /// The working tree of a git repository, as a tree object
+    GitTree { repo: PathBuf, tree: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub id: usize,
+    pub message_index: usize,
+    pub tool_name: String,
+    pub state: CheckpointState,
+}
+
+impl Checkpoint {
+    pub fn info(&self) -> CheckpointInfo {
+        let description = match &self.state {
+            CheckpointState::Files(snapshots) => snapshots
+                .iter()
+                .map(|snapshot| snapshot.path().display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            CheckpointState::GitTree { tree, .. } => {
+                format!("working tree {}", &tree[..tree.len().min(12)])
+            }
+        };
+        CheckpointInfo {
+            id: self.id,
+            message_index: self.message_index,
+            tool_name: self.tool_name.clone(),
+            description,
+        }
+    }
+}
+
+/// The checkpoints of one agent's session, oldest first
+#[derive(Debug)]
+pub struct CheckpointStore {
+    mode: CheckpointMode,
+    root: PathBuf,
+    /// Top level of the git repository containing `root`, looked up on first use
+    repo: Option<Option<PathBuf>>,
+    checkpoints: Vec<Checkpoint>,
+    next_id: usize,
+}
+
+impl Default for CheckpointStore {
+    fn default() -> Self {
+        Self::new(CheckpointMode::default(), ".")
+    }
+}
+
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
+impl CheckpointStore {
+    /// Create a store for the workspace rooted at `root`.
+    pub fn new(mode: CheckpointMode, root: impl Into<PathBuf>) -> Self {
+        Self {
+            mode,
+            root: root.into(),
+            repo: None,
+            checkpoints: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn list(&self) -> Vec<CheckpointInfo> {
+        self.checkpoints.iter().map(Checkpoint::info).collect()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Checkpoint> {
+        self.checkpoints
+            .iter()
+            .find(|checkpoint| checkpoint.id == id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
+    }
+
+    /// Forget every checkpoint, e.g. when the agent switches sessions.
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+    }
+
+    /// Take a checkpoint before a tool call runs.
+    ///
+    /// Returns the new checkpoint's id, or `None` when the call doesn't need
+    /// one: read-only file tools, other profiles, or shell commands outside a
+    /// git repository.
+    pub async fn record(
+        &mut self,
+        tool_name: &str,
+        profile: ToolProfile,
+        affected_paths: &[PathBuf],
+        message_index: usize,
+    ) -> anyhow::Result<Option<usize>> {
+        let wants_checkpoint = match profile {
+            ToolProfile::File => !affected_paths.is_empty(),
+            ToolProfile::Shell => true,
+            ToolProfile::Web | ToolProfile::Generic => false,
+        };
+        if self.mode == CheckpointMode::Off || !wants_checkpoint {
+            return Ok(None);
+        }
+
+        let repo = match self.mode {
+            CheckpointMode::Auto => self.git_repo().await,
+            _ => None,
+        };
+        // A tree checkpoint only covers files inside the repository
+        let covered = |repo: &Path| affected_paths.iter().all(|path| path.starts_with(repo));
+
+        let state = match repo {
+            Some(repo) if covered(&repo) => match snapshot_tree(&repo).await {
+                Ok(tree) => CheckpointState::GitTree { repo, tree },
+                Err(e) if !affected_paths.is_empty() => {
+                    warn!("Git checkpoint failed, snapshotting files instead: {}", e);
+                    CheckpointState::Files(snapshot_files(affected_paths)?)
+                }
+                Err(e) => return Err(e),
+            },
+            _ if affected_paths.is_empty() => {
+                debug!(
+                    "No checkpoint for '{}': not in a git repository and no files reported",
+                    tool_name
+                );
+                return Ok(None);
+            }
+            _ => CheckpointState::Files(snapshot_files(affected_paths)?),
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.checkpoints.push(Checkpoint {
+            id,
+            message_index,
+            tool_name: tool_name.to_string(),
+            state,
+        });
+        Ok(Some(id))
+    }
+
+    /// Roll the workspace back to the checkpoint with the given id.
+    ///
+    /// Every checkpoint taken from the same message onwards is restored,
+    /// newest first, and removed. Returns the history position the
+    /// conversation should be truncated to.
+    pub async fn restore(&mut self, id: usize) -> anyhow::Result<usize> {
+        let target = self
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Checkpoint {} not found", id))?;
+        let message_index = target.message_index;
+        let first = self
+            .checkpoints
+            .iter()
+            .position(|checkpoint| checkpoint.message_index >= message_index)
+            .unwrap_or(self.checkpoints.len());
+
+        while self.checkpoints.len() > first {
+            let checkpoint = self.checkpoints.last().expect("checked above");
+            restore_state(&checkpoint.state).await.map_err(|e| {
+                anyhow::anyhow!("Failed to restore checkpoint {}: {}", checkpoint.id, e)
+            })?;
+            self.checkpoints.pop();
+        }
+        Ok(message_index)
+    }
+
+    async fn git_repo(&mut self) -> Option<PathBuf> {
+        if self.repo.is_none() {
+            let repo = git(&self.root, &["rev-parse", "--show-toplevel"], None)
+                .await
+                .ok()
+                .map(|output| PathBuf::from(output.trim()))
+                .and_then(|repo| repo.canonicalize().ok());
+            self.repo = Some(repo);
+        }
+        self.repo.clone().flatten()
+    }
+}
+
+fn snapshot_files(paths: &[PathBuf]) -> anyhow::Result<Vec<FileSnapshot>> {
+    paths
+        .iter()
+        .map(|path| {
+            FileSnapshot::capture(path)
+                .map_err(|e| anyhow::anyhow!("Cannot snapshot '{}': {}", path.display(), e))
+        })
+        .collect()
+}
+
+async fn restore_state(state: &CheckpointState) -> anyhow::Result<()> {
+    match state {
+        CheckpointState::Files(snapshots) => {
+            for snapshot in snapshots.iter().rev() {
+                snapshot.restore()?;
+            }
+            Ok(())
+        }
+        CheckpointState::GitTree { repo, tree } => restore_tree(repo, tree).await,
+    }
+}
+
+/// Run git in `dir`, optionally with the shadow index, and return its stdout.
+async fn git(dir: &Path, args: &[&str], index: Option<&Path>) -> anyhow::Result<String> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let output = command.output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+async fn shadow_index(repo: &Path) -> anyhow::Result<PathBuf> {
+    let path = git(repo, &["rev-parse", "--git-path", SHADOW_INDEX], None).await?;
+    let path = PathBuf::from(path.trim());
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        repo.join(path)
+    })
+}
+
+/// Write the current working tree, including untracked files that aren't
+/// ignored, to a tree object and return its id.
+async fn snapshot_tree(repo: &Path) -> anyhow::Result<String> {
+    let index = shadow_index(repo).await?;
+    // Start from the real index so unchanged files aren't hashed again
+    let real_index = git(repo, &["rev-parse", "--git-path", "index"], None).await?;
+    let real_index = repo.join(real_index.trim());
+    if real_index.exists() {
+        fs::copy(&real_index, &index)?;
+    } else if index.exists() {
+        fs::remove_file(&index)?;
+    }
+
+    git(repo, &["add", "--all", "--", "."], Some(&index)).await?;
+    let tree = git(repo, &["write-tree"], Some(&index)).await?;
+    Ok(tree.trim().to_string())
+}
+
+/// Make the working tree match a tree taken by `snapshot_tree`.
+async fn restore_tree(repo: &Path, tree: &str) -> anyhow::Result<()> {
+    // Files created since the checkpoint have to be removed explicitly
+    let current = snapshot_tree(repo).await?;
+    let added = git(
+        repo,
+        &[
+            "diff-tree",
+            "-r",
+            "-z",
+            "--name-only",
+            "--no-renames",
+            "--diff-filter=A",
+            tree,
+            &current,
+        ],
+        None,
+    )
+    .await?;
+    for path in added.split('\0').filter(|path| !path.is_empty()) {
+        let path = repo.join(path);
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Could not remove '{}': {}", path.display(), e);
+        }
+    }
+
+    let index = shadow_index(repo).await?;
+    git(repo, &["read-tree", tree], Some(&index)).await?;
+    git(repo, &["checkout-index", "--all", "--force"], Some(&index)).await?;
+    Ok(())
+}
@@ -4,12 +4,15 @@
 //! between components in the application.
 
 use crate::config::OxideConfig;
+use crate::core::mcp::router::McpRequestRouter;
+use crate::core::mcp::supervisor::McpSupervisor;
 use crate::core::mcp_manager::McpManager;
 use crate::core::orchestrator::Orchestrator;
 use crate::core::session::SessionManager;
 use crate::core::tools::ToolRegistry;
 use crate::types::AppEvent;
 use anyhow::Result;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
@@ -19,6 +22,12 @@ pub struct Container {
     tool_registry: Option<ToolRegistry>,
     #[allow(dead_code)]
     session_manager: Option<SessionManager>,
+    /// Keeps the launched MCP servers running until `shutdown`
+    mcp_supervisor: Option<McpSupervisor>,
+    /// Where MCP server health is reported
+    status_tx: Option<mpsc::Sender<AppEvent>>,
+    /// Hands requests of the MCP servers to the interface
+    mcp_router: Option<McpRequestRouter>,
 }
 
 impl Container {
@@ -28,6 +37,9 @@ impl Container {
             config: Arc::new(config),
             tool_registry: None,
             session_manager: None,
+            mcp_supervisor: None,
+            status_tx: None,
+            mcp_router: None,
         }
     }
 
@@ -48,30 +60,66 @@ impl Container {
             let mut tool_registry = ToolRegistry::new();
             // Register tools based on configuration
             // For now, we register all tools by default
-            use crate::core::tools::{ReadFileTool, RunShellCommandTool, WriteFileTool};
-            tool_registry.add_tool(Box::new(WriteFileTool));
-            tool_registry.add_tool(Box::new(ReadFileTool));
-            tool_registry.add_tool(Box::new(RunShellCommandTool));
+            use crate::core::tools::{
+                ApplyUnifiedDiffTool, EditFileTool, GrepFilesTool, ListDirectoryTool, ReadFileTool,
+                RunShellCommandTool, WriteFileTool,
+            };
+            use crate::core::workspace::Workspace;
+            let workspace = Workspace::from_config(&self.config.tools);
+            tool_registry.add_tool(Box::new(WriteFileTool::new(workspace.clone())));
+            tool_registry.add_tool(Box::new(ReadFileTool::new(workspace.clone())));
+            tool_registry.add_tool(Box::new(ListDirectoryTool::new(workspace.clone())));
+            tool_registry.add_tool(Box::new(GrepFilesTool::new(workspace.clone())));
+            tool_registry.add_tool(Box::new(EditFileTool::new(workspace.clone())));
+            tool_registry.add_tool(Box::new(ApplyUnifiedDiffTool::new(workspace)));
+            let mut shell_tool = RunShellCommandTool::new(self.config.tools.shell.clone());
+            if let Some(workspace_root) = &self.config.tools.workspace_root {
+                shell_tool = shell_tool.with_working_dir(workspace_root);
+            }
+            tool_registry.add_tool(Box::new(shell_tool));
 
             // Log MCP configuration if present
-            if !self.config.mcp.tools.is_empty() {
+            let mcp_servers = self.config.mcp.server_configs()?;
+            if !mcp_servers.is_empty() {
                 use tracing::info;
                 info!(
-                    "MCP configuration found with {} tool(s)",
-                    self.config.mcp.tools.len()
+                    "MCP configuration found with {} server(s)",
+                    mcp_servers.len()
                 );
-                for tool in &self.config.mcp.tools {
-                    info!("  - MCP Tool: {} (command: {})", tool.name, tool.command);
+                for server in &mcp_servers {
+                    info!(
+                        "  - MCP Server: {} ({})",
+                        server.name,
+                        server.server_type.kind()
+                    );
                 }
             } else {
                 use tracing::debug;
-                debug!("No MCP tools configured");
+                debug!("No MCP servers configured");
             }
 
-            let mut mcp_manager = McpManager::new(tool_registry);
-            mcp_manager.launch_servers(&self.config.mcp.tools).await?;
+            let mut mcp_manager = McpManager::new(tool_registry)
+                .with_tool_aliases(self.config.mcp.tool_aliases.clone())
+                .with_oauth(self.config.mcp.oauth.clone());
+            if let Some(status_tx) = &self.status_tx {
+                // Servers may sample from the model the agents use once the
+                // user approved
+                let sampler = match crate::core::llm::llm_client_factory(&self.config.llm) {
+                    Ok(client) => Some((Arc::from(client), self.config.chat_model())),
+                    Err(e) => {
+                        tracing::warn!("MCP sampling is unavailable: {}", e);
+                        None
+                    }
+                };
+                mcp_manager = mcp_manager
+                    .with_status_sender(status_tx.clone())
+                    .with_client_requests(status_tx.clone(), sampler);
+            }
+            self.mcp_router = Some(mcp_manager.request_router());
+            mcp_manager.launch_servers(&mcp_servers).await?;
             mcp_manager.launch_remote_server(&self.config.mcp).await?;
-            let final_registry = mcp_manager.into_tool_registry();
+            let (final_registry, mcp_supervisor) = mcp_manager.into_parts();
+            self.mcp_supervisor = Some(mcp_supervisor);
 
             // Log the final tools in the registry
             let final_tools = final_registry.definitions();
@@ -90,12 +138,19 @@ impl Container {
         Ok(self.tool_registry.as_mut().unwrap())
     }
 
+    /// Tell MCP servers' requests whether the interface is on screen; while it
+    /// is, they are shown there instead of printed
+    pub fn set_interface_running(&self, running: bool) {
+        if let Some(router) = &self.mcp_router {
+            router.set_interface_running(running);
+        }
+    }
+
     /// Build the session manager
     #[allow(dead_code)]
     pub fn build_session_manager(&mut self) -> Result<&mut SessionManager> {
         if self.session_manager.is_none() {
-            // SessionManager is a unit struct with only static methods
-            self.session_manager = Some(SessionManager);
+            self.session_manager = Some(SessionManager::new(self.config.sessions_dir()));
         }
         Ok(self.session_manager.as_mut().unwrap())
     }
@@ -112,11 +167,17 @@ impl Container {
         let system_prompt = self.config.agent.system_prompt.clone();
         let model = self.config.agent.model.clone();
         let llm_config = self.config.llm.clone();
+        let max_tool_iterations = self.config.multi_agent.max_tool_iterations;
+
+        // MCP server health goes to the interface along with the other events
+        if self.status_tx.is_none() {
+            self.status_tx = Some(orchestrator_tx.clone());
+        }
 
         // Build dependencies (we call these to ensure they're initialized)
         let tool_registry = self.build_tool_registry().await?;
 
-        Ok(Orchestrator::new(
+        let mut orchestrator = Orchestrator::new(
             &system_prompt,
             tool_registry.clone_registry(),
             session_name,
@@ -125,6 +186,31 @@ impl Container {
             orchestrator_rx,
             model,
             llm_config,
-        ))
+        );
+        orchestrator.set_max_tool_iterations(max_tool_iterations);
+        orchestrator.set_context_config(self.config.multi_agent.context.clone());
+        orchestrator.set_sessions_dir(self.config.sessions_dir());
+        if let Some(mcp_supervisor) = &self.mcp_supervisor {
+            orchestrator.set_mcp_registry(mcp_supervisor.registry().clone());
+        }
+        orchestrator.configure_agents(&self.config.agent, &self.config.multi_agent.default_agents);
+        orchestrator.set_checkpoints(
+            self.config.tools.checkpoints,
+            self.config
+                .tools
+                .workspace_root
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".")),
+        );
+
+        Ok(orchestrator)
+    }
+
+    /// Stop the supervised MCP servers, killing their processes and removing
+    /// their Docker containers
+    pub async fn shutdown(&mut self) {
+        if let Some(mut mcp_supervisor) = self.mcp_supervisor.take() {
+            mcp_supervisor.shutdown().await;
+        }
     }
 }
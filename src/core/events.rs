@@ -2,8 +2,12 @@
 //!
 //! This module implements a robust event system for communication between components.
 
-use crate::types::{AppEvent, ChatMessage, ToolCall};
+use crate::types::{
+    AppEvent, ChatMessage, CheckpointInfo, McpElicitationAnswer, McpElicitationRequestInfo,
+    McpPromptInfo, McpResourceInfo, McpSamplingRequestInfo, ToolCall,
+};
 use anyhow::Result;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
@@ -16,7 +20,7 @@ pub enum EventType {
 
     /// Tool approval events
     ToolApprovalRequested(Vec<ToolCall>),
-    ToolApprovalResponse(crate::types::ToolApprovalResponse),
+    ToolApprovalResponse(Vec<crate::types::ToolCallDecision>),
 
     /// Agent communication events
     AgentMessage(String),
@@ -24,8 +28,12 @@ pub enum EventType {
     AgentStreamEnd,
 
     /// Tool execution events
-    ToolRequest(Vec<ToolCall>),
+    ToolRequest(Vec<ToolCall>, Vec<Option<String>>), // (calls, previews)
     ToolResult(String, String), // (tool_name, result)
+    UndoLastToolEffect,
+    ListCheckpoints,
+    CheckpointList(Vec<CheckpointInfo>),
+    RestoreCheckpoint(usize),
 
     /// Error events
     Error(String),
@@ -38,6 +46,7 @@ pub enum EventType {
     RefreshSessions,
     SessionList(Vec<String>),
     SessionSwitched(String),
+    ForkSession(String, String, Option<usize>),
     SessionHistory(Vec<ChatMessage>),
 
     /// System events
@@ -45,6 +54,20 @@ pub enum EventType {
     ConfigChanged,
     ContinueConversation, // New event to continue conversation after tool execution
     AgentStatusUpdate(String, String), // New event to update agent status (agent_name, status)
+    McpServerStatus(String, String), // Health of a supervised MCP server (server_name, status)
+    ListMcpResources,
+    McpResourceList(Vec<McpResourceInfo>),
+    ReadMcpResource(String),
+    McpResourceContent(String, String), // (uri, text)
+    ListMcpPrompts,
+    McpPromptList(Vec<McpPromptInfo>),
+    GetMcpPrompt(String, HashMap<String, String>), // (name, arguments)
+    McpPromptExpanded(String, String),             // (name, text)
+    McpSamplingRequest(McpSamplingRequestInfo),
+    McpSamplingApproval(u64, bool), // (id, approved)
+    McpElicitationRequest(McpElicitationRequestInfo),
+    McpElicitationResponse(u64, McpElicitationAnswer), // (id, answer)
+    McpAuthorizationRequired(String, String),          // (server_name, authorization URL)
 }
 
 /// Event with metadata
@@ -125,11 +148,11 @@ impl EventBus {
     pub fn publish_app_event(&self, app_event: AppEvent, source: String) -> Result<()> {
         let event_type = match app_event {
             AppEvent::UserInput(input) => EventType::UserInput(input),
-            AppEvent::ToolApproval(response) => EventType::ToolApprovalResponse(response),
+            AppEvent::ToolApproval(decisions) => EventType::ToolApprovalResponse(decisions),
             AppEvent::AgentMessage(message) => EventType::AgentMessage(message),
             AppEvent::AgentStreamChunk(chunk) => EventType::AgentStreamChunk(chunk),
             AppEvent::AgentStreamEnd => EventType::AgentStreamEnd,
-            AppEvent::ToolRequest(calls) => EventType::ToolRequest(calls),
+            AppEvent::ToolRequest(calls, previews) => EventType::ToolRequest(calls, previews),
             AppEvent::ToolResult(name, result) => EventType::ToolResult(name, result),
             AppEvent::Error(error) => EventType::Error(error),
             AppEvent::SwitchSession(session) => EventType::SwitchSession(session),
@@ -139,11 +162,40 @@ impl EventBus {
             AppEvent::RefreshSessions => EventType::RefreshSessions,
             AppEvent::SessionList(sessions) => EventType::SessionList(sessions),
             AppEvent::SessionSwitched(session) => EventType::SessionSwitched(session),
+            AppEvent::ForkSession(session, new_name, messages) => {
+                EventType::ForkSession(session, new_name, messages)
+            }
             AppEvent::SessionHistory(history) => EventType::SessionHistory(history),
             AppEvent::ContinueConversation => EventType::ContinueConversation,
             AppEvent::AgentStatusUpdate(agent_name, status) => {
                 EventType::AgentStatusUpdate(agent_name, status)
             }
+            AppEvent::UndoLastToolEffect => EventType::UndoLastToolEffect,
+            AppEvent::ListCheckpoints => EventType::ListCheckpoints,
+            AppEvent::CheckpointList(checkpoints) => EventType::CheckpointList(checkpoints),
+            AppEvent::RestoreCheckpoint(id) => EventType::RestoreCheckpoint(id),
+            AppEvent::McpServerStatus(server_name, status) => {
+                EventType::McpServerStatus(server_name, status)
+            }
+            AppEvent::ListMcpResources => EventType::ListMcpResources,
+            AppEvent::McpResourceList(resources) => EventType::McpResourceList(resources),
+            AppEvent::ReadMcpResource(uri) => EventType::ReadMcpResource(uri),
+            AppEvent::McpResourceContent(uri, text) => EventType::McpResourceContent(uri, text),
+            AppEvent::ListMcpPrompts => EventType::ListMcpPrompts,
+            AppEvent::McpPromptList(prompts) => EventType::McpPromptList(prompts),
+            AppEvent::GetMcpPrompt(name, arguments) => EventType::GetMcpPrompt(name, arguments),
+            AppEvent::McpPromptExpanded(name, text) => EventType::McpPromptExpanded(name, text),
+            AppEvent::McpSamplingRequest(request) => EventType::McpSamplingRequest(request),
+            AppEvent::McpSamplingApproval(id, approved) => {
+                EventType::McpSamplingApproval(id, approved)
+            }
+            AppEvent::McpElicitationRequest(request) => EventType::McpElicitationRequest(request),
+            AppEvent::McpElicitationResponse(id, answer) => {
+                EventType::McpElicitationResponse(id, answer)
+            }
+            AppEvent::McpAuthorizationRequired(server_name, url) => {
+                EventType::McpAuthorizationRequired(server_name, url)
+            }
         };
 
         let event = Event::new(event_type, source);
@@ -0,0 +1,462 @@
+use crate::{
+    core::llm::{MISSING_TOOL_RESULT, build_message, client::LlmClient, list_model_ids, v1_base},
+    types::{AppEvent, ChatMessage, Tool, ToolCall},
+};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, trace, warn};
+
+/// Messages API version sent with every request.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// The Messages API requires an explicit output budget.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+fn with_auth(request: RequestBuilder, api_key: Option<&str>) -> RequestBuilder {
+    let request = request.header("anthropic-version", ANTHROPIC_VERSION);
+    match api_key.filter(|key| !key.is_empty()) {
+        Some(key) => request.header("x-api-key", key),
+        None => request,
+    }
+}
+
+pub async fn list_models(
+    client: &Client,
+    api_base: &str,
+    api_key: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let url = format!("{}/models", v1_base(api_base));
+    list_model_ids(with_auth(client.get(&url), api_key)).await
+}
+
+/// Content block being assembled from streamed `content_block_*` events.
+#[derive(Debug)]
+enum StreamBlock {
+    Text,
+    ToolUse {
+        id: String,
+        name: String,
+        input_json: String,
+    },
+}
+
+/// Append a content block, merging it into the previous message when the roles match.
+///
+/// The Messages API expects alternating turns, so consecutive tool outputs and
+/// user text end up as blocks of a single `user` message.
+fn push_block(messages: &mut Vec<Value>, role: &str, block: Value) {
+    if let Some(last) = messages.last_mut()
+        && last["role"] == role
+        && let Some(content) = last["content"].as_array_mut()
+    {
+        content.push(block);
+        return;
+    }
+    messages.push(json!({ "role": role, "content": [block] }));
+}
+
+/// Every `tool_use` block must be answered before the next assistant turn.
+fn close_pending_tool_uses(messages: &mut Vec<Value>, pending: &mut VecDeque<String>) {
+    while let Some(id) = pending.pop_front() {
+        push_block(
+            messages,
+            "user",
+            json!({
+                "type": "tool_result",
+                "tool_use_id": id,
+                "content": MISSING_TOOL_RESULT,
+                "is_error": true,
+            }),
+        );
+    }
+}
+
+/// Client for the Anthropic Messages API (`/v1/messages`).
+#[derive(Debug, Clone)]
+pub struct AnthropicClient {
+    pub client: Client,
+    pub api_base: String,
+    pub api_key: Option<String>,
+    pub max_tokens: u32,
+}
+
+impl AnthropicClient {
+    pub fn new(api_base: &str, api_key: Option<String>) -> Self {
+        let client = Client::builder().no_proxy().build().unwrap_or_else(|error| {
+            warn!(
+                "Failed to build reqwest client with no_proxy, falling back to default client: {}",
+                error
+            );
+            Client::new()
+        });
+
+        Self {
+            client,
+            api_base: api_base.to_string(),
+            api_key,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+
+    /// Convert our history into a system prompt plus Messages API turns.
+    ///
+    /// `tool` messages become `tool_result` blocks; calls left without a recorded
+    /// result are closed with a placeholder before the conversation moves on.
+    /// Results that answer no `tool_use` are sent as text, and blank turns are
+    /// left out, since the API rejects both.
+    fn to_anthropic_messages(history: &[ChatMessage]) -> (Option<String>, Vec<Value>) {
+        let mut system_parts: Vec<&str> = Vec::new();
+        let mut messages: Vec<Value> = Vec::new();
+        let mut pending: VecDeque<String> = VecDeque::new();
+
+        for (message_index, message) in history.iter().enumerate() {
+            match message.role.as_str() {
+                "system" => {
+                    if !message.content.is_empty() {
+                        system_parts.push(&message.content);
+                    }
+                }
+                "assistant" => {
+                    close_pending_tool_uses(&mut messages, &mut pending);
+
+                    // Blank text blocks are rejected, so an empty turn sends nothing
+                    if !message.content.trim().is_empty() {
+                        push_block(
+                            &mut messages,
+                            "assistant",
+                            json!({ "type": "text", "text": message.content }),
+                        );
+                    }
+
+                    for (call_index, call) in message.tool_calls.iter().flatten().enumerate() {
+                        let id = call
+                            .id
+                            .clone()
+                            .unwrap_or_else(|| format!("toolu_{}_{}", message_index, call_index));
+                        let input = if call.function.arguments.is_object() {
+                            call.function.arguments.clone()
+                        } else {
+                            json!({})
+                        };
+                        push_block(
+                            &mut messages,
+                            "assistant",
+                            json!({
+                                "type": "tool_use",
+                                "id": id,
+                                "name": call.function.name,
+                                "input": input,
+                            }),
+                        );
+                        pending.push_back(id);
+                    }
+                }
+                "tool" => {
+                    // A result must answer a `tool_use` of the assistant turn before it
+                    let tool_use_id = match &message.tool_call_id {
+                        Some(id) if pending.contains(id) => {
+                            pending.retain(|pending_id| pending_id != id);
+                            Some(id.clone())
+                        }
+                        Some(_) => None,
+                        None => pending.pop_front(),
+                    };
+                    match tool_use_id {
+                        Some(tool_use_id) => push_block(
+                            &mut messages,
+                            "user",
+                            json!({
+                                "type": "tool_result",
+                                "tool_use_id": tool_use_id,
+                                "content": message.content,
+                            }),
+                        ),
+                        None if !message.content.trim().is_empty() => {
+                            warn!("Sending a tool result without a matching tool_use as text");
+                            push_block(
+                                &mut messages,
+                                "user",
+                                json!({
+                                    "type": "text",
+                                    "text": format!(
+                                        "Result of {}: {}",
+                                        message.tool_name.as_deref().unwrap_or("a tool call"),
+                                        message.content
+                                    ),
+                                }),
+                            );
+                        }
+                        None => {}
+                    }
+                }
+                _ => {
+                    close_pending_tool_uses(&mut messages, &mut pending);
+                    if !message.content.trim().is_empty() {
+                        push_block(
+                            &mut messages,
+                            "user",
+                            json!({ "type": "text", "text": message.content }),
+                        );
+                    }
+                }
+            }
+        }
+
+        close_pending_tool_uses(&mut messages, &mut pending);
+
+        let system = (!system_parts.is_empty()).then(|| system_parts.join("\n\n"));
+        (system, messages)
+    }
+
+    fn to_anthropic_tools(tools: &[Tool]) -> Vec<Value> {
+        tools
+            .iter()
+            .map(|tool| {
+                let mut input_schema = tool.function.parameters.clone();
+                if !input_schema.is_object() || input_schema.get("type").is_none() {
+                    input_schema = json!({ "type": "object", "properties": {} });
+                }
+                json!({
+                    "name": tool.function.name,
+                    "description": tool.function.description,
+                    "input_schema": input_schema,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_content(content_blocks: &Value) -> (String, Vec<ToolCall>) {
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+
+        for block in content_blocks.as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("text") => content.push_str(block["text"].as_str().unwrap_or("")),
+                Some("tool_use") => {
+                    let id = block["id"].as_str().map(str::to_string);
+                    let name = block["name"].as_str().unwrap_or("");
+                    tool_calls.push(ToolCall::new(id, name, block["input"].clone()));
+                }
+                other => debug!("Ignoring content block of type {:?}", other),
+            }
+        }
+
+        (content, tool_calls)
+    }
+
+    async fn read_stream(
+        response: reqwest::Response,
+        tx: &mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<ChatMessage> {
+        let mut content = String::new();
+        let mut blocks: Vec<Option<StreamBlock>> = Vec::new();
+        let mut stream = response.bytes_stream();
+        // Buffer raw bytes so multi-byte characters split across chunks stay intact
+        let mut buffer: Vec<u8> = Vec::new();
+
+        'stream: while let Some(chunk) = stream.next().await {
+            let chunk_data = match chunk {
+                Ok(chunk_data) => chunk_data,
+                Err(e) => {
+                    error!("Error reading stream chunk: {}", e);
+                    break;
+                }
+            };
+            trace!("Received {} bytes from Anthropic stream", chunk_data.len());
+            buffer.extend_from_slice(&chunk_data);
+
+            while let Some(newline_pos) = buffer.iter().position(|byte| *byte == b'\n') {
+                let raw_line: Vec<u8> = buffer.drain(..=newline_pos).collect();
+                let line = String::from_utf8_lossy(&raw_line);
+                let line = line.trim();
+
+                // Every event repeats its name in the payload `type`, so only data lines matter
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+
+                debug!("Received streaming event: {}", data);
+
+                let event: Value = match serde_json::from_str(data) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        error!("Error parsing SSE data: '{}', error: {}", data, e);
+                        continue;
+                    }
+                };
+
+                let index = event["index"].as_u64().unwrap_or(0) as usize;
+
+                match event["type"].as_str() {
+                    Some("content_block_start") => {
+                        let block = &event["content_block"];
+                        let stream_block = match block["type"].as_str() {
+                            Some("tool_use") => StreamBlock::ToolUse {
+                                id: block["id"].as_str().unwrap_or("").to_string(),
+                                name: block["name"].as_str().unwrap_or("").to_string(),
+                                input_json: String::new(),
+                            },
+                            _ => StreamBlock::Text,
+                        };
+                        if blocks.len() <= index {
+                            blocks.resize_with(index + 1, || None);
+                        }
+                        blocks[index] = Some(stream_block);
+                    }
+                    Some("content_block_delta") => {
+                        let delta = &event["delta"];
+                        match delta["type"].as_str() {
+                            Some("text_delta") => {
+                                let text = delta["text"].as_str().unwrap_or("");
+                                if text.is_empty() {
+                                    continue;
+                                }
+                                if tx
+                                    .send(AppEvent::AgentStreamChunk(text.to_string()))
+                                    .await
+                                    .is_err()
+                                {
+                                    error!("Failed to send stream chunk to UI");
+                                    break 'stream;
+                                }
+                                content.push_str(text);
+                            }
+                            Some("input_json_delta") => {
+                                if let Some(Some(StreamBlock::ToolUse { input_json, .. })) =
+                                    blocks.get_mut(index)
+                                {
+                                    input_json
+                                        .push_str(delta["partial_json"].as_str().unwrap_or(""));
+                                }
+                            }
+                            other => debug!("Ignoring content delta of type {:?}", other),
+                        }
+                    }
+                    Some("message_stop") => {
+                        info!("Streaming response completed");
+                        break 'stream;
+                    }
+                    Some("error") => {
+                        return Err(anyhow::anyhow!(
+                            "Anthropic stream returned error: {}",
+                            event["error"]
+                        ));
+                    }
+                    // message_start, message_delta, content_block_stop and ping carry no content
+                    _ => {}
+                }
+            }
+        }
+
+        if tx.send(AppEvent::AgentStreamEnd).await.is_err() {
+            error!("Failed to send stream end to UI");
+        }
+
+        let tool_calls = blocks
+            .into_iter()
+            .flatten()
+            .filter_map(|block| match block {
+                StreamBlock::ToolUse {
+                    id,
+                    name,
+                    input_json,
+                } => {
+                    let input = if input_json.trim().is_empty() {
+                        json!({})
+                    } else {
+                        serde_json::from_str(&input_json).unwrap_or_else(|e| {
+                            warn!("Failed to parse tool input '{}': {}", input_json, e);
+                            json!({})
+                        })
+                    };
+                    let id = (!id.is_empty()).then_some(id);
+                    Some(ToolCall::new(id, &name, input))
+                }
+                StreamBlock::Text => None,
+            })
+            .collect();
+
+        Ok(build_message(&content, tool_calls))
+    }
+}
+
+#[async_trait]
+impl LlmClient for AnthropicClient {
+    async fn chat(
+        &self,
+        model: &str,
+        history: &[ChatMessage],
+        tools: &[Tool],
+        stream: bool,
+        tx: mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<Option<ChatMessage>> {
+        info!("=== ANTHROPIC REQUEST START ===");
+        info!("Sending request to Anthropic API at {}", self.api_base);
+        info!("Model: {}", model);
+        info!("History length: {} messages", history.len());
+        info!("Streaming: {}", stream);
+
+        let url = format!("{}/messages", v1_base(&self.api_base));
+        let (system, messages) = Self::to_anthropic_messages(history);
+
+        let mut request_body = json!({
+            "model": model,
+            "max_tokens": self.max_tokens,
+            "messages": messages,
+            "stream": stream,
+        });
+
+        if let Some(system) = system {
+            request_body["system"] = json!(system);
+        }
+
+        if !tools.is_empty() {
+            info!("Adding {} tools to request", tools.len());
+            request_body["tools"] = json!(Self::to_anthropic_tools(tools));
+        }
+
+        let request = with_auth(self.client.post(&url), self.api_key.as_deref());
+        let response = request.json(&request_body).send().await.map_err(|e| {
+            error!("Failed to send request to Anthropic API: {}", e);
+            e
+        })?;
+
+        info!("=== ANTHROPIC REQUEST END ===");
+
+        let status = response.status();
+        info!("Received HTTP response with status: {}", status);
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            error!("Anthropic API returned {}: {}", status, body);
+            return Err(anyhow::anyhow!(
+                "Anthropic API returned {}: {}",
+                status,
+                body
+            ));
+        }
+
+        let message = if stream {
+            info!("Processing streaming response...");
+            Self::read_stream(response, &tx).await?
+        } else {
+            info!("Processing non-streaming response...");
+            let json: Value = response.json().await?;
+            debug!(
+                "Full JSON response: {}",
+                serde_json::to_string_pretty(&json)?
+            );
+
+            let (content, tool_calls) = Self::parse_content(&json["content"]);
+            info!("Response content length: {} characters", content.len());
+            build_message(&content, tool_calls)
+        };
+
+        info!("=== ANTHROPIC RESPONSE END ===");
+        Ok(Some(message))
+    }
+}
@@ -27,4 +27,17 @@ pub trait LlmClient: Send + Sync + Debug {
         stream: bool,
         tx: mpsc::Sender<AppEvent>,
     ) -> anyhow::Result<Option<ChatMessage>>;
+
+    /// Context length of `model` in tokens, when the provider reports it.
+    ///
+    /// Providers that size the context window per request report the window
+    /// they would use: the model's own setting, else its trained length
+    /// capped at `default_window`.
+    async fn context_length(&self, _model: &str, _default_window: usize) -> Option<usize> {
+        None
+    }
+
+    /// Context length in tokens the conversation with `model` is kept
+    /// within, for providers that size the context window per request.
+    fn set_context_length(&self, _model: &str, _length: usize) {}
 }
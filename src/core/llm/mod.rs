@@ -1,15 +1,95 @@
+pub mod anthropic;
 pub mod client;
 pub mod ollama;
+pub mod openai;
 
 use crate::config::LLMConfig;
+use crate::types::{ChatMessage, ToolCall};
+use anthropic::AnthropicClient;
 use anyhow::Result;
 use client::LlmClient;
 use ollama::OllamaClient;
+use openai::OpenAiClient;
+use reqwest::{Client, RequestBuilder};
+use tracing::info;
+
+/// Result recorded for a tool call whose output never made it into the history.
+pub(crate) const MISSING_TOOL_RESULT: &str = "No result was recorded for this tool call.";
+
+/// Normalize an API base so that it always ends with `/v1`.
+///
+/// Hosted and self-hosted servers are commonly configured either as
+/// `http://host:port` or `http://host:port/v1`.
+pub(crate) fn v1_base(api_base: &str) -> String {
+    let trimmed = api_base.trim_end_matches('/');
+    if trimmed.ends_with("/v1") {
+        trimmed.to_string()
+    } else {
+        format!("{}/v1", trimmed)
+    }
+}
+
+/// Model ids from a `/v1/models` listing, as served by OpenAI and Anthropic.
+pub(crate) async fn list_model_ids(request: RequestBuilder) -> Result<Vec<String>> {
+    let response = request.send().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list models: {}",
+            response.status()
+        ));
+    }
+
+    let json: serde_json::Value = response.json().await?;
+    let mut models = Vec::new();
+
+    if let Some(models_array) = json["data"].as_array() {
+        for model in models_array {
+            if let Some(id) = model["id"].as_str() {
+                models.push(id.to_string());
+            }
+        }
+    }
+
+    Ok(models)
+}
+
+/// Assistant message for a completed response, with its tool calls if it made any.
+pub(crate) fn build_message(content: &str, tool_calls: Vec<ToolCall>) -> ChatMessage {
+    if tool_calls.is_empty() {
+        ChatMessage::assistant(content)
+    } else {
+        info!("Response contains {} tool calls", tool_calls.len());
+        ChatMessage::tool_call(content, tool_calls)
+    }
+}
 
 pub fn llm_client_factory(config: &LLMConfig) -> Result<Box<dyn LlmClient>> {
+    let api_base = config.base_url();
     match config.provider.as_str() {
-        "ollama" => Ok(Box::new(OllamaClient::new(&config.api_base))),
+        "ollama" => Ok(Box::new(OllamaClient::new(&api_base))),
+        "openai" | "openai-compatible" => Ok(Box::new(OpenAiClient::new(
+            &api_base,
+            config.api_key.clone(),
+        ))),
+        "anthropic" => Ok(Box::new(AnthropicClient::new(
+            &api_base,
+            config.api_key.clone(),
+        ))),
         // Future providers will go here
         provider => Err(anyhow::anyhow!("Unsupported LLM provider: {}", provider)),
     }
 }
+
+/// List the models offered by the configured provider.
+pub async fn list_models(client: &Client, config: &LLMConfig) -> Result<Vec<String>> {
+    let api_base = config.base_url();
+    match config.provider.as_str() {
+        "ollama" => ollama::list_models(client, &api_base).await,
+        "openai" | "openai-compatible" => {
+            openai::list_models(client, &api_base, config.api_key.as_deref()).await
+        }
+        "anthropic" => anthropic::list_models(client, &api_base, config.api_key.as_deref()).await,
+        provider => Err(anyhow::anyhow!("Unsupported LLM provider: {}", provider)),
+    }
+}
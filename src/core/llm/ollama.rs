@@ -6,6 +6,8 @@ use async_trait::async_trait;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, trace, warn};
 
@@ -34,10 +36,59 @@ pub async fn list_models(client: &Client, api_base: &str) -> anyhow::Result<Vec<
     Ok(models)
 }
 
+/// Context window to request for `model`, from `/api/show`.
+///
+/// That is `num_ctx` when the model's parameters set it, else
+/// `default_window`, and never more than the model's trained context
+/// length. The trained length itself is not requested, as Ollama would
+/// allocate a KV cache for all of it.
+pub async fn context_length(
+    client: &Client,
+    api_base: &str,
+    model: &str,
+    default_window: usize,
+) -> anyhow::Result<Option<usize>> {
+    let url = format!("{}/api/show", api_base);
+    let response = client
+        .post(&url)
+        .json(&json!({ "model": model }))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to show model {}: {}",
+            model,
+            response.status()
+        ));
+    }
+
+    let json: serde_json::Value = response.json().await?;
+    let trained = json["model_info"].as_object().and_then(|info| {
+        info.iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+    });
+    let num_ctx = json["parameters"].as_str().and_then(|parameters| {
+        parameters.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next() == Some("num_ctx"))
+                .then(|| parts.next()?.parse::<u64>().ok())
+                .flatten()
+        })
+    });
+
+    let window = num_ctx.unwrap_or(default_window as u64);
+    let length = trained.map_or(window, |trained| trained.min(window));
+    Ok(Some(length as usize))
+}
+
 #[derive(Debug, Clone)]
 pub struct OllamaClient {
     pub client: Client,
     pub api_base: String,
+    /// Context window to request per model, sent as `num_ctx`
+    num_ctx: Arc<Mutex<HashMap<String, usize>>>,
 }
 
 impl OllamaClient {
@@ -53,6 +104,7 @@ impl OllamaClient {
         Self {
             client,
             api_base: api_base.to_string(),
+            num_ctx: Arc::default(),
         }
     }
 }
@@ -86,6 +138,18 @@ impl LlmClient for OllamaClient {
             request_body["tools"] = json!(tools);
         }
 
+        // Ollama otherwise truncates prompts to its default window
+        let num_ctx = self
+            .num_ctx
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(model)
+            .copied();
+        if let Some(num_ctx) = num_ctx {
+            info!("Context window: {} tokens", num_ctx);
+            request_body["options"] = json!({ "num_ctx": num_ctx });
+        }
+
         let response_result = self.client.post(&url).json(&request_body).send().await;
 
         info!("=== OLLAMA REQUEST END ===");
@@ -267,4 +331,21 @@ impl LlmClient for OllamaClient {
             }
         }
     }
+
+    async fn context_length(&self, model: &str, default_window: usize) -> Option<usize> {
+        match context_length(&self.client, &self.api_base, model, default_window).await {
+            Ok(length) => length,
+            Err(e) => {
+                warn!("Could not read the context length of {}: {}", model, e);
+                None
+            }
+        }
+    }
+
+    fn set_context_length(&self, model: &str, length: usize) {
+        self.num_ctx
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(model.to_string(), length);
+    }
 }
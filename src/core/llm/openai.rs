@@ -0,0 +1,370 @@
+use crate::{
+    core::llm::{MISSING_TOOL_RESULT, build_message, client::LlmClient, list_model_ids, v1_base},
+    types::{AppEvent, ChatMessage, Tool, ToolCall},
+};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, trace, warn};
+
+pub async fn list_models(
+    client: &Client,
+    api_base: &str,
+    api_key: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let url = format!("{}/models", v1_base(api_base));
+    let mut request = client.get(&url);
+    if let Some(key) = api_key.filter(|key| !key.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+    list_model_ids(request).await
+}
+
+/// Tool call being assembled from streamed `delta.tool_calls` fragments.
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: Option<String>,
+    name: String,
+    arguments: String,
+}
+
+impl PartialToolCall {
+    fn into_tool_call(self) -> ToolCall {
+        ToolCall::new(self.id, &self.name, parse_arguments(&self.arguments))
+    }
+}
+
+/// Merge a batch of streamed tool call deltas into the calls assembled so far.
+fn apply_tool_call_deltas(partials: &mut Vec<PartialToolCall>, deltas: &[Value]) {
+    for (position, delta) in deltas.iter().enumerate() {
+        let index = delta["index"]
+            .as_u64()
+            .map(|index| index as usize)
+            .unwrap_or(position);
+        if partials.len() <= index {
+            partials.resize_with(index + 1, PartialToolCall::default);
+        }
+
+        let partial = &mut partials[index];
+        if let Some(id) = delta["id"].as_str() {
+            partial.id = Some(id.to_string());
+        }
+        if let Some(name) = delta["function"]["name"].as_str() {
+            partial.name.push_str(name);
+        }
+        if let Some(arguments) = delta["function"]["arguments"].as_str() {
+            partial.arguments.push_str(arguments);
+        }
+    }
+}
+
+/// OpenAI sends tool arguments as a JSON-encoded string; decode it when possible.
+fn parse_arguments(raw: &str) -> Value {
+    if raw.trim().is_empty() {
+        return json!({});
+    }
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Encode tool arguments the way the Chat Completions API expects them (a JSON string).
+fn encode_arguments(arguments: &Value) -> String {
+    match arguments {
+        Value::String(raw) => raw.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Answer tool calls that never received a `tool` message.
+fn close_pending_tool_calls(messages: &mut Vec<Value>, pending: &mut VecDeque<String>) {
+    while let Some(id) = pending.pop_front() {
+        messages.push(json!({
+            "role": "tool",
+            "tool_call_id": id,
+            "content": MISSING_TOOL_RESULT,
+        }));
+    }
+}
+
+/// Client for any server implementing the OpenAI `/v1/chat/completions` API.
+#[derive(Debug, Clone)]
+pub struct OpenAiClient {
+    pub client: Client,
+    pub api_base: String,
+    pub api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    pub fn new(api_base: &str, api_key: Option<String>) -> Self {
+        let client = Client::builder().no_proxy().build().unwrap_or_else(|error| {
+            warn!(
+                "Failed to build reqwest client with no_proxy, falling back to default client: {}",
+                error
+            );
+            Client::new()
+        });
+
+        Self {
+            client,
+            api_base: api_base.to_string(),
+            api_key,
+        }
+    }
+
+    /// Convert our history into Chat Completions messages.
+    ///
+    /// Every assistant `tool_calls` entry must be answered by a `tool` message,
+    /// so calls left without a recorded result are closed with a placeholder.
+    /// Results that answer no pending call are sent as user text once the
+    /// pending calls are answered, since the API rejects them as `tool` messages.
+    fn to_openai_messages(history: &[ChatMessage]) -> Vec<Value> {
+        let mut messages = Vec::new();
+        let mut pending: VecDeque<String> = VecDeque::new();
+        let mut unmatched: Vec<Value> = Vec::new();
+
+        for (message_index, message) in history.iter().enumerate() {
+            if message.role == "tool" {
+                let tool_call_id = match &message.tool_call_id {
+                    Some(id) if pending.contains(id) => {
+                        pending.retain(|pending_id| pending_id != id);
+                        Some(id.clone())
+                    }
+                    Some(_) => None,
+                    None => pending.pop_front(),
+                };
+                match tool_call_id {
+                    Some(tool_call_id) => messages.push(json!({
+                        "role": "tool",
+                        "tool_call_id": tool_call_id,
+                        "content": message.content,
+                    })),
+                    None => {
+                        warn!("Sending a tool result without a matching tool call as text");
+                        unmatched.push(json!({
+                            "role": "user",
+                            "content": format!(
+                                "Result of {}: {}",
+                                message.tool_name.as_deref().unwrap_or("a tool call"),
+                                message.content
+                            ),
+                        }));
+                    }
+                }
+                if pending.is_empty() {
+                    messages.append(&mut unmatched);
+                }
+                continue;
+            }
+
+            close_pending_tool_calls(&mut messages, &mut pending);
+            messages.append(&mut unmatched);
+
+            let mut value = json!({
+                "role": message.role,
+                "content": message.content,
+            });
+
+            if let Some(tool_calls) = &message.tool_calls {
+                value["tool_calls"] = tool_calls
+                    .iter()
+                    .enumerate()
+                    .map(|(call_index, call)| {
+                        let id = call
+                            .id
+                            .clone()
+                            .unwrap_or_else(|| format!("call_{}_{}", message_index, call_index));
+                        pending.push_back(id.clone());
+                        json!({
+                            "id": id,
+                            "type": "function",
+                            "function": {
+                                "name": call.function.name,
+                                "arguments": encode_arguments(&call.function.arguments),
+                            }
+                        })
+                    })
+                    .collect();
+            }
+
+            messages.push(value);
+        }
+
+        close_pending_tool_calls(&mut messages, &mut pending);
+        messages.append(&mut unmatched);
+        messages
+    }
+
+    fn parse_tool_calls(message: &Value) -> Vec<ToolCall> {
+        let mut tool_calls = Vec::new();
+        if let Some(tool_call_array) = message["tool_calls"].as_array() {
+            let mut partials = Vec::new();
+            apply_tool_call_deltas(&mut partials, tool_call_array);
+            tool_calls.extend(partials.into_iter().map(PartialToolCall::into_tool_call));
+        }
+        tool_calls
+    }
+
+    async fn read_stream(
+        response: reqwest::Response,
+        tx: &mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<ChatMessage> {
+        let mut content = String::new();
+        let mut partials: Vec<PartialToolCall> = Vec::new();
+        let mut stream = response.bytes_stream();
+        // Buffer raw bytes so multi-byte characters split across chunks stay intact
+        let mut buffer: Vec<u8> = Vec::new();
+
+        'stream: while let Some(chunk) = stream.next().await {
+            let chunk_data = match chunk {
+                Ok(chunk_data) => chunk_data,
+                Err(e) => {
+                    error!("Error reading stream chunk: {}", e);
+                    break;
+                }
+            };
+            trace!("Received {} bytes from OpenAI stream", chunk_data.len());
+            buffer.extend_from_slice(&chunk_data);
+
+            while let Some(newline_pos) = buffer.iter().position(|byte| *byte == b'\n') {
+                let raw_line: Vec<u8> = buffer.drain(..=newline_pos).collect();
+                let line = String::from_utf8_lossy(&raw_line);
+                let line = line.trim();
+
+                // SSE comments, event names and blank separators carry no payload
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+
+                if data == "[DONE]" {
+                    info!("Streaming response completed");
+                    break 'stream;
+                }
+
+                debug!("Received streaming event: {}", data);
+
+                let parsed: Value = match serde_json::from_str(data) {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        error!("Error parsing SSE data: '{}', error: {}", data, e);
+                        continue;
+                    }
+                };
+
+                if let Some(error) = parsed.get("error") {
+                    return Err(anyhow::anyhow!("OpenAI stream returned error: {}", error));
+                }
+
+                let delta = &parsed["choices"][0]["delta"];
+
+                if let Some(c) = delta["content"].as_str()
+                    && !c.is_empty()
+                {
+                    if tx
+                        .send(AppEvent::AgentStreamChunk(c.to_string()))
+                        .await
+                        .is_err()
+                    {
+                        error!("Failed to send stream chunk to UI");
+                        break 'stream;
+                    }
+                    content.push_str(c);
+                }
+
+                if let Some(deltas) = delta["tool_calls"].as_array() {
+                    apply_tool_call_deltas(&mut partials, deltas);
+                }
+            }
+        }
+
+        if tx.send(AppEvent::AgentStreamEnd).await.is_err() {
+            error!("Failed to send stream end to UI");
+        }
+
+        let tool_calls = partials
+            .into_iter()
+            .map(PartialToolCall::into_tool_call)
+            .collect();
+        Ok(build_message(&content, tool_calls))
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    async fn chat(
+        &self,
+        model: &str,
+        history: &[ChatMessage],
+        tools: &[Tool],
+        stream: bool,
+        tx: mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<Option<ChatMessage>> {
+        info!("=== OPENAI REQUEST START ===");
+        info!(
+            "Sending request to OpenAI-compatible API at {}",
+            self.api_base
+        );
+        info!("Model: {}", model);
+        info!("History length: {} messages", history.len());
+        info!("Streaming: {}", stream);
+
+        let url = format!("{}/chat/completions", v1_base(&self.api_base));
+
+        let mut request_body = json!({
+            "model": model,
+            "messages": Self::to_openai_messages(history),
+            "stream": stream,
+        });
+
+        if !tools.is_empty() {
+            info!("Adding {} tools to request", tools.len());
+            request_body["tools"] = json!(tools);
+        }
+
+        let mut request = self.client.post(&url).json(&request_body);
+        if let Some(key) = self.api_key.as_deref().filter(|key| !key.is_empty()) {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            error!("Failed to send request to OpenAI-compatible API: {}", e);
+            e
+        })?;
+
+        info!("=== OPENAI REQUEST END ===");
+
+        let status = response.status();
+        info!("Received HTTP response with status: {}", status);
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            error!("OpenAI-compatible API returned {}: {}", status, body);
+            return Err(anyhow::anyhow!(
+                "OpenAI-compatible API returned {}: {}",
+                status,
+                body
+            ));
+        }
+
+        let message = if stream {
+            info!("Processing streaming response...");
+            Self::read_stream(response, &tx).await?
+        } else {
+            info!("Processing non-streaming response...");
+            let json: Value = response.json().await?;
+            debug!(
+                "Full JSON response: {}",
+                serde_json::to_string_pretty(&json)?
+            );
+
+            let message = &json["choices"][0]["message"];
+            let content = message["content"].as_str().unwrap_or("");
+            info!("Response content length: {} characters", content.len());
+            build_message(content, Self::parse_tool_calls(message))
+        };
+
+        info!("=== OPENAI RESPONSE END ===");
+        Ok(Some(message))
+    }
+}
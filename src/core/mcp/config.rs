@@ -1,10 +1,12 @@
 //! MCP configuration management for the OxideAgent system.
 //!
 //! This module handles parsing and managing MCP server configurations from
-//! various file formats including JSON, TOML, and YAML.
+//! various file formats including JSON, TOML, and YAML. Besides its own format
+//! it reads the `mcpServers` format used by other MCP clients.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
@@ -35,6 +37,66 @@ pub struct McpServerConfig {
     pub environment: Option<HashMap<String, String>>,
 }
 
+/// A server in the `mcpServers` format used by other MCP clients:
+/// `{"mcpServers": {"name": {"command": "npx", "args": [...], "env": {...}}}}`
+/// for local servers and `{"url": "...", "headers": {...}}` for remote ones
+#[derive(Debug, Clone, Deserialize)]
+struct McpServersEntry {
+    command: Option<String>,
+    #[serde(default)]
+    args: Option<Vec<String>>,
+    #[serde(default)]
+    env: Option<HashMap<String, String>>,
+    #[serde(default)]
+    cwd: Option<String>,
+    url: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    disabled: bool,
+    description: Option<String>,
+}
+
+impl McpServersEntry {
+    fn into_server_config(self, name: String) -> anyhow::Result<McpServerConfig> {
+        let server_type = match (self.command, self.url) {
+            (Some(command), _) => McpServerType::Command {
+                command,
+                args: self.args,
+                environment: self.env,
+                working_directory: self.cwd,
+            },
+            (None, Some(url)) => {
+                let authorization = self
+                    .headers
+                    .iter()
+                    .find(|(header, _)| header.eq_ignore_ascii_case("authorization"))
+                    .map(|(_, value)| value.as_str());
+                McpServerType::Remote {
+                    url,
+                    access_token: authorization
+                        .map(|value| value.strip_prefix("Bearer ").unwrap_or(value).to_string()),
+                    api_key: None,
+                }
+            }
+            (None, None) => {
+                return Err(anyhow::anyhow!(
+                    "MCP server '{}' has neither a command nor a url",
+                    name
+                ));
+            }
+        };
+
+        Ok(McpServerConfig {
+            name,
+            description: self.description,
+            server_type,
+            auto_start: Some(!self.disabled),
+            environment: None,
+        })
+    }
+}
+
 /// Different types of MCP servers
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
@@ -92,6 +154,18 @@ pub enum McpServerType {
     },
 }
 
+impl McpServerType {
+    /// Name of the server type as written in configuration files
+    pub fn kind(&self) -> &'static str {
+        match self {
+            McpServerType::Remote { .. } => "remote",
+            McpServerType::Docker { .. } => "docker",
+            McpServerType::Npm { .. } => "npm",
+            McpServerType::Command { .. } => "command",
+        }
+    }
+}
+
 #[allow(dead_code)] // Methods are used in tests and form part of the public API
 impl McpConfigFile {
     /// Load configuration from a file, automatically detecting the format based on extension
@@ -103,15 +177,38 @@ impl McpConfigFile {
             .and_then(|ext| ext.to_str())
             .unwrap_or("json");
 
-        match extension {
-            "json" => Ok(serde_json::from_str(&content)?),
-            "toml" => Ok(toml::from_str(&content)?),
-            "yaml" | "yml" => Ok(serde_yaml::from_str(&content)?),
-            _ => Err(anyhow::anyhow!(
-                "Unsupported configuration file format: {}",
-                extension
-            )),
-        }
+        let value: Value = match extension {
+            "json" => serde_json::from_str(&content)?,
+            "toml" => toml::from_str(&content)?,
+            "yaml" | "yml" => serde_yaml::from_str(&content)?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported configuration file format: {}",
+                    extension
+                ));
+            }
+        };
+        Self::from_value(value)
+    }
+
+    /// Parse configuration in either OxideAgent's format or the `mcpServers`
+    /// format used by other MCP clients
+    pub fn from_value(value: Value) -> anyhow::Result<Self> {
+        let Some(mcp_servers) = value.get("mcpServers") else {
+            return Ok(serde_json::from_value(value)?);
+        };
+
+        // Ordered by name, so servers start in a predictable order
+        let entries: BTreeMap<String, McpServersEntry> =
+            serde_json::from_value(mcp_servers.clone())?;
+        let servers = entries
+            .into_iter()
+            .map(|(name, entry)| entry.into_server_config(name))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            version: "1.0".to_string(),
+            servers,
+        })
     }
 
     /// Save configuration to a file, automatically detecting the format based on extension
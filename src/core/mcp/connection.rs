@@ -4,15 +4,30 @@
 //! between agents and MCP-compatible tools/services over stdio.
 
 use crate::core::mcp::config::McpServerConfig;
-use crate::core::tools::{Tool, ToolProfile};
+use crate::core::mcp::launcher::{McpLauncher, McpProcess};
+use crate::core::mcp::router::McpRequestRouter;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::process;
+use serde_json::{Value, json};
+use std::collections::HashMap;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStderr, ChildStdin, ChildStdout};
 use tokio::sync::mpsc;
-use tokio::time::{Duration, timeout};
-use tracing::{debug, error, info};
+use tokio::time::{Duration, Instant, timeout_at};
+use tracing::{debug, error, info, warn};
+
+/// MCP protocol version requested during the `initialize` handshake
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// How long to wait for the server to answer `initialize`; package runners
+/// such as `npx` may download the server first
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait for the response to an ordinary request
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a `tools/call` may run
+pub(crate) const TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(120);
 
 /// Truncate a description to the first 60 characters with an ellipsis if needed
 fn truncate_description(description: &str) -> String {
@@ -31,6 +46,187 @@ pub trait McpConnection: Send + Sync {
 
     /// Execute a tool on the MCP server
     async fn execute_tool(&mut self, tool_name: &str, args: &Value) -> Result<Value>;
+
+    /// Send a JSON-RPC request and return its result
+    async fn request(&mut self, method: &str, params: Option<Value>) -> Result<Value>;
+
+    /// Whether the server announced the capability (e.g. `resources`) during
+    /// the handshake. Assumed when the handshake result is unknown.
+    fn has_capability(&self, _capability: &str) -> bool {
+        true
+    }
+
+    /// Whether the server sent `notifications/tools/list_changed` since the
+    /// last call; the flag is cleared
+    fn take_tools_changed(&mut self) -> bool {
+        false
+    }
+
+    /// List the resources of the server using `resources/list`
+    async fn list_resources(&mut self) -> Result<Vec<McpResource>> {
+        let mut resources = Vec::new();
+        for page in self.list_all_pages("resources/list").await? {
+            let list: ResourcesListResult = serde_json::from_value(page)
+                .map_err(|e| anyhow::anyhow!("Failed to parse resources list: {}", e))?;
+            resources.extend(list.resources);
+        }
+        Ok(resources)
+    }
+
+    /// Read a resource using `resources/read`
+    async fn read_resource(&mut self, uri: &str) -> Result<Vec<McpResourceContents>> {
+        let result = self
+            .request("resources/read", Some(json!({ "uri": uri })))
+            .await?;
+        let read: ResourcesReadResult = serde_json::from_value(result)
+            .map_err(|e| anyhow::anyhow!("Failed to parse resource '{}': {}", uri, e))?;
+        Ok(read.contents)
+    }
+
+    /// List the prompt templates of the server using `prompts/list`
+    async fn list_prompts(&mut self) -> Result<Vec<McpPrompt>> {
+        let mut prompts = Vec::new();
+        for page in self.list_all_pages("prompts/list").await? {
+            let list: PromptsListResult = serde_json::from_value(page)
+                .map_err(|e| anyhow::anyhow!("Failed to parse prompts list: {}", e))?;
+            prompts.extend(list.prompts);
+        }
+        Ok(prompts)
+    }
+
+    /// Expand a prompt template with arguments using `prompts/get`
+    async fn get_prompt(
+        &mut self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<Vec<McpPromptMessage>> {
+        let result = self
+            .request(
+                "prompts/get",
+                Some(json!({ "name": name, "arguments": arguments })),
+            )
+            .await?;
+        let prompt: PromptsGetResult = serde_json::from_value(result)
+            .map_err(|e| anyhow::anyhow!("Failed to parse prompt '{}': {}", name, e))?;
+        Ok(prompt.messages)
+    }
+
+    /// Results of a paginated list method, following `nextCursor`
+    async fn list_all_pages(&mut self, method: &str) -> Result<Vec<Value>> {
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = cursor.as_ref().map(|cursor| json!({ "cursor": cursor }));
+            let page = self.request(method, params).await?;
+            cursor = page
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            pages.push(page);
+            if cursor.is_none() {
+                return Ok(pages);
+            }
+        }
+    }
+}
+
+/// A resource offered by an MCP server
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Contents of a resource; `text` for text resources, base64 `blob` otherwise
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResourceContents {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+impl McpResourceContents {
+    /// The contents as text, summarizing binary data
+    pub fn to_text(&self) -> String {
+        match (&self.text, &self.blob) {
+            (Some(text), _) => text.clone(),
+            (None, Some(blob)) => format!(
+                "[binary resource: {}, {} bytes base64]",
+                self.mime_type.as_deref().unwrap_or("unknown type"),
+                blob.len()
+            ),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// A prompt template offered by an MCP server
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct McpPrompt {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+/// An argument of a prompt template
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct McpPromptArgument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A message of an expanded prompt; `content` is a content item like those
+/// of tool results
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct McpPromptMessage {
+    pub role: String,
+    pub content: Value,
+}
+
+/// Join the messages of an expanded prompt into text for the input box
+pub fn prompt_messages_to_string(messages: &[McpPromptMessage]) -> String {
+    messages
+        .iter()
+        .map(|message| content_item_to_string(&message.content))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[derive(Deserialize)]
+struct ResourcesListResult {
+    #[serde(default)]
+    resources: Vec<McpResource>,
+}
+
+#[derive(Deserialize)]
+struct ResourcesReadResult {
+    contents: Vec<McpResourceContents>,
+}
+
+#[derive(Deserialize)]
+struct PromptsListResult {
+    #[serde(default)]
+    prompts: Vec<McpPrompt>,
+}
+
+#[derive(Deserialize)]
+struct PromptsGetResult {
+    messages: Vec<McpPromptMessage>,
 }
 
 /// MCP tool definition as specified in the MCP specification
@@ -87,6 +283,11 @@ struct ToolsListResult {
 }
 
 /// MCP connection that communicates over stdio using JSON-RPC 2.0
+///
+/// A connection opened with `new` owns the server process and kills it when
+/// dropped. A connection opened with `from_process` only borrows the pipes; the
+/// caller keeps the process, e.g. to supervise it. Requests the server sends
+/// while the client waits for a response are answered by the router.
 #[derive(Debug)]
 pub struct StdioMcpConnection {
     /// Sender for writing messages to the MCP server's stdin
@@ -97,63 +298,60 @@ pub struct StdioMcpConnection {
     request_id_counter: u32,
     /// Name of the server for logging purposes
     server_name: String,
+    /// The server process, when the connection owns it
+    process: Option<McpProcess>,
+    /// Process ID of the server
+    process_id: u32,
+    /// Result of the `initialize` handshake: protocol version, capabilities and server info
+    initialize_result: Value,
+    /// Set when the server announced that its tool list changed
+    tools_changed: bool,
+    /// Answers requests the server sends to the client
+    router: McpRequestRouter,
 }
 
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
 impl StdioMcpConnection {
-    /// Create a new stdio connection to an MCP server by launching it as a subprocess
+    /// Launch an MCP server as a subprocess and run the `initialize` handshake
     pub async fn new(config: &McpServerConfig) -> Result<Self> {
-        info!("Launching MCP server '{}' via stdio", config.name);
-
-        // Create the command based on the server configuration
-        let mut command = match &config.server_type {
-            crate::core::mcp::config::McpServerType::Command {
-                command,
-                args,
-                working_directory,
-                environment,
-            } => {
-                let mut cmd = tokio::process::Command::new(command);
-
-                if let Some(args_list) = args {
-                    cmd.args(args_list);
-                }
-
-                if let Some(dir) = working_directory {
-                    cmd.current_dir(dir);
-                }
-
-                if let Some(env_vars) = environment {
-                    for (key, value) in env_vars {
-                        cmd.env(key, value);
-                    }
-                }
+        Self::new_with_router(config, McpRequestRouter::new()).await
+    }
 
-                cmd
-            }
-            _ => {
-                return Err(anyhow::anyhow!(
-                    "Only command-based MCP servers are supported via stdio, got: {:?}",
-                    config.server_type
-                ));
-            }
-        };
+    /// Launch an MCP server like `new`, answering its requests with `router`
+    pub async fn new_with_router(
+        config: &McpServerConfig,
+        router: McpRequestRouter,
+    ) -> Result<Self> {
+        info!("Launching MCP server '{}' via stdio", config.name);
 
-        // Launch the process with stdio pipes
-        let mut child = command
-            .stdin(process::Stdio::piped())
-            .stdout(process::Stdio::piped())
-            .stderr(process::Stdio::piped())
-            .spawn()?;
+        let mut process = McpLauncher::launch(config).await?;
+        let mut connection =
+            Self::from_process_with_router(&config.name, &mut process, router).await?;
+        connection.process = Some(process);
+        Ok(connection)
+    }
 
-        let process_id = child.id();
-        info!(
-            "MCP server '{}' launched with PID: {:?}",
-            config.name, process_id
-        );
+    /// Connect to an already launched server over its stdio pipes and run the
+    /// `initialize` handshake. The pipes are taken from `process`.
+    pub async fn from_process(server_name: &str, process: &mut McpProcess) -> Result<Self> {
+        Self::from_process_with_router(server_name, process, McpRequestRouter::new()).await
+    }
 
-        // Get the stdio handles
-        let stdin = child.stdin.take().expect("Failed to get stdin handle");
-        let stdout = child.stdout.take().expect("Failed to get stdout handle");
+    /// Connect to a launched server like `from_process`, answering its
+    /// requests with `router`
+    pub async fn from_process_with_router(
+        server_name: &str,
+        process: &mut McpProcess,
+        router: McpRequestRouter,
+    ) -> Result<Self> {
+        let (stdin, stdout, stderr) = process.take_stdio().ok_or_else(|| {
+            anyhow::anyhow!(
+                "The stdio pipes of MCP server '{}' are not available",
+                server_name
+            )
+        })?;
+        let stdin = ChildStdin::from_std(stdin)?;
+        let stdout = ChildStdout::from_std(stdout)?;
 
         // Create channels for communication
         let (stdin_tx, mut stdin_rx) = mpsc::unbounded_channel::<String>();
@@ -181,12 +379,11 @@ impl StdioMcpConnection {
 
         // Spawn task to handle reading from stdout
         let mut reader = BufReader::new(stdout).lines();
-        let stdout_sender = stdout_tx.clone();
         tokio::spawn(async move {
             while let Ok(Some(line)) = reader.next_line().await {
                 if !line.trim().is_empty() {
                     debug!("Received from MCP server: {}", line);
-                    if stdout_sender.send(line.trim().to_string()).is_err() {
+                    if stdout_tx.send(line.trim().to_string()).is_err() {
                         // Receiver dropped
                         break;
                     }
@@ -194,21 +391,114 @@ impl StdioMcpConnection {
             }
         });
 
-        info!(
-            "Established stdio connection to MCP server '{}'",
-            config.name
-        );
+        // Drain stderr so a chatty server can't block on a full pipe
+        if let Some(stderr) = stderr {
+            let mut stderr_reader = BufReader::new(ChildStderr::from_std(stderr)?).lines();
+            let stderr_server_name = server_name.to_string();
+            tokio::spawn(async move {
+                while let Ok(Some(line)) = stderr_reader.next_line().await {
+                    debug!("MCP server '{}' stderr: {}", stderr_server_name, line);
+                }
+            });
+        }
 
-        Ok(Self {
+        let mut connection = Self {
             stdin_tx,
             stdout_rx,
             request_id_counter: 1,
-            server_name: config.name.clone(),
-        })
+            server_name: server_name.to_string(),
+            process: None,
+            process_id: process.pid,
+            initialize_result: Value::Null,
+            tools_changed: false,
+            router,
+        };
+        connection.initialize().await?;
+
+        info!(
+            "Established stdio connection to MCP server '{}' (PID: {})",
+            server_name, process.pid
+        );
+        Ok(connection)
+    }
+
+    /// Run the `initialize` request and send `notifications/initialized`
+    async fn initialize(&mut self) -> Result<()> {
+        let params = json!({
+            "protocolVersion": MCP_PROTOCOL_VERSION,
+            "capabilities": self.router.capabilities(),
+            "clientInfo": {
+                "name": "OxideAgent",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        });
+        let result = self
+            .send_request_with_timeout("initialize", Some(params), INITIALIZE_TIMEOUT)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "MCP server '{}' failed to initialize: {}",
+                    self.server_name,
+                    e
+                )
+            })?;
+
+        let server_version = result
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        if server_version != MCP_PROTOCOL_VERSION {
+            warn!(
+                "MCP server '{}' uses protocol version {} (requested {})",
+                self.server_name, server_version, MCP_PROTOCOL_VERSION
+            );
+        }
+        info!(
+            "MCP server '{}' initialized (protocol version {})",
+            self.server_name, server_version
+        );
+        self.initialize_result = result;
+
+        self.send_notification("notifications/initialized", None)
+    }
+
+    /// Result of the `initialize` handshake
+    pub fn initialize_result(&self) -> &Value {
+        &self.initialize_result
+    }
+
+    /// Process ID of the server, if the connection owns a running process
+    pub fn process_id(&self) -> Option<u32> {
+        self.process.as_ref().map(|_| self.process_id)
+    }
+
+    /// Send a JSON-RPC notification, which gets no response
+    pub fn send_notification(&self, method: &str, params: Option<Value>) -> Result<()> {
+        let mut notification = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+        });
+        if let Some(params) = params {
+            notification["params"] = params;
+        }
+        debug!("Sending JSON-RPC notification: {}", notification);
+        self.stdin_tx
+            .send(notification.to_string())
+            .map_err(|_| anyhow::anyhow!("Failed to send notification to MCP server"))
     }
 
     /// Send a JSON-RPC request to the MCP server and wait for a response
     pub async fn send_request(&mut self, method: &str, params: Option<Value>) -> Result<Value> {
+        self.send_request_with_timeout(method, params, REQUEST_TIMEOUT)
+            .await
+    }
+
+    async fn send_request_with_timeout(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+        wait: Duration,
+    ) -> Result<Value> {
         let request_id = self.request_id_counter;
         self.request_id_counter += 1;
 
@@ -227,47 +517,74 @@ impl StdioMcpConnection {
             return Err(anyhow::anyhow!("Failed to send request to MCP server"));
         }
 
-        // Wait for response with timeout
-        match timeout(Duration::from_secs(10), self.stdout_rx.recv()).await {
-            Ok(Some(response_str)) => {
-                debug!("Received JSON-RPC response: {}", response_str);
-
-                // Try to parse as success response first
-                if let Ok(success_response) =
-                    serde_json::from_str::<JsonRpcSuccessResponse>(&response_str)
-                {
-                    if success_response.id == request_id {
-                        Ok(success_response.result)
-                    } else {
-                        Err(anyhow::anyhow!("Response ID mismatch"))
-                    }
+        // Wait for the matching response, skipping notifications and late
+        // responses to requests that timed out
+        let mut deadline = Instant::now() + wait;
+        loop {
+            let response_str = match timeout_at(deadline, self.stdout_rx.recv()).await {
+                Ok(Some(response_str)) => response_str,
+                Ok(None) => return Err(anyhow::anyhow!("MCP server closed connection")),
+                Err(_) => {
+                    return Err(anyhow::anyhow!(
+                        "Timeout waiting for response from MCP server"
+                    ));
                 }
-                // Try to parse as error response
-                else if let Ok(error_response) =
-                    serde_json::from_str::<JsonRpcErrorResponse>(&response_str)
-                {
-                    if error_response.id == request_id {
-                        Err(anyhow::anyhow!(
-                            "MCP server error {}: {}",
-                            error_response.error.code,
-                            error_response.error.message
-                        ))
-                    } else {
-                        Err(anyhow::anyhow!("Response ID mismatch"))
-                    }
-                }
-                // Failed to parse as either type
-                else {
-                    Err(anyhow::anyhow!(
-                        "Failed to parse MCP server response: {}",
-                        response_str
-                    ))
+            };
+            debug!("Received JSON-RPC message: {}", response_str);
+
+            let Ok(message) = serde_json::from_str::<Value>(&response_str) else {
+                warn!(
+                    "Ignoring non JSON-RPC output from MCP server '{}': {}",
+                    self.server_name, response_str
+                );
+                continue;
+            };
+            if message.get("method").and_then(Value::as_str)
+                == Some("notifications/tools/list_changed")
+            {
+                info!("MCP server '{}' changed its tools", self.server_name);
+                self.tools_changed = true;
+                continue;
+            }
+            if message.get("method").is_some() && message.get("id").is_some() {
+                // The time spent on the server's request, e.g. waiting for the
+                // user, doesn't count against the pending one
+                let response = self.router.handle(&self.server_name, &message).await;
+                if self.stdin_tx.send(response.to_string()).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send response to MCP server"));
                 }
+                deadline = Instant::now() + wait;
+                continue;
+            }
+            if message.get("method").is_some()
+                || message.get("id").and_then(Value::as_u64) != Some(request_id.into())
+            {
+                debug!(
+                    "Ignoring message from MCP server '{}' while waiting for response {}",
+                    self.server_name, request_id
+                );
+                continue;
+            }
+
+            // Try to parse as success response first
+            if let Ok(success_response) =
+                serde_json::from_value::<JsonRpcSuccessResponse>(message.clone())
+            {
+                return Ok(success_response.result);
+            }
+            // Try to parse as error response
+            if let Ok(error_response) = serde_json::from_value::<JsonRpcErrorResponse>(message) {
+                return Err(anyhow::anyhow!(
+                    "MCP server error {}: {}",
+                    error_response.error.code,
+                    error_response.error.message
+                ));
             }
-            Ok(None) => Err(anyhow::anyhow!("MCP server closed connection")),
-            Err(_) => Err(anyhow::anyhow!(
-                "Timeout waiting for response from MCP server"
-            )),
+            // Failed to parse as either type
+            return Err(anyhow::anyhow!(
+                "Failed to parse MCP server response: {}",
+                response_str
+            ));
         }
     }
 
@@ -278,6 +595,7 @@ impl StdioMcpConnection {
         match self.send_request("tools/list", None).await {
             Ok(result) => match serde_json::from_value::<ToolsListResult>(result) {
                 Ok(tools_list) => {
+                    self.tools_changed = false;
                     info!(
                         "Successfully discovered {} tools from MCP server '{}'",
                         tools_list.tools.len(),
@@ -323,74 +641,79 @@ impl McpConnection for StdioMcpConnection {
     }
 
     async fn execute_tool(&mut self, tool_name: &str, args: &Value) -> Result<Value> {
-        let params = serde_json::json!({
+        let params = json!({
             "name": tool_name,
             "arguments": args
         });
 
-        // Use the existing send_request method
-        self.send_request("tools/call", Some(params)).await
+        self.send_request_with_timeout("tools/call", Some(params), TOOL_CALL_TIMEOUT)
+            .await
     }
-}
 
-// NOTE: The StdioMcpToolAdapter is kept for potential future use
-// but is not currently used since we now use McpToolAdapter from the manager module
-#[allow(dead_code)]
-/// Adapter that implements the Tool trait for MCP tools
-pub struct StdioMcpToolAdapter {
-    pub name: String,
-    pub description: String,
-    pub parameters: Value,
-    // NOTE: This is a placeholder implementation since the connection
-    // would need to be maintained outside of the adapter.
-    // In a complete implementation, this would store a reference or ID
-    // to allow the system to route tool calls to the correct connection.
-}
-
-#[allow(dead_code)]
-impl StdioMcpToolAdapter {
-    /// Create a new MCP tool adapter
-    pub fn new(name: String, description: String, parameters: Value) -> Self {
-        Self {
-            name,
-            description,
-            parameters,
-        }
-    }
-}
-
-#[async_trait::async_trait]
-impl Tool for StdioMcpToolAdapter {
-    fn name(&self) -> String {
-        self.name.clone()
+    async fn request(&mut self, method: &str, params: Option<Value>) -> Result<Value> {
+        self.send_request(method, params).await
     }
 
-    fn description(&self) -> String {
-        self.description.clone()
-    }
-
-    fn parameters(&self) -> Value {
-        self.parameters.clone()
+    fn has_capability(&self, capability: &str) -> bool {
+        match self.initialize_result.get("capabilities") {
+            Some(capabilities) => capabilities.get(capability).is_some(),
+            None => true,
+        }
     }
 
-    fn profile(&self) -> ToolProfile {
-        ToolProfile::Generic
+    fn take_tools_changed(&mut self) -> bool {
+        std::mem::take(&mut self.tools_changed)
     }
+}
 
-    async fn execute(&self, _args: &Value) -> anyhow::Result<String> {
-        // In a complete implementation, this would need to communicate with the actual MCP server
-        // This is a placeholder that indicates the functionality is not fully implemented
-        Err(anyhow::anyhow!(
-            "MCP tool execution is not fully implemented yet. Tool '{}' would be executed with provided args.",
-            self.name
-        ))
+/// Convert the result of a `tools/call` request into the tool output shown to
+/// the model.
+///
+/// Text content is passed through; images, audio and binary resources are
+/// summarized since the model can't read them from a tool result. A result
+/// flagged with `isError` becomes an error carrying the same text.
+pub fn tool_result_to_string(result: &Value) -> Result<String> {
+    let output = match result.get("content").and_then(Value::as_array) {
+        Some(content) if !content.is_empty() => content
+            .iter()
+            .map(content_item_to_string)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => match result.get("structuredContent") {
+            Some(structured) => serde_json::to_string_pretty(structured)?,
+            None => result.to_string(),
+        },
+    };
+
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return Err(anyhow::anyhow!("MCP tool reported an error: {}", output));
     }
+    Ok(output)
+}
 
-    fn clone_box(&self) -> Box<dyn Tool> {
-        Box::new(StdioMcpToolAdapter {
-            name: self.name.clone(),
-            description: self.description.clone(),
-            parameters: self.parameters.clone(),
-        })
+pub(crate) fn content_item_to_string(item: &Value) -> String {
+    let field = |name: &str| item.get(name).and_then(Value::as_str).unwrap_or_default();
+    match field("type") {
+        "text" => field("text").to_string(),
+        "image" | "audio" => format!(
+            "[{} content: {}, {} bytes base64]",
+            field("type"),
+            field("mimeType"),
+            field("data").len()
+        ),
+        "resource" => {
+            let resource = &item["resource"];
+            let uri = resource["uri"].as_str().unwrap_or_default();
+            match resource.get("text").and_then(Value::as_str) {
+                Some(text) => format!("[resource: {}]\n{}", uri, text),
+                None => format!(
+                    "[resource: {} ({}, binary)]",
+                    uri,
+                    resource["mimeType"].as_str().unwrap_or("unknown type")
+                ),
+            }
+        }
+        "resource_link" => format!("[resource link: {} ({})]", field("uri"), field("name")),
+        _ => item.to_string(),
     }
 }
@@ -1,16 +1,61 @@
 //! Implementation of the Model Context Protocol (MCP) for HTTP-based communication.
 //!
-//! This module implements the official MCP specification for communication
-//! between agents and MCP-compatible tools/services over HTTP.
+//! This module implements the Streamable HTTP transport of the MCP specification:
+//! every JSON-RPC message is POSTed to the server URL, and the server answers
+//! with a JSON body or a `text/event-stream` that may carry notifications and
+//! requests before the response. The session id the server hands out during
+//! `initialize` is sent with every later message, and a stream that drops
+//! before the response arrives is resumed with `Last-Event-ID`.
+//!
+//! Servers that reject the `initialize` POST are connected with the legacy
+//! HTTP+SSE transport instead: a GET stream announces the endpoint to POST
+//! messages to and carries the responses.
+//!
+//! A connection with an `OAuthClient` answers a 401 by getting a new access
+//! token and sending the request again. Requests the server sends, on any
+//! stream, are answered by the connection's `McpRequestRouter`.
 
 use crate::core::mcp::config::McpServerConfig;
-use crate::core::mcp::connection::{McpConnection, McpToolDefinition};
+use crate::core::mcp::connection::{
+    MCP_PROTOCOL_VERSION, McpConnection, McpToolDefinition, REQUEST_TIMEOUT, TOOL_CALL_TIMEOUT,
+};
+use crate::core::mcp::oauth::OAuthClient;
+use crate::core::mcp::router::McpRequestRouter;
+use crate::core::mcp::sse::{SseEvent, SseParser};
 use anyhow::Result;
-use reqwest::Client;
+use futures_util::StreamExt;
+use reqwest::{Client, Response, StatusCode, Url};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
+use std::fmt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, timeout, timeout_at};
 use tracing::{debug, error, info, warn};
 
+/// MCP protocol version requested over the Streamable HTTP transport
+pub const STREAMABLE_HTTP_PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Header carrying the session id assigned by the server
+const SESSION_ID_HEADER: &str = "Mcp-Session-Id";
+
+/// Header carrying the negotiated protocol version
+const PROTOCOL_VERSION_HEADER: &str = "MCP-Protocol-Version";
+
+/// How often a dropped response stream is resumed before giving up
+const MAX_RESUME_ATTEMPTS: u32 = 3;
+
+/// How the connection exchanges messages with the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpTransport {
+    /// Streamable HTTP: messages are POSTed to the server URL, which answers
+    /// with JSON or an SSE stream
+    Streamable,
+    /// Legacy HTTP+SSE: messages are POSTed to `endpoint` and answered on the
+    /// event stream opened with a GET
+    LegacySse { endpoint: String },
+}
+
 /// MCP connection that communicates over HTTP using JSON-RPC 2.0
 #[derive(Debug)]
 pub struct HttpMcpConnection {
@@ -24,10 +69,93 @@ pub struct HttpMcpConnection {
     pub api_key: Option<String>,
     /// Name of the server for logging purposes
     pub server_name: String,
+    /// Transport chosen during `initialize`; `None` until then
+    transport: Option<HttpTransport>,
+    /// Session id assigned by the server
+    session_id: Option<String>,
+    /// Protocol version the server agreed to
+    protocol_version: Option<String>,
+    /// Result of the `initialize` handshake
+    initialize_result: Value,
+    /// Counter for generating unique request IDs
+    request_id_counter: u64,
+    /// Set when the server announced that its tool list changed
+    tools_changed: bool,
+    /// Events of the stream opened with a GET, read in the background
+    events: Option<mpsc::UnboundedReceiver<SseEvent>>,
+    /// Task reading the GET stream
+    events_task: Option<JoinHandle<()>>,
+    /// Gets access tokens when the server asks for authorization
+    oauth: Option<Box<OAuthClient>>,
+    /// Answers requests the server sends to the client
+    router: McpRequestRouter,
+}
+
+/// The server no longer knows the session; a new one has to be initialized
+#[derive(Debug)]
+struct SessionExpired;
+
+impl fmt::Display for SessionExpired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MCP session expired")
+    }
+}
+
+impl std::error::Error for SessionExpired {}
+
+/// The server answered a message with an unsuccessful HTTP status
+#[derive(Debug)]
+struct HttpStatusError {
+    status: StatusCode,
+    body: String,
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "JSON-RPC request failed with status: {} (response body: {})",
+            self.status, self.body
+        )
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// The server wants an access token it accepts
+#[derive(Debug)]
+struct Unauthorized {
+    /// The `WWW-Authenticate` header of the response
+    challenge: Option<String>,
+    body: String,
+}
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MCP server requires authorization (status: {}, response body: {})",
+            StatusCode::UNAUTHORIZED,
+            self.body
+        )
+    }
+}
+
+impl std::error::Error for Unauthorized {}
+
+/// What a message received from the server meant for a pending request
+enum Received {
+    /// The response to the pending request
+    Response(Result<Value>),
+    /// A progress notification; the request is still being worked on
+    Progress,
+    /// Anything else
+    Other,
 }
 
 impl HttpMcpConnection {
-    /// Create a new HTTP connection to an MCP server
+    /// Create a new HTTP connection to an MCP server. The server is contacted
+    /// on the first request.
     pub fn new(
         config: &McpServerConfig,
         url: String,
@@ -40,521 +168,819 @@ impl HttpMcpConnection {
             access_token,
             api_key,
             server_name: config.name.clone(),
+            transport: None,
+            session_id: None,
+            protocol_version: None,
+            initialize_result: Value::Null,
+            request_id_counter: 1,
+            tools_changed: false,
+            events: None,
+            events_task: None,
+            oauth: None,
+            router: McpRequestRouter::new(),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl McpConnection for HttpMcpConnection {
-    /// Discover available tools from the MCP server using the tools/list method
-    async fn discover_tools(&mut self) -> Result<Vec<McpToolDefinition>> {
-        info!(
-            "Discovering tools from HTTP MCP server '{}'",
-            self.server_name
-        );
+    /// Answer requests of the server, such as sampling and elicitation, with
+    /// `router`
+    pub fn with_router(mut self, router: McpRequestRouter) -> Self {
+        self.router = router;
+        self
+    }
 
-        // First, attempt the MCP initialization handshake as per spec using JSON-RPC to the same URL
-        if let Err(init_err) = self.initialize_connection().await {
-            warn!(
-                "MCP initialization handshake failed for server '{}': {}. Proceeding with tools discovery anyway.",
-                self.server_name, init_err
-            );
+    /// Authorize with OAuth when the server answers 401, starting with the
+    /// cached access token
+    pub fn with_oauth(mut self, oauth: OAuthClient) -> Self {
+        if self.access_token.is_none() {
+            self.access_token = oauth.access_token().map(str::to_string);
         }
+        self.oauth = Some(Box::new(oauth));
+        self
+    }
 
-        // Use the base URL directly as provided by the configuration - don't append anything
-        let url = &self.base_url;
-
-        // JSON-RPC request for tools (sent to the same URL as initialization)
-        let request_body = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "tools/list"
-        });
+    /// Transport chosen during `initialize`, if the server was contacted
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn transport(&self) -> Option<&HttpTransport> {
+        self.transport.as_ref()
+    }
 
-        let mut request_builder = self.client.post(url).json(&request_body);
+    /// Session id assigned by the server, if any
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
 
-        // Add authentication headers if provided
-        if let Some(token) = &self.access_token {
-            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
-        }
+    /// Result of the `initialize` handshake
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn initialize_result(&self) -> &Value {
+        &self.initialize_result
+    }
 
-        if let Some(key) = &self.api_key {
-            request_builder = request_builder.header("X-API-Key", key);
+    /// Send a JSON-RPC request to the server and return its result. A request
+    /// the server refused for authorization is sent once more with a new
+    /// access token.
+    async fn send_request(&mut self, method: &str, params: Option<Value>) -> Result<Value> {
+        let result = self.send_request_once(method, params.clone()).await;
+        let Some(oauth) = self.oauth.as_mut() else {
+            return result;
+        };
+        match result {
+            Err(e) if e.downcast_ref::<Unauthorized>().is_some() => {
+                let challenge = e
+                    .downcast_ref::<Unauthorized>()
+                    .and_then(|unauthorized| unauthorized.challenge.clone());
+                info!("MCP server '{}' asked for authorization", self.server_name);
+                let access_token = oauth.authorize(challenge.as_deref()).await?;
+                self.access_token = Some(access_token);
+                self.send_request_once(method, params).await
+            }
+            result => result,
         }
+    }
 
-        // Add the required JSON-RPC content type and accept headers
-        request_builder = request_builder
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json, text/event-stream");
-
-        match request_builder.send().await {
-            Ok(response) => {
-                let status = response.status();
-                if status.is_success() || status.as_u16() == 200 {
-                    // Try to get the response body as text first for debugging
-                    let response_text = match response.text().await {
-                        Ok(text) => {
-                            debug!("Raw tools discovery response body: {}", text);
-                            text
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to read tools discovery response body as text: {}",
-                                e
-                            );
-                            return Err(anyhow::anyhow!(
-                                "Failed to read response body as text: {}",
-                                e
-                            ));
-                        }
-                    };
-
-                    // Handle SSE format response
-                    let json_data = if response_text.starts_with("event:") {
-                        // Extract JSON from SSE format
-                        if let Some(data_line) =
-                            response_text.lines().find(|line| line.starts_with("data:"))
-                        {
-                            data_line.strip_prefix("data:").unwrap_or("").trim()
-                        } else {
-                            &response_text
-                        }
-                    } else {
-                        &response_text
-                    };
-
-                    // Try to parse as JSON
-                    match serde_json::from_str::<serde_json::Value>(json_data) {
-                        Ok(json_response) => {
-                            debug!("Parsed JSON response: {:?}", json_response);
-
-                            // Check if there's an error in the response
-                            if let Some(error) = json_response.get("error") {
-                                error!(
-                                    "MCP server '{}' returned error during tools discovery: {:?} (raw response: {})",
-                                    self.server_name, error, response_text
-                                );
-                                return Err(anyhow::anyhow!(
-                                    "MCP server returned error: {:?}",
-                                    error
-                                ));
-                            }
-
-                            // Parse the tools list from the response
-                            if let Some(result) = json_response.get("result") {
-                                if let Ok(tools_list) =
-                                    serde_json::from_value::<ToolsListResult>(result.clone())
-                                {
-                                    info!(
-                                        "Successfully discovered {} tools from HTTP MCP server '{}'",
-                                        tools_list.tools.len(),
-                                        self.server_name
-                                    );
-
-                                    for tool in &tools_list.tools {
-                                        info!(
-                                            "  - Tool: {} - {}",
-                                            tool.name,
-                                            truncate_description(&tool.description)
-                                        );
-                                    }
-
-                                    Ok(tools_list.tools)
-                                } else {
-                                    error!(
-                                        "Failed to parse tools list response from HTTP MCP server '{}' (raw response: {})",
-                                        self.server_name, response_text
-                                    );
-                                    Err(anyhow::anyhow!("Failed to parse tools list response"))
-                                }
-                            } else {
-                                error!(
-                                    "No result field in response from HTTP MCP server '{}' (raw response: {})",
-                                    self.server_name, response_text
-                                );
-                                Err(anyhow::anyhow!("No result field in response"))
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to parse JSON response from HTTP MCP server '{}': {} (raw response: {})",
-                                self.server_name, e, response_text
-                            );
-                            Err(anyhow::anyhow!(
-                                "Failed to parse JSON response: {} (raw response: {})",
-                                e,
-                                response_text
-                            ))
-                        }
-                    }
-                } else {
-                    // Try to get more detailed error information
-                    let error_text = match response.text().await {
-                        Ok(text) => format!(" (response body: {})", text),
-                        Err(_) => String::new(),
-                    };
-                    error!(
-                        "JSON-RPC request to MCP server '{}' failed with status: {}{}",
-                        self.server_name, status, error_text
-                    );
-                    Err(anyhow::anyhow!(
-                        "JSON-RPC request failed with status: {}{}",
-                        status,
-                        error_text
-                    ))
+    /// Send a JSON-RPC request once. The connection is initialized first if
+    /// needed, and once more when the server forgot the session.
+    async fn send_request_once(&mut self, method: &str, params: Option<Value>) -> Result<Value> {
+        if self.transport.is_none() {
+            // Servers that skip the handshake still get their requests, but
+            // a server asking for authorization gets it first
+            if let Err(e) = self.initialize_connection().await {
+                if self.oauth.is_some() && e.downcast_ref::<Unauthorized>().is_some() {
+                    return Err(e);
                 }
-            }
-            Err(e) => {
-                error!(
-                    "Failed to send JSON-RPC request to MCP server '{}': {}",
+                warn!(
+                    "MCP initialization handshake failed for server '{}': {}. Proceeding without it.",
                     self.server_name, e
                 );
+                self.transport = Some(HttpTransport::Streamable);
+            }
+        }
 
-                Err(anyhow::anyhow!("JSON-RPC request failed: {}", e))
+        let wait = if method == "tools/call" {
+            TOOL_CALL_TIMEOUT
+        } else {
+            REQUEST_TIMEOUT
+        };
+        match self.exchange(method, params.clone(), wait).await {
+            Err(e) if e.downcast_ref::<SessionExpired>().is_some() => {
+                info!(
+                    "Session of MCP server '{}' expired, starting a new one",
+                    self.server_name
+                );
+                self.close_event_stream();
+                self.session_id = None;
+                self.initialize_connection().await?;
+                self.exchange(method, params, wait).await
             }
+            result => result,
         }
     }
 
-    /// Execute a tool on the MCP server
-    async fn execute_tool(&mut self, tool_name: &str, args: &Value) -> Result<Value> {
-        info!(
-            "Executing tool '{}' on HTTP MCP server '{}'",
-            tool_name, self.server_name
+    /// Send one request over the current transport and wait for its response
+    async fn exchange(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+        wait: Duration,
+    ) -> Result<Value> {
+        let request_id = self.request_id_counter;
+        self.request_id_counter += 1;
+        debug!(
+            "Sending '{}' request {} to HTTP MCP server '{}'",
+            method, request_id, self.server_name
         );
 
-        // Use the base URL directly as provided by the configuration - don't append anything
-        let url = &self.base_url;
-        let request_body = serde_json::json!({
+        let mut request = json!({
             "jsonrpc": "2.0",
-            "id": 1,
-            "method": "tools/call",
-            "params": {
-                "name": tool_name,
-                "arguments": args
+            "id": request_id,
+            "method": method
+        });
+        if let Some(mut params) = params {
+            // Ask for progress notifications on long-running tool calls
+            if method == "tools/call" {
+                params["_meta"] = json!({ "progressToken": request_id });
+            }
+            request["params"] = params;
+        }
+
+        match self.transport.clone() {
+            Some(HttpTransport::LegacySse { endpoint }) => {
+                let response = self.post(&endpoint, &request).await?;
+                let response = self.check_status(response).await?;
+                // Responses arrive on the event stream; a few servers answer inline
+                if is_json(&response) {
+                    let body = response.text().await?;
+                    if !body.trim().is_empty() {
+                        return self.read_json_body(&body, request_id).await;
+                    }
+                }
+                self.wait_for_stream_response(request_id, wait).await
             }
+            _ => {
+                self.drain_events().await;
+                let response = self.post(&self.base_url.clone(), &request).await?;
+                let response = self.check_status(response).await?;
+                if is_event_stream(&response) {
+                    self.read_event_stream(response, request_id, wait).await
+                } else {
+                    let body = response.text().await.map_err(|e| {
+                        anyhow::anyhow!("Failed to read response body as text: {}", e)
+                    })?;
+                    self.read_json_body(&body, request_id).await
+                }
+            }
+        }
+    }
+
+    /// Send a JSON-RPC notification, which gets no response
+    async fn send_notification(&mut self, method: &str, params: Option<Value>) -> Result<()> {
+        let mut notification = json!({
+            "jsonrpc": "2.0",
+            "method": method
         });
+        if let Some(params) = params {
+            notification["params"] = params;
+        }
+        let url = self.message_url();
+        let response = self.post(&url, &notification).await?;
+        self.check_status(response).await?;
+        Ok(())
+    }
 
-        let mut request_builder = self.client.post(url).json(&request_body);
+    /// URL messages are POSTed to
+    fn message_url(&self) -> String {
+        match &self.transport {
+            Some(HttpTransport::LegacySse { endpoint }) => endpoint.clone(),
+            _ => self.base_url.clone(),
+        }
+    }
 
+    /// POST a JSON-RPC message with the authentication and session headers
+    async fn post(&self, url: &str, message: &Value) -> Result<Response> {
+        let request_builder = self
+            .with_headers(self.client.post(url))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json, text/event-stream")
+            .json(message);
+        request_builder
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("JSON-RPC request failed: {}", e))
+    }
+
+    /// Open an event stream with a GET, resuming after `last_event_id`
+    async fn open_event_stream(
+        &mut self,
+        url: &str,
+        last_event_id: Option<&str>,
+    ) -> Result<Response> {
+        let mut request_builder = self
+            .with_headers(self.client.get(url))
+            .header("Accept", "text/event-stream");
+        if let Some(last_event_id) = last_event_id {
+            request_builder = request_builder.header("Last-Event-ID", last_event_id);
+        }
+        let response = timeout(REQUEST_TIMEOUT, request_builder.send())
+            .await
+            .map_err(|_| anyhow::anyhow!("Timeout opening the event stream"))?
+            .map_err(|e| anyhow::anyhow!("Failed to open the event stream: {}", e))?;
+        let response = self.check_status(response).await?;
+        if !is_event_stream(&response) {
+            return Err(anyhow::anyhow!(
+                "The server did not answer with an event stream"
+            ));
+        }
+        Ok(response)
+    }
+
+    fn with_headers(
+        &self,
+        mut request_builder: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
         // Add authentication headers if provided
         if let Some(token) = &self.access_token {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
-
         if let Some(key) = &self.api_key {
             request_builder = request_builder.header("X-API-Key", key);
         }
+        if let Some(session_id) = &self.session_id {
+            request_builder = request_builder.header(SESSION_ID_HEADER, session_id);
+        }
+        if let Some(version) = &self.protocol_version {
+            request_builder = request_builder.header(PROTOCOL_VERSION_HEADER, version);
+        }
+        request_builder
+    }
 
-        // Add the required JSON-RPC content type and accept headers
-        request_builder = request_builder
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json, text/event-stream");
-
-        match request_builder.send().await {
-            Ok(response) => {
-                let status = response.status();
-                if status.is_success() {
-                    // Try to get the response body as text first for debugging
-                    let response_text = match response.text().await {
-                        Ok(text) => {
-                            debug!("Raw tool execution response body: {}", text);
-                            text
-                        }
-                        Err(e) => {
-                            error!("Failed to read tool execution response body as text: {}", e);
-                            return Err(anyhow::anyhow!(
-                                "Failed to read response body as text: {}",
-                                e
-                            ));
-                        }
-                    };
-
-                    // Handle SSE format response
-                    let json_data = if response_text.starts_with("event:") {
-                        // Extract JSON from SSE format
-                        if let Some(data_line) =
-                            response_text.lines().find(|line| line.starts_with("data:"))
-                        {
-                            data_line.strip_prefix("data:").unwrap_or("").trim()
-                        } else {
-                            &response_text
-                        }
-                    } else {
-                        &response_text
-                    };
-
-                    // Try to parse as JSON
-                    match serde_json::from_str::<serde_json::Value>(json_data) {
-                        Ok(json_response) => {
-                            debug!("Received HTTP response: {:?}", json_response);
-
-                            // Check if there's an error in the response
-                            if let Some(error) = json_response.get("error") {
-                                error!(
-                                    "MCP server '{}' returned error during tool execution '{}': {:?} (raw response: {})",
-                                    self.server_name, tool_name, error, response_text
-                                );
-                                return Err(anyhow::anyhow!(
-                                    "MCP server returned error: {:?}",
-                                    error
-                                ));
-                            }
-
-                            // Return the result from the response
-                            if let Some(result) = json_response.get("result") {
-                                info!(
-                                    "Successfully executed tool '{}' on HTTP MCP server '{}'",
-                                    tool_name, self.server_name
-                                );
-                                Ok(result.clone())
-                            } else {
-                                error!(
-                                    "No result field in response from HTTP MCP server '{}' for tool '{}' (raw response: {})",
-                                    self.server_name, tool_name, response_text
-                                );
-                                Err(anyhow::anyhow!("No result field in response"))
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to parse JSON response from HTTP MCP server '{}' for tool '{}': {} (raw response: {})",
-                                self.server_name, tool_name, e, response_text
-                            );
-                            Err(anyhow::anyhow!(
-                                "Failed to parse JSON response: {} (raw response: {})",
-                                e,
-                                response_text
-                            ))
-                        }
+    /// Turn unsuccessful statuses into errors and remember the session id
+    async fn check_status(&mut self, response: Response) -> Result<Response> {
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            let challenge = response
+                .headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let body = response.text().await.unwrap_or_default();
+            return Err(Unauthorized { challenge, body }.into());
+        }
+        if status == StatusCode::NOT_FOUND && self.session_id.is_some() {
+            return Err(SessionExpired.into());
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(HttpStatusError { status, body }.into());
+        }
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            && self.session_id.as_deref() != Some(session_id)
+        {
+            debug!(
+                "MCP server '{}' assigned session id {}",
+                self.server_name, session_id
+            );
+            self.session_id = Some(session_id.to_string());
+        }
+        Ok(response)
+    }
+
+    /// Find the response to `request_id` in a JSON body holding one message
+    /// or a batch
+    async fn read_json_body(&mut self, body: &str, request_id: u64) -> Result<Value> {
+        let message: Value = serde_json::from_str(body).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to parse JSON response: {} (raw response: {})",
+                e,
+                body
+            )
+        })?;
+        if let Received::Response(result) = self.receive(message, request_id).await {
+            return result;
+        }
+        Err(anyhow::anyhow!(
+            "MCP server '{}' did not answer request {} (raw response: {})",
+            self.server_name,
+            request_id,
+            body
+        ))
+    }
+
+    /// Read an SSE response stream until the response to `request_id`
+    /// arrives, resuming the stream when it drops early
+    async fn read_event_stream(
+        &mut self,
+        mut response: Response,
+        request_id: u64,
+        wait: Duration,
+    ) -> Result<Value> {
+        let mut deadline = Instant::now() + wait;
+        let mut last_event_id: Option<String> = None;
+        let mut resume_attempts = 0;
+
+        loop {
+            let mut parser = SseParser::new();
+            let mut stream = response.bytes_stream();
+            let mut events = Vec::new();
+            loop {
+                let chunk = match timeout_at(deadline, stream.next()).await {
+                    Err(_) => {
+                        return Err(anyhow::anyhow!(
+                            "Timeout waiting for response from MCP server"
+                        ));
                     }
-                } else {
-                    // Try to get more detailed error information
-                    let error_text = match response.text().await {
-                        Ok(text) => format!(" (response body: {})", text),
-                        Err(_) => String::new(),
-                    };
-                    error!(
-                        "JSON-RPC request to execute tool '{}' on MCP server '{}' failed with status: {}{}",
-                        tool_name, self.server_name, status, error_text
-                    );
-                    Err(anyhow::anyhow!(
-                        "JSON-RPC request failed with status: {}{}",
-                        status,
-                        error_text
-                    ))
+                    Ok(None) => break,
+                    Ok(Some(Err(e))) => {
+                        warn!(
+                            "Event stream of MCP server '{}' failed: {}",
+                            self.server_name, e
+                        );
+                        break;
+                    }
+                    Ok(Some(Ok(chunk))) => chunk,
+                };
+                events.extend(parser.feed(&chunk));
+                for event in events.drain(..) {
+                    if event.id.is_some() {
+                        last_event_id = event.id.clone();
+                    }
+                    match self.receive_event(event, request_id).await {
+                        Received::Response(result) => return result,
+                        Received::Progress => deadline = Instant::now() + wait,
+                        Received::Other => {}
+                    }
+                }
+            }
+            if let Some(event) = parser.finish()
+                && let Received::Response(result) = self.receive_event(event, request_id).await
+            {
+                return result;
+            }
+
+            // The stream closed before the response arrived
+            let Some(event_id) = last_event_id.as_deref() else {
+                return Err(anyhow::anyhow!(
+                    "MCP server '{}' closed the stream before answering request {}",
+                    self.server_name,
+                    request_id
+                ));
+            };
+            resume_attempts += 1;
+            if resume_attempts > MAX_RESUME_ATTEMPTS {
+                return Err(anyhow::anyhow!(
+                    "MCP server '{}' dropped the stream of request {} {} times",
+                    self.server_name,
+                    request_id,
+                    MAX_RESUME_ATTEMPTS
+                ));
+            }
+            info!(
+                "Resuming the stream of MCP server '{}' after event {}",
+                self.server_name, event_id
+            );
+            response = self
+                .open_event_stream(&self.base_url.clone(), Some(event_id))
+                .await?;
+        }
+    }
+
+    /// Wait for the response to `request_id` on the GET event stream
+    async fn wait_for_stream_response(&mut self, request_id: u64, wait: Duration) -> Result<Value> {
+        let mut deadline = Instant::now() + wait;
+        loop {
+            let events = self
+                .events
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("No event stream to MCP server"))?;
+            let event = match timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => {
+                    return Err(anyhow::anyhow!(
+                        "The event stream of MCP server '{}' closed",
+                        self.server_name
+                    ));
                 }
+                Err(_) => {
+                    return Err(anyhow::anyhow!(
+                        "Timeout waiting for response from MCP server"
+                    ));
+                }
+            };
+            match self.receive_event(event, request_id).await {
+                Received::Response(result) => return result,
+                Received::Progress => deadline = Instant::now() + wait,
+                Received::Other => {}
             }
+        }
+    }
+
+    /// Handle notifications and requests the server sent on the GET stream
+    /// since the last request
+    async fn drain_events(&mut self) {
+        while let Some(event) = self
+            .events
+            .as_mut()
+            .and_then(|events| events.try_recv().ok())
+        {
+            self.receive_event(event, 0).await;
+        }
+    }
+
+    async fn receive_event(&mut self, event: SseEvent, request_id: u64) -> Received {
+        if event.event_type() != "message" || event.data.trim().is_empty() {
+            debug!(
+                "Ignoring '{}' event from MCP server '{}'",
+                event.event_type(),
+                self.server_name
+            );
+            return Received::Other;
+        }
+        match serde_json::from_str::<Value>(&event.data) {
+            Ok(message) => self.receive(message, request_id).await,
             Err(e) => {
-                error!(
-                    "Failed to send JSON-RPC request to execute tool '{}' on MCP server '{}': {}",
-                    tool_name, self.server_name, e
+                warn!(
+                    "Ignoring non JSON-RPC event from MCP server '{}': {} ({})",
+                    self.server_name, event.data, e
                 );
-                Err(anyhow::anyhow!("JSON-RPC request failed: {}", e))
+                Received::Other
             }
         }
     }
-}
 
-impl HttpMcpConnection {
-    /// Initialize the MCP connection following the MCP specification using JSON-RPC
+    /// Handle one JSON-RPC message, or a batch of them, from the server
+    async fn receive(&mut self, message: Value, request_id: u64) -> Received {
+        if let Value::Array(messages) = message {
+            let mut received = Received::Other;
+            for message in messages {
+                match Box::pin(self.receive(message, request_id)).await {
+                    Received::Other => {}
+                    other => received = other,
+                }
+            }
+            return received;
+        }
+
+        if let Some(method) = message.get("method").and_then(Value::as_str) {
+            if message.get("id").is_some() {
+                self.answer_server_request(&message).await;
+                // Time spent answering, e.g. waiting for the user, doesn't
+                // count against the pending request
+                return Received::Progress;
+            }
+            return self.handle_notification(method, message.get("params"));
+        }
+
+        if message.get("id").and_then(Value::as_u64) != Some(request_id) {
+            debug!(
+                "Ignoring message from MCP server '{}' while waiting for response {}",
+                self.server_name, request_id
+            );
+            return Received::Other;
+        }
+        if let Some(error) = message.get("error") {
+            error!(
+                "MCP server '{}' returned error: {}",
+                self.server_name, error
+            );
+            return Received::Response(Err(anyhow::anyhow!(
+                "MCP server returned error: {}",
+                error
+            )));
+        }
+        Received::Response(
+            message
+                .get("result")
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No result field in response")),
+        )
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Option<&Value>) -> Received {
+        let params = params.cloned().unwrap_or(Value::Null);
+        match method {
+            "notifications/progress" => {
+                let total = params
+                    .get("total")
+                    .map(|total| format!("/{}", total))
+                    .unwrap_or_default();
+                let message = params
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(|message| format!(": {}", message))
+                    .unwrap_or_default();
+                let progress = params.get("progress").cloned().unwrap_or_default();
+                info!(
+                    "MCP server '{}' progress {}{}{}",
+                    self.server_name, progress, total, message
+                );
+                Received::Progress
+            }
+            "notifications/tools/list_changed" => {
+                info!("MCP server '{}' changed its tools", self.server_name);
+                self.tools_changed = true;
+                Received::Other
+            }
+            _ => {
+                debug!(
+                    "Notification '{}' from MCP server '{}': {}",
+                    method, self.server_name, params
+                );
+                Received::Other
+            }
+        }
+    }
+
+    /// Answer a request the server sent to the client
+    async fn answer_server_request(&mut self, request: &Value) {
+        let method = request["method"].as_str().unwrap_or_default();
+        let response = self.router.handle(&self.server_name, request).await;
+        let url = self.message_url();
+        let sent = match self.post(&url, &response).await {
+            Ok(response) => self.check_status(response).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            warn!(
+                "Failed to answer '{}' request of MCP server '{}': {}",
+                method, self.server_name, e
+            );
+        }
+    }
+
+    /// Initialize the MCP connection over Streamable HTTP, falling back to
+    /// the legacy HTTP+SSE transport when the server rejects the POST
     async fn initialize_connection(&mut self) -> Result<()> {
         info!(
             "Initializing MCP connection for server '{}'",
             self.server_name
         );
 
-        // Use the same base URL for initialization as per MCP spec
-        // All MCP communication should happen on the same endpoint
-        let url = &self.base_url;
+        self.transport = Some(HttpTransport::Streamable);
+        self.protocol_version = None;
+        let result = match self
+            .exchange(
+                "initialize",
+                Some(initialize_params(
+                    STREAMABLE_HTTP_PROTOCOL_VERSION,
+                    self.router.capabilities(),
+                )),
+                REQUEST_TIMEOUT,
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(e)
+                if e.downcast_ref::<HttpStatusError>()
+                    .is_some_and(|error| error.status.is_client_error()) =>
+            {
+                info!(
+                    "MCP server '{}' rejected the Streamable HTTP initialize ({}), trying the HTTP+SSE transport",
+                    self.server_name, e
+                );
+                return self.initialize_legacy_sse().await;
+            }
+            Err(e) => {
+                self.transport = None;
+                return Err(e);
+            }
+        };
+        self.finish_initialize(result).await;
+
+        // The server may offer a stream for notifications outside of requests
+        match self.open_event_stream(&self.base_url.clone(), None).await {
+            Ok(response) => self.listen(response),
+            Err(e) => debug!(
+                "MCP server '{}' offers no event stream: {}",
+                self.server_name, e
+            ),
+        }
+        Ok(())
+    }
 
-        // Prepare the initialize request body according to MCP spec
-        // This is a JSON-RPC request to the same URL
-        let init_request_body = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "initialize",
-            "id": 1,
-            "params": {
-                "protocolVersion": "2024-11-05", // Use a supported protocol version
-                "clientInfo": {
-                    "name": "OxideAgent",
-                    "version": env!("CARGO_PKG_VERSION")
-                },
-                "capabilities": {
-                    "tools": {
-                        "listChanged": true
-                    },
-                    "resources": {},
-                    "prompts": {},
-                    "logging": {}
+    /// Connect with the legacy HTTP+SSE transport and initialize the session
+    async fn initialize_legacy_sse(&mut self) -> Result<()> {
+        self.transport = None;
+        let response = self.open_event_stream(&self.base_url.clone(), None).await?;
+        self.listen(response);
+
+        // The first event names the endpoint to POST messages to
+        let events = self
+            .events
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("No event stream to MCP server"))?;
+        let endpoint = timeout(REQUEST_TIMEOUT, async {
+            while let Some(event) = events.recv().await {
+                if event.event_type() == "endpoint" {
+                    return Some(event.data);
                 }
             }
-        });
-
-        let mut init_request_builder = self.client.post(url).json(&init_request_body);
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| anyhow::anyhow!("The event stream did not announce an endpoint"))?;
+        let endpoint = Url::parse(&self.base_url)?
+            .join(endpoint.trim())?
+            .to_string();
+        info!(
+            "Using HTTP+SSE transport for MCP server '{}' with endpoint {}",
+            self.server_name, endpoint
+        );
+        self.transport = Some(HttpTransport::LegacySse { endpoint });
+
+        let result = self
+            .exchange(
+                "initialize",
+                Some(initialize_params(
+                    MCP_PROTOCOL_VERSION,
+                    self.router.capabilities(),
+                )),
+                REQUEST_TIMEOUT,
+            )
+            .await?;
+        self.finish_initialize(result).await;
+        Ok(())
+    }
 
-        // Add authentication headers if provided
-        if let Some(token) = &self.access_token {
-            init_request_builder =
-                init_request_builder.header("Authorization", format!("Bearer {}", token));
-        }
+    /// Store the handshake result and send `notifications/initialized`
+    async fn finish_initialize(&mut self, result: Value) {
+        let version = result
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        info!(
+            "MCP server '{}' initialized (protocol version {})",
+            self.server_name,
+            version.as_deref().unwrap_or("unknown")
+        );
+        self.protocol_version = version;
+        self.initialize_result = result;
 
-        if let Some(key) = &self.api_key {
-            init_request_builder = init_request_builder.header("X-API-Key", key);
+        if let Err(e) = self
+            .send_notification("notifications/initialized", None)
+            .await
+        {
+            warn!(
+                "Failed to send initialized notification to MCP server '{}': {}",
+                self.server_name, e
+            );
         }
+    }
 
-        // Add content type and required accept headers
-        init_request_builder = init_request_builder
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json, text/event-stream");
-
-        match init_request_builder.send().await {
-            Ok(response) => {
-                let status = response.status();
-                if status.is_success() {
-                    // Try to get the response body as text first for debugging
-                    let response_text = match response.text().await {
-                        Ok(text) => {
-                            debug!("Raw initialize response body: {}", text);
-                            text
-                        }
-                        Err(e) => {
-                            warn!("Failed to read initialize response body as text: {}", e);
-                            return Ok(()); // Continue anyway
-                        }
-                    };
-
-                    // Handle SSE format response
-                    let json_data = if response_text.starts_with("event:") {
-                        // Extract JSON from SSE format
-                        if let Some(data_line) =
-                            response_text.lines().find(|line| line.starts_with("data:"))
-                        {
-                            data_line.strip_prefix("data:").unwrap_or("").trim()
-                        } else {
-                            &response_text
-                        }
-                    } else {
-                        &response_text
-                    };
-
-                    // Try to parse as JSON
-                    match serde_json::from_str::<serde_json::Value>(json_data) {
-                        Ok(init_response) => {
-                            // Check if there's an error in the response
-                            if let Some(error) = init_response.get("error") {
-                                warn!(
-                                    "MCP server '{}' returned error during initialization: {:?}",
-                                    self.server_name, error
-                                );
-                                // Don't treat this as fatal error, just warn the user
-                                Ok(())
-                            } else {
-                                info!("MCP server '{}' initialized successfully", self.server_name);
-                                debug!("Initialize response: {:?}", init_response);
-
-                                // After successful initialization, send the initialized notification
-                                if let Err(notif_err) = self.send_initialized_notification().await {
-                                    warn!("Failed to send initialized notification: {}", notif_err);
-                                    // Don't treat this as fatal, just continue
-                                }
-
-                                Ok(())
-                            }
-                        }
-                        Err(e) => {
-                            warn!(
-                                "Failed to parse initialize response from MCP server '{}' as JSON: {} (raw response: {})",
-                                self.server_name, e, response_text
-                            );
-                            // Don't treat this as fatal error, just warn the user
-                            Ok(())
-                        }
+    /// Read the events of a GET stream in the background
+    fn listen(&mut self, response: Response) {
+        self.close_event_stream();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let server_name = self.server_name.clone();
+        self.events = Some(events_rx);
+        self.events_task = Some(tokio::spawn(async move {
+            let mut parser = SseParser::new();
+            let mut stream = response.bytes_stream();
+            while let Some(chunk) = stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        warn!("Event stream of MCP server '{}' failed: {}", server_name, e);
+                        return;
+                    }
+                };
+                for event in parser.feed(&chunk) {
+                    if events_tx.send(event).is_err() {
+                        return;
                     }
-                } else {
-                    // Try to get more detailed error information
-                    let error_text = match response.text().await {
-                        Ok(text) => format!(" (response body: {})", text),
-                        Err(_) => String::new(),
-                    };
-                    warn!(
-                        "Initialize request to MCP server '{}' failed with status: {}{}",
-                        self.server_name, status, error_text
-                    );
-                    Ok(()) // Don't treat this as fatal error to maintain compatibility
                 }
             }
-            Err(e) => {
-                warn!(
-                    "Failed to send initialize request to MCP server '{}': {}",
-                    self.server_name, e
-                );
-                Ok(()) // Don't treat this as fatal error to maintain compatibility
+            if let Some(event) = parser.finish() {
+                let _ = events_tx.send(event);
             }
+            debug!("Event stream of MCP server '{}' ended", server_name);
+        }));
+    }
+
+    fn close_event_stream(&mut self) {
+        if let Some(task) = self.events_task.take() {
+            task.abort();
         }
+        self.events = None;
     }
+}
 
-    /// Send the initialized notification after successful initialization
-    async fn send_initialized_notification(&self) -> Result<()> {
+#[async_trait::async_trait]
+impl McpConnection for HttpMcpConnection {
+    /// Discover available tools from the MCP server using the tools/list method
+    async fn discover_tools(&mut self) -> Result<Vec<McpToolDefinition>> {
         info!(
-            "Sending initialized notification for server '{}'",
+            "Discovering tools from HTTP MCP server '{}'",
             self.server_name
         );
 
-        // Use the same base URL as specified in the MCP spec
-        let url = &self.base_url;
+        let mut tools = Vec::new();
+        for page in self.list_all_pages("tools/list").await? {
+            let tools_list = serde_json::from_value::<ToolsListResult>(page).map_err(|e| {
+                error!(
+                    "Failed to parse tools list response from HTTP MCP server '{}': {}",
+                    self.server_name, e
+                );
+                anyhow::anyhow!("Failed to parse tools list response: {}", e)
+            })?;
+            tools.extend(tools_list.tools);
+        }
+        self.tools_changed = false;
 
-        // Prepare the initialized notification body as per MCP spec
-        // This is a JSON-RPC notification (no id field)
-        let notification_body = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "notifications/initialized"
+        info!(
+            "Successfully discovered {} tools from HTTP MCP server '{}'",
+            tools.len(),
+            self.server_name
+        );
+        for tool in &tools {
+            info!(
+                "  - Tool: {} - {}",
+                tool.name,
+                truncate_description(&tool.description)
+            );
+        }
+        Ok(tools)
+    }
+
+    /// Execute a tool on the MCP server
+    async fn execute_tool(&mut self, tool_name: &str, args: &Value) -> Result<Value> {
+        info!(
+            "Executing tool '{}' on HTTP MCP server '{}'",
+            tool_name, self.server_name
+        );
+        let params = json!({
+            "name": tool_name,
+            "arguments": args
         });
+        self.send_request("tools/call", Some(params)).await
+    }
 
-        let mut notification_builder = self.client.post(url).json(&notification_body);
+    async fn request(&mut self, method: &str, params: Option<Value>) -> Result<Value> {
+        self.send_request(method, params).await
+    }
 
-        // Add authentication headers if provided
-        if let Some(token) = &self.access_token {
-            notification_builder =
-                notification_builder.header("Authorization", format!("Bearer {}", token));
+    fn has_capability(&self, capability: &str) -> bool {
+        match self.initialize_result.get("capabilities") {
+            Some(capabilities) => capabilities.get(capability).is_some(),
+            None => true,
         }
+    }
 
-        if let Some(key) = &self.api_key {
-            notification_builder = notification_builder.header("X-API-Key", key);
-        }
+    fn take_tools_changed(&mut self) -> bool {
+        std::mem::take(&mut self.tools_changed)
+    }
+}
 
-        // Add content type and required accept headers
-        notification_builder = notification_builder
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json, text/event-stream");
-
-        match notification_builder.send().await {
-            Ok(response) => {
-                let status = response.status();
-                if status.is_success() {
-                    info!(
-                        "Initialized notification sent successfully to server '{}'",
-                        self.server_name
-                    );
-                    Ok(())
-                } else {
-                    warn!(
-                        "Initialized notification to MCP server '{}' failed with status: {}",
-                        self.server_name, status
-                    );
-                    Ok(()) // Don't treat this as fatal error to maintain compatibility
-                }
-            }
-            Err(e) => {
-                warn!(
-                    "Failed to send initialized notification to MCP server '{}': {}",
-                    self.server_name, e
-                );
-                Ok(()) // Don't treat this as fatal error to maintain compatibility
-            }
+impl Drop for HttpMcpConnection {
+    fn drop(&mut self) {
+        self.close_event_stream();
+
+        // Tell the server the session is over
+        if let (Some(session_id), Some(HttpTransport::Streamable), Ok(runtime)) = (
+            self.session_id.take(),
+            &self.transport,
+            tokio::runtime::Handle::try_current(),
+        ) {
+            let request = self
+                .with_headers(self.client.delete(&self.base_url))
+                .header(SESSION_ID_HEADER, session_id);
+            runtime.spawn(async move {
+                let _ = request.send().await;
+            });
         }
     }
 }
 
+fn initialize_params(protocol_version: &str, capabilities: Value) -> Value {
+    json!({
+        "protocolVersion": protocol_version,
+        "clientInfo": {
+            "name": "OxideAgent",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "capabilities": capabilities
+    })
+}
+
+fn content_type(response: &Response) -> &str {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+fn is_event_stream(response: &Response) -> bool {
+    content_type(response).starts_with("text/event-stream")
+}
+
+fn is_json(response: &Response) -> bool {
+    content_type(response).starts_with("application/json")
+}
+
 /// Response structure for tools/list method
 #[derive(Serialize, Deserialize, Debug)]
 struct ToolsListResult {
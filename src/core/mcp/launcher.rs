@@ -4,37 +4,179 @@
 //! - Docker containers
 //! - NPM package runner (npx)
 //! - Astral UVX runner
+//!
+//! Every server talks MCP over its stdin/stdout, so the pipes are kept open on
+//! the returned `McpProcess`. Servers run in their own process group, which is
+//! killed (and Docker containers are removed) when the `McpProcess` is dropped.
 
 use std::collections::HashMap;
-use std::process::{Command, Stdio};
-use std::time::Duration;
-use tokio::time::sleep;
-use tracing::{error, info};
+use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use tracing::{debug, error, info, warn};
 
 use super::config::{McpServerConfig, McpServerType};
 use anyhow::{Context, Result};
 
-// Note: This struct and its fields are kept for possible future use
-#[allow(dead_code)]
 /// Process information for a spawned MCP server
+#[derive(Debug)]
 pub struct McpProcess {
-    /// Process ID of the spawned server
-    #[allow(dead_code)]
+    /// Process ID of the spawned server (the `docker run` client for Docker servers)
     pub pid: u32,
     /// Command used to start the process
-    #[allow(dead_code)]
     pub command: String,
     /// Arguments passed to the command
-    #[allow(dead_code)]
     pub args: Vec<String>,
     /// The actual process handle
-    #[allow(dead_code)]
-    pub handle: std::process::Child,
-    /// For Docker containers, store the container ID
-    #[allow(dead_code)]
+    pub handle: Child,
+    /// For Docker containers, the name of the container
     pub container_id: Option<String>,
 }
 
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
+impl McpProcess {
+    /// Take the stdio pipes of the server. Returns `None` when they were
+    /// already taken.
+    pub fn take_stdio(&mut self) -> Option<(ChildStdin, ChildStdout, Option<ChildStderr>)> {
+        let stdin = self.handle.stdin.take()?;
+        let stdout = self.handle.stdout.take()?;
+        Some((stdin, stdout, self.handle.stderr.take()))
+    }
+
+    /// Exit status of the server, or `None` while it is still running
+    pub fn exit_status(&mut self) -> Option<ExitStatus> {
+        match self.handle.try_wait() {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Could not check MCP server process {}: {}", self.pid, e);
+                None
+            }
+        }
+    }
+
+    /// Kill the server and everything it started; npx and uvx run the actual
+    /// server as their child. Does not wait for the process.
+    fn kill(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: kill(2) has no memory safety requirements; a negative pid targets the group
+        unsafe {
+            libc::kill(-(self.pid as libc::pid_t), libc::SIGKILL);
+        }
+        if self.exit_status().is_none() {
+            debug!(
+                "Killing MCP server process {} ({} {})",
+                self.pid,
+                self.command,
+                self.args.join(" ")
+            );
+            if let Err(e) = self.handle.kill() {
+                warn!("Failed to kill MCP server process {}: {}", self.pid, e);
+            }
+        }
+    }
+
+    /// Kill the server and, for Docker servers, remove its container.
+    /// Calling this more than once is harmless. This blocks, so async code
+    /// should call it through `spawn_blocking`.
+    pub fn terminate(&mut self) {
+        self.kill();
+        let _ = self.handle.wait();
+        if let Some(container_id) = self.container_id.take() {
+            remove_container(&container_id);
+        }
+    }
+}
+
+impl Drop for McpProcess {
+    fn drop(&mut self) {
+        self.kill();
+        #[cfg(unix)]
+        let (pid, reaped) = (self.pid, self.exit_status().is_some());
+        let container_id = self.container_id.take();
+        let cleanup = move || {
+            #[cfg(unix)]
+            if !reaped {
+                // SAFETY: waitpid(2) with a null status pointer only reaps the child
+                unsafe {
+                    libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), 0);
+                }
+            }
+            if let Some(container_id) = container_id {
+                remove_container(&container_id);
+            }
+        };
+
+        // Waiting for the process and removing the container block, which must
+        // not happen on a runtime worker thread
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(cleanup)),
+            Err(_) => cleanup(),
+        }
+    }
+}
+
+/// Remove a server's Docker container
+fn remove_container(container_id: &str) {
+    debug!("Removing Docker container '{}'", container_id);
+    match Command::new("docker")
+        .args(["rm", "-f", container_id])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+    {
+        Ok(status) if status.success() => {}
+        // `--rm` may already have removed the container
+        Ok(_) => debug!("Docker container '{}' was already gone", container_id),
+        Err(e) => warn!(
+            "Failed to remove Docker container '{}': {}",
+            container_id, e
+        ),
+    }
+}
+
+/// Spawn a server with piped stdio in its own process group, so that killing
+/// the group also stops the processes the server started
+fn spawn_server(command: &mut Command) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+/// Environment for a server process: the server-wide variables, overridden by
+/// the ones given for its type
+fn merge_environment(
+    config: &McpServerConfig,
+    environment: &Option<HashMap<String, String>>,
+) -> Option<HashMap<String, String>> {
+    if config.environment.is_none() && environment.is_none() {
+        return None;
+    }
+    let mut merged = config.environment.clone().unwrap_or_default();
+    merged.extend(environment.clone().unwrap_or_default());
+    Some(merged)
+}
+
+/// Docker container name for a server: `oxideagent-mcp-<name>-<random suffix>`
+fn container_name(server_name: &str) -> String {
+    let sanitized: String = server_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let suffix = nanoid::nanoid!(8, &nanoid::alphabet::SAFE);
+    format!("oxideagent-mcp-{}-{}", sanitized, suffix)
+}
+
 /// MCP Server launcher that abstracts starting an MCP server based on its configuration
 pub struct McpLauncher;
 
@@ -55,7 +197,7 @@ impl McpLauncher {
                     command,
                     ports,
                     volumes,
-                    environment,
+                    &merge_environment(config, environment),
                     &config.name,
                 )
                 .await
@@ -65,13 +207,23 @@ impl McpLauncher {
                 command,
                 args,
                 environment,
-            } => Self::launch_npx_server(package, command, args, environment, &config.name).await,
+            } => {
+                Self::launch_npx_server(
+                    package,
+                    command,
+                    args,
+                    &merge_environment(config, environment),
+                    &config.name,
+                )
+                .await
+            }
             McpServerType::Command {
                 command,
                 args,
                 environment,
                 working_directory,
             } => {
+                let environment = &merge_environment(config, environment);
                 // Check if this is a uvx command
                 if command == "uvx" {
                     Self::launch_uvx_server_from_command(command, args, environment, &config.name)
@@ -116,10 +268,17 @@ impl McpLauncher {
             .await
             .context("Failed to launch Docker MCP server - Docker not available")?;
 
+        let container_id = container_name(name);
         let mut docker_cmd = Command::new("docker");
 
-        // Add run command
-        docker_cmd.arg("run");
+        // Run attached with stdin open so the server can speak MCP over stdio;
+        // the named container is removed when the server stops
+        docker_cmd
+            .arg("run")
+            .arg("-i")
+            .arg("--rm")
+            .arg("--name")
+            .arg(&container_id);
 
         // Add port mappings
         if let Some(ports) = ports {
@@ -142,9 +301,6 @@ impl McpLauncher {
             }
         }
 
-        // Add detach flag to run in background
-        docker_cmd.arg("-d");
-
         // Add the image
         docker_cmd.arg(image);
 
@@ -153,89 +309,22 @@ impl McpLauncher {
             docker_cmd.args(cmd);
         }
 
-        // Execute the Docker command
-        let output = docker_cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .map_err(|e| {
-                error!("Failed to execute Docker command for '{}': {}", name, e);
-                anyhow::anyhow!("Failed to execute Docker command for '{}': {}", name, e)
-            })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            error!(
-                "Docker command failed for MCP server '{}':\nstdout: {}\nstderr: {}",
-                name, stdout, stderr
-            );
-            return Err(anyhow::anyhow!(
-                "Docker command failed for MCP server '{}':\nstdout: {}\nstderr: {}",
-                name,
-                stdout,
-                stderr
-            ));
-        }
-
-        // Extract the container ID from stdout
-        let container_id = String::from_utf8(output.stdout)
-            .context("Failed to parse Docker container ID")?
-            .trim()
-            .to_string();
-
-        if container_id.is_empty() {
-            return Err(anyhow::anyhow!(
-                "Failed to get container ID for MCP server '{}'",
-                name
-            ));
-        }
-
-        // Wait briefly to ensure the container is running
-        sleep(Duration::from_secs(1)).await;
-
-        // Get the PID of the container (we'll use the container ID as a form of PID for Docker)
-        // Actually get the running container's info to confirm it's running
-        let inspect_cmd = Command::new("docker")
-            .arg("inspect")
-            .arg(&container_id)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .with_context(|| format!("Failed to inspect Docker container for '{}'", name))?;
-
-        if !inspect_cmd.status.success() {
-            let stderr = String::from_utf8_lossy(&inspect_cmd.stderr);
-            return Err(anyhow::anyhow!(
-                "Failed to inspect Docker container for '{}': {}",
-                name,
-                stderr
-            ));
-        }
+        let child = spawn_server(&mut docker_cmd).map_err(|e| {
+            error!("Failed to execute Docker command for '{}': {}", name, e);
+            anyhow::anyhow!("Failed to execute Docker command for '{}': {}", name, e)
+        })?;
 
+        let pid = child.id();
         info!(
-            "Docker MCP server '{}' launched successfully with container ID: {}",
-            name, container_id
+            "Docker MCP server '{}' launched in container '{}' (PID: {})",
+            name, container_id, pid
         );
 
-        // For Docker, we'll use a dummy child process since Docker runs the container in the background
-        // In reality, we'd want more sophisticated Docker container management
-        let dummy_cmd = Command::new("echo") // Just a placeholder command
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .with_context(|| {
-                format!(
-                    "Failed to create placeholder process for Docker server '{}'",
-                    name
-                )
-            })?;
-
         Ok(McpProcess {
-            pid: 0, // Docker containers don't map directly to host PIDs
+            pid,
             command: format!("docker run {}", image),
-            args: vec![], // Already incorporated in the command above
-            handle: dummy_cmd,
+            args: command.clone().unwrap_or_default(),
+            handle: child,
             container_id: Some(container_id),
         })
     }
@@ -283,14 +372,10 @@ impl McpLauncher {
         }
 
         // Execute the npx command
-        let mut child = npx_cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                error!("Failed to spawn npx command for '{}': {}", name, e);
-                anyhow::anyhow!("Failed to spawn npx command for '{}': {}", name, e)
-            })?;
+        let mut child = spawn_server(&mut npx_cmd).map_err(|e| {
+            error!("Failed to spawn npx command for '{}': {}", name, e);
+            anyhow::anyhow!("Failed to spawn npx command for '{}': {}", name, e)
+        })?;
 
         let pid = child.id();
 
@@ -369,14 +454,10 @@ impl McpLauncher {
         }
 
         // Execute the uvx command
-        let mut child = uvx_cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                error!("Failed to spawn uvx command for '{}': {}", name, e);
-                anyhow::anyhow!("Failed to spawn uvx command for '{}': {}", name, e)
-            })?;
+        let mut child = spawn_server(&mut uvx_cmd).map_err(|e| {
+            error!("Failed to spawn uvx command for '{}': {}", name, e);
+            anyhow::anyhow!("Failed to spawn uvx command for '{}': {}", name, e)
+        })?;
 
         let pid = child.id();
 
@@ -459,14 +540,10 @@ impl McpLauncher {
         }
 
         // Execute the uvx command
-        let mut child = uvx_cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                error!("Failed to spawn uvx command for '{}': {}", name, e);
-                anyhow::anyhow!("Failed to spawn uvx command for '{}': {}", name, e)
-            })?;
+        let mut child = spawn_server(&mut uvx_cmd).map_err(|e| {
+            error!("Failed to spawn uvx command for '{}': {}", name, e);
+            anyhow::anyhow!("Failed to spawn uvx command for '{}': {}", name, e)
+        })?;
 
         let pid = child.id();
 
@@ -546,14 +623,10 @@ impl McpLauncher {
         }
 
         // Execute the command
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                error!("Failed to spawn command for '{}': {}", name, e);
-                anyhow::anyhow!("Failed to spawn command for '{}': {}", name, e)
-            })?;
+        let mut child = spawn_server(&mut cmd).map_err(|e| {
+            error!("Failed to spawn command for '{}': {}", name, e);
+            anyhow::anyhow!("Failed to spawn command for '{}': {}", name, e)
+        })?;
 
         let pid = child.id();
 
@@ -643,19 +716,6 @@ impl McpLauncher {
 
         Ok(())
     }
-
-    // Note: This method is kept for possible future use
-    #[allow(dead_code)]
-    /// Helper function to create a mock child process
-    /// This is a placeholder since we can't create a real Child process without spawning a command
-    fn create_mock_child_process() -> Result<std::process::Child> {
-        // This is a placeholder implementation
-        // In a real scenario, we'd need proper process management for Docker containers
-        // For now, we'll return an error to indicate that Docker implementation is partial
-        Err(anyhow::anyhow!(
-            "Docker process management requires more sophisticated handling"
-        ))
-    }
 }
 
 #[cfg(test)]
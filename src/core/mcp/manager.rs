@@ -1,18 +1,85 @@
 use crate::config::MCPToolConfig;
+use crate::config::McpOAuthConfig;
 use crate::core::mcp::config::{McpServerConfig, McpServerType};
-use crate::core::mcp::connection::{McpConnection, McpToolDefinition, StdioMcpConnection};
+use crate::core::mcp::connection::{
+    McpConnection, McpPrompt, McpResource, McpToolDefinition, StdioMcpConnection,
+    prompt_messages_to_string, tool_result_to_string,
+};
 use crate::core::mcp::http::HttpMcpConnection;
-use crate::core::mcp::launcher::McpLauncher;
-use crate::core::tools::{Tool, ToolProfile};
+use crate::core::mcp::oauth::{OAuthClient, interface_prompt};
+use crate::core::mcp::router::McpRequestRouter;
+use crate::core::tools::{Tool, ToolProfile, ToolSource};
 use anyhow::Result;
 use serde_json::Value;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub type ConnectionId = String;
 
+/// Registry id of the connection to the named server
+pub fn connection_id(server_name: &str) -> ConnectionId {
+    format!("{}_connection", server_name)
+}
+
+/// Name of the server behind a registry id
+pub fn server_name(connection_id: &str) -> &str {
+    connection_id
+        .strip_suffix("_connection")
+        .unwrap_or(connection_id)
+}
+
+/// Separator between the server and tool parts of a qualified MCP tool name
+pub const TOOL_NAMESPACE_SEPARATOR: &str = "__";
+
+/// Longest function name the OpenAI and Anthropic APIs accept
+pub const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// Name an MCP tool is offered under: the server name, `__` and the tool
+/// name, with characters function names can't hold replaced by `_`, e.g.
+/// `weather__get_forecast`. Names longer than [`MAX_TOOL_NAME_LEN`] are cut
+/// short and end in a hash of the full name, so they stay distinct.
+pub fn qualified_tool_name(server_name: &str, tool_name: &str) -> String {
+    let name = format!(
+        "{}{}{}",
+        function_name_chars(server_name),
+        TOOL_NAMESPACE_SEPARATOR,
+        function_name_chars(tool_name)
+    );
+    if name.len() <= MAX_TOOL_NAME_LEN {
+        return name;
+    }
+
+    let hash = format!(
+        "{:x}",
+        Sha256::digest(format!(
+            "{}{}{}",
+            server_name, TOOL_NAMESPACE_SEPARATOR, tool_name
+        ))
+    );
+    let shortened = format!("{}_{}", &name[..MAX_TOOL_NAME_LEN - 9], &hash[..8]);
+    warn!(
+        "MCP tool '{}' of server '{}' would be offered as '{}', which is longer than {} characters; offering it as '{}'. Set an alias in mcp.tool_aliases to offer it under another name.",
+        tool_name, server_name, name, MAX_TOOL_NAME_LEN, shortened
+    );
+    shortened
+}
+
+/// `name` with every character but ASCII letters, digits, `_` and `-` replaced by `_`
+fn function_name_chars(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 /// Enum to represent different types of MCP connections
 #[derive(Debug)]
 pub enum McpConnectionType {
@@ -20,19 +87,123 @@ pub enum McpConnectionType {
     Http(HttpMcpConnection),
 }
 
+impl McpConnectionType {
+    /// The connection, whatever its transport
+    pub fn as_connection(&mut self) -> &mut dyn McpConnection {
+        match self {
+            McpConnectionType::Stdio(connection) => connection,
+            McpConnectionType::Http(connection) => connection,
+        }
+    }
+}
+
 /// Global registry for MCP connections
 #[derive(Debug, Clone)]
 pub struct McpConnectionRegistry {
     pub connections: Arc<RwLock<HashMap<ConnectionId, Arc<Mutex<McpConnectionType>>>>>,
+    /// Tools discovered on each connection; replaced when a server restarts
+    tools: Arc<std::sync::RwLock<HashMap<ConnectionId, Vec<McpToolAdapter>>>>,
+    /// Names MCP tools can't take, such as those of the built-in tools
+    reserved_names: Arc<std::sync::RwLock<HashSet<String>>>,
+    /// Qualified tool name to the name the tool is offered under
+    aliases: Arc<std::sync::RwLock<HashMap<String, String>>>,
+    /// Answers the requests servers send to the client
+    router: McpRequestRouter,
 }
 
 impl McpConnectionRegistry {
     pub fn new() -> Self {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
+            tools: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            reserved_names: Arc::new(std::sync::RwLock::new(HashSet::new())),
+            aliases: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            router: McpRequestRouter::new(),
         }
     }
 
+    /// The router connections in this registry answer server requests with
+    pub fn request_router(&self) -> &McpRequestRouter {
+        &self.router
+    }
+
+    /// Keep MCP tools from taking these names
+    pub fn reserve_tool_names(&self, names: impl IntoIterator<Item = String>) {
+        self.reserved_names
+            .write()
+            .expect("MCP reserved names poisoned")
+            .extend(names);
+    }
+
+    /// Offer tools under other names, keyed by qualified tool name
+    pub fn set_tool_aliases(&self, aliases: HashMap<String, String>) {
+        *self.aliases.write().expect("MCP tool aliases poisoned") = aliases;
+    }
+
+    /// The alias configured for a qualified tool name
+    pub fn tool_alias(&self, qualified_name: &str) -> Option<String> {
+        self.aliases
+            .read()
+            .expect("MCP tool aliases poisoned")
+            .get(qualified_name)
+            .cloned()
+    }
+
+    /// Replace the tools offered through a connection and return the ones
+    /// accepted. A tool whose name is reserved or already offered by another
+    /// connection is skipped with a warning.
+    pub fn set_tools(&self, id: &ConnectionId, tools: Vec<McpToolAdapter>) -> Vec<McpToolAdapter> {
+        let mut all_tools = self.tools.write().expect("MCP tool map poisoned");
+        let mut taken: HashSet<String> = self
+            .reserved_names
+            .read()
+            .expect("MCP reserved names poisoned")
+            .clone();
+        taken.extend(
+            all_tools
+                .iter()
+                .filter(|(other_id, _)| *other_id != id)
+                .flat_map(|(_, adapters)| adapters.iter().map(|adapter| adapter.name.clone())),
+        );
+
+        let accepted: Vec<McpToolAdapter> = tools
+            .into_iter()
+            .filter(|adapter| {
+                if taken.insert(adapter.name.clone()) {
+                    return true;
+                }
+                warn!(
+                    "MCP tool '{}' of server '{}' is offered as '{}', which another tool already uses; skipping it. Set an alias in mcp.tool_aliases to offer it under another name.",
+                    adapter.tool_name,
+                    server_name(id),
+                    adapter.name
+                );
+                false
+            })
+            .collect();
+        all_tools.insert(id.clone(), accepted.clone());
+        accepted
+    }
+
+    /// Tools of every connection, ordered by connection id
+    pub fn tool_adapters(&self) -> Vec<McpToolAdapter> {
+        let all_tools = self.tools.read().expect("MCP tool map poisoned");
+        let mut ids: Vec<&ConnectionId> = all_tools.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .flat_map(|id| all_tools[id].iter().cloned())
+            .collect()
+    }
+
+    /// Drop a connection and its tools
+    pub async fn remove_connection(&self, id: &ConnectionId) {
+        self.connections.write().await.remove(id);
+        self.tools
+            .write()
+            .expect("MCP tool map poisoned")
+            .remove(id);
+    }
+
     pub async fn add_stdio_connection(&self, id: ConnectionId, connection: StdioMcpConnection) {
         let mut connections = self.connections.write().await;
         connections.insert(
@@ -67,10 +238,10 @@ impl McpConnectionRegistry {
 
         // Lock the connection and execute the tool
         let mut conn = connection.lock().await;
-        match &mut *conn {
+        let result = match &mut *conn {
             McpConnectionType::Stdio(stdio_conn) => {
                 match stdio_conn.execute_tool(tool_name, args).await {
-                    Ok(result) => Ok(result.to_string()),
+                    Ok(result) => Ok(result),
                     Err(e) => {
                         error!(
                             "Failed to execute tool '{}' on stdio connection '{}': {}",
@@ -82,7 +253,7 @@ impl McpConnectionRegistry {
             }
             McpConnectionType::Http(http_conn) => {
                 match http_conn.execute_tool(tool_name, args).await {
-                    Ok(result) => Ok(result.to_string()),
+                    Ok(result) => Ok(result),
                     Err(e) => {
                         error!(
                             "Failed to execute tool '{}' on HTTP connection '{}': {}",
@@ -92,7 +263,24 @@ impl McpConnectionRegistry {
                     }
                 }
             }
+        }?;
+
+        // Servers announce tool changes while handling requests; publish the
+        // new tools before the agent looks them up again
+        let tools_changed = conn.as_connection().take_tools_changed();
+        drop(conn);
+        if tools_changed
+            && let Err(e) = McpManager::with_registry(self.clone())
+                .discover_server_tools(server_name(connection_id))
+                .await
+        {
+            warn!(
+                "Failed to rediscover the tools of connection '{}': {}",
+                connection_id, e
+            );
         }
+
+        tool_result_to_string(&result)
     }
 
     pub async fn discover_tools_on_connection(
@@ -119,6 +307,130 @@ impl McpConnectionRegistry {
     }
 }
 
+impl McpConnectionRegistry {
+    /// Connections ordered by id
+    async fn sorted_connections(&self) -> Vec<(ConnectionId, Arc<Mutex<McpConnectionType>>)> {
+        let connections = self.connections.read().await;
+        let mut sorted: Vec<(ConnectionId, Arc<Mutex<McpConnectionType>>)> = connections
+            .iter()
+            .map(|(id, connection)| (id.clone(), connection.clone()))
+            .collect();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted
+    }
+
+    /// Resources of every server that offers them, with the server name.
+    /// Servers that fail to list them are logged and skipped.
+    pub async fn list_resources(&self) -> Vec<(String, McpResource)> {
+        let mut resources = Vec::new();
+        for (id, connection) in self.sorted_connections().await {
+            let mut conn = connection.lock().await;
+            let conn = conn.as_connection();
+            if !conn.has_capability("resources") {
+                continue;
+            }
+            match conn.list_resources().await {
+                Ok(found) => resources.extend(
+                    found
+                        .into_iter()
+                        .map(|resource| (server_name(&id).to_string(), resource)),
+                ),
+                Err(e) => warn!("Failed to list resources on connection '{}': {}", id, e),
+            }
+        }
+        resources
+    }
+
+    /// Read a resource from the first server listing its URI, as text
+    pub async fn read_resource(&self, uri: &str) -> Result<String> {
+        for (id, connection) in self.sorted_connections().await {
+            let mut conn = connection.lock().await;
+            let conn = conn.as_connection();
+            if !conn.has_capability("resources") {
+                continue;
+            }
+            let listed = match conn.list_resources().await {
+                Ok(resources) => resources.iter().any(|resource| resource.uri == uri),
+                Err(e) => {
+                    warn!("Failed to list resources on connection '{}': {}", id, e);
+                    false
+                }
+            };
+            if listed {
+                let contents = conn.read_resource(uri).await?;
+                return Ok(contents
+                    .iter()
+                    .map(|content| content.to_text())
+                    .collect::<Vec<_>>()
+                    .join("\n"));
+            }
+        }
+        Err(anyhow::anyhow!("No MCP server offers resource '{}'", uri))
+    }
+
+    /// Prompt templates of every server that offers them, with the server name.
+    /// Servers that fail to list them are logged and skipped.
+    pub async fn list_prompts(&self) -> Vec<(String, McpPrompt)> {
+        let mut prompts = Vec::new();
+        for (id, connection) in self.sorted_connections().await {
+            let mut conn = connection.lock().await;
+            let conn = conn.as_connection();
+            if !conn.has_capability("prompts") {
+                continue;
+            }
+            match conn.list_prompts().await {
+                Ok(found) => prompts.extend(
+                    found
+                        .into_iter()
+                        .map(|prompt| (server_name(&id).to_string(), prompt)),
+                ),
+                Err(e) => warn!("Failed to list prompts on connection '{}': {}", id, e),
+            }
+        }
+        prompts
+    }
+
+    /// Expand the prompt template of the first server offering `name`, as text
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<String> {
+        for (id, connection) in self.sorted_connections().await {
+            let mut conn = connection.lock().await;
+            let conn = conn.as_connection();
+            if !conn.has_capability("prompts") {
+                continue;
+            }
+            let prompt = match conn.list_prompts().await {
+                Ok(prompts) => prompts.into_iter().find(|prompt| prompt.name == name),
+                Err(e) => {
+                    warn!("Failed to list prompts on connection '{}': {}", id, e);
+                    None
+                }
+            };
+            if let Some(prompt) = prompt {
+                let missing: Vec<&str> = prompt
+                    .arguments
+                    .iter()
+                    .filter(|argument| argument.required && !arguments.contains_key(&argument.name))
+                    .map(|argument| argument.name.as_str())
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Prompt '{}' needs the argument(s): {}",
+                        name,
+                        missing.join(", ")
+                    ));
+                }
+                let messages = conn.get_prompt(name, arguments).await?;
+                return Ok(prompt_messages_to_string(&messages));
+            }
+        }
+        Err(anyhow::anyhow!("No MCP server offers prompt '{}'", name))
+    }
+}
+
 impl Default for McpConnectionRegistry {
     fn default() -> Self {
         Self::new()
@@ -140,29 +452,87 @@ pub fn init_mcp_registry() {
     MCP_CONNECTION_REGISTRY.get_or_init(|| Arc::new(McpConnectionRegistry::new()));
 }
 
-/// MCP tool adapter that uses the global registry to execute tools
+/// The tools of every server in a connection registry, as they are when the
+/// agent looks them up
 #[derive(Clone)]
+pub struct McpToolSource {
+    registry: McpConnectionRegistry,
+}
+
+impl McpToolSource {
+    pub fn new(registry: McpConnectionRegistry) -> Self {
+        Self { registry }
+    }
+}
+
+impl ToolSource for McpToolSource {
+    fn tools(&self) -> Vec<Box<dyn Tool>> {
+        self.registry
+            .tool_adapters()
+            .into_iter()
+            .map(|adapter| Box::new(adapter) as Box<dyn Tool>)
+            .collect()
+    }
+
+    fn clone_source(&self) -> Box<dyn ToolSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// MCP tool adapter that executes tools through a connection registry
+#[derive(Debug, Clone)]
 pub struct McpToolAdapter {
+    /// Name offered to the model: the alias, or the qualified name
     name: String,
+    /// Server and tool name, e.g. `weather__get_forecast`
+    qualified_name: String,
+    /// Name of the tool on its server
+    tool_name: String,
     description: String,
     parameters: Value,
     connection_id: ConnectionId, // Reference to the connection in the registry
+    /// Registry holding the connection; the global registry when unset
+    registry: Option<McpConnectionRegistry>,
 }
 
 impl McpToolAdapter {
+    /// Adapter for the server tool `tool_name`, offered under its qualified
+    /// name
     pub fn new(
-        name: String,
+        tool_name: String,
         description: String,
         parameters: Value,
         connection_id: ConnectionId,
     ) -> Self {
+        let qualified_name = qualified_tool_name(server_name(&connection_id), &tool_name);
         Self {
-            name,
+            name: qualified_name.clone(),
+            qualified_name,
+            tool_name,
             description,
             parameters,
             connection_id,
+            registry: None,
         }
     }
+
+    /// Execute calls through `registry` instead of the global registry
+    pub fn with_registry(mut self, registry: McpConnectionRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Offer the tool as `alias` instead of its qualified name
+    pub fn with_alias(mut self, alias: String) -> Self {
+        self.name = alias;
+        self
+    }
+
+    /// Name of the tool on its server
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn tool_name(&self) -> &str {
+        &self.tool_name
+    }
 }
 
 #[async_trait::async_trait]
@@ -183,10 +553,17 @@ impl Tool for McpToolAdapter {
         ToolProfile::Generic
     }
 
+    fn qualified_name(&self) -> String {
+        self.qualified_name.clone()
+    }
+
     async fn execute(&self, args: &Value) -> anyhow::Result<String> {
-        let registry = get_mcp_registry();
+        let registry = match &self.registry {
+            Some(registry) => registry.clone(),
+            None => (*get_mcp_registry()).clone(),
+        };
         registry
-            .execute_tool_on_connection(&self.connection_id, &self.name, args)
+            .execute_tool_on_connection(&self.connection_id, &self.tool_name, args)
             .await
     }
 
@@ -198,6 +575,8 @@ impl Tool for McpToolAdapter {
 /// MCP manager that handles server lifecycle and tool registration
 pub struct McpManager {
     pub registry: McpConnectionRegistry,
+    /// OAuth settings for remote servers configured without credentials
+    oauth: Option<McpOAuthConfig>,
 }
 
 impl McpManager {
@@ -205,160 +584,144 @@ impl McpManager {
         init_mcp_registry();
         Self {
             registry: (*get_mcp_registry()).clone(),
+            oauth: None,
         }
     }
 
-    pub async fn launch_servers(&self, tools: &[MCPToolConfig]) -> Result<()> {
+    /// Create a manager that keeps its connections in `registry`
+    pub fn with_registry(registry: McpConnectionRegistry) -> Self {
+        Self {
+            registry,
+            oauth: None,
+        }
+    }
+
+    /// Authorize remote servers that have no access token or API key with
+    /// OAuth
+    pub fn with_oauth(mut self, oauth: McpOAuthConfig) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Connect to the MCP server of every configured tool and return adapters
+    /// for the tools they offer. Servers that fail to start are logged and
+    /// skipped.
+    #[allow(dead_code)] // Used in tests; the application supervises its servers
+    pub async fn launch_servers(&self, tools: &[MCPToolConfig]) -> Result<Vec<McpToolAdapter>> {
         info!(
             "Starting MCP server launch process for {} tools",
             tools.len()
         );
 
+        let mut adapters = Vec::new();
         for tool_config in tools {
             info!(
                 "Attempting to launch MCP server: {} with command '{}' and args {:?}",
                 tool_config.name, tool_config.command, tool_config.args
             );
 
-            // Create a server config from the tool config
-            let server_type = McpServerType::Command {
-                command: tool_config.command.clone(),
-                args: Some(tool_config.args.clone()),
-                environment: None,
-                working_directory: None,
-            };
-
-            let config = McpServerConfig {
-                name: tool_config.name.clone(),
-                description: Some(format!("MCP server for {}", tool_config.name)),
-                server_type,
-                auto_start: Some(true),
-                environment: None,
-            };
+            let config = tool_config.server_config();
+            match self.connect_server(&config).await {
+                Ok(server_adapters) => adapters.extend(server_adapters),
+                Err(e) => error!("Failed to start MCP server '{}': {}", config.name, e),
+            }
+        }
 
-            match &config.server_type {
-                McpServerType::Remote {
-                    url,
-                    access_token,
-                    api_key,
-                } => {
-                    // For remote servers, create an HTTP connection
-                    info!(
-                        "Creating HTTP connection to remote MCP server: {} at URL: {}",
-                        config.name, url
-                    );
+        info!("Completed MCP server launch process");
+        Ok(adapters)
+    }
 
-                    let http_connection = HttpMcpConnection::new(
-                        &config,
-                        url.clone(),
-                        access_token.clone(),
-                        api_key.clone(),
+    /// Open one long-lived connection to an MCP server, add it to the registry
+    /// and return adapters for the tools it offers.
+    pub async fn connect_server(&self, config: &McpServerConfig) -> Result<Vec<McpToolAdapter>> {
+        let connection_id = connection_id(&config.name);
+
+        match &config.server_type {
+            McpServerType::Remote {
+                url,
+                access_token,
+                api_key,
+            } => {
+                // For remote servers, create an HTTP connection
+                info!(
+                    "Creating HTTP connection to remote MCP server: {} at URL: {}",
+                    config.name, url
+                );
+
+                let mut http_connection = HttpMcpConnection::new(
+                    config,
+                    url.clone(),
+                    access_token.clone(),
+                    api_key.clone(),
+                )
+                .with_router(self.registry.request_router().clone());
+                if access_token.is_none()
+                    && api_key.is_none()
+                    && let Some(oauth) = &self.oauth
+                {
+                    let prompt = interface_prompt(
+                        self.registry.request_router().clone(),
+                        oauth.open_browser,
                     );
-
-                    // Add connection to the registry
-                    let connection_id = format!("{}_connection", config.name);
-                    self.registry
-                        .add_http_connection(connection_id.clone(), http_connection)
-                        .await;
-
-                    // Discover tools from the remote server using the registry
-                    match self
-                        .registry
-                        .discover_tools_on_connection(&connection_id)
-                        .await
-                    {
-                        Ok(mcp_tools) => {
-                            info!(
-                                "Discovered {} tools from HTTP MCP server '{}':",
-                                mcp_tools.len(),
-                                config.name
-                            );
-
-                            for tool in &mcp_tools {
-                                info!(
-                                    "  - Adding MCP tool adapter: {} - {}",
-                                    tool.name,
-                                    truncate_description(&tool.description)
-                                );
-                            }
-
-                            info!(
-                                "HTTP MCP server '{}' is running and ready for communication at URL: {}",
-                                config.name, url
-                            );
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to discover tools from HTTP MCP server '{}': {}",
-                                config.name, e
-                            );
-                        }
-                    }
-                }
-                _ => {
-                    // For all other server types, use stdio connection
-                    match McpLauncher::launch(&config).await {
-                        Ok(_process) => {
-                            info!("Successfully launched MCP server: {}", config.name);
-
-                            // Connect to the server and discover its tools
-                            match StdioMcpConnection::new(&config).await {
-                                Ok(connection) => {
-                                    info!(
-                                        "Successfully established stdio connection to MCP server: {}",
-                                        config.name
-                                    );
-
-                                    // Add connection to the registry first
-                                    let connection_id = format!("{}_connection", config.name);
-                                    self.registry
-                                        .add_stdio_connection(connection_id.clone(), connection)
-                                        .await;
-
-                                    // Discover tools from the server using the registry
-                                    match self
-                                        .registry
-                                        .discover_tools_on_connection(&connection_id)
-                                        .await
-                                    {
-                                        Ok(mcp_tools) => {
-                                            info!(
-                                                "Discovered {} tools from MCP server '{}':",
-                                                mcp_tools.len(),
-                                                config.name
-                                            );
-
-                                            info!(
-                                                "MCP server '{}' is running and ready for stdio communication",
-                                                config.name
-                                            );
-                                        }
-                                        Err(e) => {
-                                            error!(
-                                                "Failed to discover tools from MCP server '{}': {}",
-                                                config.name, e
-                                            );
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    error!(
-                                        "Failed to establish stdio connection to MCP server '{}': {}",
-                                        config.name, e
-                                    );
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to launch MCP server '{}': {}", config.name, e);
-                        }
-                    }
+                    http_connection = http_connection
+                        .with_oauth(OAuthClient::new(&config.name, url, oauth).with_prompt(prompt));
                 }
+                self.registry
+                    .add_http_connection(connection_id.clone(), http_connection)
+                    .await;
+            }
+            _ => {
+                // The connection owns the process and stops it when dropped
+                let connection = StdioMcpConnection::new_with_router(
+                    config,
+                    self.registry.request_router().clone(),
+                )
+                .await?;
+                self.registry
+                    .add_stdio_connection(connection_id.clone(), connection)
+                    .await;
             }
         }
 
-        info!("Completed MCP server launch process");
-        Ok(())
+        self.discover_server_tools(&config.name).await
+    }
+
+    /// Discover the tools of a connected server and publish them in the
+    /// registry, replacing the ones found before.
+    pub async fn discover_server_tools(&self, server_name: &str) -> Result<Vec<McpToolAdapter>> {
+        let connection_id = connection_id(server_name);
+        let mcp_tools = self
+            .registry
+            .discover_tools_on_connection(&connection_id)
+            .await?;
+        info!(
+            "Discovered {} tools from MCP server '{}':",
+            mcp_tools.len(),
+            server_name
+        );
+
+        let adapters: Vec<McpToolAdapter> = mcp_tools
+            .into_iter()
+            .map(|tool| {
+                let mut adapter = McpToolAdapter::new(
+                    tool.name,
+                    tool.description,
+                    tool.input_schema,
+                    connection_id.clone(),
+                )
+                .with_registry(self.registry.clone());
+                if let Some(alias) = self.registry.tool_alias(&adapter.qualified_name) {
+                    adapter = adapter.with_alias(alias);
+                }
+                info!(
+                    "  - Adding MCP tool adapter: {} - {}",
+                    adapter.name,
+                    truncate_description(&adapter.description)
+                );
+                adapter
+            })
+            .collect();
+        Ok(self.registry.set_tools(&connection_id, adapters))
     }
 }
 
@@ -371,6 +734,7 @@ fn truncate_description(description: &str) -> String {
     }
 }
 
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
 impl McpManager {
     pub fn get_registry(&self) -> &McpConnectionRegistry {
         &self.registry
@@ -8,3 +8,7 @@ pub mod connection;
 pub mod http;
 pub mod launcher;
 pub mod manager;
+pub mod oauth;
+pub mod router;
+pub mod sse;
+pub mod supervisor;
@@ -0,0 +1,687 @@
+//! OAuth 2.1 authorization for remote MCP servers.
+//!
+//! A server that answers 401 points to its protected-resource metadata in the
+//! `WWW-Authenticate` header, or serves it at the well-known location. That
+//! metadata names the authorization server, whose own metadata gives the
+//! authorization, token and registration endpoints. The client registers
+//! itself dynamically, sends the user to the authorization URL with a PKCE
+//! challenge and receives the code on a localhost listener. It then exchanges
+//! the code for tokens. Tokens are kept in a cache file keyed by server URL, and
+//! a rejected access token is refreshed before the user is asked again.
+
+use crate::config::McpOAuthConfig;
+use crate::core::mcp::router::McpRequestRouter;
+use crate::types::AppEvent;
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::{Duration, timeout};
+use tracing::{debug, info, warn};
+
+/// Called with the server name and the URL the user has to open to authorize
+pub type AuthorizationPrompt = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// How long the user has to finish the authorization in the browser
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long a connection to the callback listener may take to send its
+/// request; browsers open connections they never use
+const CALLBACK_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Path the localhost listener expects the authorization callback on
+const CALLBACK_PATH: &str = "/callback";
+
+/// Client and tokens for one MCP server, as kept in the token cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub token_endpoint: String,
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Unix time the access token expires at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// File holding the tokens of every server, keyed by server URL
+#[derive(Debug, Clone)]
+pub struct TokenCache {
+    path: PathBuf,
+}
+
+impl TokenCache {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Tokens stored for the server at `resource`
+    pub fn load(&self, resource: &str) -> Option<StoredToken> {
+        self.read_all().remove(resource)
+    }
+
+    /// Store the tokens of the server at `resource`, keeping the other servers
+    pub fn store(&self, resource: &str, token: &StoredToken) -> Result<()> {
+        let mut tokens = self.read_all();
+        tokens.insert(resource.to_string(), token.clone());
+        let content = serde_json::to_string_pretty(&tokens)?;
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = self
+            .path
+            .with_extension(format!("tmp.{}", std::process::id()));
+        write_private(&temp_path, content.as_bytes())?;
+        fs::rename(&temp_path, &self.path).inspect_err(|_| {
+            fs::remove_file(&temp_path).ok();
+        })?;
+        Ok(())
+    }
+
+    fn read_all(&self) -> BTreeMap<String, StoredToken> {
+        let Ok(content) = fs::read_to_string(&self.path) else {
+            return BTreeMap::new();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!(
+                "Ignoring unreadable OAuth token cache '{}': {}",
+                self.path.display(),
+                e
+            );
+            BTreeMap::new()
+        })
+    }
+}
+
+/// Tokens are credentials, so only the user may read the cache
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(path)?, content)
+}
+
+/// Metadata of the MCP server as a protected resource (RFC 9728)
+#[derive(Debug, Deserialize)]
+struct ProtectedResourceMetadata {
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+    #[serde(default)]
+    scopes_supported: Vec<String>,
+}
+
+/// Metadata of an authorization server (RFC 8414)
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorizationServerMetadata {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    #[serde(default)]
+    pub registration_endpoint: Option<String>,
+    #[serde(default)]
+    pub code_challenge_methods_supported: Option<Vec<String>>,
+}
+
+/// Answer to a dynamic client registration (RFC 7591)
+#[derive(Debug, Deserialize)]
+struct ClientRegistration {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+/// Answer of the token endpoint
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// OAuth client of one remote MCP server
+pub struct OAuthClient {
+    client: Client,
+    server_name: String,
+    /// URL of the MCP server, sent as the `resource` the tokens are for
+    resource: String,
+    config: McpOAuthConfig,
+    cache: TokenCache,
+    prompt: AuthorizationPrompt,
+    token: Option<StoredToken>,
+}
+
+impl fmt::Debug for OAuthClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthClient")
+            .field("server_name", &self.server_name)
+            .field("resource", &self.resource)
+            .field("config", &self.config)
+            .field("authorized", &self.token.is_some())
+            .finish()
+    }
+}
+
+impl OAuthClient {
+    /// Create the OAuth client of the server at `resource`, starting with the
+    /// tokens cached for it
+    pub fn new(server_name: &str, resource: &str, config: &McpOAuthConfig) -> Self {
+        let cache = TokenCache::new(&config.token_cache);
+        let token = cache.load(resource);
+        Self {
+            client: Client::new(),
+            server_name: server_name.to_string(),
+            resource: resource.to_string(),
+            config: config.clone(),
+            cache,
+            prompt: default_prompt(config.open_browser),
+            token,
+        }
+    }
+
+    /// Send the user to the authorization URL through `prompt` instead of
+    /// printing it
+    pub fn with_prompt(mut self, prompt: AuthorizationPrompt) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// Access token to send, if the server was authorized before
+    pub fn access_token(&self) -> Option<&str> {
+        self.token.as_ref().map(|token| token.access_token.as_str())
+    }
+
+    /// Get a new access token after the server rejected the current one:
+    /// refresh it when possible, otherwise ask the user to authorize.
+    /// `challenge` is the `WWW-Authenticate` header of the 401 response.
+    pub async fn authorize(&mut self, challenge: Option<&str>) -> Result<String> {
+        if let Some(token) = self.token.clone()
+            && let Some(refresh_token) = &token.refresh_token
+        {
+            match self.refresh(&token, refresh_token).await {
+                Ok(access_token) => return Ok(access_token),
+                Err(e) => warn!(
+                    "Refreshing the token of MCP server '{}' failed, authorizing again: {}",
+                    self.server_name, e
+                ),
+            }
+        }
+        self.authorize_with_code(challenge).await
+    }
+
+    async fn refresh(&mut self, token: &StoredToken, refresh_token: &str) -> Result<String> {
+        info!(
+            "Refreshing the access token of MCP server '{}'",
+            self.server_name
+        );
+        let response = self
+            .request_token(
+                &token.token_endpoint,
+                &[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", refresh_token),
+                    ("client_id", &token.client_id),
+                    ("resource", &self.resource),
+                ],
+                token.client_secret.as_deref(),
+            )
+            .await?;
+        let refreshed = StoredToken {
+            access_token: response.access_token,
+            // Servers that don't rotate refresh tokens leave it out
+            refresh_token: response
+                .refresh_token
+                .or_else(|| token.refresh_token.clone()),
+            expires_at: expires_at(response.expires_in),
+            ..token.clone()
+        };
+        Ok(self.store(refreshed))
+    }
+
+    /// Run the authorization code flow with PKCE
+    async fn authorize_with_code(&mut self, challenge: Option<&str>) -> Result<String> {
+        let (metadata, scopes_supported) = self.discover(challenge).await?;
+        if let Some(methods) = &metadata.code_challenge_methods_supported
+            && !methods.iter().any(|method| method == "S256")
+        {
+            return Err(anyhow::anyhow!(
+                "The authorization server of MCP server '{}' does not support PKCE with S256",
+                self.server_name
+            ));
+        }
+
+        let listener = TcpListener::bind(("127.0.0.1", self.config.callback_port))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to listen for the OAuth callback: {}", e))?;
+        let redirect_uri = format!(
+            "http://127.0.0.1:{}{}",
+            listener.local_addr()?.port(),
+            CALLBACK_PATH
+        );
+        let (client_id, client_secret) = self.client_credentials(&metadata, &redirect_uri).await?;
+
+        let code_verifier = nanoid::nanoid!(64);
+        let state = nanoid::nanoid!(32);
+        let mut authorization_url = Url::parse(&metadata.authorization_endpoint)?;
+        authorization_url
+            .query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &client_id)
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("code_challenge", &pkce_challenge(&code_verifier))
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", &state)
+            .append_pair("resource", &self.resource);
+        let scope = self
+            .config
+            .scope
+            .clone()
+            .or_else(|| (!scopes_supported.is_empty()).then(|| scopes_supported.join(" ")));
+        if let Some(scope) = &scope {
+            authorization_url
+                .query_pairs_mut()
+                .append_pair("scope", scope);
+        }
+
+        info!(
+            "Waiting for the user to authorize MCP server '{}'",
+            self.server_name
+        );
+        (self.prompt)(&self.server_name, authorization_url.as_str());
+        let code = timeout(AUTHORIZATION_TIMEOUT, wait_for_callback(&listener, &state))
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Timeout waiting for the authorization of MCP server '{}'",
+                    self.server_name
+                )
+            })??;
+
+        let response = self
+            .request_token(
+                &metadata.token_endpoint,
+                &[
+                    ("grant_type", "authorization_code"),
+                    ("code", &code),
+                    ("redirect_uri", &redirect_uri),
+                    ("client_id", &client_id),
+                    ("code_verifier", &code_verifier),
+                    ("resource", &self.resource),
+                ],
+                client_secret.as_deref(),
+            )
+            .await?;
+        info!("MCP server '{}' authorized", self.server_name);
+        Ok(self.store(StoredToken {
+            client_id,
+            client_secret,
+            token_endpoint: metadata.token_endpoint,
+            access_token: response.access_token,
+            refresh_token: response.refresh_token,
+            expires_at: expires_at(response.expires_in),
+        }))
+    }
+
+    /// Find the authorization server of the MCP server and the scopes it
+    /// advertises
+    async fn discover(
+        &self,
+        challenge: Option<&str>,
+    ) -> Result<(AuthorizationServerMetadata, Vec<String>)> {
+        let resource = Url::parse(&self.resource)?;
+        let mut candidates: Vec<String> = challenge
+            .and_then(resource_metadata_url)
+            .into_iter()
+            .collect();
+        candidates.extend(well_known_urls(&resource, "oauth-protected-resource"));
+        let mut resource_metadata = None;
+        for url in candidates {
+            match self.get_json::<ProtectedResourceMetadata>(&url).await {
+                Ok(metadata) => {
+                    resource_metadata = Some(metadata);
+                    break;
+                }
+                Err(e) => debug!("No protected resource metadata at {}: {}", url, e),
+            }
+        }
+
+        // Servers without resource metadata are their own authorization server
+        let (issuer, scopes_supported) = match resource_metadata {
+            Some(metadata) => (
+                metadata
+                    .authorization_servers
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "MCP server '{}' names no authorization server",
+                            self.server_name
+                        )
+                    })?,
+                metadata.scopes_supported,
+            ),
+            None => (resource.origin().ascii_serialization(), Vec::new()),
+        };
+        let issuer = Url::parse(&issuer)?;
+
+        let mut candidates = well_known_urls(&issuer, "oauth-authorization-server");
+        candidates.extend(well_known_urls(&issuer, "openid-configuration"));
+        if !issuer.path().trim_end_matches('/').is_empty() {
+            candidates.push(format!(
+                "{}/.well-known/openid-configuration",
+                issuer.as_str().trim_end_matches('/')
+            ));
+        }
+        for url in candidates {
+            match self.get_json::<AuthorizationServerMetadata>(&url).await {
+                Ok(metadata) => return Ok((metadata, scopes_supported)),
+                Err(e) => debug!("No authorization server metadata at {}: {}", url, e),
+            }
+        }
+
+        // Authorization servers without metadata use the default endpoints
+        debug!(
+            "Using the default OAuth endpoints of {} for MCP server '{}'",
+            issuer, self.server_name
+        );
+        Ok((
+            AuthorizationServerMetadata {
+                authorization_endpoint: issuer.join("/authorize")?.to_string(),
+                token_endpoint: issuer.join("/token")?.to_string(),
+                registration_endpoint: Some(issuer.join("/register")?.to_string()),
+                code_challenge_methods_supported: None,
+            },
+            scopes_supported,
+        ))
+    }
+
+    /// The configured client id, or a client registered for `redirect_uri`
+    async fn client_credentials(
+        &self,
+        metadata: &AuthorizationServerMetadata,
+        redirect_uri: &str,
+    ) -> Result<(String, Option<String>)> {
+        if let Some(client_id) = &self.config.client_id {
+            return Ok((client_id.clone(), None));
+        }
+        let registration_endpoint = metadata.registration_endpoint.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "The authorization server of MCP server '{}' offers no client registration; set mcp.oauth.client_id",
+                self.server_name
+            )
+        })?;
+        let response = self
+            .client
+            .post(registration_endpoint)
+            .json(&serde_json::json!({
+                "client_name": "OxideAgent",
+                "redirect_uris": [redirect_uri],
+                "grant_types": ["authorization_code", "refresh_token"],
+                "response_types": ["code"],
+                "token_endpoint_auth_method": "none"
+            }))
+            .send()
+            .await?;
+        let registration: ClientRegistration = read_json(response)
+            .await
+            .map_err(|e| anyhow::anyhow!("Client registration failed: {}", e))?;
+        debug!(
+            "Registered OAuth client {} for MCP server '{}'",
+            registration.client_id, self.server_name
+        );
+        Ok((registration.client_id, registration.client_secret))
+    }
+
+    async fn request_token(
+        &self,
+        token_endpoint: &str,
+        params: &[(&str, &str)],
+        client_secret: Option<&str>,
+    ) -> Result<TokenResponse> {
+        let mut form: Vec<(&str, &str)> = params.to_vec();
+        if let Some(client_secret) = client_secret {
+            form.push(("client_secret", client_secret));
+        }
+        let response = self.client.post(token_endpoint).form(&form).send().await?;
+        read_json(response)
+            .await
+            .map_err(|e| anyhow::anyhow!("Token request failed: {}", e))
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self
+            .client
+            .get(url)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+        read_json(response).await
+    }
+
+    /// Keep the token for this run and the next ones
+    fn store(&mut self, token: StoredToken) -> String {
+        if let Err(e) = self.cache.store(&self.resource, &token) {
+            warn!(
+                "Failed to store the OAuth token of MCP server '{}' in '{}': {}",
+                self.server_name,
+                self.config.token_cache.display(),
+                e
+            );
+        }
+        let access_token = token.access_token.clone();
+        self.token = Some(token);
+        access_token
+    }
+}
+
+async fn read_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!(
+            "status {} (response body: {})",
+            status,
+            body
+        ));
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| anyhow::anyhow!("invalid response: {} (response body: {})", e, body))
+}
+
+/// The request line and headers sent on a connection
+async fn read_request_head(stream: &mut tokio::net::TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 16 * 1024 {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    Ok(request)
+}
+
+/// Accept connections on the callback listener until the authorization
+/// server redirects the browser to it, and return the code
+async fn wait_for_callback(listener: &TcpListener, state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let request = match timeout(CALLBACK_READ_TIMEOUT, read_request_head(&mut stream)).await {
+            Ok(Ok(request)) => request,
+            Ok(Err(e)) => {
+                debug!("Failed to read from the OAuth callback listener: {}", e);
+                continue;
+            }
+            Err(_) => {
+                debug!("Dropping an idle connection to the OAuth callback listener");
+                continue;
+            }
+        };
+        let request = String::from_utf8_lossy(&request);
+        let target = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or("/");
+        let url = Url::parse("http://127.0.0.1")?.join(target)?;
+        if url.path() != CALLBACK_PATH {
+            // Browsers also ask for things like the favicon
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await;
+            continue;
+        }
+
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        let result = if params.get("state").map(String::as_str) != Some(state) {
+            Err(anyhow::anyhow!(
+                "The OAuth callback carried the wrong state"
+            ))
+        } else if let Some(error) = params.get("error") {
+            Err(anyhow::anyhow!(
+                "Authorization was refused: {}{}",
+                error,
+                params
+                    .get("error_description")
+                    .map(|description| format!(" ({})", description))
+                    .unwrap_or_default()
+            ))
+        } else {
+            params
+                .get("code")
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("The OAuth callback carried no code"))
+        };
+        let body = match &result {
+            Ok(_) => "Authorization complete. You can close this window.",
+            Err(_) => "Authorization failed. You can close this window.",
+        };
+        let _ = stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .as_bytes(),
+            )
+            .await;
+        return result;
+    }
+}
+
+/// PKCE `S256` challenge of a code verifier (RFC 7636)
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// The `resource_metadata` URL of a `WWW-Authenticate` header
+pub fn resource_metadata_url(challenge: &str) -> Option<String> {
+    let start = challenge.find("resource_metadata=")? + "resource_metadata=".len();
+    let value = &challenge[start..];
+    let value = match value.strip_prefix('"') {
+        Some(quoted) => &quoted[..quoted.find('"')?],
+        None => value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .next()
+            .unwrap_or_default(),
+    };
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Well-known metadata URLs for `url`: the path-aware one first, then the
+/// one at the root
+fn well_known_urls(url: &Url, suffix: &str) -> Vec<String> {
+    let origin = url.origin().ascii_serialization();
+    let path = url.path().trim_end_matches('/');
+    let mut urls = Vec::new();
+    if !path.is_empty() {
+        urls.push(format!("{}/.well-known/{}{}", origin, suffix, path));
+    }
+    urls.push(format!("{}/.well-known/{}", origin, suffix));
+    urls
+}
+
+fn expires_at(expires_in: Option<u64>) -> Option<u64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    expires_in.map(|expires_in| now + expires_in)
+}
+
+/// Print the authorization URL and open it in the browser
+fn default_prompt(open_browser: bool) -> AuthorizationPrompt {
+    Arc::new(move |server_name, url| {
+        info!(
+            "Authorization URL for MCP server '{}': {}",
+            server_name, url
+        );
+        eprintln!(
+            "MCP server '{}' needs authorization. Open this URL to sign in:\n  {}",
+            server_name, url
+        );
+        if open_browser {
+            open_in_browser(url);
+        }
+    })
+}
+
+/// Show the authorization URL in the interface while it is running, where
+/// printed text would not be seen, and print it otherwise
+pub fn interface_prompt(router: McpRequestRouter, open_browser: bool) -> AuthorizationPrompt {
+    let print = default_prompt(open_browser);
+    Arc::new(move |server_name, url| {
+        let event = AppEvent::McpAuthorizationRequired(server_name.to_string(), url.to_string());
+        if !router.notify_interface(event) {
+            print(server_name, url);
+            return;
+        }
+        info!(
+            "Authorization URL for MCP server '{}': {}",
+            server_name, url
+        );
+        if open_browser {
+            open_in_browser(url);
+        }
+    })
+}
+
+fn open_in_browser(url: &str) {
+    let (program, args): (&str, Vec<&str>) = if cfg!(target_os = "macos") {
+        ("open", vec![url])
+    } else if cfg!(windows) {
+        ("rundll32", vec!["url.dll,FileProtocolHandler", url])
+    } else {
+        ("xdg-open", vec![url])
+    };
+    if let Err(e) = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    {
+        debug!("Failed to open the browser with {}: {}", program, e);
+    }
+}
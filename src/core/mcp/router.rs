@@ -0,0 +1,346 @@
+//! Requests MCP servers send to the client.
+//!
+//! Besides answering requests, a server may send its own: `ping`,
+//! `sampling/createMessage` to have the client's language model write a
+//! message, and `elicitation/create` to ask the user for input. Both
+//! connection types hand these to one `McpRequestRouter`. A sampling request
+//! reaches the model only after the user approved it through the interface;
+//! an elicitation request is answered with the form the user filled in.
+
+use crate::core::llm::client::LlmClient;
+use crate::core::mcp::connection::content_item_to_string;
+use crate::types::{
+    AppEvent, ChatMessage, McpElicitationAnswer, McpElicitationField, McpElicitationFieldKind,
+    McpElicitationRequestInfo, McpSamplingRequestInfo,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Duration, timeout};
+use tracing::{debug, info, warn};
+
+/// How long a request waits for the user before it is given up
+const USER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// JSON-RPC error code of a request the user rejected
+const USER_REJECTED: i64 = -1;
+
+/// JSON-RPC error code of an unknown method
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The model sampling requests are sent to
+#[derive(Debug, Clone)]
+struct Sampler {
+    client: Arc<dyn LlmClient>,
+    model: String,
+}
+
+#[derive(Debug, Default)]
+struct RouterState {
+    /// Where requests for the user are sent
+    event_tx: RwLock<Option<mpsc::Sender<AppEvent>>>,
+    /// Whether the interface is on screen, so that nothing else can be shown
+    interface_running: AtomicBool,
+    sampler: RwLock<Option<Sampler>>,
+    pending_samplings: Mutex<HashMap<u64, oneshot::Sender<bool>>>,
+    pending_elicitations: Mutex<HashMap<u64, oneshot::Sender<McpElicitationAnswer>>>,
+    next_id: AtomicU64,
+}
+
+/// Answers the requests MCP servers send to the client. Clones share the
+/// pending requests, so the interface's answers reach the connection that
+/// waits for them.
+#[derive(Debug, Clone, Default)]
+pub struct McpRequestRouter {
+    state: Arc<RouterState>,
+}
+
+impl McpRequestRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the user about sampling and elicitation requests through `event_tx`
+    pub fn set_interface(&self, event_tx: mpsc::Sender<AppEvent>) {
+        *self.state.event_tx.write().expect("MCP router poisoned") = Some(event_tx);
+    }
+
+    /// Tell the router whether the interface is on screen and showing its events
+    pub fn set_interface_running(&self, running: bool) {
+        self.state
+            .interface_running
+            .store(running, Ordering::SeqCst);
+    }
+
+    /// Show `event` to the user through the interface while it is running.
+    /// Returns `false` when no interface is on screen to show it.
+    pub fn notify_interface(&self, event: AppEvent) -> bool {
+        if !self.state.interface_running.load(Ordering::SeqCst) {
+            return false;
+        }
+        match self.interface() {
+            Some(event_tx) => event_tx.try_send(event).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Answer approved sampling requests with `model` of `client`
+    pub fn set_sampler(&self, client: Arc<dyn LlmClient>, model: String) {
+        *self.state.sampler.write().expect("MCP router poisoned") = Some(Sampler { client, model });
+    }
+
+    /// Client capabilities announced during `initialize`: elicitation needs
+    /// someone to ask, sampling also needs a model
+    pub fn capabilities(&self) -> Value {
+        let mut capabilities = json!({});
+        if self.interface().is_some() {
+            capabilities["elicitation"] = json!({});
+            if self.sampler().is_some() {
+                capabilities["sampling"] = json!({});
+            }
+        }
+        capabilities
+    }
+
+    /// Handle a request of the named server and return the JSON-RPC response
+    /// to send back
+    pub async fn handle(&self, server_name: &str, request: &Value) -> Value {
+        let method = request["method"].as_str().unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "ping" => Ok(json!({})),
+            "sampling/createMessage" => self.sample(server_name, &params).await,
+            "elicitation/create" => self.elicit(server_name, &params).await,
+            _ => {
+                debug!(
+                    "MCP server '{}' sent unsupported request '{}'",
+                    server_name, method
+                );
+                Err((METHOD_NOT_FOUND, "Method not found".to_string()))
+            }
+        };
+
+        let mut response = json!({
+            "jsonrpc": "2.0",
+            "id": request["id"]
+        });
+        match result {
+            Ok(result) => response["result"] = result,
+            Err((code, message)) => {
+                response["error"] = json!({ "code": code, "message": message });
+            }
+        }
+        response
+    }
+
+    /// Pass the user's decision on a sampling request to the connection
+    /// waiting for it
+    pub fn approve_sampling(&self, id: u64, approved: bool) {
+        let pending = self
+            .state
+            .pending_samplings
+            .lock()
+            .expect("MCP router poisoned")
+            .remove(&id);
+        match pending {
+            Some(pending) => {
+                let _ = pending.send(approved);
+            }
+            None => warn!("No MCP sampling request {} is waiting for approval", id),
+        }
+    }
+
+    /// Pass the user's answer to an elicitation request to the connection
+    /// waiting for it
+    pub fn answer_elicitation(&self, id: u64, answer: McpElicitationAnswer) {
+        let pending = self
+            .state
+            .pending_elicitations
+            .lock()
+            .expect("MCP router poisoned")
+            .remove(&id);
+        match pending {
+            Some(pending) => {
+                let _ = pending.send(answer);
+            }
+            None => warn!("No MCP elicitation request {} is waiting for an answer", id),
+        }
+    }
+
+    fn interface(&self) -> Option<mpsc::Sender<AppEvent>> {
+        self.state
+            .event_tx
+            .read()
+            .expect("MCP router poisoned")
+            .clone()
+    }
+
+    fn sampler(&self) -> Option<Sampler> {
+        self.state
+            .sampler
+            .read()
+            .expect("MCP router poisoned")
+            .clone()
+    }
+
+    /// Have the model answer a `sampling/createMessage` request the user approved
+    async fn sample(&self, server_name: &str, params: &Value) -> Result<Value, (i64, String)> {
+        let (Some(event_tx), Some(sampler)) = (self.interface(), self.sampler()) else {
+            return Err((METHOD_NOT_FOUND, "Sampling is not supported".to_string()));
+        };
+
+        let messages: Vec<ChatMessage> = params["messages"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|message| {
+                let text = content_item_to_string(&message["content"]);
+                match message["role"].as_str() {
+                    Some("assistant") => ChatMessage::assistant(&text),
+                    _ => ChatMessage::user(&text),
+                }
+            })
+            .collect();
+        let system_prompt = params["systemPrompt"].as_str().map(str::to_string);
+
+        let id = self.state.next_id.fetch_add(1, Ordering::Relaxed);
+        let (approval_tx, approval_rx) = oneshot::channel();
+        self.state
+            .pending_samplings
+            .lock()
+            .expect("MCP router poisoned")
+            .insert(id, approval_tx);
+        let request = McpSamplingRequestInfo {
+            id,
+            server: server_name.to_string(),
+            system_prompt: system_prompt.clone(),
+            messages: messages.clone(),
+            max_tokens: params["maxTokens"].as_u64(),
+        };
+        info!("MCP server '{}' asks to sample from the model", server_name);
+        let approved = event_tx
+            .send(AppEvent::McpSamplingRequest(request))
+            .await
+            .is_ok()
+            && matches!(
+                timeout(USER_RESPONSE_TIMEOUT, approval_rx).await,
+                Ok(Ok(true))
+            );
+        self.state
+            .pending_samplings
+            .lock()
+            .expect("MCP router poisoned")
+            .remove(&id);
+        if !approved {
+            info!("Sampling request of MCP server '{}' rejected", server_name);
+            return Err((USER_REJECTED, "User rejected sampling request".to_string()));
+        }
+
+        let mut history: Vec<ChatMessage> = system_prompt
+            .as_deref()
+            .map(ChatMessage::system)
+            .into_iter()
+            .collect();
+        history.extend(messages);
+        // The answer is not streamed, so nothing is sent on the channel
+        let (tx, _rx) = mpsc::channel(1);
+        let reply = sampler
+            .client
+            .chat(&sampler.model, &history, &[], false, tx)
+            .await
+            .map_err(|e| (USER_REJECTED, format!("Sampling failed: {}", e)))?
+            .ok_or_else(|| (USER_REJECTED, "The model gave no answer".to_string()))?;
+        Ok(json!({
+            "role": "assistant",
+            "content": { "type": "text", "text": reply.content },
+            "model": sampler.model,
+            "stopReason": "endTurn"
+        }))
+    }
+
+    /// Ask the user to fill in the form of an `elicitation/create` request
+    async fn elicit(&self, server_name: &str, params: &Value) -> Result<Value, (i64, String)> {
+        let Some(event_tx) = self.interface() else {
+            return Err((METHOD_NOT_FOUND, "Elicitation is not supported".to_string()));
+        };
+
+        let id = self.state.next_id.fetch_add(1, Ordering::Relaxed);
+        let (answer_tx, answer_rx) = oneshot::channel();
+        self.state
+            .pending_elicitations
+            .lock()
+            .expect("MCP router poisoned")
+            .insert(id, answer_tx);
+        let request = McpElicitationRequestInfo {
+            id,
+            server: server_name.to_string(),
+            message: params["message"].as_str().unwrap_or_default().to_string(),
+            fields: elicitation_fields(&params["requestedSchema"]),
+        };
+        info!("MCP server '{}' asks the user for input", server_name);
+        let answer = if event_tx
+            .send(AppEvent::McpElicitationRequest(request))
+            .await
+            .is_ok()
+        {
+            match timeout(USER_RESPONSE_TIMEOUT, answer_rx).await {
+                Ok(Ok(answer)) => answer,
+                _ => McpElicitationAnswer::Cancel,
+            }
+        } else {
+            McpElicitationAnswer::Cancel
+        };
+        self.state
+            .pending_elicitations
+            .lock()
+            .expect("MCP router poisoned")
+            .remove(&id);
+
+        Ok(match answer {
+            McpElicitationAnswer::Accept(content) => {
+                json!({ "action": "accept", "content": content })
+            }
+            McpElicitationAnswer::Decline => json!({ "action": "decline" }),
+            McpElicitationAnswer::Cancel => json!({ "action": "cancel" }),
+        })
+    }
+}
+
+/// The fields of the flat object schema an elicitation request asks for
+pub fn elicitation_fields(schema: &Value) -> Vec<McpElicitationField> {
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema["properties"].as_object() else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let kind = match (property["type"].as_str(), property["enum"].as_array()) {
+                (_, Some(options)) => McpElicitationFieldKind::Choice(
+                    options
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect(),
+                ),
+                (Some("number"), _) => McpElicitationFieldKind::Number,
+                (Some("integer"), _) => McpElicitationFieldKind::Integer,
+                (Some("boolean"), _) => McpElicitationFieldKind::Boolean,
+                _ => McpElicitationFieldKind::Text,
+            };
+            McpElicitationField {
+                name: name.clone(),
+                title: property["title"].as_str().map(str::to_string),
+                description: property["description"].as_str().map(str::to_string),
+                kind,
+                required: required.contains(&name.as_str()),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,110 @@
+//! Incremental parser for `text/event-stream` (Server-Sent Events) bodies.
+//!
+//! MCP servers reached over HTTP stream JSON-RPC messages as SSE events. The
+//! parser is fed the body chunk by chunk as it arrives, so events are handled
+//! before the stream ends and events split across chunks are reassembled.
+
+/// One event of an SSE stream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Value of the `event:` field; `None` means the default `message` type
+    pub event: Option<String>,
+    /// The `data:` lines of the event joined with newlines
+    pub data: String,
+    /// Value of the `id:` field, used to resume the stream
+    pub id: Option<String>,
+    /// Reconnection time requested with the `retry:` field, in milliseconds
+    pub retry: Option<u64>,
+}
+
+impl SseEvent {
+    /// Type of the event, `message` when the server named none
+    pub fn event_type(&self) -> &str {
+        self.event.as_deref().unwrap_or("message")
+    }
+}
+
+/// Parser that turns chunks of an SSE body into events
+#[derive(Debug, Default)]
+pub struct SseParser {
+    /// Bytes of a line that has not been terminated yet
+    buffer: Vec<u8>,
+    /// The event whose fields are being read
+    pending: SseEvent,
+    /// Whether the pending event has seen any field
+    has_fields: bool,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next chunk of the body and return the events it completed
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(newline_pos) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let raw_line: Vec<u8> = self.buffer.drain(..=newline_pos).collect();
+            let line = String::from_utf8_lossy(&raw_line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Finish the stream, returning an event the server did not terminate
+    /// with a blank line
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+        let line = line.trim_end_matches('\r');
+        if !line.is_empty() {
+            self.process_line(line);
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            // Comment, often sent as a keep-alive
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.pending.event = Some(value.to_string()),
+            "data" => {
+                self.pending.data.push_str(value);
+                self.pending.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.pending.id = Some(value.to_string()),
+            "retry" => match value.parse() {
+                Ok(retry) => self.pending.retry = Some(retry),
+                Err(_) => return None,
+            },
+            _ => return None,
+        }
+        self.has_fields = true;
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if !self.has_fields {
+            return None;
+        }
+        self.has_fields = false;
+        let mut event = std::mem::take(&mut self.pending);
+        if event.data.ends_with('\n') {
+            event.data.pop();
+        }
+        Some(event)
+    }
+}
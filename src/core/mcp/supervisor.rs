@@ -0,0 +1,342 @@
+//! Supervision of locally launched MCP servers.
+//!
+//! The supervisor owns the `McpProcess` of every Docker, npx, uvx and command
+//! server it starts. Each server runs in its own task that watches the process,
+//! restarts it with exponential backoff when it exits, rediscovers its tools and
+//! swaps them in the connection registry. Status changes are sent as
+//! `AppEvent::McpServerStatus` events so the interface can show server health.
+
+use crate::core::mcp::config::{McpServerConfig, McpServerType};
+use crate::core::mcp::connection::StdioMcpConnection;
+use crate::core::mcp::launcher::{McpLauncher, McpProcess};
+use crate::core::mcp::manager::{McpConnectionRegistry, McpManager, connection_id};
+use crate::types::AppEvent;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, sleep};
+use tracing::{error, info, warn};
+
+/// Health of a supervised MCP server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum McpServerStatus {
+    /// The server is being launched for the first time
+    Starting,
+    /// The server is connected and offers this many tools
+    Running(usize),
+    /// The server exited and is restarted after a delay (attempt, delay)
+    Restarting(u32, Duration),
+    /// The server gave up after too many failed restarts
+    Failed(String),
+    /// The server was stopped by the supervisor
+    Stopped,
+}
+
+impl fmt::Display for McpServerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            McpServerStatus::Starting => write!(f, "Starting"),
+            McpServerStatus::Running(tools) => write!(f, "Running ({} tools)", tools),
+            McpServerStatus::Restarting(attempt, delay) => write!(
+                f,
+                "Restarting (attempt {} in {:.1}s)",
+                attempt,
+                delay.as_secs_f64()
+            ),
+            McpServerStatus::Failed(reason) => write!(f, "Failed: {}", reason),
+            McpServerStatus::Stopped => write!(f, "Stopped"),
+        }
+    }
+}
+
+/// When and how often a crashed server is restarted
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Delay before the first restart; doubled for every further attempt
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between restarts
+    pub max_delay: Duration,
+    /// Restarts in a row before giving up; `None` restarts forever
+    pub max_restarts: Option<u32>,
+    /// A server that ran this long resets the restart count when it exits
+    pub stable_after: Duration,
+    /// How often the server process is checked for an exit
+    pub check_interval: Duration,
+}
+
+impl RestartPolicy {
+    /// Delay before the given restart attempt (starting at 1)
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_restarts: Some(5),
+            stable_after: Duration::from_secs(60),
+            check_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Supervisor that owns the processes of locally launched MCP servers
+pub struct McpSupervisor {
+    registry: McpConnectionRegistry,
+    policy: RestartPolicy,
+    status_tx: Option<mpsc::Sender<AppEvent>>,
+    statuses: Arc<RwLock<HashMap<String, McpServerStatus>>>,
+    shutdown_tx: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl McpSupervisor {
+    /// Create a supervisor that publishes connections and tools in `registry`
+    pub fn new(registry: McpConnectionRegistry) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        Self {
+            registry,
+            policy: RestartPolicy::default(),
+            status_tx: None,
+            statuses: Arc::new(RwLock::new(HashMap::new())),
+            shutdown_tx,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Use `policy` for servers supervised from now on
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Send server status changes to `status_tx`
+    pub fn with_status_sender(mut self, status_tx: mpsc::Sender<AppEvent>) -> Self {
+        self.status_tx = Some(status_tx);
+        self
+    }
+
+    /// Launch a server and keep it running. Returns the number of tools found
+    /// by the first launch; when it fails the error is returned and the server
+    /// is retried in the background like after a crash.
+    pub async fn supervise(&mut self, config: McpServerConfig) -> Result<usize> {
+        if let McpServerType::Remote { .. } = config.server_type {
+            return Err(anyhow::anyhow!(
+                "MCP server '{}' is remote and has no process to supervise",
+                config.name
+            ));
+        }
+
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let server = SupervisedServer {
+            manager: McpManager::with_registry(self.registry.clone()),
+            policy: self.policy.clone(),
+            reporter: StatusReporter {
+                server_name: config.name.clone(),
+                statuses: self.statuses.clone(),
+                status_tx: self.status_tx.clone(),
+            },
+            shutdown_rx: self.shutdown_tx.subscribe(),
+            config,
+        };
+        self.tasks.push(tokio::spawn(server.run(ready_tx)));
+
+        ready_rx
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("MCP server supervisor task ended")))
+    }
+
+    /// Registry holding the connections and tools of the servers
+    pub fn registry(&self) -> &McpConnectionRegistry {
+        &self.registry
+    }
+
+    /// Current status of the named server
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn status(&self, server_name: &str) -> Option<McpServerStatus> {
+        let statuses = self.statuses.read().expect("MCP status map poisoned");
+        statuses.get(server_name).cloned()
+    }
+
+    /// Status of every supervised server, ordered by name
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn statuses(&self) -> Vec<(String, McpServerStatus)> {
+        let statuses = self.statuses.read().expect("MCP status map poisoned");
+        let mut statuses: Vec<(String, McpServerStatus)> = statuses
+            .iter()
+            .map(|(name, status)| (name.clone(), status.clone()))
+            .collect();
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        statuses
+    }
+
+    /// Stop every server, kill its process and remove Docker containers
+    pub async fn shutdown(&mut self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        info!("Stopping {} supervised MCP server(s)", self.tasks.len());
+        let _ = self.shutdown_tx.send(true);
+        for task in self.tasks.drain(..) {
+            if let Err(e) = task.await {
+                error!("MCP server supervisor task failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Records the status of one server and forwards it to the interface
+struct StatusReporter {
+    server_name: String,
+    statuses: Arc<RwLock<HashMap<String, McpServerStatus>>>,
+    status_tx: Option<mpsc::Sender<AppEvent>>,
+}
+
+impl StatusReporter {
+    async fn report(&self, status: McpServerStatus) {
+        info!("MCP server '{}' status: {}", self.server_name, status);
+        self.statuses
+            .write()
+            .expect("MCP status map poisoned")
+            .insert(self.server_name.clone(), status.clone());
+        if let Some(status_tx) = &self.status_tx {
+            let _ = status_tx
+                .send(AppEvent::McpServerStatus(
+                    self.server_name.clone(),
+                    status.to_string(),
+                ))
+                .await;
+        }
+    }
+}
+
+/// One server and everything its supervising task needs
+struct SupervisedServer {
+    config: McpServerConfig,
+    manager: McpManager,
+    policy: RestartPolicy,
+    reporter: StatusReporter,
+    shutdown_rx: watch::Receiver<bool>,
+}
+
+impl SupervisedServer {
+    /// Launch, watch and restart the server until it is shut down or gives up
+    async fn run(mut self, ready_tx: oneshot::Sender<Result<usize>>) {
+        let mut ready_tx = Some(ready_tx);
+        let mut restarts = 0;
+        self.reporter.report(McpServerStatus::Starting).await;
+
+        loop {
+            match self.start().await {
+                Ok((process, tool_count)) => {
+                    self.reporter
+                        .report(McpServerStatus::Running(tool_count))
+                        .await;
+                    if let Some(ready_tx) = ready_tx.take() {
+                        let _ = ready_tx.send(Ok(tool_count));
+                    }
+
+                    let started = Instant::now();
+                    let exited = self.watch(process).await;
+                    self.manager
+                        .registry
+                        .remove_connection(&connection_id(&self.config.name))
+                        .await;
+                    if !exited {
+                        self.reporter.report(McpServerStatus::Stopped).await;
+                        return;
+                    }
+                    if started.elapsed() >= self.policy.stable_after {
+                        restarts = 0;
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to start MCP server '{}': {}", self.config.name, e);
+                    if let Some(ready_tx) = ready_tx.take() {
+                        let _ = ready_tx.send(Err(e));
+                    }
+                }
+            }
+
+            if self
+                .policy
+                .max_restarts
+                .is_some_and(|max_restarts| restarts >= max_restarts)
+            {
+                self.reporter
+                    .report(McpServerStatus::Failed(format!(
+                        "gave up after {} restarts",
+                        restarts
+                    )))
+                    .await;
+                return;
+            }
+
+            restarts += 1;
+            let delay = self.policy.delay_for(restarts);
+            self.reporter
+                .report(McpServerStatus::Restarting(restarts, delay))
+                .await;
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = self.shutdown_rx.changed() => {
+                    self.reporter.report(McpServerStatus::Stopped).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Launch the server, connect to it and publish its tools
+    async fn start(&self) -> Result<(McpProcess, usize)> {
+        let mut process = McpLauncher::launch(&self.config).await?;
+        let connection = StdioMcpConnection::from_process_with_router(
+            &self.config.name,
+            &mut process,
+            self.manager.registry.request_router().clone(),
+        )
+        .await?;
+
+        let id = connection_id(&self.config.name);
+        self.manager
+            .registry
+            .add_stdio_connection(id.clone(), connection)
+            .await;
+        match self.manager.discover_server_tools(&self.config.name).await {
+            Ok(tools) => Ok((process, tools.len())),
+            Err(e) => {
+                self.manager.registry.remove_connection(&id).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Wait until the process exits (returns `true`) or the supervisor shuts
+    /// down (returns `false`, after stopping the process)
+    async fn watch(&mut self, mut process: McpProcess) -> bool {
+        loop {
+            if let Some(status) = process.exit_status() {
+                warn!("MCP server '{}' exited with {}", self.config.name, status);
+                return true;
+            }
+            tokio::select! {
+                _ = sleep(self.policy.check_interval) => {}
+                _ = self.shutdown_rx.changed() => {
+                    // Killing the process and removing its container blocks
+                    let _ = tokio::task::spawn_blocking(move || process.terminate()).await;
+                    return false;
+                }
+            }
+        }
+    }
+}
@@ -2,108 +2,112 @@
 //
 // This module is responsible for launching and managing MCP servers.
 
-use crate::config::{MCPConfig, MCPToolConfig};
+use crate::config::{MCPConfig, McpOAuthConfig};
+use crate::core::llm::client::LlmClient;
 use crate::core::mcp::config::{McpServerConfig, McpServerType};
-use crate::core::mcp::connection::StdioMcpConnection;
-use crate::core::mcp::manager::{McpManager as NewMcpManager, McpToolAdapter};
+use crate::core::mcp::manager::{
+    McpConnectionRegistry, McpManager as NewMcpManager, McpToolSource,
+};
+use crate::core::mcp::router::McpRequestRouter;
+use crate::core::mcp::supervisor::McpSupervisor;
 use crate::core::tools::ToolRegistry;
+use crate::types::AppEvent;
 use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 use tracing::{error, info};
 
-/// Truncate a description to the first 60 characters with an ellipsis if needed
-fn truncate_description(description: &str) -> String {
-    if description.len() > 60 {
-        format!("{}...", &description[..60])
-    } else {
-        description.to_string()
-    }
-}
-
 pub struct McpManager {
     tool_registry: ToolRegistry,
     new_manager: NewMcpManager,
+    supervisor: McpSupervisor,
 }
 
 impl McpManager {
-    pub fn new(tool_registry: ToolRegistry) -> Self {
+    pub fn new(mut tool_registry: ToolRegistry) -> Self {
+        // Each manager keeps its own connections, so servers and tools of one
+        // container never show up in another
+        let new_manager = NewMcpManager::with_registry(McpConnectionRegistry::new());
+        // MCP tools never take the name of a tool registered before them
+        new_manager
+            .registry
+            .reserve_tool_names(tool_registry.tool_names());
+        // MCP tools are looked up in the connection registry on every use, so
+        // tools swapped in after a server restart reach the agents
+        tool_registry.add_source(Box::new(McpToolSource::new(new_manager.registry.clone())));
+        let supervisor = McpSupervisor::new(new_manager.registry.clone());
         Self {
             tool_registry,
-            new_manager: NewMcpManager::new(),
+            new_manager,
+            supervisor,
         }
     }
 
-    pub async fn launch_servers(&mut self, tools: &[MCPToolConfig]) -> Result<()> {
-        info!(
-            "Starting MCP server launch process for {} tools",
-            tools.len()
-        );
+    /// Send the health of supervised servers to `status_tx`
+    pub fn with_status_sender(mut self, status_tx: mpsc::Sender<AppEvent>) -> Self {
+        self.supervisor = self.supervisor.with_status_sender(status_tx);
+        self
+    }
 
-        // Use the new manager to launch servers
-        self.new_manager.launch_servers(tools).await?;
+    /// Offer MCP tools under the given names, keyed by qualified tool name
+    pub fn with_tool_aliases(self, aliases: HashMap<String, String>) -> Self {
+        self.new_manager.registry.set_tool_aliases(aliases);
+        self
+    }
 
-        // Now we need to create tool adapters that reference the connections in the registry
-        for tool_config in tools {
-            // Connect to the server to discover its tools (for registration purposes)
-            let server_type = McpServerType::Command {
-                command: tool_config.command.clone(),
-                args: Some(tool_config.args.clone()),
-                environment: None,
-                working_directory: None,
-            };
+    /// Authorize remote servers without a token or API key with OAuth
+    pub fn with_oauth(mut self, oauth: McpOAuthConfig) -> Self {
+        self.new_manager = self.new_manager.with_oauth(oauth);
+        self
+    }
 
-            let config = McpServerConfig {
-                name: tool_config.name.clone(),
-                description: Some(format!("MCP server for {}", tool_config.name)),
-                server_type,
-                auto_start: Some(true),
-                environment: None,
-            };
+    /// Let servers ask the user for input through `event_tx` and, when a model
+    /// is given, sample from it once the user approved
+    pub fn with_client_requests(
+        self,
+        event_tx: mpsc::Sender<AppEvent>,
+        sampler: Option<(Arc<dyn LlmClient>, String)>,
+    ) -> Self {
+        let router = self.new_manager.registry.request_router();
+        router.set_interface(event_tx);
+        if let Some((client, model)) = sampler {
+            router.set_sampler(client, model);
+        }
+        self
+    }
+
+    /// Start every server by its type: local servers run under the supervisor,
+    /// remote servers get an HTTP connection. Servers with `auto_start` turned
+    /// off are skipped.
+    pub async fn launch_servers(&mut self, servers: &[McpServerConfig]) -> Result<()> {
+        info!(
+            "Starting MCP server launch process for {} servers",
+            servers.len()
+        );
 
-            // Create a temporary connection to discover tools (this is not ideal but needed for now)
-            match StdioMcpConnection::new(&config).await {
-                Ok(mut connection) => {
-                    match connection.discover_tools().await {
-                        Ok(mcp_tools) => {
-                            info!(
-                                "Discovered {} tools from MCP server '{}':",
-                                mcp_tools.len(),
-                                tool_config.name
-                            );
-
-                            let connection_id = format!("{}_connection", tool_config.name);
-
-                            // Add discovered tools to the registry as adapters
-                            for mcp_tool in mcp_tools {
-                                info!(
-                                    "  - Adding MCP tool adapter: {} - {}",
-                                    mcp_tool.name,
-                                    truncate_description(&mcp_tool.description)
-                                );
-
-                                // Create an adapter for the tool
-                                let tool_adapter = McpToolAdapter::new(
-                                    mcp_tool.name.clone(),
-                                    mcp_tool.description.clone(),
-                                    mcp_tool.input_schema.clone(),
-                                    connection_id.clone(),
-                                );
-
-                                self.tool_registry.add_tool(Box::new(tool_adapter));
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to discover tools from MCP server '{}': {}",
-                                tool_config.name, e
-                            );
-                        }
+        // Tools of every server execute through the connection registry
+        for config in servers {
+            if config.auto_start == Some(false) {
+                info!("Skipping MCP server '{}': auto start is off", config.name);
+                continue;
+            }
+            match &config.server_type {
+                McpServerType::Remote { url, .. } => {
+                    if let Err(e) = self.new_manager.connect_server(config).await {
+                        error!(
+                            "Failed to connect to remote MCP server '{}' at {}: {}",
+                            config.name, url, e
+                        );
                     }
                 }
-                Err(e) => {
-                    error!(
-                        "Failed to establish stdio connection to MCP server '{}': {}",
-                        tool_config.name, e
-                    );
+                _ => {
+                    if let Err(e) = self.supervisor.supervise(config.clone()).await {
+                        error!(
+                            "Failed to start MCP server '{}', retrying in the background: {}",
+                            config.name, e
+                        );
+                    }
                 }
             }
         }
@@ -140,89 +144,33 @@ impl McpManager {
                 environment: None,
             };
 
-            // Handle remote server launch using the new manager's approach
-            match &server_config.server_type {
-                McpServerType::Remote {
-                    url,
-                    access_token,
-                    api_key,
-                } => {
-                    // For remote servers, create an HTTP connection
+            // The discovered tools are offered through the registry's tool source
+            match self.new_manager.connect_server(&server_config).await {
+                Ok(_) => {
                     info!(
-                        "Creating HTTP connection to remote MCP server at URL: {}",
-                        url
-                    );
-
-                    let http_connection = crate::core::mcp::http::HttpMcpConnection::new(
-                        &server_config,
-                        url.clone(),
-                        access_token.clone(),
-                        api_key.clone(),
+                        "HTTP MCP server '{}' is running and ready for communication at URL: {}",
+                        server_config.name, server_url
                     );
-
-                    // Add connection to the registry
-                    let connection_id = format!("{}_connection", server_config.name);
-                    self.new_manager
-                        .get_registry()
-                        .add_http_connection(connection_id.clone(), http_connection)
-                        .await;
-
-                    // Discover tools from the remote server using the registry
-                    match self
-                        .new_manager
-                        .get_registry()
-                        .discover_tools_on_connection(&connection_id)
-                        .await
-                    {
-                        Ok(mcp_tools) => {
-                            info!(
-                                "Discovered {} tools from HTTP MCP server '{}':",
-                                mcp_tools.len(),
-                                server_config.name
-                            );
-
-                            // Add discovered tools to the registry as adapters
-                            for mcp_tool in mcp_tools {
-                                info!(
-                                    "  - Adding MCP tool adapter: {} - {}",
-                                    mcp_tool.name,
-                                    truncate_description(&mcp_tool.description)
-                                );
-
-                                // Create an adapter for the tool
-                                let tool_adapter = McpToolAdapter::new(
-                                    mcp_tool.name.clone(),
-                                    mcp_tool.description.clone(),
-                                    mcp_tool.input_schema.clone(),
-                                    connection_id.clone(),
-                                );
-
-                                self.tool_registry.add_tool(Box::new(tool_adapter));
-                            }
-
-                            info!(
-                                "HTTP MCP server '{}' is running and ready for communication at URL: {}",
-                                server_config.name, url
-                            );
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to discover tools from HTTP MCP server '{}': {}",
-                                server_config.name, e
-                            );
-                        }
-                    }
                 }
-                _ => {
-                    // This shouldn't happen for a remote server, but just to satisfy the compiler
-                    error!("Unexpected server type in remote server processing");
+                Err(e) => {
+                    error!(
+                        "Failed to discover tools from HTTP MCP server '{}': {}",
+                        server_config.name, e
+                    );
                 }
             }
         }
         Ok(())
     }
 
-    pub fn into_tool_registry(self) -> ToolRegistry {
-        self.tool_registry
+    /// The router that hands server requests to the interface
+    pub fn request_router(&self) -> McpRequestRouter {
+        self.new_manager.registry.request_router().clone()
+    }
+
+    /// The tool registry and the supervisor that keeps the MCP servers running.
+    /// Dropping the supervisor stops the servers.
+    pub fn into_parts(self) -> (ToolRegistry, McpSupervisor) {
+        (self.tool_registry, self.supervisor)
     }
 }
@@ -1,6 +1,7 @@
 //! Core modules for the OxideAgent system.
 
 pub mod agents;
+pub mod checkpoints;
 pub mod container;
 pub mod events;
 pub mod interface;
@@ -13,3 +14,6 @@ pub mod orchestrator;
 pub mod session;
 pub mod tool_permissions;
 pub mod tools;
+pub mod transcript;
+pub mod undo;
+pub mod workspace;
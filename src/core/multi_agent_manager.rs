@@ -1,14 +1,23 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{RwLock, broadcast, mpsc};
 
-use crate::config::LLMConfig;
+use crate::config::{
+    CheckpointMode, ContextConfig, LLMConfig, ToolScope, default_max_tool_iterations,
+};
 use crate::core::agents::Agent;
+use crate::core::checkpoints::CheckpointStore;
 use crate::core::session::{SessionManager, SessionState};
-use crate::core::tool_permissions::GlobalToolPermissions;
+use crate::core::tool_permissions::{GlobalToolPermissions, RuleOutcome};
 use crate::core::tools::ToolRegistry;
-use crate::types::{AppEvent, ToolApprovalResponse, ToolCall};
-use tracing::{error, info};
+use crate::core::undo::{ToolEffect, UndoStack};
+use crate::types::{AppEvent, ToolApprovalResponse, ToolCall, ToolCallDecision};
+use tracing::{error, info, warn};
+
+/// Tool result recorded when a deny list or permission rule rejects a call.
+const AUTO_DENIED_MESSAGE: &str =
+    "Tool execution denied by the user's tool permissions. Do not request this call again.";
 
 struct ChatContext<'a> {
     agent: &'a mut Agent,
@@ -18,6 +27,10 @@ struct ChatContext<'a> {
     event_tx: &'a broadcast::Sender<AppEvent>,
     session_state: &'a Arc<RwLock<SessionState>>,
     global_permissions: &'a mut GlobalToolPermissions,
+    undo_stack: &'a mut UndoStack,
+    checkpoints: &'a mut CheckpointStore,
+    status_key: &'a str,
+    max_tool_iterations: usize,
 }
 
 struct ApprovalContext<'a> {
@@ -27,6 +40,8 @@ struct ApprovalContext<'a> {
     tool_registry: &'a ToolRegistry,
     session_state: &'a Arc<RwLock<SessionState>>,
     global_permissions: &'a mut GlobalToolPermissions,
+    undo_stack: &'a mut UndoStack,
+    checkpoints: &'a mut CheckpointStore,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
@@ -68,6 +83,14 @@ pub struct MultiAgentManager {
     system_prompt: String,
     llm_config: LLMConfig,
     event_tx: broadcast::Sender<AppEvent>,
+    max_tool_iterations: usize,
+    context_config: ContextConfig,
+    checkpoint_mode: CheckpointMode,
+    workspace_root: PathBuf,
+    /// Tool scopes by agent name; agents without one see every tool
+    tool_scopes: HashMap<String, ToolScope>,
+    /// Where agents load and save their sessions
+    sessions: SessionManager,
 }
 
 impl MultiAgentManager {
@@ -83,9 +106,46 @@ impl MultiAgentManager {
             system_prompt,
             llm_config,
             event_tx,
+            max_tool_iterations: default_max_tool_iterations(),
+            context_config: ContextConfig::default(),
+            checkpoint_mode: CheckpointMode::default(),
+            workspace_root: PathBuf::from("."),
+            tool_scopes: HashMap::new(),
+            sessions: SessionManager::new("."),
+        }
+    }
+
+    /// Load and save the sessions of agents created later with `sessions`.
+    pub fn set_sessions(&mut self, sessions: SessionManager) {
+        self.sessions = sessions;
+    }
+
+    /// Restrict the tools offered to agents created later under `agent_name`.
+    pub fn set_tool_scope(&mut self, agent_name: &str, scope: ToolScope) {
+        if scope.is_unrestricted() {
+            self.tool_scopes.remove(agent_name);
+        } else {
+            self.tool_scopes.insert(agent_name.to_string(), scope);
         }
     }
 
+    /// Choose how agents checkpoint the workspace rooted at `workspace_root`
+    /// before file and shell tools run.
+    pub fn set_checkpoints(&mut self, mode: CheckpointMode, workspace_root: impl Into<PathBuf>) {
+        self.checkpoint_mode = mode;
+        self.workspace_root = workspace_root.into();
+    }
+
+    /// Limit how many times an agent re-queries the LLM after auto-approved tool calls.
+    pub fn set_max_tool_iterations(&mut self, max_tool_iterations: usize) {
+        self.max_tool_iterations = max_tool_iterations;
+    }
+
+    /// Choose how agents created later keep their history within the model's context window.
+    pub fn set_context_config(&mut self, context_config: ContextConfig) {
+        self.context_config = context_config;
+    }
+
     pub async fn create_agent(
         &self,
         agent_name: &str,
@@ -94,32 +154,38 @@ impl MultiAgentManager {
     ) -> anyhow::Result<AgentId> {
         let agent_id = AgentId::new(&format!("agent_{}", nanoid::nanoid!(8)));
 
-        // Create session filename for the agent
-        let session_file = SessionManager::get_session_filename(session_name.as_deref());
+        let session_name = session_name.unwrap_or_else(|| "default".to_string());
 
         // Load session state if it exists
-        let session_state = (SessionManager::load_state(&session_file)?).unwrap_or_default();
+        let session_state = self.sessions.load(&session_name)?.unwrap_or_default();
 
         // Create channels for the agent
         let (agent_tx, mut agent_rx) = mpsc::channel(100);
 
         // Clone necessary references for the task
         let agent_clone = self.system_prompt.clone();
-        let tool_registry_clone = self.tool_registry.clone_registry();
+        let tool_registry_clone = match self.tool_scopes.get(agent_name) {
+            Some(scope) => self.tool_registry.filtered(scope),
+            None => self.tool_registry.clone_registry(),
+        };
         let session_state_clone = Arc::new(RwLock::new(session_state.clone()));
         let session_state_for_task = session_state_clone.clone();
         let llm_config_clone = self.llm_config.clone();
         let event_tx_clone = self.event_tx.clone();
         let name_clone = agent_name.to_string();
         let model_clone = model.to_string();
-        let session_name_clone = session_name.unwrap_or_else(|| "default".to_string());
+        let max_tool_iterations = self.max_tool_iterations;
+        let context_config = self.context_config.clone();
+        let checkpoint_mode = self.checkpoint_mode;
+        let workspace_root = self.workspace_root.clone();
+        let sessions = self.sessions.clone();
 
         // Pre-clone values that will be used outside the async task
         let task_agent_name = name_clone.clone();
         let task_agent_tx = agent_tx.clone();
         let task_agent_id_for_task = agent_id.clone();
         let task_agent_id_for_handle = agent_id.clone();
-        let task_session_name = session_name_clone.clone();
+        let task_session_name = session_name;
 
         // Start the agent task
         // Start the agent task
@@ -129,6 +195,7 @@ impl MultiAgentManager {
                 .expect("Failed to create LLM client");
 
             let mut agent = Agent::new(&agent_clone, llm_client);
+            agent.set_context_config(context_config);
             // Set the model from session or use provided model
             let agent_model = if session_state.model() != "qwen3:4b" {
                 session_state.model().to_string()
@@ -143,11 +210,26 @@ impl MultiAgentManager {
             let mut pending_tool_calls: Option<Vec<ToolCall>> = None;
 
             // Global permissions for this agent task
-            let mut global_permissions = GlobalToolPermissions::load().unwrap_or_default();
+            let mut global_permissions = GlobalToolPermissions::load().unwrap_or_else(|e| {
+                warn!("{}", e);
+                event_tx_clone
+                    .send(AppEvent::Error(format!("{}; no tool is pre-approved", e)))
+                    .ok();
+                GlobalToolPermissions::failed_to_load(&e)
+            });
+
+            // Before-images of files changed by this agent's tool calls
+            let mut undo_stack = UndoStack::default();
+
+            // Workspace checkpoints for the current session
+            let mut checkpoints = CheckpointStore::new(checkpoint_mode, workspace_root);
+
+            // Key under which this agent's status is reported to the interface
+            let status_key = format!("{}-{}", name_clone, task_agent_id_for_task);
 
             // Notify that the agent is starting
             let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                format!("{}-{}", name_clone, task_agent_id_for_task),
+                status_key.clone(),
                 "Active".to_string(),
             ));
 
@@ -176,16 +258,11 @@ impl MultiAgentManager {
                                 // Save current state
                                 {
                                     let state_guard = session_state_for_task.read().await;
-                                    let session_file = SessionManager::get_session_filename(
-                                        if current_session_name == "default" {
-                                            None
-                                        } else {
-                                            Some(&current_session_name)
-                                        },
-                                    );
-                                    if let Err(e) =
-                                        SessionManager::save_state(&session_file, &state_guard)
-                                    {
+                                    if let Err(e) = sessions.save(
+                                        &current_session_name,
+                                        &state_guard,
+                                        Some(&name_clone),
+                                    ) {
                                         error!("Failed to save session state: {}", e);
                                         event_tx_clone
                                             .send(AppEvent::Error(format!(
@@ -198,20 +275,7 @@ impl MultiAgentManager {
                                 }
 
                                 // Load new state
-                                let new_name_str = if new_session_name == "default" {
-                                    "default"
-                                } else {
-                                    &new_session_name
-                                };
-                                let session_opt = if new_session_name == "default" {
-                                    None
-                                } else {
-                                    Some(new_session_name.as_str())
-                                };
-                                let new_session_file =
-                                    SessionManager::get_session_filename(session_opt);
-
-                                match SessionManager::load_state(&new_session_file) {
+                                match sessions.load(&new_session_name) {
                                     Ok(loaded_state) => {
                                         let new_state =
                                             loaded_state.unwrap_or_else(SessionState::new);
@@ -226,8 +290,11 @@ impl MultiAgentManager {
                                         // Update agent history
                                         agent.history = new_state.history().clone();
 
+                                        // Checkpoints refer to the old session's history
+                                        checkpoints.clear();
+
                                         // Update local session name
-                                        current_session_name = new_name_str.to_string();
+                                        current_session_name = new_session_name;
 
                                         // Notify TUI
                                         event_tx_clone
@@ -250,10 +317,52 @@ impl MultiAgentManager {
                                     }
                                 }
                             }
+                            AppEvent::ForkSession(source, new_name, message_index) => {
+                                // A fork of the current session includes its latest messages
+                                if source == current_session_name
+                                    && let Err(e) = sessions.save(
+                                        &current_session_name,
+                                        &*session_state_for_task.read().await,
+                                        Some(&name_clone),
+                                    )
+                                {
+                                    error!("Failed to save session state: {}", e);
+                                }
+
+                                match sessions.fork(&source, &new_name, message_index) {
+                                    Ok(metadata) => {
+                                        let kept = metadata
+                                            .parent
+                                            .as_ref()
+                                            .map(|parent| parent.message_index)
+                                            .unwrap_or_default();
+                                        event_tx_clone
+                                            .send(AppEvent::AgentMessage(format!(
+                                                "Forked session '{}' at message {} into '{}'.",
+                                                source, kept, new_name
+                                            )))
+                                            .ok();
+                                        // Continue in the fork
+                                        if let Err(e) =
+                                            agent_tx.try_send(AppEvent::SwitchSession(new_name))
+                                        {
+                                            error!("Failed to switch to forked session: {}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        event_tx_clone
+                                            .send(AppEvent::Error(format!(
+                                                "Failed to fork session: {}",
+                                                e
+                                            )))
+                                            .ok();
+                                    }
+                                }
+                            }
                             AppEvent::UserInput(input) => {
                                 // Update agent status
                                 let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                                    format!("{}-{}", name_clone, task_agent_id_for_task),
+                                    status_key.clone(),
                                     "Processing".to_string(),
                                 ));
 
@@ -275,6 +384,10 @@ impl MultiAgentManager {
                                     event_tx: &event_tx_clone,
                                     session_state: &session_state_for_task,
                                     global_permissions: &mut global_permissions,
+                                    undo_stack: &mut undo_stack,
+                                    checkpoints: &mut checkpoints,
+                                    status_key: &status_key,
+                                    max_tool_iterations,
                                 };
                                 if let Err(e) =
                                     Self::chat_with_agent(chat_context, &mut pending_tool_calls)
@@ -283,13 +396,22 @@ impl MultiAgentManager {
                                     event_tx_clone.send(AppEvent::Error(e.to_string())).ok();
                                 }
 
+                                // Keep the session and its metadata up to date on disk
+                                if let Err(e) = sessions.save(
+                                    &current_session_name,
+                                    &*session_state_for_task.read().await,
+                                    Some(&name_clone),
+                                ) {
+                                    error!("Failed to save session state: {}", e);
+                                }
+
                                 // Update status back to Idle
                                 let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                                    format!("{}-{}", name_clone, task_agent_id_for_task),
+                                    status_key.clone(),
                                     "Idle".to_string(),
                                 ));
                             }
-                            AppEvent::ToolApproval(response) => {
+                            AppEvent::ToolApproval(decisions) => {
                                 if let Some(tool_calls) = pending_tool_calls.take() {
                                     let approval_context = ApprovalContext {
                                         tx: &agent_tx,
@@ -298,11 +420,13 @@ impl MultiAgentManager {
                                         tool_registry: &tool_registry_clone,
                                         session_state: &session_state_for_task,
                                         global_permissions: &mut global_permissions,
+                                        undo_stack: &mut undo_stack,
+                                        checkpoints: &mut checkpoints,
                                     };
                                     if let Err(e) = Self::handle_tool_approval(
                                         approval_context,
                                         &tool_calls,
-                                        response,
+                                        &decisions,
                                     )
                                     .await
                                     {
@@ -313,7 +437,7 @@ impl MultiAgentManager {
                             AppEvent::ContinueConversation => {
                                 // Update agent status
                                 let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                                    format!("{}-{}", name_clone, task_agent_id_for_task),
+                                    status_key.clone(),
                                     "Processing".to_string(),
                                 ));
 
@@ -326,6 +450,10 @@ impl MultiAgentManager {
                                     event_tx: &event_tx_clone,
                                     session_state: &session_state_for_task,
                                     global_permissions: &mut global_permissions,
+                                    undo_stack: &mut undo_stack,
+                                    checkpoints: &mut checkpoints,
+                                    status_key: &status_key,
+                                    max_tool_iterations,
                                 };
                                 if let Err(e) =
                                     Self::chat_with_agent(chat_context, &mut pending_tool_calls)
@@ -334,12 +462,83 @@ impl MultiAgentManager {
                                     event_tx_clone.send(AppEvent::Error(e.to_string())).ok();
                                 }
 
+                                // Keep the session and its metadata up to date on disk
+                                if let Err(e) = sessions.save(
+                                    &current_session_name,
+                                    &*session_state_for_task.read().await,
+                                    Some(&name_clone),
+                                ) {
+                                    error!("Failed to save session state: {}", e);
+                                }
+
                                 // Update status back to Idle
                                 let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                                    format!("{}-{}", name_clone, task_agent_id_for_task),
+                                    status_key.clone(),
                                     "Idle".to_string(),
                                 ));
                             }
+                            AppEvent::UndoLastToolEffect => match undo_stack.undo_last() {
+                                Ok(Some(effect)) => {
+                                    let paths: Vec<String> = effect
+                                        .snapshots
+                                        .iter()
+                                        .map(|snapshot| format!("'{}'", snapshot.path().display()))
+                                        .collect();
+                                    event_tx_clone
+                                        .send(AppEvent::AgentMessage(format!(
+                                            "Undid '{}': restored {}",
+                                            effect.tool_name,
+                                            paths.join(", ")
+                                        )))
+                                        .ok();
+                                }
+                                Ok(None) => {
+                                    event_tx_clone
+                                        .send(AppEvent::AgentMessage(
+                                            "Nothing to undo.".to_string(),
+                                        ))
+                                        .ok();
+                                }
+                                Err(e) => {
+                                    error!("Failed to undo tool effect: {}", e);
+                                    event_tx_clone
+                                        .send(AppEvent::Error(format!("Failed to undo: {}", e)))
+                                        .ok();
+                                }
+                            },
+                            AppEvent::ListCheckpoints => {
+                                event_tx_clone
+                                    .send(AppEvent::CheckpointList(checkpoints.list()))
+                                    .ok();
+                            }
+                            AppEvent::RestoreCheckpoint(checkpoint_id) => {
+                                match checkpoints.restore(checkpoint_id).await {
+                                    Ok(message_index) => {
+                                        agent.history.truncate(message_index);
+                                        session_state_for_task
+                                            .write()
+                                            .await
+                                            .set_history(agent.history.clone());
+                                        // Undo snapshots and pending calls belong to the discarded turns
+                                        undo_stack = UndoStack::default();
+                                        pending_tool_calls = None;
+
+                                        event_tx_clone
+                                            .send(AppEvent::SessionHistory(agent.history.clone()))
+                                            .ok();
+                                        event_tx_clone
+                                            .send(AppEvent::AgentMessage(format!(
+                                                "Restored checkpoint {}: files and conversation rolled back to message {}.",
+                                                checkpoint_id, message_index
+                                            )))
+                                            .ok();
+                                    }
+                                    Err(e) => {
+                                        error!("Failed to restore checkpoint: {}", e);
+                                        event_tx_clone.send(AppEvent::Error(e.to_string())).ok();
+                                    }
+                                }
+                            }
                             AppEvent::AgentStatusUpdate(_, _) => {
                                 // Ignore status updates sent to agent - these are for TUI
                             }
@@ -363,7 +562,7 @@ impl MultiAgentManager {
                     Err(_) => {
                         // Timeout occurred, update status and continue
                         let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                            format!("{}-{}", name_clone, task_agent_id_for_task),
+                            status_key.clone(),
                             "Idle".to_string(),
                         ));
                     }
@@ -372,7 +571,7 @@ impl MultiAgentManager {
 
             // Notify that the agent task is ending
             let _ = event_tx_clone.send(AppEvent::AgentStatusUpdate(
-                format!("{}-{}", name_clone, task_agent_id_for_task),
+                status_key.clone(),
                 "Stopped".to_string(),
             ));
 
@@ -401,11 +600,15 @@ impl MultiAgentManager {
         Ok(agent_id)
     }
 
+    /// Run the agentic loop: query the LLM, execute auto-approved tool calls and feed
+    /// their results back until the model answers without tool calls, a call needs
+    /// user approval, or `max_tool_iterations` round-trips have been made.
     async fn chat_with_agent(
         context: ChatContext<'_>,
         pending_tool_calls: &mut Option<Vec<ToolCall>>,
     ) -> anyhow::Result<()> {
         let tool_definitions = context.tool_registry.definitions();
+        let max_iterations = context.max_tool_iterations.max(1);
 
         info!("=== MULTI-AGENT CHAT REQUEST START ===");
         info!(
@@ -421,131 +624,149 @@ impl MultiAgentManager {
             );
         }
 
-        info!(
-            "Sending chat request to agent with model: {}...",
-            context.model
-        );
-        let response = match context
-            .agent
-            .chat(
-                context.model,
-                &tool_definitions,
-                true, // Enable streaming by default
-                context.sender.clone(),
-            )
-            .await
-        {
-            Ok(response) => response,
-            Err(e) => {
-                let error_msg = format!("Error communicating with LLM: {}", e);
-                context
-                    .event_tx
-                    .send(AppEvent::Error(error_msg.clone()))
-                    .ok();
-                context.agent.add_user_message(&format!("Error: {}", e));
-                return Err(e);
-            }
-        };
+        for iteration in 1..=max_iterations {
+            context
+                .event_tx
+                .send(AppEvent::AgentStatusUpdate(
+                    context.status_key.to_string(),
+                    format!("Processing (iteration {}/{})", iteration, max_iterations),
+                ))
+                .ok();
 
-        info!("=== MULTI-AGENT CHAT REQUEST END ===");
+            info!(
+                "Sending chat request to agent with model: {} (iteration {}/{})...",
+                context.model, iteration, max_iterations
+            );
+            let response = match context
+                .agent
+                .chat(
+                    context.model,
+                    &tool_definitions,
+                    true, // Enable streaming by default
+                    context.sender.clone(),
+                )
+                .await
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    let error_msg = format!("Error communicating with LLM: {}", e);
+                    context
+                        .event_tx
+                        .send(AppEvent::Error(error_msg.clone()))
+                        .ok();
+                    context.agent.add_user_message(&format!("Error: {}", e));
+                    return Err(e);
+                }
+            };
+
+            let Some(tool_calls) = response
+                .and_then(|response| response.tool_calls)
+                .filter(|tool_calls| !tool_calls.is_empty())
+            else {
+                break;
+            };
 
-        if let Some(response) = response
-            && let Some(tool_calls) = &response.tool_calls
-        {
             info!("=== MULTI-AGENT RECEIVED TOOL CALLS ===");
             info!("Received {} tool calls from agent", tool_calls.len());
 
             // Read session state once before checking permissions (async-safe)
             let session_state_guard = context.session_state.read().await;
 
-            // Check if all tools are already approved
-            let all_approved = tool_calls.iter().all(|tool_call| {
-                // Check global permissions first
-                if context
-                    .global_permissions
-                    .is_allowed(&tool_call.function.name)
-                {
-                    info!("Tool '{}' is globally approved", tool_call.function.name);
-                    return true;
-                }
-                // Check session permissions (using pre-acquired read guard)
-                if session_state_guard.is_tool_allowed(&tool_call.function.name) {
-                    info!("Tool '{}' is session approved", tool_call.function.name);
-                    return true;
+            // Resolve each call against the permission rules and allow lists
+            let mut tool_calls_to_run = Vec::with_capacity(tool_calls.len());
+            let mut all_approved = true;
+            for tool_call in tool_calls {
+                let outcome = Self::resolve_permission(
+                    context.global_permissions,
+                    &session_state_guard,
+                    context.tool_registry,
+                    &tool_call,
+                );
+                info!(
+                    "Tool '{}' permission: {:?}",
+                    tool_call.function.name, outcome
+                );
+                match outcome {
+                    RuleOutcome::Allow => tool_calls_to_run.push(tool_call),
+                    RuleOutcome::Ask => {
+                        all_approved = false;
+                        tool_calls_to_run.push(tool_call);
+                    }
+                    RuleOutcome::Deny => {
+                        context
+                            .agent
+                            .add_tool_message(&tool_call, AUTO_DENIED_MESSAGE);
+                        context
+                            .event_tx
+                            .send(AppEvent::AgentMessage(format!(
+                                "Tool '{}' execution denied by tool permissions.",
+                                tool_call.function.name
+                            )))
+                            .ok();
+                    }
                 }
-                info!("Tool '{}' requires approval", tool_call.function.name);
-                // Not approved
-                false
-            });
+            }
 
             // Drop the read guard before potential writes
             drop(session_state_guard);
 
-            if all_approved {
-                info!("All tool calls are approved, executing automatically...");
-                // Execute all tools without approval
-                for (i, tool_call) in tool_calls.iter().enumerate() {
-                    info!(
-                        "Executing tool call {}: {} with args: {}",
-                        i + 1,
-                        tool_call.function.name,
-                        tool_call.function.arguments
-                    );
-                    if let Some(tool) = context.tool_registry.get_tool(&tool_call.function.name) {
-                        match tool.execute(&tool_call.function.arguments).await {
-                            Ok(tool_output) => {
-                                info!(
-                                    "Tool '{}' completed with output: {}",
-                                    tool_call.function.name, tool_output
-                                );
-                                context
-                                    .event_tx
-                                    .send(AppEvent::ToolResult(
-                                        tool_call.function.name.clone(),
-                                        tool_output.clone(),
-                                    ))
-                                    .ok(); // Use ok() to handle potential broadcast errors gracefully
-                                context.agent.add_user_message(&format!(
-                                    "The tool '{}' produced this output:\n{}",
-                                    tool_call.function.name, tool_output
-                                ));
-                            }
-                            Err(e) => {
-                                let error_msg = format!(
-                                    "Error executing tool '{}': {}",
-                                    tool_call.function.name, e
-                                );
-                                context
-                                    .event_tx
-                                    .send(AppEvent::Error(error_msg.clone()))
-                                    .ok(); // Use ok() to handle potential broadcast errors gracefully
-                                context.agent.add_user_message(&format!(
-                                    "Error executing tool '{}': {}",
-                                    tool_call.function.name, e
-                                ));
-                            }
-                        }
-                    } else {
-                        let error_msg = format!("Unknown tool: {}", tool_call.function.name);
-                        context
-                            .event_tx
-                            .send(AppEvent::Error(error_msg.clone()))
-                            .ok(); // Use ok() to handle potential broadcast errors gracefully
-                        context.agent.add_user_message(&error_msg);
-                    }
-                }
-            } else {
+            if !all_approved {
                 info!("Some tool calls require approval, requesting user approval...");
+                let previews = tool_calls_to_run
+                    .iter()
+                    .map(|tool_call| {
+                        context
+                            .tool_registry
+                            .get_tool(&tool_call.function.name)
+                            .and_then(|tool| tool.preview(&tool_call.function.arguments))
+                    })
+                    .collect();
                 // Send tool calls for approval
                 context
                     .event_tx
-                    .send(AppEvent::ToolRequest(tool_calls.clone()))
+                    .send(AppEvent::ToolRequest(tool_calls_to_run.clone(), previews))
                     .ok(); // Use ok() to handle potential broadcast errors gracefully
-                *pending_tool_calls = Some(tool_calls.clone());
+                *pending_tool_calls = Some(tool_calls_to_run);
+                break;
+            }
+
+            info!("All tool calls are approved, executing automatically...");
+            for (i, tool_call) in tool_calls_to_run.iter().enumerate() {
+                info!(
+                    "Executing tool call {}: {} with args: {}",
+                    i + 1,
+                    tool_call.function.name,
+                    tool_call.function.arguments
+                );
+                Self::execute_tool_call(
+                    context.agent,
+                    context.tool_registry,
+                    context.event_tx,
+                    context.undo_stack,
+                    context.checkpoints,
+                    tool_call,
+                )
+                .await;
             }
             info!("=== MULTI-AGENT TOOL CALL PROCESSING END ===");
+
+            if iteration == max_iterations {
+                warn!(
+                    "Reached the limit of {} tool iterations, waiting for user input",
+                    max_iterations
+                );
+                context
+                    .event_tx
+                    .send(AppEvent::AgentMessage(format!(
+                        "Stopped after {} tool iterations. Send a message to let the agent continue.",
+                        max_iterations
+                    )))
+                    .ok();
+            }
         }
 
+        info!("=== MULTI-AGENT CHAT REQUEST END ===");
+
         // Save the session state after each interaction
         {
             let mut state = context.session_state.write().await;
@@ -556,10 +777,121 @@ impl MultiAgentManager {
         Ok(())
     }
 
+    /// Resolve a tool call against the global and session permissions.
+    ///
+    /// Permissions are keyed by the qualified tool name, so an MCP tool
+    /// offered under an alias keeps the permissions of `server__tool`. The
+    /// session deny list is checked first; everything else is decided by
+    /// `GlobalToolPermissions::resolve`.
+    fn resolve_permission(
+        global_permissions: &GlobalToolPermissions,
+        session_state: &SessionState,
+        tool_registry: &ToolRegistry,
+        tool_call: &ToolCall,
+    ) -> RuleOutcome {
+        let mut function = tool_call.function.clone();
+        function.name = tool_registry.qualified_name(&function.name);
+        if session_state.is_tool_denied(&function.name) {
+            return RuleOutcome::Deny;
+        }
+        let session_allowed = session_state.is_tool_allowed(&function.name);
+        global_permissions.resolve(&function, session_allowed)
+    }
+
+    /// Execute a single tool call and record its result as a `tool` message.
+    ///
+    /// A workspace checkpoint is taken first, and files the tool reports as
+    /// affected are snapshotted; the snapshot is pushed onto the undo stack
+    /// when the call succeeds.
+    async fn execute_tool_call(
+        agent: &mut Agent,
+        tool_registry: &ToolRegistry,
+        event_tx: &broadcast::Sender<AppEvent>,
+        undo_stack: &mut UndoStack,
+        checkpoints: &mut CheckpointStore,
+        tool_call: &ToolCall,
+    ) {
+        let Some(tool) = tool_registry.get_tool(&tool_call.function.name) else {
+            let error_msg = format!("Unknown tool: {}", tool_call.function.name);
+            event_tx.send(AppEvent::Error(error_msg.clone())).ok();
+            agent.add_tool_message(tool_call, &error_msg);
+            return;
+        };
+
+        let affected_paths = tool.affected_paths(&tool_call.function.arguments);
+
+        // Tie the checkpoint to the assistant message that requested the call
+        let message_index = agent
+            .history
+            .iter()
+            .rposition(|message| message.tool_calls.is_some())
+            .unwrap_or(agent.history.len());
+        if let Err(e) = checkpoints
+            .record(
+                &tool_call.function.name,
+                tool.profile(),
+                &affected_paths,
+                message_index,
+            )
+            .await
+        {
+            warn!(
+                "Could not checkpoint the workspace before '{}': {}",
+                tool_call.function.name, e
+            );
+        }
+
+        let before_image = if affected_paths.is_empty() {
+            None
+        } else {
+            match ToolEffect::capture(&tool_call.function.name, &affected_paths) {
+                Ok(effect) => Some(effect),
+                Err(e) => {
+                    warn!(
+                        "Could not snapshot files for '{}', it can't be undone: {}",
+                        tool_call.function.name, e
+                    );
+                    None
+                }
+            }
+        };
+
+        match tool.execute(&tool_call.function.arguments).await {
+            Ok(tool_output) => {
+                if let Some(effect) = before_image {
+                    undo_stack.push(effect);
+                }
+                info!(
+                    "Tool '{}' completed with output: {}",
+                    tool_call.function.name, tool_output
+                );
+                event_tx
+                    .send(AppEvent::ToolResult(
+                        tool_call.function.name.clone(),
+                        tool_output.clone(),
+                    ))
+                    .ok();
+                agent.add_tool_message(tool_call, &tool_output);
+            }
+            Err(e) => {
+                let error_msg =
+                    format!("Error executing tool '{}': {}", tool_call.function.name, e);
+                event_tx.send(AppEvent::Error(error_msg.clone())).ok();
+                agent.add_tool_message(tool_call, &error_msg);
+            }
+        }
+    }
+
+    /// Apply the user's per-call decisions to a pending tool call batch.
+    ///
+    /// Calls without an explicit decision run only if they are already allowed,
+    /// and calls matched by a deny rule never run. Denied calls are reported
+    /// back to the model as denied tool results, and the conversation goes on
+    /// even when every call was denied, so the model can react to it.
     async fn handle_tool_approval(
         context: ApprovalContext<'_>,
         tool_calls: &[ToolCall],
-        response: ToolApprovalResponse,
+        decisions: &[ToolCallDecision],
     ) -> anyhow::Result<()> {
         let tx = context.tx;
         let event_tx = context.event_tx;
@@ -567,130 +899,103 @@ impl MultiAgentManager {
         let tool_registry = context.tool_registry;
         let session_state = context.session_state;
         let global_permissions = context.global_permissions;
+        let undo_stack = context.undo_stack;
+        let checkpoints = context.checkpoints;
 
-        match response {
-            ToolApprovalResponse::Allow => {
-                for tool_call in tool_calls {
-                    if let Some(tool) = tool_registry.get_tool(&tool_call.function.name) {
-                        match tool.execute(&tool_call.function.arguments).await {
-                            Ok(tool_output) => {
-                                event_tx.send(AppEvent::ToolResult(
-                                    tool_call.function.name.clone(),
-                                    tool_output.clone(),
-                                ))?;
-                                agent.add_user_message(&format!(
-                                    "The tool '{}' produced this output:\n{}",
-                                    tool_call.function.name, tool_output
-                                ));
-                            }
-                            Err(e) => {
-                                let error_msg = format!(
-                                    "Error executing tool '{}': {}",
-                                    tool_call.function.name, e
-                                );
-                                event_tx.send(AppEvent::Error(error_msg.clone()))?;
-                                agent.add_user_message(&error_msg);
-                            }
-                        }
-                    } else {
-                        let error_msg = format!("Unknown tool: {}", tool_call.function.name);
-                        event_tx.send(AppEvent::Error(error_msg.clone()))?;
-                        agent.add_user_message(&error_msg);
-                    }
+        let mut global_permissions_changed = false;
+
+        for (index, tool_call) in tool_calls.iter().enumerate() {
+            let tool_name = &tool_registry.qualified_name(&tool_call.function.name);
+            let outcome = Self::resolve_permission(
+                global_permissions,
+                &*session_state.read().await,
+                tool_registry,
+                tool_call,
+            );
+            let response = match (
+                outcome,
+                decisions.iter().find(|decision| decision.index == index),
+            ) {
+                // Deny lists and rules can't be overridden from the approval prompt
+                (RuleOutcome::Deny, _) => {
+                    agent.add_tool_message(tool_call, AUTO_DENIED_MESSAGE);
+                    continue;
                 }
-                // Send event to continue conversation
-                tx.send(AppEvent::ContinueConversation).await?;
-            }
-            ToolApprovalResponse::AlwaysAllow => {
-                // Add tools to global permissions
-                for tool_call in tool_calls {
-                    global_permissions.add_allowed(&tool_call.function.name);
+                (_, Some(decision)) => decision.response.clone(),
+                (RuleOutcome::Allow, None) => ToolApprovalResponse::Allow,
+                (RuleOutcome::Ask, None) => ToolApprovalResponse::Deny,
+            };
+            info!(
+                "Tool call {} ('{}') decision: {:?}",
+                index, tool_name, response
+            );
+
+            match response {
+                ToolApprovalResponse::Allow => {}
+                ToolApprovalResponse::AlwaysAllow => {
+                    global_permissions.add_allowed(tool_name);
+                    global_permissions_changed = true;
                 }
-                // Save global permissions
-                if let Err(e) = global_permissions.save() {
-                    event_tx.send(AppEvent::Error(format!(
-                        "Failed to save global tool permissions: {}",
-                        e
-                    )))?;
+                ToolApprovalResponse::AlwaysAllowSession => {
+                    session_state
+                        .write()
+                        .await
+                        .add_allowed_tool(tool_name.clone());
                 }
-
-                // Execute tools
-                for tool_call in tool_calls {
-                    if let Some(tool) = tool_registry.get_tool(&tool_call.function.name) {
-                        match tool.execute(&tool_call.function.arguments).await {
-                            Ok(tool_output) => {
-                                event_tx.send(AppEvent::ToolResult(
-                                    tool_call.function.name.clone(),
-                                    tool_output.clone(),
-                                ))?;
-                                agent.add_user_message(&format!(
-                                    "The tool '{}' produced this output:\n{}",
-                                    tool_call.function.name, tool_output
-                                ));
-                            }
-                            Err(e) => {
-                                let error_msg = format!(
-                                    "Error executing tool '{}': {}",
-                                    tool_call.function.name, e
-                                );
-                                event_tx.send(AppEvent::Error(error_msg.clone()))?;
-                                agent.add_user_message(&error_msg);
-                            }
-                        }
-                    } else {
-                        let error_msg = format!("Unknown tool: {}", tool_call.function.name);
-                        event_tx.send(AppEvent::Error(error_msg.clone()))?;
-                        agent.add_user_message(&error_msg);
-                    }
+                ToolApprovalResponse::AlwaysDeny => {
+                    global_permissions.add_denied(tool_name);
+                    global_permissions_changed = true;
+                    agent.add_tool_message(tool_call, AUTO_DENIED_MESSAGE);
+                    event_tx.send(AppEvent::AgentMessage(format!(
+                        "Tool '{}' will always be denied.",
+                        tool_name
+                    )))?;
+                    continue;
                 }
-                // Send event to continue conversation
-                tx.send(AppEvent::ContinueConversation).await?;
-            }
-            ToolApprovalResponse::AlwaysAllowSession => {
-                // Add tools to session permissions
-                for tool_call in tool_calls {
+                ToolApprovalResponse::AlwaysDenySession => {
                     session_state
                         .write()
                         .await
-                        .add_allowed_tool(tool_call.function.name.clone());
+                        .add_denied_tool(tool_name.clone());
+                    agent.add_tool_message(tool_call, AUTO_DENIED_MESSAGE);
+                    event_tx.send(AppEvent::AgentMessage(format!(
+                        "Tool '{}' will be denied for the rest of this session.",
+                        tool_name
+                    )))?;
+                    continue;
                 }
-
-                // Execute tools
-                for tool_call in tool_calls {
-                    if let Some(tool) = tool_registry.get_tool(&tool_call.function.name) {
-                        match tool.execute(&tool_call.function.arguments).await {
-                            Ok(tool_output) => {
-                                event_tx.send(AppEvent::ToolResult(
-                                    tool_call.function.name.clone(),
-                                    tool_output.clone(),
-                                ))?;
-                                agent.add_user_message(&format!(
-                                    "The tool '{}' produced this output:\n{}",
-                                    tool_call.function.name, tool_output
-                                ));
-                            }
-                            Err(e) => {
-                                let error_msg = format!(
-                                    "Error executing tool '{}': {}",
-                                    tool_call.function.name, e
-                                );
-                                event_tx.send(AppEvent::Error(error_msg.clone()))?;
-                                agent.add_user_message(&error_msg);
-                            }
-                        }
-                    } else {
-                        let error_msg = format!("Unknown tool: {}", tool_call.function.name);
-                        event_tx.send(AppEvent::Error(error_msg.clone()))?;
-                        agent.add_user_message(&error_msg);
-                    }
+                ToolApprovalResponse::Deny => {
+                    agent.add_tool_message(tool_call, "Tool execution denied by user.");
+                    event_tx.send(AppEvent::AgentMessage(format!(
+                        "Tool '{}' execution denied.",
+                        tool_name
+                    )))?;
+                    continue;
                 }
-                // Send event to continue conversation
-                tx.send(AppEvent::ContinueConversation).await?;
-            }
-            ToolApprovalResponse::Deny => {
-                agent.add_user_message("Tool execution denied by user.");
-                event_tx.send(AppEvent::AgentMessage("Tool execution denied.".to_string()))?;
             }
+
+            Self::execute_tool_call(
+                agent,
+                tool_registry,
+                event_tx,
+                undo_stack,
+                checkpoints,
+                tool_call,
+            )
+            .await;
+        }
+
+        // Save global permissions
+        if global_permissions_changed && let Err(e) = global_permissions.save() {
+            event_tx.send(AppEvent::Error(format!(
+                "Failed to save global tool permissions: {}",
+                e
+            )))?;
+        }
+
+        // Every call now has a result, denials included, for the model to see
+        if !tool_calls.is_empty() {
+            tx.send(AppEvent::ContinueConversation).await?;
         }
         Ok(())
     }
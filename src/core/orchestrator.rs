@@ -1,8 +1,10 @@
-use crate::config::LLMConfig;
+use crate::config::{AgentConfig, CheckpointMode, ContextConfig, LLMConfig};
+use crate::core::mcp::manager::McpConnectionRegistry;
 use crate::core::multi_agent_manager::{AgentId, MultiAgentManager};
 use crate::core::session::SessionManager;
 use crate::core::tools::ToolRegistry;
-use crate::types::{AppEvent, ChatMessage};
+use crate::types::{AppEvent, ChatMessage, McpPromptInfo, McpResourceInfo};
+use std::path::PathBuf;
 use tokio::sync::mpsc;
 use tracing::error;
 
@@ -15,6 +17,12 @@ pub struct Orchestrator {
     rx: mpsc::Receiver<AppEvent>,
     model: String,
     llm_config: LLMConfig,
+    /// Named agents from the configuration, created on first switch
+    agent_configs: Vec<AgentConfig>,
+    /// Connections to the MCP servers, for their resources, prompts and requests
+    mcp_registry: Option<McpConnectionRegistry>,
+    /// Where sessions are saved
+    sessions: SessionManager,
 }
 
 impl Orchestrator {
@@ -74,11 +82,55 @@ impl Orchestrator {
             rx,
             model,
             llm_config,
+            agent_configs: Vec::new(),
+            mcp_registry: None,
+            sessions: SessionManager::new("."),
         }
     }
 
-    pub fn list_sessions() -> anyhow::Result<Vec<String>> {
-        SessionManager::list_sessions()
+    /// Register the tool scope of the default agent and the configured named
+    /// agents, whose models are used when switching to them.
+    pub fn configure_agents(&mut self, default_agent: &AgentConfig, agents: &[AgentConfig]) {
+        self.multi_agent_manager
+            .set_tool_scope("default", default_agent.tool_scope.clone());
+        for agent in agents {
+            self.multi_agent_manager
+                .set_tool_scope(&agent.name, agent.tool_scope.clone());
+        }
+        self.agent_configs = agents.to_vec();
+    }
+
+    /// Limit how many times an agent re-queries the LLM after auto-approved tool calls.
+    pub fn set_max_tool_iterations(&mut self, max_tool_iterations: usize) {
+        self.multi_agent_manager
+            .set_max_tool_iterations(max_tool_iterations);
+    }
+
+    /// Choose how agents keep their history within the model's context window.
+    pub fn set_context_config(&mut self, context_config: ContextConfig) {
+        self.multi_agent_manager.set_context_config(context_config);
+    }
+
+    /// Choose how agents checkpoint the workspace before file and shell tools run.
+    pub fn set_checkpoints(&mut self, mode: CheckpointMode, workspace_root: impl Into<PathBuf>) {
+        self.multi_agent_manager
+            .set_checkpoints(mode, workspace_root);
+    }
+
+    /// Browse resources and prompts of the MCP servers in `registry` and pass
+    /// the user's answers to their requests back to them.
+    pub fn set_mcp_registry(&mut self, registry: McpConnectionRegistry) {
+        self.mcp_registry = Some(registry);
+    }
+
+    /// Keep sessions in `dir` instead of the current directory.
+    pub fn set_sessions_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.sessions = SessionManager::new(dir);
+        self.multi_agent_manager.set_sessions(self.sessions.clone());
+    }
+
+    pub fn list_sessions(&self) -> anyhow::Result<Vec<String>> {
+        self.sessions.list_sessions()
     }
 
     pub async fn initialize_default_agent(
@@ -120,11 +172,11 @@ impl Orchestrator {
                             .await?;
                     }
                 }
-                AppEvent::ToolApproval(response) => {
+                AppEvent::ToolApproval(decisions) => {
                     if let Some(agent_id) = &self.active_agent_id
                         && let Err(e) = self
                             .multi_agent_manager
-                            .send_event_to_agent(agent_id, AppEvent::ToolApproval(response))
+                            .send_event_to_agent(agent_id, AppEvent::ToolApproval(decisions))
                             .await
                     {
                         self.tx.send(AppEvent::Error(e.to_string())).await?;
@@ -151,6 +203,30 @@ impl Orchestrator {
                     }
                 }
 
+                AppEvent::ForkSession(session_name, new_name, messages) => {
+                    if let Some(agent_id) = &self.active_agent_id {
+                        // The agent saves its session first, so a fork of it is current
+                        if let Err(e) = self
+                            .multi_agent_manager
+                            .send_event_to_agent(
+                                agent_id,
+                                AppEvent::ForkSession(session_name, new_name, messages),
+                            )
+                            .await
+                        {
+                            self.tx
+                                .send(AppEvent::Error(format!("Failed to fork session: {}", e)))
+                                .await?;
+                        }
+                    } else {
+                        self.tx
+                            .send(AppEvent::Error(
+                                "No active agent to fork session for".to_string(),
+                            ))
+                            .await?;
+                    }
+                }
+
                 AppEvent::SwitchAgent(agent_name, current_session) => {
                     // Check if agent exists
                     if let Some(agent) = self
@@ -185,12 +261,20 @@ impl Orchestrator {
                                 .await?;
                         }
                     } else {
-                        // Determine model based on agent name (simple mapping for now)
-                        let model = match agent_name.as_str() {
-                            "Qwen" => "qwen3:4b",
-                            "Llama" => "llama3.2",
-                            "Granite" => "granite3.3",
-                            _ => "qwen3:4b", // default fallback
+                        // Use the configured model, or map the agent name to one
+                        let configured = self
+                            .agent_configs
+                            .iter()
+                            .find(|agent| agent.name == agent_name)
+                            .map(|agent| agent.model.clone());
+                        let model = match configured.as_deref() {
+                            Some(model) => model,
+                            None => match agent_name.as_str() {
+                                "Qwen" => "qwen3:4b",
+                                "Llama" => "llama3.2",
+                                "Granite" => "granite3.3",
+                                _ => "qwen3:4b", // default fallback
+                            },
                         };
 
                         // Create new agent
@@ -249,7 +333,7 @@ impl Orchestrator {
                         )))
                         .await?;
                 }
-                AppEvent::ListSessions => match Orchestrator::list_sessions() {
+                AppEvent::ListSessions => match self.list_sessions() {
                     Ok(sessions) => {
                         let session_list = sessions.join(", ");
                         self.tx
@@ -263,7 +347,7 @@ impl Orchestrator {
                         self.tx.send(AppEvent::Error(e.to_string())).await?;
                     }
                 },
-                AppEvent::RefreshSessions => match Orchestrator::list_sessions() {
+                AppEvent::RefreshSessions => match self.list_sessions() {
                     Ok(sessions) => {
                         self.tx.send(AppEvent::SessionList(sessions)).await?;
                     }
@@ -281,6 +365,101 @@ impl Orchestrator {
                         self.tx.send(AppEvent::Error(e.to_string())).await?;
                     }
                 }
+                AppEvent::ListMcpResources => {
+                    let resources = match &self.mcp_registry {
+                        Some(registry) => registry.list_resources().await,
+                        None => Vec::new(),
+                    };
+                    let resources = resources
+                        .into_iter()
+                        .map(|(server, resource)| McpResourceInfo {
+                            server,
+                            uri: resource.uri,
+                            name: resource.name,
+                            description: resource.description,
+                        })
+                        .collect();
+                    self.tx.send(AppEvent::McpResourceList(resources)).await?;
+                }
+                AppEvent::ReadMcpResource(uri) => {
+                    let content = match &self.mcp_registry {
+                        Some(registry) => registry.read_resource(&uri).await,
+                        None => Err(anyhow::anyhow!("No MCP servers are connected")),
+                    };
+                    match content {
+                        Ok(text) => {
+                            self.tx
+                                .send(AppEvent::McpResourceContent(uri, text))
+                                .await?;
+                        }
+                        Err(e) => {
+                            self.tx.send(AppEvent::Error(e.to_string())).await?;
+                        }
+                    }
+                }
+                AppEvent::ListMcpPrompts => {
+                    let prompts = match &self.mcp_registry {
+                        Some(registry) => registry.list_prompts().await,
+                        None => Vec::new(),
+                    };
+                    let prompts = prompts
+                        .into_iter()
+                        .map(|(server, prompt)| {
+                            let (required, optional): (Vec<_>, Vec<_>) =
+                                prompt.arguments.into_iter().partition(|a| a.required);
+                            McpPromptInfo {
+                                server,
+                                name: prompt.name,
+                                description: prompt.description,
+                                required_arguments: required.len(),
+                                arguments: required
+                                    .into_iter()
+                                    .chain(optional)
+                                    .map(|argument| argument.name)
+                                    .collect(),
+                            }
+                        })
+                        .collect();
+                    self.tx.send(AppEvent::McpPromptList(prompts)).await?;
+                }
+                AppEvent::GetMcpPrompt(name, arguments) => {
+                    let text = match &self.mcp_registry {
+                        Some(registry) => registry.get_prompt(&name, &arguments).await,
+                        None => Err(anyhow::anyhow!("No MCP servers are connected")),
+                    };
+                    match text {
+                        Ok(text) => {
+                            self.tx
+                                .send(AppEvent::McpPromptExpanded(name, text))
+                                .await?;
+                        }
+                        Err(e) => {
+                            self.tx.send(AppEvent::Error(e.to_string())).await?;
+                        }
+                    }
+                }
+                AppEvent::McpSamplingApproval(id, approved) => {
+                    if let Some(registry) = &self.mcp_registry {
+                        registry.request_router().approve_sampling(id, approved);
+                    }
+                }
+                AppEvent::McpElicitationResponse(id, answer) => {
+                    if let Some(registry) = &self.mcp_registry {
+                        registry.request_router().answer_elicitation(id, answer);
+                    }
+                }
+                event @ (AppEvent::UndoLastToolEffect
+                | AppEvent::ListCheckpoints
+                | AppEvent::RestoreCheckpoint(_)) => {
+                    if let Some(agent_id) = &self.active_agent_id
+                        && let Err(e) = self
+                            .multi_agent_manager
+                            .send_event_to_agent(agent_id, event)
+                            .await
+                    {
+                        self.tx.send(AppEvent::Error(e.to_string())).await?;
+                    }
+                }
                 _ => {}
             }
         }
@@ -1,12 +1,30 @@
 //! Session management for the OxideAgent system.
 //!
 //! This module handles session persistence, loading, saving, and listing.
+//!
+//! Sessions live in one directory (by default the XDG data directory), next
+//! to an index file with the metadata of every session, so listing sessions
+//! doesn't parse each session file.
 
+use crate::core::agents::context::message_tokens;
+use crate::core::transcript::{self, ExportFormat};
 use crate::types::ChatMessage;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
-use tracing::warn;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Name of the index file in the sessions directory
+const INDEX_FILE: &str = "index.json";
+
+/// Longest title taken from the first user message
+const TITLE_MAX_CHARS: usize = 60;
+
+/// Serializes read-modify-write cycles of index files within the process
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SessionState {
@@ -15,10 +33,24 @@ pub struct SessionState {
     /// Tools that are allowed for this specific session
     #[serde(default)] // Add default to handle missing field in existing files
     allowed_tools: Vec<String>,
+    /// Tools that are never allowed for this specific session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    denied_tools: Vec<String>,
     /// The model used for this session
     #[serde(default = "default_model")]
     // Add default to handle missing field in existing files
     model: String,
+    /// The session this one was forked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<SessionParent>,
+}
+
+/// Where a forked session branched off
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionParent {
+    pub session: String,
+    /// Number of the parent's messages the fork started with
+    pub message_index: usize,
 }
 
 /// Default model function for deserialization
@@ -32,7 +64,9 @@ impl SessionState {
         Self {
             history: Vec::new(),
             allowed_tools: Vec::new(), // Explicitly initialize as empty
-            model: default_model(),    // Initialize with default model
+            denied_tools: Vec::new(),
+            model: default_model(), // Initialize with default model
+            parent: None,
         }
     }
 
@@ -51,6 +85,7 @@ impl SessionState {
 
     /// Add a tool to the session allowed list
     pub fn add_allowed_tool(&mut self, tool_name: String) {
+        self.denied_tools.retain(|tool| *tool != tool_name);
         if !self.allowed_tools.contains(&tool_name) {
             self.allowed_tools.push(tool_name);
         }
@@ -68,6 +103,31 @@ impl SessionState {
         self.allowed_tools.clone()
     }
 
+    /// Check if a tool is denied for this session
+    pub fn is_tool_denied(&self, tool_name: &str) -> bool {
+        self.denied_tools.iter().any(|tool| tool == tool_name)
+    }
+
+    /// Add a tool to the session denied list, removing it from the allowed list
+    pub fn add_denied_tool(&mut self, tool_name: String) {
+        self.allowed_tools.retain(|tool| *tool != tool_name);
+        if !self.denied_tools.contains(&tool_name) {
+            self.denied_tools.push(tool_name);
+        }
+    }
+
+    /// Remove a tool from the session denied list
+    pub fn remove_denied_tool(&mut self, tool_name: &str) -> bool {
+        let initial_len = self.denied_tools.len();
+        self.denied_tools.retain(|tool| tool != tool_name);
+        self.denied_tools.len() < initial_len
+    }
+
+    /// List all tools denied for this session
+    pub fn list_denied_tools(&self) -> Vec<String> {
+        self.denied_tools.clone()
+    }
+
     /// Get the model for this session
     pub fn model(&self) -> &str {
         &self.model
@@ -77,6 +137,26 @@ impl SessionState {
     pub fn set_model(&mut self, model: String) {
         self.model = model;
     }
+
+    /// The session this one was forked from
+    pub fn parent(&self) -> Option<&SessionParent> {
+        self.parent.as_ref()
+    }
+
+    /// A new session starting with the first `message_index` messages of
+    /// this one, named `session`, with its model and tool permissions
+    pub fn fork(&self, session: &str, message_index: usize) -> Self {
+        Self {
+            history: self.history[..message_index.min(self.history.len())].to_vec(),
+            allowed_tools: self.allowed_tools.clone(),
+            denied_tools: self.denied_tools.clone(),
+            model: self.model.clone(),
+            parent: Some(SessionParent {
+                session: session.to_string(),
+                message_index,
+            }),
+        }
+    }
 }
 
 impl Default for SessionState {
@@ -85,9 +165,331 @@ impl Default for SessionState {
     }
 }
 
-pub struct SessionManager;
+/// What the index records about a session
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionMetadata {
+    pub name: String,
+    /// Unix time the session was first saved
+    pub created_at: u64,
+    /// Unix time the session was last saved
+    pub updated_at: u64,
+    /// Start of the first user message
+    #[serde(default)]
+    pub title: String,
+    /// Agent that last saved the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default)]
+    pub message_count: usize,
+    /// Estimated tokens of the history, at about four characters per token
+    #[serde(default)]
+    pub token_estimate: usize,
+    /// The session this one was forked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<SessionParent>,
+}
+
+impl SessionMetadata {
+    /// Describe `state`, saved as session `name` by `agent` at `updated_at`
+    fn describe(name: &str, state: &SessionState, agent: Option<&str>, updated_at: u64) -> Self {
+        let title = state
+            .history
+            .iter()
+            .find(|message| message.role == "user")
+            .and_then(|message| message.content.lines().find(|line| !line.trim().is_empty()))
+            .map(|line| line.trim().chars().take(TITLE_MAX_CHARS).collect())
+            .unwrap_or_default();
+        Self {
+            name: name.to_string(),
+            created_at: updated_at,
+            updated_at,
+            title,
+            agent: agent.map(str::to_string),
+            model: state.model.clone(),
+            message_count: state.history.len(),
+            token_estimate: estimate_tokens(&state.history),
+            parent: state.parent.clone(),
+        }
+    }
+}
+
+/// Rough token count of `history`: about four characters per token
+pub fn estimate_tokens(history: &[ChatMessage]) -> usize {
+    history.iter().map(message_tokens).sum()
+}
+
+/// Contents of the index file
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SessionIndex {
+    /// Metadata by session name
+    #[serde(default)]
+    sessions: BTreeMap<String, SessionMetadata>,
+    /// Directories whose session files were already imported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    migrated: Vec<PathBuf>,
+}
+
+/// Sessions kept in one directory
+#[derive(Debug, Clone)]
+pub struct SessionManager {
+    dir: PathBuf,
+}
 
 impl SessionManager {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Directory the session files and the index are kept in
+    #[allow(dead_code)] // Used in tests and part of the public API
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the file of session `name`; `default` is the unnamed session
+    pub fn session_path(&self, name: &str) -> anyhow::Result<PathBuf> {
+        validate_session_name(name)?;
+        let name = (name != "default").then_some(name);
+        Ok(self.dir.join(Self::get_session_filename(name)))
+    }
+
+    /// Load session `name`, or `None` when it was never saved
+    pub fn load(&self, name: &str) -> anyhow::Result<Option<SessionState>> {
+        Self::load_state(self.session_path(name)?)
+    }
+
+    /// Save session `name` and record its metadata in the index
+    pub fn save(
+        &self,
+        name: &str,
+        state: &SessionState,
+        agent: Option<&str>,
+    ) -> anyhow::Result<SessionMetadata> {
+        Self::save_state(self.session_path(name)?, state)?;
+
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+        let mut metadata = SessionMetadata::describe(name, state, agent, unix_now());
+        if let Some(previous) = index.sessions.get(name) {
+            metadata.created_at = previous.created_at;
+        }
+        index.sessions.insert(name.to_string(), metadata.clone());
+        self.write_index(&index)?;
+        Ok(metadata)
+    }
+
+    /// Metadata of every session, most recently updated first.
+    ///
+    /// Session files the index doesn't know yet (e.g. copied into the
+    /// directory by hand) are read once and added to it; entries of deleted
+    /// files are dropped.
+    pub fn list_metadata(&self) -> anyhow::Result<Vec<SessionMetadata>> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+        let files = self.session_files()?;
+
+        let mut changed = false;
+        let before = index.sessions.len();
+        index.sessions.retain(|name, _| files.contains_key(name));
+        changed |= index.sessions.len() != before;
+        for (name, (path, modified)) in &files {
+            if index.sessions.contains_key(name) {
+                continue;
+            }
+            let state = Self::load_state(path)?.unwrap_or_default();
+            index.sessions.insert(
+                name.clone(),
+                SessionMetadata::describe(name, &state, None, *modified),
+            );
+            changed = true;
+        }
+        if changed && let Err(e) = self.write_index(&index) {
+            warn!("Failed to update session index: {}", e);
+        }
+
+        let mut sessions: Vec<SessionMetadata> = index.sessions.into_values().collect();
+        sessions.sort_by(|a, b| {
+            b.updated_at
+                .cmp(&a.updated_at)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(sessions)
+    }
+
+    /// Metadata of session `name`
+    pub fn metadata(&self, name: &str) -> anyhow::Result<Option<SessionMetadata>> {
+        Ok(self
+            .list_metadata()?
+            .into_iter()
+            .find(|metadata| metadata.name == name))
+    }
+
+    /// Names of all sessions, most recently updated first
+    pub fn list_sessions(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .list_metadata()?
+            .into_iter()
+            .map(|metadata| metadata.name)
+            .collect())
+    }
+
+    /// Render session `name` as a transcript in `format`
+    pub fn export(&self, name: &str, format: ExportFormat) -> anyhow::Result<String> {
+        let state = self
+            .load(name)?
+            .ok_or_else(|| anyhow::anyhow!("Session '{}' does not exist", name))?;
+        let metadata = match self.metadata(name)? {
+            Some(metadata) => metadata,
+            None => SessionMetadata::describe(name, &state, None, unix_now()),
+        };
+        transcript::render(&metadata, &state.history, format)
+    }
+
+    /// Create session `name` from a JSON Lines transcript. The model is taken
+    /// from the transcript; tool permissions are not, so an imported session
+    /// starts without any.
+    pub fn import(&self, name: &str, jsonl: &str) -> anyhow::Result<SessionMetadata> {
+        if self.session_path(name)?.exists() {
+            return Err(anyhow::anyhow!("Session '{}' already exists", name));
+        }
+        let transcript = transcript::parse_json_lines(jsonl)?;
+        let mut state = SessionState::new();
+        if let Some(metadata) = &transcript.metadata {
+            state.set_model(metadata.model.clone());
+        }
+        state.set_history(transcript.history);
+        let agent = transcript
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.agent.as_deref());
+        self.save(name, &state, agent)
+    }
+
+    /// Fork session `name` into the new session `new_name`, keeping the
+    /// first `message_index` messages, or all of them when `None`
+    pub fn fork(
+        &self,
+        name: &str,
+        new_name: &str,
+        message_index: Option<usize>,
+    ) -> anyhow::Result<SessionMetadata> {
+        if self.session_path(new_name)?.exists() {
+            return Err(anyhow::anyhow!("Session '{}' already exists", new_name));
+        }
+        let state = self
+            .load(name)?
+            .ok_or_else(|| anyhow::anyhow!("Session '{}' does not exist", name))?;
+        let message_index = message_index.unwrap_or(state.history.len());
+        if message_index > state.history.len() {
+            return Err(anyhow::anyhow!(
+                "Session '{}' has only {} messages",
+                name,
+                state.history.len()
+            ));
+        }
+        let agent = self.metadata(name)?.and_then(|metadata| metadata.agent);
+        self.save(new_name, &state.fork(name, message_index), agent.as_deref())
+    }
+
+    /// Import the session files `old_dir` holds from before sessions had a
+    /// directory of their own. Each directory is imported once; sessions
+    /// that already exist here are left alone. The old files are kept.
+    /// Returns the names of the imported sessions.
+    pub fn migrate_from(&self, old_dir: &Path) -> anyhow::Result<Vec<String>> {
+        let old_dir = old_dir
+            .canonicalize()
+            .unwrap_or_else(|_| old_dir.to_path_buf());
+        if self.dir.canonicalize().ok().as_ref() == Some(&old_dir) {
+            return Ok(Vec::new());
+        }
+
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+        if index.migrated.contains(&old_dir) {
+            return Ok(Vec::new());
+        }
+
+        let mut imported = Vec::new();
+        for (name, (path, modified)) in session_files_in(&old_dir)? {
+            let new_path = match self.session_path(&name) {
+                Ok(new_path) => new_path,
+                Err(e) => {
+                    warn!("Not importing {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if index.sessions.contains_key(&name) || new_path.exists() {
+                warn!(
+                    "Not importing session '{}' from {}: a session of that name exists",
+                    name,
+                    old_dir.display()
+                );
+                continue;
+            }
+            let Some(state) = Self::load_state(&path)? else {
+                continue;
+            };
+            Self::save_state(&new_path, &state)?;
+            index.sessions.insert(
+                name.clone(),
+                SessionMetadata::describe(&name, &state, None, modified),
+            );
+            imported.push(name);
+        }
+        if !imported.is_empty() {
+            info!(
+                "Imported sessions {} from {} into {}",
+                imported.join(", "),
+                old_dir.display(),
+                self.dir.display()
+            );
+        }
+        index.migrated.push(old_dir);
+        self.write_index(&index)?;
+        Ok(imported)
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(INDEX_FILE)
+    }
+
+    /// The index, or an empty one when it is missing or unreadable
+    fn read_index(&self) -> SessionIndex {
+        let path = self.index_path();
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!(
+                    "Failed to parse session index '{}', rebuilding it: {}",
+                    path.display(),
+                    e
+                );
+                SessionIndex::default()
+            }),
+            Err(_) => SessionIndex::default(),
+        }
+    }
+
+    fn write_index(&self, index: &SessionIndex) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.index_path();
+        let temp_path = path.with_extension(format!("tmp.{}", std::process::id()));
+        fs::write(&temp_path, serde_json::to_string_pretty(index)?)?;
+        fs::rename(&temp_path, &path).inspect_err(|_| {
+            fs::remove_file(&temp_path).ok();
+        })?;
+        Ok(())
+    }
+
+    /// Session files in the sessions directory
+    fn session_files(&self) -> anyhow::Result<BTreeMap<String, (PathBuf, u64)>> {
+        if !self.dir.exists() {
+            return Ok(BTreeMap::new());
+        }
+        session_files_in(&self.dir)
+    }
+
     /// Load session state from a file
     pub fn load_state<P: AsRef<Path>>(path: P) -> anyhow::Result<Option<SessionState>> {
         let path = path.as_ref();
@@ -200,66 +602,6 @@ impl SessionManager {
         }
     }
 
-    /// List all available sessions
-    pub fn list_sessions() -> anyhow::Result<Vec<String>> {
-        let mut sessions = Vec::new();
-
-        // Check for the default session file
-        if Path::new("session.json").exists()
-            && let Ok(metadata) = fs::metadata("session.json")
-            && let Ok(modified) = metadata.modified()
-        {
-            sessions.push(("default".to_string(), modified));
-        }
-
-        // Look for named session files with retry mechanism to handle race conditions
-        let mut attempts = 0;
-        let max_attempts = 3;
-        let entries = loop {
-            match fs::read_dir(".") {
-                Ok(dir_entries) => break dir_entries,
-                Err(e) => {
-                    if attempts >= max_attempts - 1 {
-                        return Err(e.into());
-                    } else {
-                        attempts += 1;
-                        std::thread::sleep(std::time::Duration::from_millis(10 * attempts));
-                    }
-                }
-            }
-        };
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_file()
-                && let Some(file_name) = path.file_name()
-                && let Some(file_name_str) = file_name.to_str()
-                && file_name_str.starts_with("session_")
-                && file_name_str.ends_with(".json")
-            {
-                // Extract session name from file name
-                let session_name = file_name_str
-                    .strip_prefix("session_")
-                    .unwrap()
-                    .strip_suffix(".json")
-                    .unwrap();
-
-                if let Ok(metadata) = path.metadata()
-                    && let Ok(modified) = metadata.modified()
-                {
-                    sessions.push((session_name.to_string(), modified));
-                }
-            }
-        }
-
-        // Sort by modification time (newest first)
-        sessions.sort_by(|a, b| b.1.cmp(&a.1));
-
-        Ok(sessions.into_iter().map(|(name, _)| name).collect())
-    }
-
     /// Get the session filename for a given session name
     pub fn get_session_filename(session_name: Option<&str>) -> String {
         match session_name {
@@ -268,3 +610,52 @@ impl SessionManager {
         }
     }
 }
+
+/// Check that `name` can name a session file: it may not be empty or hold
+/// path separators, drive separators or `..`
+pub fn validate_session_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name.contains(['/', '\\', ':', '\0']) || name.contains("..") {
+        return Err(anyhow::anyhow!("Invalid session name '{}'", name));
+    }
+    Ok(())
+}
+
+/// Session files in `dir` by session name, with their modification time
+fn session_files_in(dir: &Path) -> anyhow::Result<BTreeMap<String, (PathBuf, u64)>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let name = if file_name == "session.json" {
+            "default"
+        } else if let Some(name) = file_name
+            .strip_prefix("session_")
+            .and_then(|name| name.strip_suffix(".json"))
+        {
+            name
+        } else {
+            continue;
+        };
+        if !path.is_file() {
+            continue;
+        }
+        let modified = path
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_secs())
+            .unwrap_or_default();
+        files.insert(name.to_string(), (path, modified));
+    }
+    Ok(files)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
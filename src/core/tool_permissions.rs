@@ -2,19 +2,344 @@
 //!
 //! This module handles persistent storage and management of tool permissions,
 //! including global permissions and session-specific permissions.
+//!
+//! Besides plain tool names, global permissions can hold argument-pattern rules
+//! that match a tool call's arguments and allow, deny or ask about the call.
+//! Rules are stored in `tool_permissions.json` next to the allowed tools:
+//!
+//! ```json
+//! {
+//!   "allowed_tools": ["read_file"],
+//!   "rules": [
+//!     {
+//!       "tool": "run_shell_command",
+//!       "conditions": [{ "path": "command", "regex": "^cargo (test|check)( --?[a-z-]+)*$" }],
+//!       "outcome": "allow"
+//!     },
+//!     {
+//!       "tool": "write_file",
+//!       "conditions": [{ "path": "path", "glob": "./src/**" }],
+//!       "outcome": "allow"
+//!     }
+//!   ]
+//! }
+//! ```
 
+use crate::types::ToolFunction;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use tracing::{debug, warn};
 
+/// What happens to a tool call matched by a permission rule
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOutcome {
+    /// Execute the call without asking
+    Allow,
+    /// Ask the user, even if the tool is on an allow list
+    Ask,
+    /// Reject the call without asking
+    Deny,
+}
+
+/// How an argument value is matched
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentPattern {
+    /// Glob over path-like values; `*` stays within a path segment, `**` crosses them
+    Glob(String),
+    /// Regular expression searched in the value (anchor it with `^`/`$` as needed)
+    Regex(String),
+    /// Exact JSON equality
+    Equals(Value),
+}
+
+/// An [`ArgumentPattern`] compiled once, when its rule is created or loaded
+#[derive(Debug, Clone)]
+enum CompiledPattern {
+    Glob(GlobMatcher),
+    Regex(Regex),
+    Equals(Value),
+}
+
+impl ArgumentPattern {
+    fn compile(&self) -> anyhow::Result<CompiledPattern> {
+        match self {
+            ArgumentPattern::Glob(pattern) => Ok(CompiledPattern::Glob(
+                GlobBuilder::new(&normalize_path(pattern))
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| anyhow::anyhow!("Invalid glob '{}': {}", pattern, e))?
+                    .compile_matcher(),
+            )),
+            ArgumentPattern::Regex(pattern) => Ok(CompiledPattern::Regex(
+                Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid regex '{}': {}", pattern, e))?,
+            )),
+            ArgumentPattern::Equals(value) => Ok(CompiledPattern::Equals(value.clone())),
+        }
+    }
+}
+
+/// Serialized form of an [`ArgumentCondition`]
+#[derive(Serialize, Deserialize)]
+struct ConditionSpec {
+    path: String,
+    #[serde(flatten)]
+    pattern: ArgumentPattern,
+}
+
+/// A condition on one argument of a tool call
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "ConditionSpec", into = "ConditionSpec")]
+pub struct ArgumentCondition {
+    /// JSON path into the arguments, e.g. `command`, `$.options.cwd` or `files[0]`
+    path: String,
+    pattern: ArgumentPattern,
+    compiled: CompiledPattern,
+}
+
+impl PartialEq for ArgumentCondition {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.pattern == other.pattern
+    }
+}
+
+impl TryFrom<ConditionSpec> for ArgumentCondition {
+    type Error = anyhow::Error;
+
+    fn try_from(spec: ConditionSpec) -> anyhow::Result<Self> {
+        Self::new(&spec.path, spec.pattern)
+    }
+}
+
+impl From<ArgumentCondition> for ConditionSpec {
+    fn from(condition: ArgumentCondition) -> Self {
+        Self {
+            path: condition.path,
+            pattern: condition.pattern,
+        }
+    }
+}
+
+#[allow(dead_code)] // Constructors are used in tests and form part of the public API
+impl ArgumentCondition {
+    /// Compile a condition, failing on an invalid glob or regex
+    pub fn new(path: &str, pattern: ArgumentPattern) -> anyhow::Result<Self> {
+        Ok(Self {
+            path: path.to_string(),
+            compiled: pattern.compile()?,
+            pattern,
+        })
+    }
+
+    pub fn glob(path: &str, pattern: &str) -> anyhow::Result<Self> {
+        Self::new(path, ArgumentPattern::Glob(pattern.to_string()))
+    }
+
+    pub fn regex(path: &str, pattern: &str) -> anyhow::Result<Self> {
+        Self::new(path, ArgumentPattern::Regex(pattern.to_string()))
+    }
+
+    pub fn equals(path: &str, value: Value) -> Self {
+        Self {
+            path: path.to_string(),
+            compiled: CompiledPattern::Equals(value.clone()),
+            pattern: ArgumentPattern::Equals(value),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn pattern(&self) -> &ArgumentPattern {
+        &self.pattern
+    }
+
+    /// Check the condition against a tool call's arguments.
+    ///
+    /// A missing argument never matches.
+    pub fn matches(&self, arguments: &Value) -> bool {
+        let Some(value) = lookup_json_path(arguments, &self.path) else {
+            return false;
+        };
+
+        match &self.compiled {
+            CompiledPattern::Equals(expected) => value == expected,
+            CompiledPattern::Regex(regex) => regex.is_match(&value_as_text(value)),
+            CompiledPattern::Glob(glob) => glob.is_match(normalize_path(&value_as_text(value))),
+        }
+    }
+}
+
+/// Serialized form of a [`PermissionRule`]
+#[derive(Serialize, Deserialize)]
+struct RuleSpec {
+    tool: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    conditions: Vec<ArgumentCondition>,
+    outcome: RuleOutcome,
+}
+
+/// A permission rule matching a tool name and conditions on its arguments
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "RuleSpec", into = "RuleSpec")]
+pub struct PermissionRule {
+    /// Tool name, may be a glob such as `mcp_*`
+    tool: String,
+    tool_matcher: GlobMatcher,
+    /// All conditions must match for the rule to apply
+    pub conditions: Vec<ArgumentCondition>,
+    pub outcome: RuleOutcome,
+}
+
+impl PartialEq for PermissionRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.tool == other.tool
+            && self.conditions == other.conditions
+            && self.outcome == other.outcome
+    }
+}
+
+impl TryFrom<RuleSpec> for PermissionRule {
+    type Error = anyhow::Error;
+
+    fn try_from(spec: RuleSpec) -> anyhow::Result<Self> {
+        let mut rule = Self::new(&spec.tool, spec.outcome)?;
+        rule.conditions = spec.conditions;
+        Ok(rule)
+    }
+}
+
+impl From<PermissionRule> for RuleSpec {
+    fn from(rule: PermissionRule) -> Self {
+        Self {
+            tool: rule.tool,
+            conditions: rule.conditions,
+            outcome: rule.outcome,
+        }
+    }
+}
+
+impl PermissionRule {
+    /// Create a rule for `tool`, failing if it is an invalid glob
+    pub fn new(tool: &str, outcome: RuleOutcome) -> anyhow::Result<Self> {
+        let tool_matcher = GlobBuilder::new(tool)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid tool glob '{}': {}", tool, e))?
+            .compile_matcher();
+        Ok(Self {
+            tool: tool.to_string(),
+            tool_matcher,
+            conditions: Vec::new(),
+            outcome,
+        })
+    }
+
+    #[allow(dead_code)] // Used in tests and form part of the public API
+    pub fn with_condition(mut self, condition: ArgumentCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    #[allow(dead_code)] // Used in tests and form part of the public API
+    pub fn tool(&self) -> &str {
+        &self.tool
+    }
+
+    /// Check whether the rule applies to a tool call
+    pub fn matches(&self, function: &ToolFunction) -> bool {
+        self.tool_matcher.is_match(&function.name)
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(&function.arguments))
+    }
+}
+
+/// Look up a value by a simple JSON path (`a.b`, `$.a.b`, `a[0].b`).
+fn lookup_json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+
+    let mut current = value;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, mut indices) = match segment.find('[') {
+            Some(position) => segment.split_at(position),
+            None => (segment, ""),
+        };
+
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+
+        while let Some(rest) = indices.strip_prefix('[') {
+            let end = rest.find(']')?;
+            let index: usize = rest[..end].trim().parse().ok()?;
+            current = current.get(index)?;
+            indices = &rest[end + 1..];
+        }
+    }
+
+    Some(current)
+}
+
+/// Render an argument value as text for pattern matching.
+fn value_as_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Lexically normalize a path so `./src/../..` tricks cannot slip past a glob.
+fn normalize_path(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let mut parts: Vec<&str> = Vec::new();
+
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|last| *last != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            part => parts.push(part),
+        }
+    }
+
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
 /// Global tool permissions that apply across all sessions
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct GlobalToolPermissions {
     /// Set of tools that are always allowed
     #[serde(default)]
     allowed_tools: HashSet<String>,
+    /// Set of tools that are never allowed
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    denied_tools: HashSet<String>,
+    /// Argument-pattern rules, evaluated before the allow lists
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rules: Vec<PermissionRule>,
+    /// Why the file these permissions stand in for failed to load
+    #[serde(skip)]
+    load_error: Option<String>,
 }
 
 #[allow(dead_code)] // Methods are used in tests and form part of the public API
@@ -24,6 +349,15 @@ impl GlobalToolPermissions {
         Self::default()
     }
 
+    /// Empty permissions standing in for a file that failed to load. They are
+    /// never saved, so the rules and lists in the file are kept.
+    pub fn failed_to_load(error: &anyhow::Error) -> Self {
+        Self {
+            load_error: Some(error.to_string()),
+            ..Self::default()
+        }
+    }
+
     /// Load global tool permissions from the default file
     pub fn load() -> anyhow::Result<Self> {
         Self::load_from_path("tool_permissions.json")
@@ -46,8 +380,17 @@ impl GlobalToolPermissions {
                             debug!("Empty file, returning default");
                             return Ok(Self::default());
                         }
-                        match serde_json::from_str(&content) {
-                            Ok(permissions) => {
+                        match serde_json::from_str::<Value>(&content) {
+                            // Invalid rules are an error rather than a silent non-match
+                            Ok(value) => {
+                                let permissions: Self =
+                                    serde_json::from_value(value).map_err(|e| {
+                                        anyhow::anyhow!(
+                                            "Invalid tool permissions in '{}': {}",
+                                            path.display(),
+                                            e
+                                        )
+                                    })?;
                                 debug!("Successfully parsed permissions: {:?}", permissions);
                                 return Ok(permissions);
                             }
@@ -94,6 +437,12 @@ impl GlobalToolPermissions {
 
     /// Save global tool permissions to a specific file path
     pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        if let Some(error) = &self.load_error {
+            return Err(anyhow::anyhow!(
+                "Not saving tool permissions over a file that failed to load: {}",
+                error
+            ));
+        }
         let content = serde_json::to_string_pretty(self)?;
 
         // Ensure the directory exists before writing
@@ -147,6 +496,7 @@ impl GlobalToolPermissions {
 
     /// Add a tool to the global allowed list
     pub fn add_allowed(&mut self, tool_name: &str) {
+        self.denied_tools.remove(tool_name);
         self.allowed_tools.insert(tool_name.to_string());
     }
 
@@ -159,4 +509,69 @@ impl GlobalToolPermissions {
     pub fn list_allowed(&self) -> Vec<String> {
         self.allowed_tools.iter().cloned().collect()
     }
+
+    /// Check if a tool is denied globally
+    pub fn is_denied(&self, tool_name: &str) -> bool {
+        self.denied_tools.contains(tool_name)
+    }
+
+    /// Add a tool to the global denied list, removing it from the allowed list
+    pub fn add_denied(&mut self, tool_name: &str) {
+        self.allowed_tools.remove(tool_name);
+        self.denied_tools.insert(tool_name.to_string());
+    }
+
+    /// Remove a tool from the global denied list
+    pub fn remove_denied(&mut self, tool_name: &str) -> bool {
+        self.denied_tools.remove(tool_name)
+    }
+
+    /// List all globally denied tools
+    pub fn list_denied(&self) -> Vec<String> {
+        self.denied_tools.iter().cloned().collect()
+    }
+
+    /// Add an argument-pattern rule
+    pub fn add_rule(&mut self, rule: PermissionRule) {
+        self.rules.push(rule);
+    }
+
+    /// Remove the rule at the given position
+    pub fn remove_rule(&mut self, index: usize) -> Option<PermissionRule> {
+        (index < self.rules.len()).then(|| self.rules.remove(index))
+    }
+
+    /// List all argument-pattern rules
+    pub fn rules(&self) -> &[PermissionRule] {
+        &self.rules
+    }
+
+    /// Evaluate the rules against a tool call.
+    ///
+    /// Returns `None` when no rule matches. Otherwise deny overrides ask, and
+    /// ask overrides allow.
+    pub fn evaluate_rules(&self, function: &ToolFunction) -> Option<RuleOutcome> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(function))
+            .map(|rule| rule.outcome)
+            .max()
+    }
+
+    /// Decide what to do with a tool call.
+    ///
+    /// Tools on the global deny list are always denied. Otherwise matching
+    /// rules win, and without one the call is allowed if the tool is on the
+    /// global allow list or `session_allowed` is set, and asked about if not.
+    pub fn resolve(&self, function: &ToolFunction, session_allowed: bool) -> RuleOutcome {
+        if self.is_denied(&function.name) {
+            return RuleOutcome::Deny;
+        }
+
+        match self.evaluate_rules(function) {
+            Some(outcome) => outcome,
+            None if session_allowed || self.is_allowed(&function.name) => RuleOutcome::Allow,
+            None => RuleOutcome::Ask,
+        }
+    }
 }
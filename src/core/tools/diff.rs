@@ -0,0 +1,37 @@
+//! Unified diff previews of file changes, shown when asking for tool approval.
+
+use similar::TextDiff;
+
+/// Longest preview shown; the rest of the diff is summarized in a marker line.
+const MAX_PREVIEW_LINES: usize = 200;
+
+/// Render the change from `old` to `new` as a unified diff with git-style
+/// `a/`/`b/` headers. A file that does not exist yet is diffed against
+/// `/dev/null`.
+pub fn unified_diff(path: &str, old: Option<&str>, new: &str) -> String {
+    let old_header = match old {
+        Some(_) => format!("a/{}", path),
+        None => "/dev/null".to_string(),
+    };
+    if old == Some(new) {
+        return format!("No changes to '{}'.", path);
+    }
+
+    let rendered = TextDiff::from_lines(old.unwrap_or_default(), new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_header, &format!("b/{}", path))
+        .to_string();
+
+    let total_lines = rendered.lines().count();
+    if total_lines <= MAX_PREVIEW_LINES {
+        return rendered.trim_end().to_string();
+    }
+    let mut preview: Vec<&str> = rendered.lines().take(MAX_PREVIEW_LINES).collect();
+    let marker = format!(
+        "[... diff truncated, {} more lines]",
+        total_lines - MAX_PREVIEW_LINES
+    );
+    preview.push(&marker);
+    preview.join("\n")
+}
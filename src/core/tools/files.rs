@@ -0,0 +1,507 @@
+//! File tools for browsing, searching and editing the workspace.
+
+use super::{Tool, ToolProfile};
+use crate::core::workspace::Workspace;
+use async_trait::async_trait;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::RegexBuilder;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Directories skipped unless explicitly listed or searched.
+const DEFAULT_IGNORES: &[&str] = &[".git", "target", "node_modules"];
+
+/// Most entries `list_directory` returns.
+const MAX_LIST_ENTRIES: usize = 1000;
+
+/// Files larger than this are skipped by `grep_files`.
+const MAX_GREP_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Ignore rules made of the defaults, a root `.gitignore` and caller patterns.
+struct IgnoreRules {
+    matcher: GlobSet,
+}
+
+impl IgnoreRules {
+    fn load(root: &Path, extra_patterns: &[String]) -> anyhow::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for name in DEFAULT_IGNORES {
+            builder.add(Glob::new(&format!("**/{}", name))?);
+        }
+
+        // Plain `.gitignore` patterns; negations are not supported
+        if let Ok(gitignore) = fs::read_to_string(root.join(".gitignore")) {
+            for line in gitignore.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                    continue;
+                }
+                if let Err(e) = add_ignore_pattern(&mut builder, line) {
+                    warn!("Skipping .gitignore pattern '{}': {}", line, e);
+                }
+            }
+        }
+
+        for pattern in extra_patterns {
+            add_ignore_pattern(&mut builder, pattern)
+                .map_err(|e| anyhow::anyhow!("Invalid ignore pattern '{}': {}", pattern, e))?;
+        }
+
+        Ok(Self {
+            matcher: builder.build()?,
+        })
+    }
+
+    fn is_ignored(&self, relative: &Path) -> bool {
+        self.matcher.is_match(relative)
+    }
+}
+
+/// Add a gitignore-style pattern: anchored when it contains a `/`, matched
+/// against any path segment otherwise.
+fn add_ignore_pattern(builder: &mut GlobSetBuilder, pattern: &str) -> anyhow::Result<()> {
+    let pattern = pattern.trim_end_matches('/');
+    let glob = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+    builder.add(GlobBuilder::new(&glob).literal_separator(true).build()?);
+    Ok(())
+}
+
+/// Read a list of strings from a tool argument.
+fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Show a path relative to the directory being listed or searched.
+fn display_path(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+// Tool for listing the contents of a directory.
+#[derive(Clone, Default)]
+pub struct ListDirectoryTool {
+    workspace: Workspace,
+}
+
+impl ListDirectoryTool {
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+fn list_entries(
+    base: &Path,
+    dir: &Path,
+    depth: usize,
+    max_depth: usize,
+    ignore: &IgnoreRules,
+    entries: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let mut children: Vec<_> = fs::read_dir(dir)?.filter_map(Result::ok).collect();
+    children.sort_by_key(|entry| entry.file_name());
+
+    for child in children {
+        if entries.len() >= MAX_LIST_ENTRIES {
+            return Ok(());
+        }
+
+        let path = child.path();
+        let relative = path.strip_prefix(base).unwrap_or(&path);
+        if ignore.is_ignored(relative) {
+            continue;
+        }
+
+        // Don't follow symlinks, they may point outside the workspace
+        let file_type = child.file_type()?;
+        let indent = "  ".repeat(depth - 1);
+        let name = child.file_name().to_string_lossy().to_string();
+        if file_type.is_dir() {
+            entries.push(format!("{}{}/", indent, name));
+            if depth < max_depth {
+                list_entries(base, &path, depth + 1, max_depth, ignore, entries)?;
+            }
+        } else if file_type.is_symlink() {
+            entries.push(format!("{}{}@", indent, name));
+        } else {
+            entries.push(format!("{}{}", indent, name));
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl Tool for ListDirectoryTool {
+    fn name(&self) -> String {
+        "list_directory".to_string()
+    }
+
+    fn description(&self) -> String {
+        "List files and directories, optionally recursing to a given depth".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The directory to list. Defaults to the workspace root"
+                },
+                "depth": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 10,
+                    "description": "How many levels to descend (1 lists only direct children). Defaults to 1"
+                },
+                "ignore": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Extra gitignore-style patterns to skip. .git, target, node_modules and the root .gitignore are always skipped"
+                }
+            }
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::File
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let path = args["path"]
+            .as_str()
+            .filter(|path| !path.is_empty())
+            .unwrap_or(".");
+        let depth = args["depth"].as_u64().unwrap_or(1).clamp(1, 10) as usize;
+        let dir = self.workspace.resolve_read(path)?;
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!("'{}' is not a directory", path));
+        }
+
+        let ignore = IgnoreRules::load(&dir, &string_list(&args["ignore"]))?;
+        let mut entries = Vec::new();
+        list_entries(&dir, &dir, 1, depth, &ignore, &mut entries)?;
+
+        if entries.is_empty() {
+            return Ok(format!("Directory '{}' is empty.", path));
+        }
+        if entries.len() >= MAX_LIST_ENTRIES {
+            entries.push(format!(
+                "[... listing stopped after {} entries]",
+                MAX_LIST_ENTRIES
+            ));
+        }
+        Ok(entries.join("\n"))
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
+
+// Tool for searching file contents with a regular expression.
+#[derive(Clone, Default)]
+pub struct GrepFilesTool {
+    workspace: Workspace,
+}
+
+impl GrepFilesTool {
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+/// Search options shared by every file visited.
+struct GrepSearch<'a> {
+    base: &'a Path,
+    regex: regex::Regex,
+    include: Option<GlobSet>,
+    ignore: IgnoreRules,
+    max_results: usize,
+}
+
+impl GrepSearch<'_> {
+    fn visit(&self, path: &Path, matches: &mut Vec<String>) -> anyhow::Result<()> {
+        if matches.len() >= self.max_results {
+            return Ok(());
+        }
+
+        let relative = path.strip_prefix(self.base).unwrap_or(path);
+        if !relative.as_os_str().is_empty() && self.ignore.is_ignored(relative) {
+            return Ok(());
+        }
+
+        let metadata = fs::symlink_metadata(path)?;
+        if metadata.is_dir() {
+            // An unreadable subdirectory shouldn't end the whole search
+            let entries = match fs::read_dir(path) {
+                Ok(entries) => entries,
+                Err(e) if relative.as_os_str().is_empty() => return Err(e.into()),
+                Err(e) => {
+                    warn!("Skipping unreadable directory '{}': {}", path.display(), e);
+                    return Ok(());
+                }
+            };
+            let mut children: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .collect();
+            children.sort();
+            for child in children {
+                self.visit(&child, matches)?;
+            }
+        } else if metadata.is_file() {
+            self.search_file(path, metadata.len(), matches);
+        }
+
+        Ok(())
+    }
+
+    fn search_file(&self, path: &Path, size: u64, matches: &mut Vec<String>) {
+        let name = display_path(self.base, path);
+        if let Some(include) = &self.include {
+            let file_name = path.file_name().map(Path::new).unwrap_or(path);
+            if !include.is_match(&name) && !include.is_match(file_name) {
+                return;
+            }
+        }
+        if size > MAX_GREP_FILE_BYTES {
+            return;
+        }
+
+        // Skip binary and unreadable files
+        let Ok(bytes) = fs::read(path) else {
+            return;
+        };
+        if bytes.iter().take(8192).any(|byte| *byte == 0) {
+            return;
+        }
+
+        let content = String::from_utf8_lossy(&bytes);
+        for (number, line) in content.lines().enumerate() {
+            if matches.len() >= self.max_results {
+                return;
+            }
+            if self.regex.is_match(line) {
+                matches.push(format!("{}:{}: {}", name, number + 1, line));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for GrepFilesTool {
+    fn name(&self) -> String {
+        "grep_files".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Search files in the workspace for lines matching a regular expression".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "The regular expression to search for"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to search. Defaults to the workspace root"
+                },
+                "include": {
+                    "type": "string",
+                    "description": "Only search files matching this glob, e.g. '*.rs' or 'src/**/*.toml'"
+                },
+                "case_insensitive": {
+                    "type": "boolean",
+                    "description": "Ignore case when matching. Defaults to false"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Most matching lines to return. Defaults to 100"
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::File
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let pattern = args["pattern"].as_str().unwrap_or("");
+        if pattern.is_empty() {
+            return Err(anyhow::anyhow!("'pattern' argument is required"));
+        }
+        let path = args["path"]
+            .as_str()
+            .filter(|path| !path.is_empty())
+            .unwrap_or(".");
+        let max_results = args["max_results"].as_u64().unwrap_or(100).max(1) as usize;
+
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(args["case_insensitive"].as_bool().unwrap_or(false))
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid regex '{}': {}", pattern, e))?;
+        let include = match args["include"].as_str().filter(|glob| !glob.is_empty()) {
+            Some(glob) => Some(
+                Glob::new(glob)
+                    .and_then(|compiled| GlobSetBuilder::new().add(compiled).build())
+                    .map_err(|e| anyhow::anyhow!("Invalid include glob '{}': {}", glob, e))?,
+            ),
+            None => None,
+        };
+
+        let target = self.workspace.resolve_read(path)?;
+        let base = if target.is_dir() {
+            target.clone()
+        } else {
+            target.parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let search = GrepSearch {
+            base: &base,
+            regex,
+            include,
+            ignore: IgnoreRules::load(&base, &[])?,
+            max_results,
+        };
+
+        let mut matches = Vec::new();
+        search.visit(&target, &mut matches)?;
+
+        if matches.is_empty() {
+            return Ok(format!("No matches for '{}'.", pattern));
+        }
+        if matches.len() >= max_results {
+            matches.push(format!("[... stopped after {} matches]", max_results));
+        }
+        Ok(matches.join("\n"))
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
+
+// Tool for editing a file by replacing an exact string.
+#[derive(Clone, Default)]
+pub struct EditFileTool {
+    workspace: Workspace,
+}
+
+impl EditFileTool {
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for EditFileTool {
+    fn name(&self) -> String {
+        "edit_file".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Edit a file by replacing an exact string. The string must be unique unless replace_all is set".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path to the file to edit"
+                },
+                "old_string": {
+                    "type": "string",
+                    "description": "The exact text to replace, including whitespace and indentation"
+                },
+                "new_string": {
+                    "type": "string",
+                    "description": "The text to replace it with"
+                },
+                "replace_all": {
+                    "type": "boolean",
+                    "description": "Replace every occurrence instead of requiring a unique match. Defaults to false"
+                }
+            },
+            "required": ["path", "old_string", "new_string"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::File
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let path = args["path"].as_str().unwrap_or("");
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("'path' argument is required"));
+        }
+        let old_string = args["old_string"].as_str().unwrap_or("");
+        if old_string.is_empty() {
+            return Err(anyhow::anyhow!("'old_string' argument is required"));
+        }
+        let Some(new_string) = args["new_string"].as_str() else {
+            return Err(anyhow::anyhow!("'new_string' argument is required"));
+        };
+        let replace_all = args["replace_all"].as_bool().unwrap_or(false);
+
+        let resolved = self.workspace.resolve_write(path)?;
+        let content = fs::read_to_string(&resolved)?;
+        let occurrences = content.matches(old_string).count();
+        match occurrences {
+            0 => {
+                return Err(anyhow::anyhow!("'old_string' was not found in '{}'", path));
+            }
+            1 => {}
+            count if !replace_all => {
+                return Err(anyhow::anyhow!(
+                    "'old_string' appears {} times in '{}'. Include more surrounding text to make it unique, or set replace_all",
+                    count,
+                    path
+                ));
+            }
+            _ => {}
+        }
+
+        fs::write(&resolved, content.replace(old_string, new_string))?;
+        Ok(format!(
+            "Replaced {} occurrence(s) in '{}'.",
+            occurrences, path
+        ))
+    }
+
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        args["path"]
+            .as_str()
+            .and_then(|path| self.workspace.resolve_write(path).ok())
+            .into_iter()
+            .collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,621 @@
+use crate::config::{ShellToolConfig, ToolScope};
+use crate::core::workspace::Workspace;
+use crate::types::Tool as ApiTool;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)] // Variants may be used in different configurations
+pub enum ToolProfile {
+    File,
+    Shell,
+    Web,
+    Generic,
+}
+
+use async_trait::async_trait;
+
+mod diff;
+mod files;
+mod patch;
+
+pub use diff::unified_diff;
+pub use files::{EditFileTool, GrepFilesTool, ListDirectoryTool};
+pub use patch::ApplyUnifiedDiffTool;
+
+// The main trait for any tool that can be executed by the agent.
+#[async_trait]
+#[allow(dead_code)] // Trait methods are part of the public API
+pub trait Tool: Send + Sync {
+    fn name(&self) -> String;
+    fn description(&self) -> String;
+    fn parameters(&self) -> Value;
+    fn profile(&self) -> ToolProfile;
+    async fn execute(&self, args: &Value) -> anyhow::Result<String>;
+
+    // Describes what a call would change, shown alongside the approval prompt.
+    fn preview(&self, _args: &Value) -> Option<String> {
+        None
+    }
+
+    // Files a call may modify. Their contents are snapshotted before the call
+    // runs so its effect can be undone.
+    fn affected_paths(&self, _args: &Value) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    // Name tool permissions are keyed by. Tools offered under an alias report
+    // the name they are registered with, so permissions survive renaming.
+    fn qualified_name(&self) -> String {
+        self.name()
+    }
+
+    // Provides the full tool definition for the Ollama API.
+    fn definition(&self) -> ApiTool {
+        ApiTool::new(&self.name(), &self.description(), self.parameters())
+    }
+
+    // The definition, if the tool has one of the given profiles.
+    fn definition_if(&self, profiles: &[ToolProfile]) -> Option<ApiTool> {
+        profiles
+            .contains(&self.profile())
+            .then(|| self.definition())
+    }
+
+    // Method to clone the tool as a boxed trait object
+    fn clone_box(&self) -> Box<dyn Tool>;
+}
+
+// A set of tools that can change while the registry is in use, such as the
+// tools of an MCP server that is restarted or rediscovered.
+pub trait ToolSource: Send + Sync {
+    // The tools currently offered
+    fn tools(&self) -> Vec<Box<dyn Tool>>;
+
+    // Method to clone the source as a boxed trait object
+    fn clone_source(&self) -> Box<dyn ToolSource>;
+}
+
+// A registry to hold all available tools.
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn Tool>>,
+    sources: Vec<Box<dyn ToolSource>>,
+    // Scope applied to the tools of the sources when they are looked up
+    source_scope: Option<ToolScope>,
+}
+
+#[allow(dead_code)] // Methods are used in the application and form part of the public API
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self {
+            tools: Vec::new(),
+            sources: Vec::new(),
+            source_scope: None,
+        }
+    }
+
+    // Adds a tool; a tool whose name is already taken is ignored with a warning
+    pub fn add_tool(&mut self, tool: Box<dyn Tool>) {
+        if self.tools.iter().any(|t| t.name() == tool.name()) {
+            warn!(
+                "Tool '{}' is already registered; ignoring the duplicate",
+                tool.name()
+            );
+            return;
+        }
+        self.tools.push(tool);
+    }
+
+    // Names of the tools added directly, not through a source
+    pub fn tool_names(&self) -> Vec<String> {
+        self.tools.iter().map(|t| t.name()).collect()
+    }
+
+    pub fn add_source(&mut self, source: Box<dyn ToolSource>) {
+        self.sources.push(source);
+    }
+
+    // Tools currently offered by the sources, after the scope. A source tool
+    // never shadows a tool added directly or an earlier source tool.
+    fn source_tools(&self) -> Vec<Box<dyn Tool>> {
+        let mut names: HashSet<String> = self.tool_names().into_iter().collect();
+        self.sources
+            .iter()
+            .flat_map(|source| source.tools())
+            .filter(|tool| {
+                if !names.insert(tool.name()) {
+                    debug!("Tool '{}' is shadowed by another tool", tool.name());
+                    return false;
+                }
+                match &self.source_scope {
+                    Some(scope) => scope.allows(&tool.name(), tool.profile()),
+                    None => true,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_tool(&self, name: &str) -> Option<Box<dyn Tool>> {
+        self.tools
+            .iter()
+            .find(|t| t.name() == name)
+            .map(|t| t.clone_box())
+            .or_else(|| self.source_tools().into_iter().find(|t| t.name() == name))
+    }
+
+    // The name permissions for the named tool are keyed by
+    pub fn qualified_name(&self, name: &str) -> String {
+        self.get_tool(name)
+            .map(|tool| tool.qualified_name())
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    pub fn definitions(&self) -> Vec<ApiTool> {
+        self.tools
+            .iter()
+            .map(|t| t.definition())
+            .chain(self.source_tools().iter().map(|t| t.definition()))
+            .collect()
+    }
+
+    pub fn definitions_with_profiles(&self, profiles: &[ToolProfile]) -> Vec<ApiTool> {
+        self.tools
+            .iter()
+            .map(|t| t.definition_if(profiles))
+            .chain(
+                self.source_tools()
+                    .iter()
+                    .map(|t| t.definition_if(profiles)),
+            )
+            .flatten()
+            .collect()
+    }
+
+    // Creates a registry holding only the tools the scope lets through. The
+    // scope also applies to tools the sources offer later.
+    pub fn filtered(&self, scope: &ToolScope) -> Self {
+        for name in scope.include_tools.iter().chain(&scope.exclude_tools) {
+            if self.get_tool(name).is_none() {
+                warn!("Tool scope names unknown tool '{}'", name);
+            }
+        }
+
+        let mut new_registry = ToolRegistry::new();
+        for tool in &self.tools {
+            if scope.allows(&tool.name(), tool.profile()) {
+                new_registry.tools.push(tool.clone_box());
+            } else {
+                debug!("Tool '{}' is out of scope", tool.name());
+            }
+        }
+        new_registry.sources = self.sources.iter().map(|s| s.clone_source()).collect();
+        new_registry.source_scope = Some(scope.clone());
+        new_registry
+    }
+
+    // Clone method that creates a new registry with the same tool definitions
+    pub fn clone_registry(&self) -> Self {
+        // Create a new registry with cloned tools
+        let mut new_registry = ToolRegistry::new();
+        // Clone all tools using their clone_box method
+        for tool in &self.tools {
+            new_registry.tools.push(tool.clone_box());
+        }
+        new_registry.sources = self.sources.iter().map(|s| s.clone_source()).collect();
+        new_registry.source_scope = self.source_scope.clone();
+        new_registry
+    }
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tool for writing content to a file.
+#[derive(Clone, Default)]
+pub struct WriteFileTool {
+    workspace: Workspace,
+}
+
+impl WriteFileTool {
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for WriteFileTool {
+    fn name(&self) -> String {
+        "write_file".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Write content to a file".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path to the file to write"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "The content to write to the file"
+                }
+            },
+            "required": ["path", "content"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::File
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let path = args["path"].as_str().unwrap_or("");
+        let content = args["content"].as_str().unwrap_or("");
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("'path' argument is required"));
+        }
+        let resolved = self.workspace.resolve_write(path)?;
+        fs::write(resolved, content)?;
+        Ok(format!("File '{}' written successfully.", path))
+    }
+
+    fn preview(&self, args: &Value) -> Option<String> {
+        let path = args["path"].as_str().filter(|path| !path.is_empty())?;
+        let content = args["content"].as_str().unwrap_or("");
+        let resolved = self.workspace.resolve_write(path).ok()?;
+        let current = fs::read_to_string(resolved).ok();
+        Some(unified_diff(path, current.as_deref(), content))
+    }
+
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        args["path"]
+            .as_str()
+            .and_then(|path| self.workspace.resolve_write(path).ok())
+            .into_iter()
+            .collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
+
+// Tool for reading content from a file.
+#[derive(Clone, Default)]
+pub struct ReadFileTool {
+    workspace: Workspace,
+}
+
+impl ReadFileTool {
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadFileTool {
+    fn name(&self) -> String {
+        "read_file".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read content from a file, optionally limited to a range of lines".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path to the file to read"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "First line to return (1-based, inclusive). Defaults to the first line"
+                },
+                "end_line": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Last line to return (1-based, inclusive). Defaults to the last line"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::File
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let path = args["path"].as_str().unwrap_or("");
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("'path' argument is required"));
+        }
+        let resolved = self.workspace.resolve_read(path)?;
+        let content = fs::read_to_string(resolved)?;
+
+        let start_line = args["start_line"].as_u64();
+        let end_line = args["end_line"].as_u64();
+        if start_line.is_none() && end_line.is_none() {
+            return Ok(content);
+        }
+
+        let total_lines = content.lines().count();
+        let start = start_line.unwrap_or(1).max(1) as usize;
+        let end = end_line.map_or(total_lines, |end| (end as usize).min(total_lines));
+        if start > total_lines || start > end {
+            return Err(anyhow::anyhow!(
+                "Invalid line range {}-{} for '{}' ({} lines)",
+                start,
+                end_line.map_or("end".to_string(), |end| end.to_string()),
+                path,
+                total_lines
+            ));
+        }
+
+        Ok(content
+            .lines()
+            .skip(start - 1)
+            .take(end - start + 1)
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
+
+// Tool for running a shell command.
+#[derive(Clone, Default)]
+pub struct RunShellCommandTool {
+    config: ShellToolConfig,
+    working_dir: Option<PathBuf>,
+}
+
+#[allow(dead_code)] // Methods are used in the application and form part of the public API
+impl RunShellCommandTool {
+    pub fn new(config: ShellToolConfig) -> Self {
+        Self {
+            config,
+            working_dir: None,
+        }
+    }
+
+    /// Run commands in the given directory instead of the current one.
+    pub fn with_working_dir(mut self, working_dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(working_dir.into());
+        self
+    }
+}
+
+/// Output read from a child process pipe, capped at a byte limit.
+#[derive(Default)]
+struct CappedOutput {
+    kept: Vec<u8>,
+    total: usize,
+    /// Reading stopped while a leftover process still held the pipe open
+    held_open: bool,
+}
+
+impl CappedOutput {
+    fn render(&self) -> String {
+        let mut text = String::from_utf8_lossy(&self.kept).to_string();
+        if self.total > self.kept.len() {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&format!(
+                "[... output truncated, {} more bytes]",
+                self.total - self.kept.len()
+            ));
+        }
+        if self.held_open {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str("[output truncated: pipe held open]");
+        }
+        text
+    }
+}
+
+/// A pipe read in the background into a buffer shared with the caller
+struct PipeReader {
+    task: JoinHandle<()>,
+    output: Arc<Mutex<CappedOutput>>,
+}
+
+/// Read a pipe to the end, keeping at most `limit` bytes so the child never blocks.
+fn read_capped<R: AsyncRead + Unpin + Send + 'static>(mut reader: R, limit: usize) -> PipeReader {
+    let output = Arc::new(Mutex::new(CappedOutput::default()));
+    let shared = output.clone();
+    let task = tokio::spawn(async move {
+        let mut buffer = [0u8; 8192];
+        while let Ok(read) = reader.read(&mut buffer).await {
+            if read == 0 {
+                break;
+            }
+            let mut output = shared.lock().unwrap_or_else(|e| e.into_inner());
+            output.total += read;
+            let keep = limit.saturating_sub(output.kept.len()).min(read);
+            output.kept.extend_from_slice(&buffer[..keep]);
+        }
+    });
+    PipeReader { task, output }
+}
+
+/// Collect a pipe reader, stopping with what was read so far if the pipe is
+/// held open by a leftover process.
+async fn collect_output(mut reader: PipeReader) -> CappedOutput {
+    let finished = tokio::time::timeout(Duration::from_secs(1), &mut reader.task)
+        .await
+        .is_ok();
+    if !finished {
+        reader.task.abort();
+    }
+    let mut output = std::mem::take(&mut *reader.output.lock().unwrap_or_else(|e| e.into_inner()));
+    output.held_open = !finished;
+    output
+}
+
+/// Kill a child started in its own process group, along with everything it spawned.
+async fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: kill(2) has no memory safety requirements; a negative pid targets the group
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    child.kill().await.ok();
+}
+
+/// Combine stdout, stderr and the exit status into the tool result.
+fn format_command_output(stdout: &CappedOutput, stderr: &CappedOutput, status: &str) -> String {
+    let mut result = stdout.render();
+    let stderr = stderr.render();
+    if !stderr.is_empty() {
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push('\n');
+        }
+        result.push_str("[stderr]\n");
+        result.push_str(&stderr);
+    }
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(&format!("[{}]", status));
+    result
+}
+
+#[async_trait]
+impl Tool for RunShellCommandTool {
+    fn name(&self) -> String {
+        "run_shell_command".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Run a shell command".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "The shell command to run"
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::Shell
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let command = args["command"].as_str().unwrap_or("");
+        if command.is_empty() {
+            return Err(anyhow::anyhow!("'command' argument is required"));
+        }
+
+        let mut process = Command::new("sh");
+        process
+            .arg("-c")
+            .arg(command)
+            .env_clear()
+            .envs(
+                self.config
+                    .env_allowlist
+                    .iter()
+                    .filter_map(|name| std::env::var_os(name).map(|value| (name, value))),
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Run the command in its own process group so a timeout kills its children too
+        #[cfg(unix)]
+        process.process_group(0);
+        if let Some(working_dir) = &self.working_dir {
+            process.current_dir(working_dir);
+        }
+
+        let mut child = process.spawn()?;
+        let limit = self.config.max_output_bytes;
+        let stdout = child.stdout.take().map(|pipe| read_capped(pipe, limit));
+        let stderr = child.stderr.take().map(|pipe| read_capped(pipe, limit));
+
+        let status = if self.config.timeout_secs == 0 {
+            Some(child.wait().await?)
+        } else {
+            let timeout = Duration::from_secs(self.config.timeout_secs);
+            match tokio::time::timeout(timeout, child.wait()).await {
+                Ok(status) => Some(status?),
+                Err(_) => {
+                    warn!(
+                        "Shell command timed out after {}s, killing it: {}",
+                        self.config.timeout_secs, command
+                    );
+                    kill_process_tree(&mut child).await;
+                    None
+                }
+            }
+        };
+
+        let collect = |reader: Option<PipeReader>| async move {
+            match reader {
+                Some(reader) => collect_output(reader).await,
+                None => CappedOutput::default(),
+            }
+        };
+        let (stdout, stderr) = tokio::join!(collect(stdout), collect(stderr));
+
+        let Some(status) = status else {
+            anyhow::bail!(
+                "Command timed out after {} seconds and was killed.\n{}",
+                self.config.timeout_secs,
+                format_command_output(&stdout, &stderr, "killed")
+            );
+        };
+
+        let exit = match status.code() {
+            Some(code) => format!("exit code: {}", code),
+            None => "terminated by signal".to_string(),
+        };
+        let result = format_command_output(&stdout, &stderr, &exit);
+        if !status.success() {
+            anyhow::bail!("Command failed.\n{}", result);
+        }
+        Ok(result)
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,389 @@
+//! Tool for applying unified diffs to files in the workspace.
+
+use super::{Tool, ToolProfile};
+use crate::core::workspace::Workspace;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// One `@@ -a,b +c,d @@` section of a file diff.
+#[derive(Debug, Default)]
+struct Hunk {
+    old_start: usize,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+/// The changes to one file.
+#[derive(Debug, Default)]
+struct FilePatch {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+    /// Set by a `\ No newline at end of file` marker after a new-side line
+    new_missing_newline: bool,
+}
+
+impl FilePatch {
+    fn target(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// Strip the `a/`/`b/` prefix and any timestamp from a `---`/`+++` header path.
+fn parse_header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parse the old start line from `@@ -12,5 +12,6 @@ optional context`.
+fn parse_hunk_header(line: &str) -> anyhow::Result<usize> {
+    let ranges = line
+        .strip_prefix("@@")
+        .and_then(|rest| rest.split("@@").next())
+        .ok_or_else(|| anyhow::anyhow!("Malformed hunk header: {}", line))?;
+    let old_range = ranges
+        .split_whitespace()
+        .find_map(|range| range.strip_prefix('-'))
+        .ok_or_else(|| anyhow::anyhow!("Malformed hunk header: {}", line))?;
+    let start = old_range.split(',').next().unwrap_or(old_range);
+    start
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Malformed hunk header: {}", line))
+}
+
+fn parse_unified_diff(diff: &str) -> anyhow::Result<Vec<FilePatch>> {
+    let mut patches: Vec<FilePatch> = Vec::new();
+    // Trailing blank lines would otherwise read as empty context lines
+    let mut lines = diff.trim_end().lines().peekable();
+    let mut last_was_new_side = false;
+
+    while let Some(line) = lines.next() {
+        // A `---` line is a file header only when a `+++` line follows; otherwise
+        // it removes a line that starts with `--`
+        if let Some(old_header) = line.strip_prefix("--- ")
+            && let Some(new_header) = lines.next_if(|next| next.starts_with("+++ "))
+        {
+            patches.push(FilePatch {
+                old_path: parse_header_path(old_header),
+                new_path: parse_header_path(&new_header[4..]),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let Some(patch) = patches.last_mut() else {
+                return Err(anyhow::anyhow!(
+                    "Hunk found before a '---'/'+++' file header"
+                ));
+            };
+            patch.hunks.push(Hunk {
+                old_start: parse_hunk_header(line)?,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(hunk) = patches.last_mut().and_then(|patch| patch.hunks.last_mut()) else {
+            // Text before the first file, e.g. `diff --git` or commit messages
+            continue;
+        };
+
+        if line.starts_with('\\') {
+            if last_was_new_side {
+                patches.last_mut().unwrap().new_missing_newline = true;
+            }
+            continue;
+        }
+
+        match line.chars().next() {
+            Some('+') => {
+                hunk.new_lines.push(line[1..].to_string());
+                last_was_new_side = true;
+            }
+            Some('-') => {
+                hunk.old_lines.push(line[1..].to_string());
+                last_was_new_side = false;
+            }
+            Some(' ') => {
+                hunk.old_lines.push(line[1..].to_string());
+                hunk.new_lines.push(line[1..].to_string());
+                last_was_new_side = true;
+            }
+            // Some generators drop the space on empty context lines
+            None => {
+                hunk.old_lines.push(String::new());
+                hunk.new_lines.push(String::new());
+                last_was_new_side = true;
+            }
+            _ => {
+                // `diff --git`, `index ...` and similar lines between files
+                last_was_new_side = false;
+            }
+        }
+    }
+
+    if patches.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No file headers ('---'/'+++') found in the diff"
+        ));
+    }
+    Ok(patches)
+}
+
+fn lines_match(actual: &[String], expected: &[String]) -> bool {
+    actual.len() == expected.len()
+        && actual.iter().zip(expected).all(|(actual, expected)| {
+            actual.trim_end_matches('\r') == expected.trim_end_matches('\r')
+        })
+}
+
+/// Find where a hunk applies, preferring the position closest to `expected`.
+fn find_hunk_position(
+    lines: &[String],
+    old_lines: &[String],
+    expected: usize,
+    min: usize,
+) -> Option<usize> {
+    if old_lines.is_empty() {
+        return Some(expected.clamp(min, lines.len()));
+    }
+    let last_start = lines.len().checked_sub(old_lines.len())?;
+    let fits = |start: usize| {
+        start >= min
+            && start <= last_start
+            && lines_match(&lines[start..start + old_lines.len()], old_lines)
+    };
+
+    (0..=lines.len()).find_map(|offset| {
+        [expected.checked_sub(offset), expected.checked_add(offset)]
+            .into_iter()
+            .flatten()
+            .find(|start| fits(*start))
+    })
+}
+
+/// Apply a file's hunks to its current content.
+fn apply_file_patch(original: &str, patch: &FilePatch) -> anyhow::Result<String> {
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    let mut had_trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut min = 0usize;
+    let mut delta = 0isize;
+
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        // Pure insertions use the line after which to insert; others the first line
+        let stated = if hunk.old_lines.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (stated as isize + delta).max(0) as usize;
+        let position =
+            find_hunk_position(&lines, &hunk.old_lines, expected, min).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Hunk {} for '{}' does not match the file (expected near line {})",
+                    index + 1,
+                    patch.target(),
+                    hunk.old_start
+                )
+            })?;
+
+        if position + hunk.old_lines.len() == lines.len() && !hunk.new_lines.is_empty() {
+            // The hunk touches the end of the file; the diff decides the final newline
+            had_trailing_newline = !patch.new_missing_newline;
+        }
+        lines.splice(
+            position..position + hunk.old_lines.len(),
+            hunk.new_lines.iter().cloned(),
+        );
+        min = position + hunk.new_lines.len();
+        delta += hunk.new_lines.len() as isize - hunk.old_lines.len() as isize;
+    }
+
+    let mut result = lines.join("\n");
+    if had_trailing_newline && !lines.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// A checked change to one file, with what it replaces.
+struct FileChange {
+    path: String,
+    resolved: PathBuf,
+    /// `None` for a file the diff creates
+    original: Option<String>,
+    /// `None` for a file the diff deletes
+    updated: Option<String>,
+}
+
+impl FileChange {
+    fn apply(&self) -> std::io::Result<()> {
+        match &self.updated {
+            Some(content) => {
+                if let Some(parent) = self.resolved.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&self.resolved, content)
+            }
+            None => fs::remove_file(&self.resolved),
+        }
+    }
+
+    fn revert(&self) -> std::io::Result<()> {
+        match &self.original {
+            Some(content) => fs::write(&self.resolved, content),
+            None => fs::remove_file(&self.resolved),
+        }
+    }
+}
+
+// Tool for applying a unified diff to one or more files.
+#[derive(Clone, Default)]
+pub struct ApplyUnifiedDiffTool {
+    workspace: Workspace,
+}
+
+impl ApplyUnifiedDiffTool {
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for ApplyUnifiedDiffTool {
+    fn name(&self) -> String {
+        "apply_unified_diff".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Apply a unified diff (as produced by `diff -u` or `git diff`) to files in the workspace. Either every file is patched or none is".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "diff": {
+                    "type": "string",
+                    "description": "The unified diff, with '---'/'+++' file headers and '@@' hunks. Use /dev/null as the old file to create a file, or as the new file to delete one. Put all hunks of a file under one header; renames are not supported"
+                }
+            },
+            "required": ["diff"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::File
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        let diff = args["diff"].as_str().unwrap_or("");
+        if diff.trim().is_empty() {
+            return Err(anyhow::anyhow!("'diff' argument is required"));
+        }
+
+        // Work out every change before touching the filesystem
+        let mut changes: Vec<FileChange> = Vec::new();
+        for patch in parse_unified_diff(diff)? {
+            let path = patch.target().to_string();
+            if let (Some(old_path), Some(new_path)) = (&patch.old_path, &patch.new_path)
+                && old_path != new_path
+            {
+                return Err(anyhow::anyhow!(
+                    "Cannot rename '{}' to '{}': renames are not supported",
+                    old_path,
+                    new_path
+                ));
+            }
+            let resolved = self.workspace.resolve_write(&path)?;
+            // Each section would read the file as it is on disk and undo the ones before
+            if changes.iter().any(|change| change.resolved == resolved) {
+                return Err(anyhow::anyhow!(
+                    "'{}' appears more than once in the diff; put all of its hunks under one file header",
+                    path
+                ));
+            }
+            let original = match &patch.old_path {
+                Some(_) => Some(
+                    fs::read_to_string(&resolved)
+                        .map_err(|e| anyhow::anyhow!("Cannot read '{}': {}", path, e))?,
+                ),
+                None if resolved.exists() => {
+                    return Err(anyhow::anyhow!(
+                        "Cannot create '{}': the file already exists",
+                        path
+                    ));
+                }
+                None => None,
+            };
+            // Deletions are checked against the file just like edits
+            let patched = apply_file_patch(original.as_deref().unwrap_or_default(), &patch)?;
+            let updated = match patch.new_path {
+                Some(_) => Some(patched),
+                None if patched.is_empty() => None,
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "Cannot delete '{}': the diff does not remove all of its content",
+                        path
+                    ));
+                }
+            };
+            changes.push(FileChange {
+                path,
+                resolved,
+                original,
+                updated,
+            });
+        }
+
+        let mut summary = Vec::new();
+        for (index, change) in changes.iter().enumerate() {
+            if let Err(e) = change.apply() {
+                // Put back the files already changed so no partial patch is left behind
+                for applied in changes[..index].iter().rev() {
+                    if let Err(e) = applied.revert() {
+                        warn!("Could not roll back '{}': {}", applied.path, e);
+                    }
+                }
+                return Err(anyhow::anyhow!(
+                    "Cannot write '{}': {}. No file was changed",
+                    change.path,
+                    e
+                ));
+            }
+            summary.push(match change.updated {
+                Some(_) => format!("Patched '{}'", change.path),
+                None => format!("Deleted '{}'", change.path),
+            });
+        }
+        Ok(summary.join("\n"))
+    }
+
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        let Ok(patches) = parse_unified_diff(args["diff"].as_str().unwrap_or("")) else {
+            return Vec::new();
+        };
+        patches
+            .iter()
+            .filter_map(|patch| self.workspace.resolve_write(patch.target()).ok())
+            .collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
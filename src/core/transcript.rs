@@ -0,0 +1,295 @@
+//! Readable transcripts of sessions.
+//!
+//! A session's history is rendered as Markdown for review comments, as a
+//! standalone HTML page, or as JSON Lines: a `session` line with the
+//! session's metadata followed by one `message` line per chat message. JSON
+//! Lines transcripts can be read back into a session.
+
+use crate::core::session::SessionMetadata;
+use crate::types::{ChatMessage, ToolCall};
+use serde::{Deserialize, Serialize};
+
+/// Format a session is exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    JsonLines,
+    Html,
+}
+
+impl From<crate::cli::ExportFormat> for ExportFormat {
+    fn from(cli_format: crate::cli::ExportFormat) -> Self {
+        match cli_format {
+            crate::cli::ExportFormat::Md => ExportFormat::Markdown,
+            crate::cli::ExportFormat::Jsonl => ExportFormat::JsonLines,
+            crate::cli::ExportFormat::Html => ExportFormat::Html,
+        }
+    }
+}
+
+/// One line of a JSON Lines transcript
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TranscriptLine {
+    Session(SessionMetadata),
+    Message(ChatMessage),
+}
+
+/// A transcript read back from JSON Lines
+#[derive(Debug)]
+pub struct Transcript {
+    /// Metadata of the exported session, when the transcript has it
+    pub metadata: Option<SessionMetadata>,
+    pub history: Vec<ChatMessage>,
+}
+
+/// Render `history` of the session described by `metadata` in `format`
+pub fn render(
+    metadata: &SessionMetadata,
+    history: &[ChatMessage],
+    format: ExportFormat,
+) -> anyhow::Result<String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(metadata, history)),
+        ExportFormat::JsonLines => render_json_lines(metadata, history),
+        ExportFormat::Html => Ok(render_html(metadata, history)),
+    }
+}
+
+/// Read a JSON Lines transcript. Lines without a `type` are taken as
+/// messages, so plain message logs can be imported too.
+pub fn parse_json_lines(content: &str) -> anyhow::Result<Transcript> {
+    let mut transcript = Transcript {
+        metadata: None,
+        history: Vec::new(),
+    };
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: serde_json::Value = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("Line {} is not JSON: {}", number + 1, e))?;
+        let parsed = if value.get("type").is_some() {
+            serde_json::from_value(value)
+        } else {
+            serde_json::from_value(value).map(TranscriptLine::Message)
+        };
+        match parsed.map_err(|e| anyhow::anyhow!("Line {}: {}", number + 1, e))? {
+            TranscriptLine::Session(metadata) => transcript.metadata = Some(metadata),
+            TranscriptLine::Message(message) => transcript.history.push(message),
+        }
+    }
+    Ok(transcript)
+}
+
+fn render_json_lines(
+    metadata: &SessionMetadata,
+    history: &[ChatMessage],
+) -> anyhow::Result<String> {
+    let mut out = serde_json::to_string(&TranscriptLine::Session(metadata.clone()))?;
+    out.push('\n');
+    for message in history {
+        out.push_str(&serde_json::to_string(&TranscriptLine::Message(
+            message.clone(),
+        ))?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn render_markdown(metadata: &SessionMetadata, history: &[ChatMessage]) -> String {
+    let mut out = format!("# {}\n\n", title(metadata));
+    for (label, value) in summary(metadata) {
+        out.push_str(&format!("- **{}:** {}\n", label, value));
+    }
+
+    for message in history {
+        let mut heading = role_label(message);
+        if let Some(tool_name) = &message.tool_name {
+            heading.push_str(&format!(" `{}`", tool_name));
+        }
+        if let Some(timestamp) = message.timestamp {
+            heading.push_str(&format!(" · {}", format_timestamp(timestamp)));
+        }
+        out.push_str(&format!("\n## {}\n\n", heading));
+
+        if message.role == "tool" {
+            out.push_str(&fenced(&message.content, ""));
+        } else if !message.content.trim().is_empty() {
+            out.push_str(message.content.trim_end());
+            out.push('\n');
+        }
+        for call in message.tool_calls.iter().flatten() {
+            out.push_str(&format!("\n**Tool call** `{}`", call.function.name));
+            if let Some(id) = &call.id {
+                out.push_str(&format!(" ({})", id));
+            }
+            out.push_str("\n\n");
+            out.push_str(&fenced(&arguments(call), "json"));
+        }
+    }
+    out
+}
+
+fn render_html(metadata: &SessionMetadata, history: &[ChatMessage]) -> String {
+    let title = escape_html(&title(metadata));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<h1>{}</h1>\n<dl>\n",
+        title, HTML_STYLE, title
+    );
+    for (label, value) in summary(metadata) {
+        out.push_str(&format!(
+            "<dt>{}</dt><dd>{}</dd>\n",
+            label,
+            escape_html(&value)
+        ));
+    }
+    out.push_str("</dl>\n");
+
+    for message in history {
+        out.push_str(&format!(
+            "<section class=\"message {}\">\n<header><strong>{}</strong>",
+            escape_html(&message.role),
+            escape_html(&role_label(message))
+        ));
+        if let Some(tool_name) = &message.tool_name {
+            out.push_str(&format!(" <code>{}</code>", escape_html(tool_name)));
+        }
+        if let Some(timestamp) = message.timestamp {
+            out.push_str(&format!(" <time>{}</time>", format_timestamp(timestamp)));
+        }
+        out.push_str("</header>\n");
+        if !message.content.is_empty() {
+            out.push_str(&format!("<pre>{}</pre>\n", escape_html(&message.content)));
+        }
+        for call in message.tool_calls.iter().flatten() {
+            out.push_str(&format!(
+                "<details open><summary>Tool call <code>{}</code></summary><pre>{}</pre></details>\n",
+                escape_html(&call.function.name),
+                escape_html(&arguments(call))
+            ));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+const HTML_STYLE: &str =
+    "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+dt { font-weight: bold; float: left; clear: left; margin-right: 0.5em; }
+.message { border-left: 4px solid #999; margin: 1em 0; padding: 0.5em 1em; }
+.message.user { border-color: #2a7ae2; }
+.message.assistant { border-color: #2e9e4f; }
+.message.tool { border-color: #d08a00; }
+header time { color: #666; margin-left: 0.5em; }
+pre { white-space: pre-wrap; word-wrap: break-word; background: #f6f8fa; padding: 0.5em; }
+";
+
+fn title(metadata: &SessionMetadata) -> String {
+    if metadata.title.is_empty() {
+        format!("Session {}", metadata.name)
+    } else {
+        metadata.title.clone()
+    }
+}
+
+/// Labelled metadata shown above the messages
+fn summary(metadata: &SessionMetadata) -> Vec<(&'static str, String)> {
+    let mut summary = vec![("Session", metadata.name.clone())];
+    if let Some(parent) = &metadata.parent {
+        summary.push((
+            "Forked from",
+            format!("{} at message {}", parent.session, parent.message_index),
+        ));
+    }
+    if let Some(agent) = &metadata.agent {
+        summary.push(("Agent", agent.clone()));
+    }
+    summary.push(("Model", metadata.model.clone()));
+    summary.push(("Created", format_timestamp(metadata.created_at)));
+    summary.push(("Updated", format_timestamp(metadata.updated_at)));
+    summary.push((
+        "Messages",
+        format!(
+            "{} (~{} tokens)",
+            metadata.message_count, metadata.token_estimate
+        ),
+    ));
+    summary
+}
+
+fn role_label(message: &ChatMessage) -> String {
+    match message.role.as_str() {
+        "user" => "User".to_string(),
+        "assistant" => "Assistant".to_string(),
+        "system" => "System".to_string(),
+        "tool" => "Tool result".to_string(),
+        role => role.to_string(),
+    }
+}
+
+/// Tool call arguments, pretty-printed
+fn arguments(call: &ToolCall) -> String {
+    match &call.function.arguments {
+        serde_json::Value::String(raw) => raw.clone(),
+        arguments => {
+            serde_json::to_string_pretty(arguments).unwrap_or_else(|_| arguments.to_string())
+        }
+    }
+}
+
+/// `content` in a Markdown code block whose fence is longer than any
+/// backtick run inside it
+fn fenced(content: &str, language: &str) -> String {
+    let longest_run = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}\n", fence, language, content.trim_end(), fence)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unix time as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
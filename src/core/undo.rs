@@ -0,0 +1,142 @@
+//! Before-images of files changed by tool calls.
+//!
+//! Before a tool that modifies files runs, the current contents of the files
+//! it reports through `Tool::affected_paths` are captured. Restoring the
+//! snapshot undoes the call: files are written back with their old contents
+//! and files the call created are removed.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Number of tool effects kept for undo per agent.
+pub const DEFAULT_UNDO_CAPACITY: usize = 50;
+
+/// The contents of one file before a tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSnapshot {
+    path: PathBuf,
+    /// `None` when the file did not exist
+    contents: Option<Vec<u8>>,
+}
+
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
+impl FileSnapshot {
+    /// Capture the current contents of `path`.
+    pub fn capture(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let contents = match fs::read(&path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, contents })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file existed when the snapshot was taken
+    pub fn existed(&self) -> bool {
+        self.contents.is_some()
+    }
+
+    /// Put the file back the way it was.
+    pub fn restore(&self) -> std::io::Result<()> {
+        match &self.contents {
+            Some(contents) => {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&self.path, contents)
+            }
+            None => match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// The files one tool call was about to change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolEffect {
+    pub tool_name: String,
+    pub snapshots: Vec<FileSnapshot>,
+}
+
+impl ToolEffect {
+    /// Snapshot every path a tool call may modify.
+    pub fn capture(tool_name: &str, paths: &[PathBuf]) -> std::io::Result<Self> {
+        let snapshots = paths
+            .iter()
+            .map(FileSnapshot::capture)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(Self {
+            tool_name: tool_name.to_string(),
+            snapshots,
+        })
+    }
+
+    /// Restore every file, in reverse order of capture.
+    pub fn restore(&self) -> anyhow::Result<()> {
+        for snapshot in self.snapshots.iter().rev() {
+            snapshot.restore().map_err(|e| {
+                anyhow::anyhow!("Failed to restore '{}': {}", snapshot.path.display(), e)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// The most recent tool effects, newest last.
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    effects: Vec<ToolEffect>,
+    capacity: usize,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNDO_CAPACITY)
+    }
+}
+
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
+impl UndoStack {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            effects: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an effect, dropping the oldest one when the stack is full.
+    pub fn push(&mut self, effect: ToolEffect) {
+        if self.effects.len() == self.capacity {
+            self.effects.remove(0);
+        }
+        self.effects.push(effect);
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Restore the files changed by the most recent effect.
+    ///
+    /// Returns the undone effect, or `None` when there is nothing to undo. An
+    /// effect that fails to restore stays on the stack so it can be retried.
+    pub fn undo_last(&mut self) -> anyhow::Result<Option<ToolEffect>> {
+        let Some(effect) = self.effects.last() else {
+            return Ok(None);
+        };
+        effect.restore()?;
+        Ok(self.effects.pop())
+    }
+}
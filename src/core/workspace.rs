@@ -0,0 +1,207 @@
+//! Workspace confinement for the OxideAgent file tools.
+//!
+//! A workspace is a primary root plus optional extra roots, each readable or
+//! read-write. File tools resolve every path through the workspace: relative
+//! paths are taken from the primary root, symlinks are followed, and the
+//! resulting path must stay inside one of the roots.
+
+use crate::config::{FileAccessMode, ToolsConfig};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+/// A directory the file tools may access
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceRoot {
+    pub path: PathBuf,
+    pub mode: FileAccessMode,
+}
+
+/// The set of directories the file tools are confined to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    /// The primary root comes first; relative paths are resolved against it
+    roots: Vec<WorkspaceRoot>,
+}
+
+impl Default for Workspace {
+    /// A read-write workspace rooted at the current directory
+    fn default() -> Self {
+        Self::new(".", FileAccessMode::ReadWrite)
+    }
+}
+
+#[allow(dead_code)] // Methods are used in tests and form part of the public API
+impl Workspace {
+    /// Create a workspace with a single primary root.
+    ///
+    /// Roots are canonicalized when a path is resolved, so they may be
+    /// relative or not exist yet.
+    pub fn new(root: impl Into<PathBuf>, mode: FileAccessMode) -> Self {
+        Self {
+            roots: vec![WorkspaceRoot {
+                path: root.into(),
+                mode,
+            }],
+        }
+    }
+
+    /// Allow access to an additional root
+    pub fn with_extra_root(mut self, root: impl Into<PathBuf>, mode: FileAccessMode) -> Self {
+        self.roots.push(WorkspaceRoot {
+            path: root.into(),
+            mode,
+        });
+        self
+    }
+
+    /// Build the workspace described by the `[tools]` config section
+    pub fn from_config(config: &ToolsConfig) -> Self {
+        let root = config
+            .workspace_root
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        config
+            .extra_roots
+            .iter()
+            .fold(Self::new(root, config.file_access), |workspace, extra| {
+                workspace.with_extra_root(&extra.path, extra.mode)
+            })
+    }
+
+    /// The primary root
+    pub fn root(&self) -> &Path {
+        &self.roots[0].path
+    }
+
+    /// All roots, primary first
+    pub fn roots(&self) -> &[WorkspaceRoot] {
+        &self.roots
+    }
+
+    /// Resolve a path for reading. The path must exist.
+    pub fn resolve_read(&self, path: &str) -> anyhow::Result<PathBuf> {
+        self.resolve(path, false)
+    }
+
+    /// Resolve a path for writing. The file itself may not exist yet.
+    pub fn resolve_write(&self, path: &str) -> anyhow::Result<PathBuf> {
+        self.resolve(path, true)
+    }
+
+    fn resolve(&self, path: &str, write: bool) -> anyhow::Result<PathBuf> {
+        let primary = self.root();
+        let requested = Path::new(path);
+        let candidate = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            primary.join(requested)
+        };
+
+        let resolved = canonicalize_for_access(&candidate, write)
+            .map_err(|e| anyhow::anyhow!("Cannot resolve path '{}': {}", path, e))?;
+
+        // Pick the most specific root containing the path
+        let containing_root = self
+            .roots
+            .iter()
+            .filter_map(|root| match root.path.canonicalize() {
+                Ok(canonical) => Some((canonical, root.mode)),
+                Err(e) => {
+                    warn!(
+                        "Workspace root '{}' is not accessible: {}",
+                        root.path.display(),
+                        e
+                    );
+                    None
+                }
+            })
+            .filter(|(canonical, _)| resolved.starts_with(canonical))
+            .max_by_key(|(canonical, _)| canonical.components().count());
+
+        match containing_root {
+            None => Err(anyhow::anyhow!(
+                "Access denied: '{}' is outside the workspace root '{}'",
+                path,
+                primary.display()
+            )),
+            Some((canonical, FileAccessMode::ReadOnly)) if write => Err(anyhow::anyhow!(
+                "Access denied: '{}' is in the read-only workspace root '{}'",
+                path,
+                canonical.display()
+            )),
+            Some(_) => Ok(resolved),
+        }
+    }
+}
+
+/// Symlinks followed while resolving a path before giving up, as in Linux's `ELOOP`
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Canonicalize a path, following symlinks.
+///
+/// For reads the whole path must exist. For writes the deepest existing
+/// ancestor is canonicalized and the missing components are appended; those
+/// may not contain `..`, since they cannot be checked against the filesystem.
+/// A dangling symlink counts as existing: writing through it creates its
+/// target, so the target is resolved in its place.
+fn canonicalize_for_access(path: &Path, write: bool) -> std::io::Result<PathBuf> {
+    if !write {
+        return path.canonicalize();
+    }
+    canonicalize_for_write(path, 0)
+}
+
+fn canonicalize_for_write(path: &Path, hops: usize) -> std::io::Result<PathBuf> {
+    // `symlink_metadata` does not follow links, so a dangling one is not taken for missing
+    let present = |path: &Path| fs::symlink_metadata(path).is_ok();
+
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while !present(existing) {
+        let Some(name) = existing.file_name() else {
+            break;
+        };
+        missing.push(name.to_owned());
+        existing = match existing.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+    }
+
+    let escapes = path
+        .strip_prefix(existing)
+        .map(|rest| {
+            rest.components()
+                .any(|component| matches!(component, Component::ParentDir))
+        })
+        .unwrap_or(true);
+    if escapes {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "'..' is not allowed in the part of a path that does not exist yet",
+        ));
+    }
+
+    let mut resolved = match existing.canonicalize() {
+        Ok(resolved) => resolved,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && existing.is_symlink() => {
+            if hops >= MAX_SYMLINK_HOPS {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "too many levels of symbolic links",
+                ));
+            }
+            let target = fs::read_link(existing)?;
+            let mut target = match existing.parent() {
+                Some(parent) => parent.join(target),
+                None => target,
+            };
+            target.extend(missing.iter().rev());
+            return canonicalize_for_write(&target, hops + 1);
+        }
+        Err(e) => return Err(e),
+    };
+    resolved.extend(missing.iter().rev());
+    Ok(resolved)
+}
@@ -2,11 +2,14 @@ use ratatui::{
     Frame,
     layout::Rect,
     style::{Color, Modifier, Style},
-    text::Span,
+    text::{Line, Span, Text},
     widgets::{Block, BorderType, Borders, Paragraph, Wrap},
 };
 
-use crate::{core::agents::AgentId, types::ToolCall};
+use crate::{
+    core::agents::AgentId,
+    types::{ToolApprovalResponse, ToolCall},
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -14,7 +17,60 @@ pub enum Message {
     Agent(AgentId, String),
     Thinking(AgentId, String, bool), // AgentId, content, is_expanded
     ToolOutput(String, bool),        // content, is_expanded
-    ToolConfirmation(Vec<ToolCall>),
+    ToolConfirmation(
+        Vec<ToolCall>,
+        Vec<Option<String>>,
+        Vec<ToolApprovalResponse>,
+    ), // calls, change previews, decisions made so far
+}
+
+/// Short label describing a tool approval decision.
+pub fn approval_label(response: &ToolApprovalResponse) -> &'static str {
+    match response {
+        ToolApprovalResponse::Allow => "Allowed",
+        ToolApprovalResponse::AlwaysAllow => "Always Allowed",
+        ToolApprovalResponse::AlwaysAllowSession => "Always Allowed for Session",
+        ToolApprovalResponse::Deny => "Denied",
+        ToolApprovalResponse::AlwaysDeny => "Always Denied",
+        ToolApprovalResponse::AlwaysDenySession => "Always Denied for Session",
+    }
+}
+
+/// Status of the tool call at `index` given the decisions made so far.
+fn decision_status(decisions: &[ToolApprovalResponse], index: usize) -> &'static str {
+    match decisions.get(index) {
+        Some(response) => approval_label(response),
+        None if index == decisions.len() => "Awaiting decision",
+        None => "Pending",
+    }
+}
+
+/// Text shown for a call's changes: its diff preview when there is one,
+/// the pretty-printed arguments otherwise.
+fn call_details(call: &ToolCall, preview: Option<&String>) -> String {
+    match preview {
+        Some(preview) => format!("Changes:\n{}", preview),
+        None => format!(
+            "Arguments: {}",
+            serde_json::to_string_pretty(&call.function.arguments)
+                .unwrap_or_else(|_| "Invalid JSON".to_string())
+        ),
+    }
+}
+
+/// Style a unified diff line: additions green, removals red, hunk headers cyan.
+fn diff_line_style(line: &str) -> Style {
+    if line.starts_with("+++") || line.starts_with("---") {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else if line.starts_with('+') {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::default().fg(Color::Red)
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    }
 }
 
 use std::fmt;
@@ -26,12 +82,15 @@ impl fmt::Display for Message {
             Message::Agent(_, s) => write!(f, "{}", s),
             Message::Thinking(_, s, _) => write!(f, "{}", s),
             Message::ToolOutput(s, _) => write!(f, "{}", s),
-            Message::ToolConfirmation(calls) => {
-                let _confirmation_text = String::from("Agent wants to use the following tools:\n");
-                for call in calls {
-                    let args = serde_json::to_string_pretty(&call.function.arguments)
-                        .unwrap_or_else(|_| "Invalid JSON".to_string());
-                    write!(f, "- {}: \n{}", call.function.name, args)?;
+            Message::ToolConfirmation(calls, previews, decisions) => {
+                for (index, call) in calls.iter().enumerate() {
+                    write!(
+                        f,
+                        "- {} [{}]: \n{}",
+                        call.function.name,
+                        decision_status(decisions, index),
+                        call_details(call, previews.get(index).and_then(Option::as_ref))
+                    )?;
                 }
                 write!(f, "Do you approve?")
             }
@@ -141,17 +200,27 @@ impl Message {
                     )
                     .wrap(Wrap { trim: true })
             }
-            Message::ToolConfirmation(calls) => {
-                let mut text = String::new();
-                for call in calls {
-                    text.push_str(&format!("Tool: {}\n", call.function.name));
-                    text.push_str(&format!(
-                        "Arguments: {}\n\n",
-                        serde_json::to_string_pretty(&call.function.arguments)
-                            .unwrap_or_else(|_| "Invalid JSON".to_string())
-                    ));
+            Message::ToolConfirmation(calls, previews, decisions) => {
+                let mut lines = Vec::new();
+                for (index, call) in calls.iter().enumerate() {
+                    lines.push(Line::from(format!(
+                        "Tool {}/{}: {} [{}]",
+                        index + 1,
+                        calls.len(),
+                        call.function.name,
+                        decision_status(decisions, index)
+                    )));
+                    let preview = previews.get(index).and_then(Option::as_ref);
+                    for line in call_details(call, preview).lines() {
+                        let style = match preview {
+                            Some(_) => diff_line_style(line),
+                            None => Style::default(),
+                        };
+                        lines.push(Line::styled(line.to_string(), style));
+                    }
+                    lines.push(Line::default());
                 }
-                Paragraph::new(text)
+                Paragraph::new(Text::from(lines))
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
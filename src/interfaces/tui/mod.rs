@@ -1,6 +1,8 @@
 use crate::core::agents::AgentId;
 use crate::core::interface::{EventEmitter, InputHandler, Interface, OutputHandler};
-use crate::types::{AppEvent, ChatMessage, ToolApprovalResponse, ToolCall};
+use crate::types::{
+    AppEvent, ChatMessage, McpElicitationAnswer, ToolApprovalResponse, ToolCall, ToolCallDecision,
+};
 use async_trait::async_trait;
 use crossterm::{
     event::{
@@ -25,7 +27,9 @@ use tui_input::Input;
 use tui_input::backend::crossterm::EventHandler;
 
 pub mod message;
+pub mod server_request;
 use message::Message;
+use server_request::{ElicitationForm, ServerRequest};
 
 // TODO: Add state for tracking selected item in switcher overlay
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -43,6 +47,8 @@ pub struct Tui {
     status_messages: Vec<String>,
     input: Input,
     tool_calls: Vec<ToolCall>,
+    // Decisions made so far for `tool_calls`, in order
+    tool_decisions: Vec<ToolApprovalResponse>,
     is_awaiting_confirmation: bool,
     show_status_overlay: bool,
     show_agent_overlay: bool,
@@ -61,6 +67,12 @@ pub struct Tui {
     switcher_scroll: usize,
     // Multi-agent status tracking
     agent_statuses: std::collections::HashMap<String, String>, // Agent name to status
+    // Supervised MCP server health
+    mcp_server_statuses: std::collections::BTreeMap<String, String>, // Server name to status
+    // MCP resources to send with the next message (uri, text)
+    pending_attachments: Vec<(String, String)>,
+    // Sampling and elicitation requests of MCP servers; the first is shown
+    server_requests: std::collections::VecDeque<ServerRequest>,
     // Help overlay toggle
     show_help_overlay: bool,
 }
@@ -84,11 +96,8 @@ impl Tui {
         // Convert session history to TUI messages
         let messages = Self::convert_history_to_messages(session_history);
 
-        // Get available sessions from orchestrator
-        let available_sessions = match crate::core::orchestrator::Orchestrator::list_sessions() {
-            Ok(sessions) => sessions,
-            Err(_) => vec!["default".to_string()], // Fallback to default if listing fails
-        };
+        // The orchestrator sends the stored sessions when the switcher opens
+        let available_sessions = vec![session_name.clone()];
 
         Ok(Self {
             terminal,
@@ -98,6 +107,7 @@ impl Tui {
             status_messages: Vec::new(),
             input: Input::default(),
             tool_calls: Vec::new(),
+            tool_decisions: Vec::new(),
             is_awaiting_confirmation: false,
             show_status_overlay: false,
             show_agent_overlay: false,
@@ -114,6 +124,9 @@ impl Tui {
             available_sessions,
             switcher_scroll: 0,
             agent_statuses: std::collections::HashMap::new(),
+            mcp_server_statuses: std::collections::BTreeMap::new(),
+            pending_attachments: Vec::new(),
+            server_requests: std::collections::VecDeque::new(),
             show_help_overlay: false,
         })
     }
@@ -140,6 +153,13 @@ impl Tui {
                         messages.push(Message::Agent(AgentId::Ollama, chat_message.content));
                     }
                 }
+                "tool" => {
+                    let name = chat_message.tool_name.as_deref().unwrap_or("unknown");
+                    messages.push(Message::ToolOutput(
+                        format!("Tool '{}' result: {}", name, chat_message.content),
+                        false,
+                    ));
+                }
                 "system" => {
                     // We typically don't display system messages in the UI
                     // But we could if needed
@@ -165,7 +185,7 @@ impl Tui {
                     f,
                     &self.messages,
                     &self.input,
-                    &self.tool_calls,
+                    &self.tool_calls[self.tool_decisions.len().min(self.tool_calls.len())..],
                     self.is_awaiting_confirmation,
                     &mut self.message_positions,
                     &self.session_name,
@@ -182,16 +202,16 @@ impl Tui {
                     &self.available_sessions,
                     self.switcher_scroll,
                     &self.agent_statuses,
+                    &self.mcp_server_statuses,
                     self.show_help_overlay,
+                    self.server_requests.front(),
                 );
             })?;
 
             if event::poll(Duration::from_millis(100))? {
                 match event::read()? {
-                    Event::Key(key) => {
-                        if self.handle_key_event(key).await? {
-                            break;
-                        }
+                    Event::Key(key) if self.handle_key_event(key).await? => {
+                        break;
                     }
                     Event::Mouse(mouse) => {
                         self.handle_mouse_event(mouse);
@@ -310,9 +330,11 @@ impl Tui {
                     self.messages.push(Message::Agent(AgentId::Ollama, content));
                 }
             }
-            AppEvent::ToolRequest(calls) => {
+            AppEvent::ToolRequest(calls, previews) => {
                 self.tool_calls = calls.clone();
-                self.messages.push(Message::ToolConfirmation(calls));
+                self.tool_decisions.clear();
+                self.messages
+                    .push(Message::ToolConfirmation(calls, previews, Vec::new()));
                 self.is_awaiting_confirmation = true;
             }
             AppEvent::ToolResult(name, result) => {
@@ -356,7 +378,7 @@ impl Tui {
                 // This event is handled by the orchestrator, not the TUI
                 // The TUI doesn't need to do anything special here
             }
-            AppEvent::SwitchSession(_) => {
+            AppEvent::SwitchSession(_) | AppEvent::ForkSession(..) => {
                 // This event is sent to the orchestrator, not handled here
             }
             AppEvent::SwitchAgent(agent_name, _) => {
@@ -383,10 +405,162 @@ impl Tui {
             AppEvent::ListSessions => {
                 // This event is sent to the orchestrator, not handled here
             }
+            AppEvent::UndoLastToolEffect
+            | AppEvent::ListCheckpoints
+            | AppEvent::RestoreCheckpoint(_) => {
+                // These events are sent to the orchestrator, not handled here
+            }
+            AppEvent::CheckpointList(checkpoints) => {
+                let content = if checkpoints.is_empty() {
+                    "No checkpoints yet.".to_string()
+                } else {
+                    let lines: Vec<String> = checkpoints
+                        .iter()
+                        .map(|checkpoint| {
+                            format!(
+                                "#{} (message {}) before {}: {}",
+                                checkpoint.id,
+                                checkpoint.message_index,
+                                checkpoint.tool_name,
+                                checkpoint.description
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "Checkpoints (restore with /restore <id>):\n{}",
+                        lines.join("\n")
+                    )
+                };
+                self.messages.push(Message::ToolOutput(content, true));
+            }
             AppEvent::AgentStatusUpdate(agent_name, status) => {
                 // Update the agent status in our local map
                 self.agent_statuses.insert(agent_name, status);
             }
+            AppEvent::McpServerStatus(server_name, status) => {
+                self.mcp_server_statuses.insert(server_name, status);
+            }
+            AppEvent::ListMcpResources
+            | AppEvent::ReadMcpResource(_)
+            | AppEvent::ListMcpPrompts
+            | AppEvent::GetMcpPrompt(_, _) => {
+                // These events are sent to the orchestrator, not handled here
+            }
+            AppEvent::McpResourceList(resources) => {
+                let content = if resources.is_empty() {
+                    "No MCP resources available.".to_string()
+                } else {
+                    let lines: Vec<String> = resources
+                        .iter()
+                        .map(|resource| {
+                            let mut line = format!(
+                                "[{}] {} - {}",
+                                resource.server, resource.uri, resource.name
+                            );
+                            if let Some(description) = &resource.description {
+                                line.push_str(&format!(": {}", description));
+                            }
+                            line
+                        })
+                        .collect();
+                    format!(
+                        "MCP resources (attach with /attach <uri>):\n{}",
+                        lines.join("\n")
+                    )
+                };
+                self.messages.push(Message::ToolOutput(content, true));
+            }
+            AppEvent::McpResourceContent(uri, text) => {
+                self.messages.push(Message::ToolOutput(
+                    format!(
+                        "Attached resource {} ({} characters); it is sent with your next message.",
+                        uri,
+                        text.chars().count()
+                    ),
+                    false,
+                ));
+                self.pending_attachments.push((uri, text));
+            }
+            AppEvent::McpPromptList(prompts) => {
+                let content = if prompts.is_empty() {
+                    "No MCP prompts available.".to_string()
+                } else {
+                    let lines: Vec<String> = prompts
+                        .iter()
+                        .map(|prompt| {
+                            let arguments: Vec<String> = prompt
+                                .arguments
+                                .iter()
+                                .enumerate()
+                                .map(|(index, argument)| {
+                                    if index < prompt.required_arguments {
+                                        argument.clone()
+                                    } else {
+                                        format!("[{}]", argument)
+                                    }
+                                })
+                                .collect();
+                            let mut line = format!(
+                                "[{}] {}({})",
+                                prompt.server,
+                                prompt.name,
+                                arguments.join(", ")
+                            );
+                            if let Some(description) = &prompt.description {
+                                line.push_str(&format!(" - {}", description));
+                            }
+                            line
+                        })
+                        .collect();
+                    format!(
+                        "MCP prompts (expand with /prompt <name> key=value ...):\n{}",
+                        lines.join("\n")
+                    )
+                };
+                self.messages.push(Message::ToolOutput(content, true));
+            }
+            AppEvent::McpSamplingRequest(request) => {
+                self.messages.push(Message::ToolOutput(
+                    format!(
+                        "MCP server '{}' asks to sample from the model:\n{}",
+                        request.server,
+                        server_request::sampling_text(&request)
+                    ),
+                    false,
+                ));
+                self.server_requests
+                    .push_back(ServerRequest::Sampling(request));
+            }
+            AppEvent::McpElicitationRequest(request) => {
+                self.messages.push(Message::ToolOutput(
+                    format!(
+                        "MCP server '{}' asks for input: {}",
+                        request.server, request.message
+                    ),
+                    false,
+                ));
+                self.server_requests
+                    .push_back(ServerRequest::Elicitation(ElicitationForm::new(request)));
+            }
+            AppEvent::McpAuthorizationRequired(server_name, url) => {
+                self.messages.push(Message::ToolOutput(
+                    format!(
+                        "MCP server '{}' needs authorization. Open this URL to sign in:\n{}",
+                        server_name, url
+                    ),
+                    true,
+                ));
+            }
+            AppEvent::McpSamplingApproval(_, _) | AppEvent::McpElicitationResponse(_, _) => {
+                // These events are sent to the orchestrator, not handled here
+            }
+            AppEvent::McpPromptExpanded(name, text) => {
+                self.input = Input::new(text);
+                self.messages.push(Message::ToolOutput(
+                    format!("Expanded prompt '{}' into the input box.", name),
+                    false,
+                ));
+            }
         }
         Ok(())
     }
@@ -401,26 +575,29 @@ impl Tui {
                     self.show_model_overlay = false;
                     return Ok(false);
                 }
-                KeyCode::Char('a') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                    // Close agent overlay if open
-                    if self.show_agent_overlay {
-                        self.show_agent_overlay = false;
-                        return Ok(false);
-                    }
+                // Close agent overlay if open
+                KeyCode::Char('a')
+                    if key.modifiers.contains(event::KeyModifiers::CONTROL)
+                        && self.show_agent_overlay =>
+                {
+                    self.show_agent_overlay = false;
+                    return Ok(false);
                 }
-                KeyCode::Char('s') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                    // Close session overlay if open
-                    if self.show_session_overlay {
-                        self.show_session_overlay = false;
-                        return Ok(false);
-                    }
+                // Close session overlay if open
+                KeyCode::Char('s')
+                    if key.modifiers.contains(event::KeyModifiers::CONTROL)
+                        && self.show_session_overlay =>
+                {
+                    self.show_session_overlay = false;
+                    return Ok(false);
                 }
-                KeyCode::Char('m') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                    // Close model overlay if open
-                    if self.show_model_overlay {
-                        self.show_model_overlay = false;
-                        return Ok(false);
-                    }
+                // Close model overlay if open
+                KeyCode::Char('m')
+                    if key.modifiers.contains(event::KeyModifiers::CONTROL)
+                        && self.show_model_overlay =>
+                {
+                    self.show_model_overlay = false;
+                    return Ok(false);
                 }
                 KeyCode::Esc => {
                     self.show_agent_overlay = false;
@@ -440,45 +617,64 @@ impl Tui {
                     self.select_switcher_item().await?;
                     return Ok(false);
                 }
+                // Fork the selected session: the new name and message count are typed in
+                KeyCode::Char('f') if self.show_session_overlay => {
+                    if let SwitcherSelection::Session(idx) = self.switcher_selection
+                        && let Some(session) = self.available_sessions.get(idx)
+                    {
+                        self.input = Input::new(format!("/fork {} {}-fork ", session, session));
+                        self.show_session_overlay = false;
+                    }
+                    return Ok(false);
+                }
                 _ => {}
             }
         }
 
+        // Requests of MCP servers are answered before anything else
+        if !key.modifiers.contains(event::KeyModifiers::CONTROL)
+            && let Some(request) = self.server_requests.front_mut()
+        {
+            match request {
+                ServerRequest::Sampling(_) => match key.code {
+                    KeyCode::Char('y') => self.approve_sampling(true).await?,
+                    KeyCode::Char('n') | KeyCode::Esc => self.approve_sampling(false).await?,
+                    _ => {}
+                },
+                ServerRequest::Elicitation(form) => match key.code {
+                    KeyCode::Esc => {
+                        self.answer_elicitation(McpElicitationAnswer::Decline)
+                            .await?
+                    }
+                    KeyCode::Enter => {
+                        let complete = form.submit(self.input.value());
+                        let values = form.values.clone();
+                        self.input.reset();
+                        if complete {
+                            self.answer_elicitation(McpElicitationAnswer::Accept(values))
+                                .await?;
+                        }
+                    }
+                    _ => {
+                        self.input.handle_event(&Event::Key(key));
+                    }
+                },
+            }
+            return Ok(false);
+        }
+
         if self.is_awaiting_confirmation {
-            match key.code {
-                KeyCode::Char('1') => {
-                    self.tx
-                        .send(AppEvent::ToolApproval(ToolApprovalResponse::Allow))
-                        .await?;
-                    self.messages.push(Message::User("Allowed".to_string()));
-                    self.is_awaiting_confirmation = false;
-                }
-                KeyCode::Char('2') => {
-                    self.tx
-                        .send(AppEvent::ToolApproval(ToolApprovalResponse::AlwaysAllow))
-                        .await?;
-                    self.messages
-                        .push(Message::User("Always Allowed".to_string()));
-                    self.is_awaiting_confirmation = false;
-                }
-                KeyCode::Char('3') => {
-                    self.tx
-                        .send(AppEvent::ToolApproval(
-                            ToolApprovalResponse::AlwaysAllowSession,
-                        ))
-                        .await?;
-                    self.messages
-                        .push(Message::User("Always Allowed for Session".to_string()));
-                    self.is_awaiting_confirmation = false;
-                }
-                KeyCode::Char('4') => {
-                    self.tx
-                        .send(AppEvent::ToolApproval(ToolApprovalResponse::Deny))
-                        .await?;
-                    self.messages.push(Message::User("Denied".to_string()));
-                    self.is_awaiting_confirmation = false;
-                }
-                _ => {}
+            let response = match key.code {
+                KeyCode::Char('1') => Some(ToolApprovalResponse::Allow),
+                KeyCode::Char('2') => Some(ToolApprovalResponse::AlwaysAllow),
+                KeyCode::Char('3') => Some(ToolApprovalResponse::AlwaysAllowSession),
+                KeyCode::Char('4') => Some(ToolApprovalResponse::Deny),
+                KeyCode::Char('5') => Some(ToolApprovalResponse::AlwaysDeny),
+                KeyCode::Char('6') => Some(ToolApprovalResponse::AlwaysDenySession),
+                _ => None,
+            };
+            if let Some(response) = response {
+                self.record_tool_decision(response).await?;
             }
             return Ok(false);
         }
@@ -517,6 +713,10 @@ impl Tui {
                     self.tx.send(AppEvent::RefreshSessions).await?;
                 }
             }
+            KeyCode::Char('z') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                // Undo the file changes of the last tool call
+                self.tx.send(AppEvent::UndoLastToolEffect).await?;
+            }
             KeyCode::Char('l') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                 // Toggle model overlay
                 self.show_model_overlay = !self.show_model_overlay;
@@ -537,13 +737,72 @@ impl Tui {
                         let session_name = stripped.trim().to_string();
                         self.tx.send(AppEvent::SwitchSession(session_name)).await?;
                         self.messages.push(Message::User(user_input.clone()));
+                    } else if let Some(stripped) = user_input.strip_prefix("/fork ") {
+                        match parse_fork_command(stripped) {
+                            Ok((session, new_name, messages)) => {
+                                self.tx
+                                    .send(AppEvent::ForkSession(session, new_name, messages))
+                                    .await?;
+                                self.messages.push(Message::User(user_input.clone()));
+                            }
+                            Err(e) => {
+                                self.messages
+                                    .push(Message::ToolOutput(format!("Error: {}", e), false));
+                            }
+                        }
                     } else if let Some(stripped) = user_input.strip_prefix("/model ") {
                         let model_name = stripped.trim().to_string();
                         self.tx.send(AppEvent::SwitchModel(model_name)).await?;
                         self.messages.push(Message::User(user_input.clone()));
+                    } else if user_input.trim() == "/undo" {
+                        self.tx.send(AppEvent::UndoLastToolEffect).await?;
+                        self.messages.push(Message::User(user_input.clone()));
+                    } else if user_input.trim() == "/checkpoints" {
+                        self.tx.send(AppEvent::ListCheckpoints).await?;
+                        self.messages.push(Message::User(user_input.clone()));
+                    } else if let Some(stripped) = user_input.strip_prefix("/restore ") {
+                        match stripped.trim().trim_start_matches('#').parse() {
+                            Ok(checkpoint_id) => {
+                                self.tx
+                                    .send(AppEvent::RestoreCheckpoint(checkpoint_id))
+                                    .await?;
+                                self.messages.push(Message::User(user_input.clone()));
+                            }
+                            Err(_) => {
+                                self.messages.push(Message::ToolOutput(
+                                    format!("Error: '{}' is not a checkpoint id", stripped.trim()),
+                                    false,
+                                ));
+                            }
+                        }
+                    } else if user_input.trim() == "/resources" {
+                        self.tx.send(AppEvent::ListMcpResources).await?;
+                        self.messages.push(Message::User(user_input.clone()));
+                    } else if let Some(stripped) = user_input.strip_prefix("/attach ") {
+                        let uri = stripped.trim().to_string();
+                        self.tx.send(AppEvent::ReadMcpResource(uri)).await?;
+                        self.messages.push(Message::User(user_input.clone()));
+                    } else if user_input.trim() == "/prompts" {
+                        self.tx.send(AppEvent::ListMcpPrompts).await?;
+                        self.messages.push(Message::User(user_input.clone()));
+                    } else if let Some(stripped) = user_input.strip_prefix("/prompt ") {
+                        match parse_prompt_command(stripped) {
+                            Ok((name, arguments)) => {
+                                self.tx
+                                    .send(AppEvent::GetMcpPrompt(name, arguments))
+                                    .await?;
+                                self.messages.push(Message::User(user_input.clone()));
+                            }
+                            Err(e) => {
+                                self.messages
+                                    .push(Message::ToolOutput(format!("Error: {}", e), false));
+                            }
+                        }
                     } else {
                         self.messages.push(Message::User(user_input.clone()));
-                        self.tx.send(AppEvent::UserInput(user_input)).await?;
+                        let message = with_attachments(user_input, &self.pending_attachments);
+                        self.pending_attachments.clear();
+                        self.tx.send(AppEvent::UserInput(message)).await?;
                     }
                     self.input.reset();
                 }
@@ -703,20 +962,109 @@ impl Tui {
         Ok(())
     }
 
+    /// Record the decision for the current tool call and, once every call in the
+    /// batch has one, send them all to the agent.
+    async fn record_tool_decision(&mut self, response: ToolApprovalResponse) -> anyhow::Result<()> {
+        self.tool_decisions.push(response);
+
+        if let Some(Message::ToolConfirmation(_, _, decisions)) = self
+            .messages
+            .iter_mut()
+            .rev()
+            .find(|message| matches!(message, Message::ToolConfirmation(..)))
+        {
+            *decisions = self.tool_decisions.clone();
+        }
+
+        if self.tool_decisions.len() < self.tool_calls.len() {
+            return Ok(());
+        }
+
+        let summary = self
+            .tool_calls
+            .iter()
+            .zip(&self.tool_decisions)
+            .map(|(call, response)| {
+                format!(
+                    "{} {}",
+                    message::approval_label(response),
+                    call.function.name
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let decisions = self
+            .tool_decisions
+            .drain(..)
+            .enumerate()
+            .map(|(index, response)| ToolCallDecision::new(index, response))
+            .collect();
+
+        self.tx.send(AppEvent::ToolApproval(decisions)).await?;
+        self.messages.push(Message::User(summary));
+        self.tool_calls.clear();
+        self.is_awaiting_confirmation = false;
+        Ok(())
+    }
+
+    /// Send the decision on the sampling request shown to the user
+    async fn approve_sampling(&mut self, approved: bool) -> anyhow::Result<()> {
+        if let Some(ServerRequest::Sampling(request)) = self.server_requests.pop_front() {
+            self.tx
+                .send(AppEvent::McpSamplingApproval(request.id, approved))
+                .await?;
+            let decision = if approved { "Approved" } else { "Rejected" };
+            self.messages.push(Message::User(format!(
+                "{} sampling request of MCP server '{}'",
+                decision, request.server
+            )));
+        }
+        Ok(())
+    }
+
+    /// Send the answer to the elicitation request shown to the user
+    async fn answer_elicitation(&mut self, answer: McpElicitationAnswer) -> anyhow::Result<()> {
+        if let Some(ServerRequest::Elicitation(form)) = self.server_requests.pop_front() {
+            let summary = match &answer {
+                McpElicitationAnswer::Accept(values) => format!(
+                    "Sent to MCP server '{}': {}",
+                    form.request.server,
+                    serde_json::Value::Object(values.clone())
+                ),
+                McpElicitationAnswer::Decline | McpElicitationAnswer::Cancel => format!(
+                    "Declined the request of MCP server '{}'",
+                    form.request.server
+                ),
+            };
+            self.tx
+                .send(AppEvent::McpElicitationResponse(form.request.id, answer))
+                .await?;
+            self.messages.push(Message::User(summary));
+        }
+        Ok(())
+    }
+
     #[allow(dead_code)]
     fn show_help(&mut self) {
         let help_text = r#"Available commands:
 - Ctrl+q: Quit the application
 - Ctrl+a: Toggle agent/session switcher
 - Ctrl+o: Show this help message
+- Ctrl+z: Undo the file changes of the last tool call
 - /switch <session_name>: Switch to a different session
+- /fork <session> <new_name> [messages]: Fork a session, keeping its first messages
+- /undo: Undo the file changes of the last tool call
+- /checkpoints: List workspace checkpoints
+- /restore <id>: Restore files and conversation to a checkpoint
 - Type your message and press Enter to chat
 
-Tool approval options (when prompted):
+Tool approval options (asked for each tool call):
 - 1: Allow tool execution
 - 2: Always allow this tool
 - 3: Always allow this tool for this session
-- 4: Deny tool execution"#;
+- 4: Deny tool execution
+- 5: Always deny this tool
+- 6: Always deny this tool for this session"#;
 
         self.messages
             .push(Message::ToolOutput(help_text.to_string(), true));
@@ -821,31 +1169,19 @@ impl Interface for Tui {
         for message in &self.messages {
             match message {
                 Message::User(content) => {
-                    history.push(ChatMessage {
-                        role: "user".to_string(),
-                        content: content.clone(),
-                        tool_calls: None,
-                    });
+                    history.push(ChatMessage::user(content));
                 }
                 Message::Agent(_, content) => {
-                    history.push(ChatMessage {
-                        role: "assistant".to_string(),
-                        content: content.clone(),
-                        tool_calls: None,
-                    });
+                    history.push(ChatMessage::assistant(content));
                 }
                 Message::Thinking(_, content, _) => {
-                    history.push(ChatMessage {
-                        role: "assistant".to_string(),
-                        content: content.clone(),
-                        tool_calls: None,
-                    });
+                    history.push(ChatMessage::assistant(content));
                 }
                 Message::ToolOutput(_content, _) => {
                     // Tool outputs are typically not part of the session history
                     // But we might want to include some of them
                 }
-                Message::ToolConfirmation(_) => {
+                Message::ToolConfirmation(..) => {
                     // Tool confirmations are not part of the session history
                 }
             }
@@ -858,16 +1194,75 @@ impl Interface for Tui {
     }
 }
 
+// Split `/prompt <name> key=value ...` into the prompt name and its arguments
+fn parse_prompt_command(
+    command: &str,
+) -> anyhow::Result<(String, std::collections::HashMap<String, String>)> {
+    let mut parts = command.split_whitespace();
+    let name = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Usage: /prompt <name> key=value ..."))?;
+    let mut arguments = std::collections::HashMap::new();
+    for part in parts {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Prompt argument '{}' is not key=value", part))?;
+        arguments.insert(key.to_string(), value.to_string());
+    }
+    Ok((name.to_string(), arguments))
+}
+
+// Split `/fork <session> <new_name> [messages]` into its parts
+fn parse_fork_command(command: &str) -> anyhow::Result<(String, String, Option<usize>)> {
+    let usage = || anyhow::anyhow!("Usage: /fork <session> <new_name> [messages]");
+    let mut parts = command.split_whitespace();
+    let session = parts.next().ok_or_else(usage)?;
+    let new_name = parts.next().ok_or_else(usage)?;
+    let messages = match parts.next() {
+        Some(messages) => Some(
+            messages
+                .parse()
+                .map_err(|_| anyhow::anyhow!("'{}' is not a number of messages", messages))?,
+        ),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return Err(usage());
+    }
+    Ok((session.to_string(), new_name.to_string(), messages))
+}
+
+// The user message with the text of attached MCP resources appended
+fn with_attachments(message: String, attachments: &[(String, String)]) -> String {
+    attachments.iter().fold(message, |message, (uri, text)| {
+        format!("{}\n\n[Attached resource: {}]\n{}", message, uri, text)
+    })
+}
+
+// Title of the conversation pane: the session and the health of MCP servers
+fn chat_title(
+    session_name: &str,
+    mcp_server_statuses: &std::collections::BTreeMap<String, String>,
+) -> String {
+    let mut title = format!("Conversation - Session: {}", session_name);
+    if !mcp_server_statuses.is_empty() {
+        let servers: Vec<String> = mcp_server_statuses
+            .iter()
+            .map(|(server_name, status)| format!("{} [{}]", server_name, status))
+            .collect();
+        title.push_str(&format!(" - MCP: {}", servers.join(", ")));
+    }
+    title
+}
+
 fn render_chat_history(
     f: &mut Frame,
     area: Rect,
     messages: &[Message],
     message_positions: &mut Vec<(usize, Rect)>,
-    session_name: &str,
+    title: &str,
 ) {
-    let chat_history_block = Block::default()
-        .title(format!("Conversation - Session: {}", session_name))
-        .borders(Borders::ALL);
+    let chat_history_block = Block::default().title(title).borders(Borders::ALL);
     let inner_chat_area = chat_history_block.inner(area);
     f.render_widget(chat_history_block, area);
 
@@ -969,8 +1364,12 @@ fn ui(
     available_sessions: &[String],
     switcher_scroll: usize,
     agent_statuses: &std::collections::HashMap<String, String>,
+    mcp_server_statuses: &std::collections::BTreeMap<String, String>,
     show_help_overlay: bool,
+    server_request: Option<&ServerRequest>,
 ) {
+    let session_title = chat_title(session_name, mcp_server_statuses);
+
     if show_help_overlay {
         let area = centered_rect(60, 50, f.area());
         let help_text = vec![
@@ -980,14 +1379,42 @@ fn ui(
             Line::from(vec![Span::raw("- Ctrl+s: Open Session Switcher")]),
             Line::from(vec![Span::raw("- Ctrl+l: Open Model Switcher")]),
             Line::from(vec![Span::raw("- Ctrl+o: Toggle this help")]),
+            Line::from(vec![Span::raw(
+                "- Ctrl+z, /undo: Undo last tool file change",
+            )]),
             Line::from(vec![Span::raw("- /switch <session_name>: Switch session")]),
+            Line::from(vec![Span::raw(
+                "- /fork <session> <new_name> [messages]: Fork a session (f in the switcher)",
+            )]),
             Line::from(vec![Span::raw("- /model <model_name>: Switch model")]),
+            Line::from(vec![Span::raw(
+                "- /checkpoints: List workspace checkpoints",
+            )]),
+            Line::from(vec![Span::raw(
+                "- /restore <id>: Roll back to a checkpoint",
+            )]),
+            Line::from(vec![Span::raw("- /resources: List MCP resources")]),
+            Line::from(vec![Span::raw(
+                "- /attach <uri>: Attach a resource to the next message",
+            )]),
+            Line::from(vec![Span::raw("- /prompts: List MCP prompt templates")]),
+            Line::from(vec![Span::raw(
+                "- /prompt <name> key=value ...: Expand a prompt into the input",
+            )]),
             Line::from(vec![Span::raw("")]),
-            Line::from(vec![Span::raw("Tool Approvals:")]),
+            Line::from(vec![Span::raw("Tool Approvals (per call):")]),
             Line::from(vec![Span::raw("- 1: Allow once")]),
             Line::from(vec![Span::raw("- 2: Always allow")]),
             Line::from(vec![Span::raw("- 3: Always allow for session")]),
             Line::from(vec![Span::raw("- 4: Deny")]),
+            Line::from(vec![Span::raw("- 5: Always deny")]),
+            Line::from(vec![Span::raw("- 6: Always deny for session")]),
+            Line::from(vec![Span::raw("")]),
+            Line::from(vec![Span::raw("MCP Server Requests:")]),
+            Line::from(vec![Span::raw("- y / n: Approve or reject sampling")]),
+            Line::from(vec![Span::raw(
+                "- Enter: Submit a form field, Esc: Decline the form",
+            )]),
         ];
 
         // Render background (chat history) dimmed or as is
@@ -997,7 +1424,7 @@ fn ui(
             .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
             .split(f.area());
 
-        render_chat_history(f, chunks[0], messages, message_positions, session_name);
+        render_chat_history(f, chunks[0], messages, message_positions, &session_title);
         render_input_box(f, chunks[1], input, tool_calls, is_awaiting_confirmation);
 
         // Render help popup
@@ -1027,7 +1454,7 @@ fn ui(
             )
             .split(f.area());
 
-        render_chat_history(f, chunks[0], messages, message_positions, session_name);
+        render_chat_history(f, chunks[0], messages, message_positions, &session_title);
         render_switcher_panel(
             f,
             chunks[1],
@@ -1050,9 +1477,28 @@ fn ui(
             .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
             .split(f.area());
 
-        render_chat_history(f, chunks[0], messages, message_positions, session_name);
+        render_chat_history(f, chunks[0], messages, message_positions, &session_title);
         render_input_box(f, chunks[1], input, tool_calls, is_awaiting_confirmation);
     }
+
+    if let Some(request) = server_request {
+        render_server_request(f, request);
+    }
+}
+
+// Popup showing a request of an MCP server above the input box
+fn render_server_request(f: &mut Frame, request: &ServerRequest) {
+    let area = centered_rect(70, 60, f.area());
+    let block = Block::default()
+        .title(request.title())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta));
+    let paragraph = Paragraph::new(request.text())
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1127,7 +1573,7 @@ fn render_switcher_panel(
                 ));
             }
             (
-                "Switch Session (Ctrl+S / Esc to close)",
+                "Switch Session (Enter: Switch, f: Fork, Ctrl+S / Esc to close)",
                 text,
                 available_sessions.len(),
             )
@@ -1195,7 +1641,7 @@ fn render_input_box(
     is_awaiting_confirmation: bool,
 ) {
     let title = if is_awaiting_confirmation {
-        "Approve tool call? (1: Allow, 2: Always Allow, 3: Always Allow (Session), 4: Deny)"
+        "Approve this tool call? (1: Allow, 2: Always Allow, 3: Always Allow (Session), 4: Deny, 5: Always Deny, 6: Always Deny (Session))"
     } else {
         "Input (Press Ctrl+q to quit, Ctrl+o for help)"
     };
@@ -1205,8 +1651,9 @@ fn render_input_box(
     f.render_widget(block, area);
 
     if is_awaiting_confirmation {
+        // `tool_calls` holds the calls still awaiting a decision; the first is current
         let mut text = String::new();
-        for call in tool_calls {
+        if let Some(call) = tool_calls.first() {
             text.push_str(&format!("Tool: {}\n", call.function.name));
             text.push_str(&format!(
                 "Arguments: {}\n\n",
@@ -1214,6 +1661,12 @@ fn render_input_box(
                     .unwrap_or_else(|_| "Invalid JSON".to_string())
             ));
         }
+        if tool_calls.len() > 1 {
+            text.push_str(&format!(
+                "{} more tool call(s) waiting for a decision",
+                tool_calls.len() - 1
+            ));
+        }
         let confirmation_paragraph = Paragraph::new(text).wrap(Wrap { trim: true });
         f.render_widget(confirmation_paragraph, inner_area);
     } else {
@@ -0,0 +1,137 @@
+use serde_json::{Map, Value};
+
+use crate::types::{
+    McpElicitationField, McpElicitationFieldKind, McpElicitationRequestInfo, McpSamplingRequestInfo,
+};
+
+/// A request of an MCP server waiting for the user
+#[derive(Debug, Clone)]
+pub enum ServerRequest {
+    Sampling(McpSamplingRequestInfo),
+    Elicitation(ElicitationForm),
+}
+
+impl ServerRequest {
+    /// Title of the popup showing the request
+    pub fn title(&self) -> String {
+        match self {
+            ServerRequest::Sampling(request) => format!(
+                "MCP server '{}' asks to sample from the model (y: Approve, n: Reject)",
+                request.server
+            ),
+            ServerRequest::Elicitation(form) => format!(
+                "MCP server '{}' asks for input (Enter: Next field, Esc: Decline)",
+                form.request.server
+            ),
+        }
+    }
+
+    /// Body of the popup showing the request
+    pub fn text(&self) -> String {
+        match self {
+            ServerRequest::Sampling(request) => sampling_text(request),
+            ServerRequest::Elicitation(form) => form.text(),
+        }
+    }
+}
+
+/// The messages a sampling request would send to the model
+pub fn sampling_text(request: &McpSamplingRequestInfo) -> String {
+    let mut text = String::new();
+    if let Some(system_prompt) = &request.system_prompt {
+        text.push_str(&format!("System prompt: {}\n\n", system_prompt));
+    }
+    for message in &request.messages {
+        text.push_str(&format!("[{}] {}\n", message.role, message.content));
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        text.push_str(&format!("\nAt most {} tokens", max_tokens));
+    }
+    text
+}
+
+/// An elicitation request being filled in one field at a time
+#[derive(Debug, Clone)]
+pub struct ElicitationForm {
+    pub request: McpElicitationRequestInfo,
+    /// Values entered so far, by field name
+    pub values: Map<String, Value>,
+    /// Index of the field being filled in
+    pub field: usize,
+    /// Why the last input was refused
+    pub error: Option<String>,
+}
+
+impl ElicitationForm {
+    pub fn new(request: McpElicitationRequestInfo) -> Self {
+        Self {
+            request,
+            values: Map::new(),
+            field: 0,
+            error: None,
+        }
+    }
+
+    /// Take the input for the current field and move to the next one.
+    /// Returns `true` once every field was filled in.
+    pub fn submit(&mut self, input: &str) -> bool {
+        let Some(field) = self.request.fields.get(self.field) else {
+            return true;
+        };
+        match field.parse(input) {
+            Ok(value) => {
+                if let Some(value) = value {
+                    self.values.insert(field.name.clone(), value);
+                }
+                self.error = None;
+                self.field += 1;
+            }
+            Err(error) => self.error = Some(error),
+        }
+        self.field >= self.request.fields.len()
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!("{}\n\n", self.request.message);
+        for (index, field) in self.request.fields.iter().enumerate() {
+            let marker = if index == self.field { "->" } else { "  " };
+            let value = self
+                .values
+                .get(&field.name)
+                .map(|value| match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+                .unwrap_or_default();
+            text.push_str(&format!("{} {}: {}\n", marker, field_label(field), value));
+            if index == self.field
+                && let Some(description) = &field.description
+            {
+                text.push_str(&format!("     {}\n", description));
+            }
+        }
+        text.push_str("\nType the value in the input box and press Enter");
+        if let Some(error) = &self.error {
+            text.push_str(&format!("\nError: {}", error));
+        }
+        text
+    }
+}
+
+/// Name, kind and whether a field is required, e.g. `Age (integer, required)`
+fn field_label(field: &McpElicitationField) -> String {
+    let kind = match &field.kind {
+        McpElicitationFieldKind::Text => "text".to_string(),
+        McpElicitationFieldKind::Number => "number".to_string(),
+        McpElicitationFieldKind::Integer => "integer".to_string(),
+        McpElicitationFieldKind::Boolean => "yes/no".to_string(),
+        McpElicitationFieldKind::Choice(options) => format!("one of {}", options.join(", ")),
+    };
+    let required = if field.required { ", required" } else { "" };
+    format!(
+        "{} ({}{})",
+        field.title.as_deref().unwrap_or(&field.name),
+        kind,
+        required
+    )
+}
@@ -32,26 +32,15 @@ async fn main() -> anyhow::Result<()> {
     let client = Client::new();
 
     let llm_config = config::LLMConfig {
-        provider: "ollama".to_string(),
-        api_base: args
-            .llm_api_base
+        provider: args
+            .llm_provider
             .clone()
-            .unwrap_or_else(config::default_api_base),
+            .unwrap_or_else(config::default_provider),
+        api_base: args.llm_api_base.clone(),
         api_key: args.llm_api_key.clone(),
         model: args.llm_model.clone(),
     };
 
-    // Fetch the list of available Ollama models
-    let available_models = match core::llm::ollama::list_models(&client, &llm_config.api_base).await
-    {
-        Ok(models) => models,
-        Err(e) => {
-            eprintln!("Error fetching Ollama models: {}", e);
-            // Exit gracefully if Ollama is not available
-            return Ok(());
-        }
-    };
-
     // Load configuration from file if specified, otherwise use default config
     let config_from_file = if let Some(config_path) = &args.config {
         Some(config::OxideConfig::from_file(config_path)?)
@@ -68,16 +57,59 @@ async fn main() -> anyhow::Result<()> {
     // Validate the configuration
     config.validate()?;
 
+    // Sessions used to be written to the directory the agent ran in
+    let sessions = crate::core::session::SessionManager::new(config.sessions_dir());
+    if let Err(e) = sessions.migrate_from(std::path::Path::new(".")) {
+        tracing::warn!("Failed to import sessions of the current directory: {}", e);
+    }
+
+    if let Some(session) = &args.export_session {
+        // Failures are returned so scripts see a non-zero exit status
+        let transcript = sessions
+            .export(session, args.format.into())
+            .map_err(|e| anyhow::anyhow!("Error exporting session '{}': {}", session, e))?;
+        print!("{}", transcript);
+        return Ok(());
+    }
+
+    if let Some(path) = &args.import_session {
+        let name = config.session.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "imported".to_string())
+        });
+        let metadata = std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| sessions.import(&name, &content))
+            .map_err(|e| anyhow::anyhow!("Error importing '{}': {}", path.display(), e))?;
+        println!(
+            "Imported {} messages into session '{}'.",
+            metadata.message_count, metadata.name
+        );
+        return Ok(());
+    }
+
     // Handle session listing if requested
     if config.list_sessions {
-        match crate::core::session::SessionManager::list_sessions() {
+        match sessions.list_metadata() {
             Ok(sessions) => {
                 if sessions.is_empty() {
                     println!("No sessions found.");
                 } else {
                     println!("Available sessions:");
                     for session in sessions {
-                        println!("  - {}", session);
+                        println!(
+                            "  - {} ({} messages, ~{} tokens, {}){}",
+                            session.name,
+                            session.message_count,
+                            session.token_estimate,
+                            session.model,
+                            if session.title.is_empty() {
+                                String::new()
+                            } else {
+                                format!(": {}", session.title)
+                            }
+                        );
                     }
                 }
             }
@@ -88,6 +120,21 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    // Fetch the list of available models from the configured provider
+    let available_models = match core::llm::list_models(&client, &config.llm).await {
+        Ok(models) => models,
+        Err(e) => {
+            eprintln!(
+                "Error fetching models from {} at {}: {}",
+                config.llm.provider,
+                config.llm.base_url(),
+                e
+            );
+            // Exit gracefully if the provider is not available
+            return Ok(());
+        }
+    };
+
     // Create the container
     let mut container = crate::core::container::Container::new(config);
 
@@ -108,14 +155,7 @@ async fn main() -> anyhow::Result<()> {
         .await?;
 
     // Initialize the default agent
-    let model = container.config().llm.model.clone().unwrap_or_else(|| {
-        let agent_type = &container.config().agent.agent_type;
-        match agent_type {
-            config::AgentType::Qwen => "qwen3:4b".to_string(),
-            config::AgentType::Llama => "llama3.2".to_string(),
-            config::AgentType::Granite => "granite3.3".to_string(),
-        }
-    });
+    let model = container.config().chat_model();
     orchestrator
         .initialize_default_agent(container.config().session.clone(), model)
         .await?;
@@ -151,6 +191,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize the interface
     interface.init().await?;
+    container.set_interface_running(true);
     info!("Interface initialized successfully");
 
     info!("Starting TUI interface for session: {}", session_name);
@@ -162,9 +203,14 @@ async fn main() -> anyhow::Result<()> {
     info!("TUI interface ended for session: {}", session_name);
 
     // Cleanup the interface
+    container.set_interface_running(false);
     interface.cleanup().await?;
     info!("Interface cleanup completed");
 
+    // Stop the MCP servers so no child processes or containers outlive us
+    container.shutdown().await;
+    info!("MCP servers stopped");
+
     Ok(())
 }
 
@@ -187,6 +233,7 @@ fn create_default_config_from_cli(
         model,
         name: agent_type.name().to_string(),
         system_prompt: agent_type.system_prompt().to_string(),
+        tool_scope: config::ToolScope::default(),
     };
 
     config::OxideConfig {
@@ -195,6 +242,7 @@ fn create_default_config_from_cli(
         no_stream: args.no_stream.unwrap_or(false),
         session: args.session.clone(),
         list_sessions: args.list_sessions.unwrap_or(false),
+        sessions_dir: args.sessions_dir.clone(),
         interface: args
             .interface
             .clone()
@@ -204,8 +252,13 @@ fn create_default_config_from_cli(
             server: args.mcp_server.clone(),
             auth_token: args.mcp_auth_token.clone(),
             tools: vec![],
+            servers: vec![],
+            config_file: args.mcp_config_file.clone(),
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: llm_config.clone(),
+        tools: config::ToolsConfig::default(),
     }
 }
 
@@ -252,6 +305,10 @@ fn merge_configs(
                 base_config.list_sessions = file_config.list_sessions;
             }
 
+            if args.sessions_dir.is_none() {
+                base_config.sessions_dir = file_config.sessions_dir;
+            }
+
             if args.interface.is_some() {
                 base_config.interface = args.interface.clone().unwrap().into();
             } else {
@@ -272,13 +329,34 @@ fn merge_configs(
             }
 
             base_config.mcp.tools = file_config.mcp.tools; // Keep file config tools
+            base_config.mcp.servers = file_config.mcp.servers;
+            base_config.mcp.tool_aliases = file_config.mcp.tool_aliases;
+            base_config.mcp.oauth = file_config.mcp.oauth;
+            if args.mcp_config_file.is_none() {
+                base_config.mcp.config_file = file_config.mcp.config_file;
+            }
 
             // For LLM config, use file config but allow CLI to influence it
-            base_config.llm = file_config.llm;
+            let cli_llm = std::mem::replace(&mut base_config.llm, file_config.llm);
+            if args.llm_provider.is_some() {
+                base_config.llm.provider = cli_llm.provider;
+            }
+            if args.llm_api_base.is_some() {
+                base_config.llm.api_base = cli_llm.api_base;
+            }
+            if args.llm_api_key.is_some() {
+                base_config.llm.api_key = cli_llm.api_key;
+            }
+            if args.llm_model.is_some() {
+                base_config.llm.model = cli_llm.model;
+            }
 
             // For multi-agent config, use file config
             base_config.multi_agent = file_config.multi_agent;
 
+            // Tool configuration only comes from the config file
+            base_config.tools = file_config.tools;
+
             base_config
         }
         None => base_config, // Use CLI defaults only
@@ -1,35 +1,198 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ToolApprovalResponse {
     Allow,
     AlwaysAllow,
     AlwaysAllowSession,
     Deny,
+    AlwaysDeny,
+    AlwaysDenySession,
+}
+
+/// The user's decision for one entry of a pending tool call batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCallDecision {
+    /// Position of the call in the batch sent with `AppEvent::ToolRequest`
+    pub index: usize,
+    pub response: ToolApprovalResponse,
+}
+
+impl ToolCallDecision {
+    pub fn new(index: usize, response: ToolApprovalResponse) -> Self {
+        Self { index, response }
+    }
+}
+
+/// A workspace checkpoint as listed to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointInfo {
+    pub id: usize,
+    /// Position in the session history of the message that requested the tool call
+    pub message_index: usize,
+    pub tool_name: String,
+    /// What was captured, e.g. the files snapshotted
+    pub description: String,
+}
+
+/// A resource of an MCP server as listed to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpResourceInfo {
+    pub server: String,
+    pub uri: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// A prompt template of an MCP server as listed to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpPromptInfo {
+    pub server: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Argument names; required ones are listed first
+    pub arguments: Vec<String>,
+    pub required_arguments: usize,
+}
+
+/// A `sampling/createMessage` request of an MCP server, shown to the user
+/// for approval before it reaches the model.
+#[derive(Debug, Clone)]
+pub struct McpSamplingRequestInfo {
+    /// Id to answer the request with
+    pub id: u64,
+    pub server: String,
+    pub system_prompt: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    pub max_tokens: Option<u64>,
+}
+
+/// Kind of value an elicitation field takes
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpElicitationFieldKind {
+    Text,
+    Number,
+    Integer,
+    Boolean,
+    /// One of the listed values
+    Choice(Vec<String>),
+}
+
+/// A field of the form an MCP server asks the user to fill in
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpElicitationField {
+    /// Property name in the answer
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub kind: McpElicitationFieldKind,
+    pub required: bool,
+}
+
+impl McpElicitationField {
+    /// Parse what the user typed for this field. Empty input leaves an
+    /// optional field out (`None`); a required one is an error.
+    pub fn parse(&self, input: &str) -> Result<Option<Value>, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return if self.required {
+                Err(format!("'{}' is required", self.name))
+            } else {
+                Ok(None)
+            };
+        }
+        let value = match &self.kind {
+            McpElicitationFieldKind::Text => Value::String(input.to_string()),
+            McpElicitationFieldKind::Number => input
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("'{}' is not a number", input))?,
+            McpElicitationFieldKind::Integer => input
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("'{}' is not an integer", input))?,
+            McpElicitationFieldKind::Boolean => match input.to_lowercase().as_str() {
+                "y" | "yes" | "true" | "1" => Value::Bool(true),
+                "n" | "no" | "false" | "0" => Value::Bool(false),
+                _ => return Err(format!("'{}' is not yes or no", input)),
+            },
+            McpElicitationFieldKind::Choice(options) => {
+                if !options.iter().any(|option| option == input) {
+                    return Err(format!("Choose one of: {}", options.join(", ")));
+                }
+                Value::String(input.to_string())
+            }
+        };
+        Ok(Some(value))
+    }
+}
+
+/// An `elicitation/create` request of an MCP server: a message and the form
+/// the user is asked to fill in.
+#[derive(Debug, Clone)]
+pub struct McpElicitationRequestInfo {
+    /// Id to answer the request with
+    pub id: u64,
+    pub server: String,
+    pub message: String,
+    pub fields: Vec<McpElicitationField>,
+}
+
+/// The user's answer to an elicitation request
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpElicitationAnswer {
+    /// The form was submitted with these values
+    Accept(serde_json::Map<String, Value>),
+    /// The user refused to answer
+    Decline,
+    /// The request was dismissed without a choice
+    Cancel,
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Variants are used in the application and form part of the public API
 pub enum AppEvent {
     UserInput(String),
-    ToolApproval(ToolApprovalResponse),
+    ToolApproval(Vec<ToolCallDecision>),
     AgentMessage(String),
     AgentStreamChunk(String),
     AgentStreamEnd,
-    ToolRequest(Vec<ToolCall>),
+    ToolRequest(Vec<ToolCall>, Vec<Option<String>>), // calls, change preview for each call
     ToolResult(String, String),
     Error(String),
-    SwitchSession(String),             // New event for switching sessions
+    SwitchSession(String),       // New event for switching sessions
     SwitchAgent(String, String), // New event for switching agents (agent_name, session_context)
     SwitchModel(String),         // New event for switching models
     ListSessions,                // New event for listing sessions
     RefreshSessions,             // New event for refreshing sessions without displaying response
     SessionList(Vec<String>),    // New event to send session list to TUI
     SessionSwitched(String),     // New event to notify TUI that session has been switched
-    SessionHistory(Vec<ChatMessage>), // New event to send session history to TUI
-    ContinueConversation,        // New event to continue conversation after tool execution
+    ForkSession(String, String, Option<usize>), // Fork a session into a new one (session, new name, messages kept; all when None)
+    SessionHistory(Vec<ChatMessage>),           // New event to send session history to TUI
+    ContinueConversation, // New event to continue conversation after tool execution
     AgentStatusUpdate(String, String), // New event to update agent status (agent_name, status)
+    UndoLastToolEffect,   // Restore the files changed by the last tool call
+    ListCheckpoints,      // Ask the active agent for its workspace checkpoints
+    CheckpointList(Vec<CheckpointInfo>), // Checkpoints of the active agent, oldest first
+    RestoreCheckpoint(usize), // Restore files and conversation to a checkpoint (checkpoint id)
+    McpServerStatus(String, String), // Health of a supervised MCP server (server_name, status)
+    ListMcpResources,     // Ask for the resources of every MCP server
+    McpResourceList(Vec<McpResourceInfo>), // Resources of the MCP servers
+    ReadMcpResource(String), // Read a resource to attach to the next message (uri)
+    McpResourceContent(String, String), // Text of a resource read for attaching (uri, text)
+    ListMcpPrompts,       // Ask for the prompt templates of every MCP server
+    McpPromptList(Vec<McpPromptInfo>), // Prompt templates of the MCP servers
+    GetMcpPrompt(String, HashMap<String, String>), // Expand a prompt template (name, arguments)
+    McpPromptExpanded(String, String), // Expanded prompt for the input box (name, text)
+    McpSamplingRequest(McpSamplingRequestInfo), // An MCP server asks to sample from the model
+    McpSamplingApproval(u64, bool), // The user's decision on a sampling request (id, approved)
+    McpElicitationRequest(McpElicitationRequestInfo), // An MCP server asks the user for input
+    McpElicitationResponse(u64, McpElicitationAnswer), // The user's answer to an elicitation (id, answer)
+    McpAuthorizationRequired(String, String), // An MCP server needs the user to sign in (server_name, authorization URL)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +201,15 @@ pub struct ChatMessage {
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Id of the tool call this message answers (only set for `tool` messages)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Name of the tool that produced this message (only set for `tool` messages)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    /// Unix time the message was created (missing in older sessions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
 }
 
 impl ChatMessage {
@@ -46,6 +218,9 @@ impl ChatMessage {
             role: "user".to_string(),
             content: content.to_string(),
             tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            timestamp: unix_now(),
         }
     }
 
@@ -54,6 +229,9 @@ impl ChatMessage {
             role: "assistant".to_string(),
             content: content.to_string(),
             tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            timestamp: unix_now(),
         }
     }
 
@@ -62,6 +240,9 @@ impl ChatMessage {
             role: "system".to_string(),
             content: content.to_string(),
             tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            timestamp: unix_now(),
         }
     }
 
@@ -70,15 +251,53 @@ impl ChatMessage {
             role: "assistant".to_string(),
             content: content.to_string(),
             tool_calls: Some(tool_calls),
+            tool_call_id: None,
+            tool_name: None,
+            timestamp: unix_now(),
+        }
+    }
+
+    /// Result of a tool call, linked back to the call it answers.
+    pub fn tool(tool_call: &ToolCall, content: &str) -> Self {
+        Self {
+            role: "tool".to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: tool_call.id.clone(),
+            tool_name: Some(tool_call.function.name.clone()),
+            timestamp: unix_now(),
         }
     }
 }
 
+/// Current Unix time, or `None` when the clock is before the epoch
+fn unix_now() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|now| now.as_secs())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
+    /// Provider-assigned id used to link the call to its result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub function: ToolFunction,
 }
 
+impl ToolCall {
+    pub fn new(id: Option<String>, name: &str, arguments: Value) -> Self {
+        Self {
+            id,
+            function: ToolFunction {
+                name: name.to_string(),
+                arguments,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolFunction {
     pub name: String,
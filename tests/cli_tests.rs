@@ -158,3 +158,23 @@ fn test_cli_interface_arg_discord() {
         assert_eq!(cli_args.interface, Some(InterfaceType::Discord));
     }
 }
+
+#[test]
+fn test_cli_export_session_args() {
+    let cli_args = OxideAgent::cli::Args::try_parse_from([
+        "oxide-agent",
+        "--export-session",
+        "review",
+        "--format",
+        "html",
+    ])
+    .unwrap();
+    assert_eq!(cli_args.export_session, Some("review".to_string()));
+    assert_eq!(cli_args.format, OxideAgent::cli::ExportFormat::Html);
+
+    // Markdown is the default format
+    let cli_args =
+        OxideAgent::cli::Args::try_parse_from(["oxide-agent", "--export-session", "review"])
+            .unwrap();
+    assert_eq!(cli_args.format, OxideAgent::cli::ExportFormat::Md);
+}
@@ -0,0 +1,188 @@
+#!/usr/bin/env python3
+"""Minimal MCP server speaking JSON-RPC over stdio, used by the MCP tests.
+
+It only answers after the initialize handshake, interleaves notifications
+with responses and offers tools returning the different content types, a
+paginated resource list and a prompt template. The unlisted `ask` and
+`summarize` tools send elicitation and sampling requests to the client.
+"""
+
+import json
+import os
+import sys
+
+initialized = False
+
+TOOLS = [
+    {
+        "name": "echo",
+        "description": "Echo the given text",
+        "inputSchema": {
+            "type": "object",
+            "properties": {"text": {"type": "string"}},
+            "required": ["text"],
+        },
+    },
+    {"name": "mixed", "description": "Return every content type", "inputSchema": {"type": "object"}},
+    {"name": "fail", "description": "Report a tool error", "inputSchema": {"type": "object"}},
+    {"name": "pid", "description": "Return the server process id", "inputSchema": {"type": "object"}},
+]
+
+RESOURCES = [
+    {"uri": "file:///notes.txt", "name": "notes.txt", "description": "Meeting notes", "mimeType": "text/plain"},
+    {"uri": "file:///logo.png", "name": "logo.png", "mimeType": "image/png"},
+]
+
+PROMPTS = [
+    {
+        "name": "review",
+        "description": "Review a file",
+        "arguments": [
+            {"name": "file", "description": "File to review", "required": True},
+            {"name": "focus", "description": "What to look at"},
+        ],
+    },
+]
+
+
+def send(message):
+    sys.stdout.write(json.dumps(message) + "\n")
+    sys.stdout.flush()
+
+
+def request_client(method, params):
+    """Send a request to the client and return its response"""
+    request_id = "client-" + method
+    send({"jsonrpc": "2.0", "id": request_id, "method": method, "params": params})
+    for line in sys.stdin:
+        message = json.loads(line)
+        if message.get("id") == request_id and "method" not in message:
+            return message
+
+
+def text(value):
+    return {"content": [{"type": "text", "text": value}]}
+
+
+def call_tool(name, arguments):
+    if name == "echo":
+        return text(arguments.get("text", ""))
+    if name == "mixed":
+        return {
+            "content": [
+                {"type": "text", "text": "Here is the report"},
+                {"type": "image", "data": "aGVsbG8=", "mimeType": "image/png"},
+                {
+                    "type": "resource",
+                    "resource": {"uri": "file:///report.txt", "mimeType": "text/plain", "text": "report body"},
+                },
+                {"type": "resource_link", "uri": "file:///data.csv", "name": "data.csv"},
+            ]
+        }
+    if name == "fail":
+        return {"content": [{"type": "text", "text": "disk is full"}], "isError": True}
+    if name == "pid":
+        return text(str(os.getpid()))
+    if name == "ask":
+        response = request_client(
+            "elicitation/create",
+            {
+                "message": "Which city?",
+                "requestedSchema": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}},
+                    "required": ["city"],
+                },
+            },
+        )
+        return text(json.dumps(response.get("result") or response.get("error")))
+    if name == "summarize":
+        response = request_client(
+            "sampling/createMessage",
+            {
+                "messages": [{"role": "user", "content": {"type": "text", "text": arguments.get("text", "")}}],
+                "maxTokens": 50,
+            },
+        )
+        return text(json.dumps(response.get("result") or response.get("error")))
+    return None
+
+
+def read_resource(uri):
+    if uri == "file:///notes.txt":
+        return {"contents": [{"uri": uri, "mimeType": "text/plain", "text": "Ship on Friday"}]}
+    if uri == "file:///logo.png":
+        return {"contents": [{"uri": uri, "mimeType": "image/png", "blob": "aGVsbG8="}]}
+    return None
+
+
+def get_prompt(name, arguments):
+    if name != "review":
+        return None
+    request = "Review " + arguments.get("file", "")
+    if arguments.get("focus"):
+        request += " with a focus on " + arguments["focus"]
+    return {
+        "description": "Review a file",
+        "messages": [
+            {"role": "assistant", "content": {"type": "text", "text": "You are a careful reviewer."}},
+            {"role": "user", "content": {"type": "text", "text": request}},
+        ],
+    }
+
+
+for line in sys.stdin:
+    line = line.strip()
+    if not line:
+        continue
+    request = json.loads(line)
+    method = request.get("method")
+    request_id = request.get("id")
+
+    if method == "notifications/initialized":
+        initialized = True
+        continue
+    if request_id is None:
+        continue
+
+    if method == "initialize":
+        result = {
+            "protocolVersion": request["params"]["protocolVersion"],
+            "capabilities": {"tools": {}, "resources": {}, "prompts": {}},
+            "serverInfo": {"name": "fake-mcp-server", "version": "0.1.0"},
+        }
+    elif not initialized:
+        send({"jsonrpc": "2.0", "id": request_id, "error": {"code": -32002, "message": "Server not initialized"}})
+        continue
+    elif method == "tools/list":
+        # Servers may log while handling a request
+        send({"jsonrpc": "2.0", "method": "notifications/message", "params": {"level": "info", "data": "listing"}})
+        result = {"tools": TOOLS}
+    elif method == "tools/call":
+        result = call_tool(request["params"]["name"], request["params"].get("arguments") or {})
+        if result is None:
+            send({"jsonrpc": "2.0", "id": request_id, "error": {"code": -32602, "message": "Unknown tool"}})
+            continue
+    elif method == "resources/list":
+        # One resource per page to exercise cursors
+        index = int((request.get("params") or {}).get("cursor") or 0)
+        result = {"resources": RESOURCES[index : index + 1]}
+        if index + 1 < len(RESOURCES):
+            result["nextCursor"] = str(index + 1)
+    elif method == "resources/read":
+        result = read_resource(request["params"]["uri"])
+        if result is None:
+            send({"jsonrpc": "2.0", "id": request_id, "error": {"code": -32002, "message": "Resource not found"}})
+            continue
+    elif method == "prompts/list":
+        result = {"prompts": PROMPTS}
+    elif method == "prompts/get":
+        result = get_prompt(request["params"]["name"], request["params"].get("arguments") or {})
+        if result is None:
+            send({"jsonrpc": "2.0", "id": request_id, "error": {"code": -32602, "message": "Unknown prompt"}})
+            continue
+    else:
+        send({"jsonrpc": "2.0", "id": request_id, "error": {"code": -32601, "message": "Method not found"}})
+        continue
+
+    send({"jsonrpc": "2.0", "id": request_id, "result": result})
@@ -0,0 +1,789 @@
+//! Integration tests for the agentic tool loop and tool approvals in the multi-agent manager.
+
+use OxideAgent::config::{CheckpointMode, FileAccessMode, LLMConfig, ToolScope};
+use OxideAgent::core::mocks::{MockRunShellCommandTool, MockShellExecutor};
+use OxideAgent::core::multi_agent_manager::MultiAgentManager;
+use OxideAgent::core::session::{SessionManager, SessionState};
+use OxideAgent::core::tools::{Tool, ToolProfile, ToolRegistry, WriteFileTool};
+use OxideAgent::core::workspace::Workspace;
+use OxideAgent::types::{AppEvent, ToolApprovalResponse, ToolCallDecision};
+use async_trait::async_trait;
+use httpmock::prelude::*;
+use once_cell::sync::Lazy;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::broadcast;
+
+/// Sessions of the agents started by these tests
+static SESSIONS_DIR: Lazy<TempDir> = Lazy::new(|| TempDir::new().unwrap());
+
+#[derive(Clone)]
+struct EchoTool;
+
+#[async_trait]
+impl Tool for EchoTool {
+    fn name(&self) -> String {
+        "echo".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Echo the given text".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "text": { "type": "string" } },
+            "required": ["text"]
+        })
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::Generic
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        Ok(args["text"].as_str().unwrap_or_default().to_string())
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
+
+/// `echo` offered under an alias of the qualified name `tools__echo`
+#[derive(Clone)]
+struct AliasedEchoTool;
+
+#[async_trait]
+impl Tool for AliasedEchoTool {
+    fn name(&self) -> String {
+        EchoTool.name()
+    }
+
+    fn description(&self) -> String {
+        EchoTool.description()
+    }
+
+    fn parameters(&self) -> Value {
+        EchoTool.parameters()
+    }
+
+    fn profile(&self) -> ToolProfile {
+        ToolProfile::Generic
+    }
+
+    fn qualified_name(&self) -> String {
+        "tools__echo".to_string()
+    }
+
+    async fn execute(&self, args: &Value) -> anyhow::Result<String> {
+        EchoTool.execute(args).await
+    }
+
+    fn clone_box(&self) -> Box<dyn Tool> {
+        Box::new(self.clone())
+    }
+}
+
+const TOOL_CALL_STREAM: &str = concat!(
+    "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_echo\",\"type\":\"function\",\"function\":{\"name\":\"echo\",\"arguments\":\"{\\\"text\\\":\\\"ping\\\"}\"}}]}}]}\n\n",
+    "data: [DONE]\n\n",
+);
+
+const ANSWER_STREAM: &str = concat!(
+    "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"The tool said ping\"}}]}\n\n",
+    "data: [DONE]\n\n",
+);
+
+async fn start_agent(
+    server: &MockServer,
+    session_name: &str,
+    max_tool_iterations: usize,
+    configure_session: impl FnOnce(&mut SessionState),
+) -> (
+    MultiAgentManager,
+    OxideAgent::core::multi_agent_manager::AgentId,
+    broadcast::Receiver<AppEvent>,
+) {
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.add_tool(Box::new(EchoTool));
+    start_agent_with_tools(
+        server,
+        tool_registry,
+        session_name,
+        |manager| manager.set_max_tool_iterations(max_tool_iterations),
+        configure_session,
+    )
+    .await
+}
+
+/// Start an agent whose `write_file` tool and checkpoints use `workspace_root`.
+async fn start_file_agent(
+    server: &MockServer,
+    workspace_root: &Path,
+    session_name: &str,
+    configure_session: impl FnOnce(&mut SessionState),
+) -> (
+    MultiAgentManager,
+    OxideAgent::core::multi_agent_manager::AgentId,
+    broadcast::Receiver<AppEvent>,
+) {
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.add_tool(Box::new(WriteFileTool::new(Workspace::new(
+        workspace_root,
+        FileAccessMode::ReadWrite,
+    ))));
+    start_agent_with_tools(
+        server,
+        tool_registry,
+        session_name,
+        |manager| manager.set_checkpoints(CheckpointMode::Files, workspace_root),
+        configure_session,
+    )
+    .await
+}
+
+async fn start_agent_with_tools(
+    server: &MockServer,
+    tool_registry: ToolRegistry,
+    session_name: &str,
+    configure_manager: impl FnOnce(&mut MultiAgentManager),
+    configure_session: impl FnOnce(&mut SessionState),
+) -> (
+    MultiAgentManager,
+    OxideAgent::core::multi_agent_manager::AgentId,
+    broadcast::Receiver<AppEvent>,
+) {
+    let (event_tx, event_rx) = broadcast::channel(500);
+    let mut manager = MultiAgentManager::new(
+        "You are a test agent.".to_string(),
+        tool_registry,
+        LLMConfig {
+            provider: "openai".to_string(),
+            api_base: Some(server.base_url()),
+            api_key: None,
+            model: None,
+        },
+        event_tx,
+    );
+    manager.set_sessions(SessionManager::new(SESSIONS_DIR.path()));
+    configure_manager(&mut manager);
+
+    let agent_id = manager
+        .create_agent("looper", "test-model", Some(session_name.to_string()))
+        .await
+        .unwrap();
+
+    // Set up session permissions before the first message arrives
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    configure_session(&mut *handle.session_state.write().await);
+
+    manager
+        .send_event_to_agent(&agent_id, AppEvent::UserInput("Say ping".to_string()))
+        .await
+        .unwrap();
+
+    (manager, agent_id, event_rx)
+}
+
+const WRITE_CALL_STREAM: &str = concat!(
+    "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_write\",\"type\":\"function\",\"function\":{\"name\":\"write_file\",\"arguments\":\"{\\\"path\\\":\\\"notes.txt\\\",\\\"content\\\":\\\"new line\\\\n\\\"}\"}}]}}]}\n\n",
+    "data: [DONE]\n\n",
+);
+
+/// Mock an LLM that asks to write `notes.txt` and then answers.
+fn mock_write_then_answer(server: &MockServer) -> httpmock::Mock<'_> {
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_excludes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(WRITE_CALL_STREAM);
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    })
+}
+
+/// Wait for the first event `select` accepts.
+async fn wait_for<T>(
+    event_rx: &mut broadcast::Receiver<AppEvent>,
+    select: impl Fn(AppEvent) -> Option<T>,
+) -> T {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        match tokio::time::timeout_at(deadline, event_rx.recv()).await {
+            Ok(Ok(event)) => {
+                if let Some(selected) = select(event) {
+                    return selected;
+                }
+            }
+            other => panic!("Expected event not received: {:?}", other),
+        }
+    }
+}
+
+fn allow_echo(session: &mut SessionState) {
+    session.add_allowed_tool("echo".to_string());
+}
+
+/// Collect events until the agent reports `Idle` after processing.
+async fn collect_until_idle(event_rx: &mut broadcast::Receiver<AppEvent>) -> Vec<AppEvent> {
+    let mut events = Vec::new();
+    let mut processing = false;
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, event_rx.recv()).await {
+        if let AppEvent::AgentStatusUpdate(_, status) = &event {
+            if status.starts_with("Processing") {
+                processing = true;
+            } else if processing && status == "Idle" {
+                events.push(event);
+                break;
+            }
+        }
+        events.push(event);
+    }
+
+    events
+}
+
+fn iteration_statuses(events: &[AppEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            AppEvent::AgentStatusUpdate(_, status) if status.starts_with("Processing (") => {
+                Some(status.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_auto_approved_tool_calls_continue_conversation() {
+    let server = MockServer::start();
+    let answer_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+    let tool_call_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_excludes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(TOOL_CALL_STREAM);
+    });
+
+    let (manager, _agent_id, mut event_rx) =
+        start_agent(&server, "agentic_loop_continue", 5, allow_echo).await;
+    let events = collect_until_idle(&mut event_rx).await;
+
+    tool_call_mock.assert_calls(1);
+    answer_mock.assert_calls(1);
+
+    assert!(events.iter().any(|event| matches!(
+        event,
+        AppEvent::ToolResult(name, output) if name == "echo" && output == "ping"
+    )));
+    assert_eq!(
+        iteration_statuses(&events),
+        vec!["Processing (iteration 1/5)", "Processing (iteration 2/5)"]
+    );
+
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    let history = handle.session_state.read().await.history().clone();
+    let tool_message = history
+        .iter()
+        .find(|message| message.role == "tool")
+        .unwrap();
+    assert_eq!(tool_message.tool_call_id.as_deref(), Some("call_echo"));
+    assert_eq!(tool_message.content, "ping");
+    assert_eq!(history.last().unwrap().content, "The tool said ping");
+
+    // The finished turn is saved with its metadata
+    let metadata = SessionManager::new(SESSIONS_DIR.path())
+        .metadata("agentic_loop_continue")
+        .unwrap()
+        .unwrap();
+    assert_eq!(metadata.agent.as_deref(), Some("looper"));
+    assert_eq!(metadata.title, "Say ping");
+    assert_eq!(metadata.message_count, history.len());
+}
+
+#[tokio::test]
+async fn test_scoped_agent_only_sees_allowed_tools() {
+    let server = MockServer::start();
+    let scoped_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes(r#""name":"echo""#)
+            .body_excludes("run_shell_command");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.add_tool(Box::new(EchoTool));
+    tool_registry.add_tool(Box::new(MockRunShellCommandTool::new(Arc::new(
+        Mutex::new(MockShellExecutor::new()),
+    ))));
+    let (_manager, _agent_id, mut event_rx) = start_agent_with_tools(
+        &server,
+        tool_registry,
+        "agentic_loop_scoped",
+        |manager| {
+            manager.set_tool_scope(
+                "looper",
+                ToolScope {
+                    allowed_profiles: vec![ToolProfile::Generic],
+                    ..ToolScope::default()
+                },
+            )
+        },
+        |_| {},
+    )
+    .await;
+    collect_until_idle(&mut event_rx).await;
+
+    scoped_mock.assert_calls(1);
+}
+
+#[tokio::test]
+async fn test_tool_loop_stops_at_max_iterations() {
+    let server = MockServer::start();
+    let tool_call_mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(TOOL_CALL_STREAM);
+    });
+
+    let (_manager, _agent_id, mut event_rx) =
+        start_agent(&server, "agentic_loop_limit", 2, allow_echo).await;
+    let events = collect_until_idle(&mut event_rx).await;
+
+    tool_call_mock.assert_calls(2);
+    assert_eq!(
+        iteration_statuses(&events),
+        vec!["Processing (iteration 1/2)", "Processing (iteration 2/2)"]
+    );
+    assert!(events.iter().any(|event| matches!(
+        event,
+        AppEvent::AgentMessage(message) if message.contains("Stopped after 2 tool iterations")
+    )));
+}
+
+#[tokio::test]
+async fn test_per_call_approval_reports_denied_calls() {
+    let server = MockServer::start();
+    let answer_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+    let tool_call_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_excludes(r#""role":"tool""#);
+        let body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[",
+            "{\"index\":0,\"id\":\"call_a\",\"type\":\"function\",\"function\":{\"name\":\"echo\",\"arguments\":\"{\\\"text\\\":\\\"ping\\\"}\"}},",
+            "{\"index\":1,\"id\":\"call_b\",\"type\":\"function\",\"function\":{\"name\":\"echo\",\"arguments\":\"{\\\"text\\\":\\\"secret\\\"}\"}}",
+            "]}}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(body);
+    });
+
+    let (manager, agent_id, mut event_rx) =
+        start_agent(&server, "agentic_loop_per_call", 5, |_| {}).await;
+
+    // Wait for the approval request covering both calls
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    let requested = loop {
+        match tokio::time::timeout_at(deadline, event_rx.recv()).await {
+            Ok(Ok(AppEvent::ToolRequest(calls, _))) => break calls,
+            Ok(Ok(_)) => continue,
+            other => panic!("No tool request received: {:?}", other),
+        }
+    };
+    assert_eq!(requested.len(), 2);
+
+    manager
+        .send_event_to_agent(
+            &agent_id,
+            AppEvent::ToolApproval(vec![
+                ToolCallDecision::new(0, ToolApprovalResponse::Allow),
+                ToolCallDecision::new(1, ToolApprovalResponse::Deny),
+            ]),
+        )
+        .await
+        .unwrap();
+
+    let events = collect_until_idle(&mut event_rx).await;
+
+    tool_call_mock.assert_calls(1);
+    answer_mock.assert_calls(1);
+
+    let tool_results: Vec<&String> = events
+        .iter()
+        .filter_map(|event| match event {
+            AppEvent::ToolResult(_, output) => Some(output),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tool_results, vec!["ping"]);
+
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    let history = handle.session_state.read().await.history().clone();
+    let tool_messages: Vec<(Option<&str>, &str)> = history
+        .iter()
+        .filter(|message| message.role == "tool")
+        .map(|message| (message.tool_call_id.as_deref(), message.content.as_str()))
+        .collect();
+    assert_eq!(
+        tool_messages,
+        vec![
+            (Some("call_a"), "ping"),
+            (Some("call_b"), "Tool execution denied by user."),
+        ]
+    );
+    assert_eq!(history.last().unwrap().content, "The tool said ping");
+}
+
+#[tokio::test]
+async fn test_denying_every_call_continues_conversation() {
+    let server = MockServer::start();
+    let answer_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+    let tool_call_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_excludes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(TOOL_CALL_STREAM);
+    });
+
+    let (manager, agent_id, mut event_rx) =
+        start_agent(&server, "agentic_loop_deny_all", 5, |_| {}).await;
+
+    wait_for(&mut event_rx, |event| match event {
+        AppEvent::ToolRequest(calls, _) => Some(calls),
+        _ => None,
+    })
+    .await;
+    manager
+        .send_event_to_agent(
+            &agent_id,
+            AppEvent::ToolApproval(vec![ToolCallDecision::new(0, ToolApprovalResponse::Deny)]),
+        )
+        .await
+        .unwrap();
+
+    collect_until_idle(&mut event_rx).await;
+
+    // The model is asked again and sees the denial
+    tool_call_mock.assert_calls(1);
+    answer_mock.assert_calls(1);
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    let history = handle.session_state.read().await.history().clone();
+    let last_two: Vec<&str> = history[history.len() - 2..]
+        .iter()
+        .map(|message| message.content.as_str())
+        .collect();
+    assert_eq!(
+        last_two,
+        vec!["Tool execution denied by user.", "The tool said ping"]
+    );
+}
+
+#[tokio::test]
+async fn test_session_denied_tool_is_rejected_without_asking() {
+    let server = MockServer::start();
+    let answer_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes("denied by the user's tool permissions");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+    let tool_call_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_excludes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(TOOL_CALL_STREAM);
+    });
+
+    let (manager, _agent_id, mut event_rx) =
+        start_agent(&server, "agentic_loop_denied", 5, |session| {
+            session.add_denied_tool("echo".to_string())
+        })
+        .await;
+    let events = collect_until_idle(&mut event_rx).await;
+
+    tool_call_mock.assert_calls(1);
+    answer_mock.assert_calls(1);
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, AppEvent::ToolRequest(..) | AppEvent::ToolResult(..)))
+    );
+
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    let history = handle.session_state.read().await.history().clone();
+    let tool_message = history
+        .iter()
+        .find(|message| message.role == "tool")
+        .unwrap();
+    assert_eq!(tool_message.tool_call_id.as_deref(), Some("call_echo"));
+    assert!(tool_message.content.starts_with("Tool execution denied"));
+}
+
+#[tokio::test]
+async fn test_permissions_are_keyed_by_qualified_tool_name() {
+    let server = MockServer::start();
+    let answer_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_includes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+    let tool_call_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .body_excludes(r#""role":"tool""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(TOOL_CALL_STREAM);
+    });
+
+    // A deny on the alias means nothing; the qualified name is what counts
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.add_tool(Box::new(AliasedEchoTool));
+    let (_manager, _agent_id, mut event_rx) = start_agent_with_tools(
+        &server,
+        tool_registry,
+        "agentic_loop_qualified",
+        |_| {},
+        |session| {
+            session.add_denied_tool("echo".to_string());
+            session.add_allowed_tool("tools__echo".to_string());
+        },
+    )
+    .await;
+    let events = collect_until_idle(&mut event_rx).await;
+
+    tool_call_mock.assert_calls(1);
+    answer_mock.assert_calls(1);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        AppEvent::ToolResult(name, output) if name == "echo" && output == "ping"
+    )));
+}
+
+#[tokio::test]
+async fn test_write_file_approval_shows_diff_and_can_be_undone() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    fs::write(&file_path, "old line\n").unwrap();
+
+    let server = MockServer::start();
+    let answer_mock = mock_write_then_answer(&server);
+    let (manager, agent_id, mut event_rx) =
+        start_file_agent(&server, temp_dir.path(), "agentic_loop_undo", |_| {}).await;
+
+    let previews = wait_for(&mut event_rx, |event| match event {
+        AppEvent::ToolRequest(_, previews) => Some(previews),
+        _ => None,
+    })
+    .await;
+    assert_eq!(
+        previews,
+        vec![Some(
+            "--- a/notes.txt\n+++ b/notes.txt\n@@ -1 +1 @@\n-old line\n+new line".to_string()
+        )]
+    );
+
+    manager
+        .send_event_to_agent(
+            &agent_id,
+            AppEvent::ToolApproval(vec![ToolCallDecision::new(0, ToolApprovalResponse::Allow)]),
+        )
+        .await
+        .unwrap();
+    collect_until_idle(&mut event_rx).await;
+    answer_mock.assert_calls(1);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "new line\n");
+
+    let agent_message = |event| match event {
+        AppEvent::AgentMessage(message) => Some(message),
+        _ => None,
+    };
+    manager
+        .send_event_to_agent(&agent_id, AppEvent::UndoLastToolEffect)
+        .await
+        .unwrap();
+    let message = wait_for(&mut event_rx, agent_message).await;
+    assert!(message.starts_with("Undid 'write_file': restored"));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "old line\n");
+
+    manager
+        .send_event_to_agent(&agent_id, AppEvent::UndoLastToolEffect)
+        .await
+        .unwrap();
+    let message = wait_for(&mut event_rx, agent_message).await;
+    assert_eq!(message, "Nothing to undo.");
+}
+
+#[tokio::test]
+async fn test_restore_checkpoint_rolls_back_files_and_conversation() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    fs::write(&file_path, "old line\n").unwrap();
+
+    let server = MockServer::start();
+    mock_write_then_answer(&server);
+    let (manager, agent_id, mut event_rx) = start_file_agent(
+        &server,
+        temp_dir.path(),
+        "agentic_loop_checkpoint",
+        |session| session.add_allowed_tool("write_file".to_string()),
+    )
+    .await;
+    collect_until_idle(&mut event_rx).await;
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "new line\n");
+
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    let history = handle.session_state.read().await.history().clone();
+    let request_index = history
+        .iter()
+        .position(|message| message.tool_calls.is_some())
+        .unwrap();
+
+    let checkpoint_list = |event| match event {
+        AppEvent::CheckpointList(checkpoints) => Some(checkpoints),
+        _ => None,
+    };
+    manager
+        .send_event_to_agent(&agent_id, AppEvent::ListCheckpoints)
+        .await
+        .unwrap();
+    let checkpoints = wait_for(&mut event_rx, checkpoint_list).await;
+    assert_eq!(checkpoints.len(), 1);
+    assert_eq!(checkpoints[0].tool_name, "write_file");
+    assert_eq!(checkpoints[0].message_index, request_index);
+
+    manager
+        .send_event_to_agent(&agent_id, AppEvent::RestoreCheckpoint(checkpoints[0].id))
+        .await
+        .unwrap();
+    let restored_history = wait_for(&mut event_rx, |event| match event {
+        AppEvent::SessionHistory(history) => Some(history),
+        _ => None,
+    })
+    .await;
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "old line\n");
+    assert_eq!(restored_history.len(), request_index);
+    assert_eq!(restored_history.last().unwrap().content, "Say ping");
+    assert_eq!(
+        handle.session_state.read().await.history().len(),
+        request_index
+    );
+
+    manager
+        .send_event_to_agent(&agent_id, AppEvent::ListCheckpoints)
+        .await
+        .unwrap();
+    assert!(wait_for(&mut event_rx, checkpoint_list).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_forking_the_current_session_switches_to_the_fork() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(ANSWER_STREAM);
+    });
+
+    let (manager, agent_id, mut event_rx) =
+        start_agent(&server, "agentic_loop_fork", 5, allow_echo).await;
+    collect_until_idle(&mut event_rx).await;
+
+    // Keep the question, drop the answer
+    manager
+        .send_event_to_agent(
+            &agent_id,
+            AppEvent::ForkSession(
+                "agentic_loop_fork".to_string(),
+                "agentic_loop_fork_retry".to_string(),
+                Some(1),
+            ),
+        )
+        .await
+        .unwrap();
+
+    let switched = wait_for(&mut event_rx, |event| match event {
+        AppEvent::SessionSwitched(name) => Some(name),
+        _ => None,
+    })
+    .await;
+    assert_eq!(switched, "agentic_loop_fork_retry");
+    let history = wait_for(&mut event_rx, |event| match event {
+        AppEvent::SessionHistory(history) => Some(history),
+        _ => None,
+    })
+    .await;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].content, "Say ping");
+
+    let handle = manager.get_agent_by_name("looper").await.unwrap();
+    assert!(handle.session_state.read().await.is_tool_allowed("echo"));
+
+    // The original session keeps the whole conversation
+    let sessions = SessionManager::new(SESSIONS_DIR.path());
+    let parent = sessions.metadata("agentic_loop_fork").unwrap().unwrap();
+    assert_eq!(parent.message_count, 2);
+    let fork = sessions
+        .metadata("agentic_loop_fork_retry")
+        .unwrap()
+        .unwrap();
+    assert_eq!(fork.parent.unwrap().session, "agentic_loop_fork");
+}
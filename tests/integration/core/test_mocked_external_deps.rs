@@ -32,7 +32,7 @@ async fn test_file_operations_with_mock_filesystem() {
     use OxideAgent::core::tools::{Tool, WriteFileTool};
 
     // Test the WriteFileTool
-    let tool = WriteFileTool;
+    let tool = WriteFileTool::default();
     assert_eq!(tool.name(), "write_file");
 }
 
@@ -40,6 +40,6 @@ async fn test_file_operations_with_mock_filesystem() {
 async fn test_shell_commands_with_mock_executor() {
     use OxideAgent::core::tools::{RunShellCommandTool, Tool};
 
-    let tool = RunShellCommandTool;
+    let tool = RunShellCommandTool::default();
     assert_eq!(tool.name(), "run_shell_command");
 }
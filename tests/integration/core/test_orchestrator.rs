@@ -16,19 +16,25 @@ fn test_orchestrator_creation() {
             model: "qwen3:4b".to_string(),
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: false,
         session: Some("test_session".to_string()),
         list_sessions: false,
+        sessions_dir: None,
         interface: InterfaceType::Tui,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11434".to_string(),
+            api_base: Some("http://localhost:11434".to_string()),
             api_key: None,
             model: None,
         },
@@ -46,9 +52,9 @@ fn test_orchestrator_creation() {
 #[test]
 fn test_tool_registry_creation() {
     let mut tool_registry = ToolRegistry::new();
-    tool_registry.add_tool(Box::new(WriteFileTool));
-    tool_registry.add_tool(Box::new(ReadFileTool));
-    tool_registry.add_tool(Box::new(RunShellCommandTool));
+    tool_registry.add_tool(Box::new(WriteFileTool::default()));
+    tool_registry.add_tool(Box::new(ReadFileTool::default()));
+    tool_registry.add_tool(Box::new(RunShellCommandTool::default()));
 
     let tools = tool_registry.definitions();
     assert_eq!(tools.len(), 3);
@@ -18,19 +18,22 @@ async fn test_tool_execution_workflow() {
             model: "qwen:latest".to_string(),
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: true, // Use non-streaming for easier testing
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11434".to_string(),
+            api_base: Some("http://localhost:11434".to_string()),
             api_key: None,
             model: None,
         },
         interface: InterfaceType::Tui,
         list_sessions: false,
+        sessions_dir: None,
         mcp: Default::default(),
         session: None,
         multi_agent: Default::default(),
+        tools: Default::default(),
     };
 
     // Create channels for communication
@@ -65,23 +68,30 @@ async fn test_orchestrator_session_switching() {
             model: "qwen3:4b".to_string(),
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: false,
         session: Some("initial_session".to_string()),
         list_sessions: false,
+        sessions_dir: None,
         interface: InterfaceType::Tui,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11434".to_string(),
+            api_base: Some("http://localhost:11434".to_string()),
             api_key: None,
             model: None,
         },
         multi_agent: Default::default(),
+        tools: Default::default(),
     };
 
     // Create channels for communication
@@ -130,23 +140,30 @@ async fn test_orchestrator_tool_approvals() {
             model: "qwen3:4b".to_string(),
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: true,
         session: Some(temp_session_name.to_string()),
         list_sessions: false,
+        sessions_dir: None,
         interface: InterfaceType::Tui,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11434".to_string(),
+            api_base: Some("http://localhost:11434".to_string()),
             api_key: None,
             model: None,
         },
         multi_agent: Default::default(),
+        tools: Default::default(),
     };
 
     // Create channels for communication
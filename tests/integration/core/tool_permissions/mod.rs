@@ -1,6 +1,10 @@
 //! Integration tests for the tool permissions module.
 
-use OxideAgent::core::tool_permissions::GlobalToolPermissions;
+use OxideAgent::core::tool_permissions::{
+    ArgumentCondition, GlobalToolPermissions, PermissionRule, RuleOutcome,
+};
+use OxideAgent::types::ToolFunction;
+use serde_json::{Value, json};
 use std::fs;
 use tempfile::TempDir;
 
@@ -145,3 +149,213 @@ fn test_nonexistent_permissions_file() {
     let permissions = load_result.unwrap();
     assert_eq!(permissions.list_allowed().len(), 0);
 }
+
+fn call(name: &str, arguments: Value) -> ToolFunction {
+    ToolFunction {
+        name: name.to_string(),
+        arguments,
+    }
+}
+
+#[test]
+fn test_rule_regex_condition_allows_matching_commands() {
+    let mut permissions = GlobalToolPermissions::new();
+    permissions.add_rule(
+        PermissionRule::new("run_shell_command", RuleOutcome::Allow)
+            .unwrap()
+            .with_condition(ArgumentCondition::regex("command", "^cargo (test|check)").unwrap()),
+    );
+
+    assert_eq!(
+        permissions.resolve(
+            &call("run_shell_command", json!({"command": "cargo test --all"})),
+            false
+        ),
+        RuleOutcome::Allow
+    );
+    assert_eq!(
+        permissions.resolve(
+            &call("run_shell_command", json!({"command": "rm -rf /"})),
+            false
+        ),
+        RuleOutcome::Ask
+    );
+    assert_eq!(
+        permissions.evaluate_rules(&call("run_shell_command", json!({}))),
+        None
+    );
+}
+
+#[test]
+fn test_rule_glob_condition_is_path_aware() {
+    let mut permissions = GlobalToolPermissions::new();
+    permissions.add_rule(
+        PermissionRule::new("write_file", RuleOutcome::Allow)
+            .unwrap()
+            .with_condition(ArgumentCondition::glob("path", "./src/**").unwrap()),
+    );
+
+    let outcome = |path: &str| {
+        permissions.evaluate_rules(&call("write_file", json!({"path": path, "content": ""})))
+    };
+    assert_eq!(outcome("src/core/mod.rs"), Some(RuleOutcome::Allow));
+    assert_eq!(outcome("./src/main.rs"), Some(RuleOutcome::Allow));
+    assert_eq!(outcome("tests/main.rs"), None);
+    assert_eq!(outcome("./src/../../etc/passwd"), None);
+}
+
+#[test]
+fn test_rule_deny_overrides_allow_and_ask() {
+    let mut permissions = GlobalToolPermissions::new();
+    permissions.add_allowed("run_shell_command");
+    permissions.add_rule(PermissionRule::new("run_shell_command", RuleOutcome::Allow).unwrap());
+    permissions.add_rule(
+        PermissionRule::new("run_shell_command", RuleOutcome::Ask)
+            .unwrap()
+            .with_condition(ArgumentCondition::regex("command", "^git ").unwrap()),
+    );
+    permissions.add_rule(
+        PermissionRule::new("run_*", RuleOutcome::Deny)
+            .unwrap()
+            .with_condition(ArgumentCondition::regex("command", r"\brm\b").unwrap()),
+    );
+
+    let resolve = |command: &str| {
+        permissions.resolve(
+            &call("run_shell_command", json!({"command": command})),
+            true,
+        )
+    };
+    assert_eq!(resolve("ls"), RuleOutcome::Allow);
+    assert_eq!(resolve("git push"), RuleOutcome::Ask);
+    assert_eq!(resolve("git rm file"), RuleOutcome::Deny);
+}
+
+#[test]
+fn test_rule_conditions_use_json_paths() {
+    let arguments = json!({
+        "options": {"cwd": "/tmp"},
+        "files": ["a.txt", "b.txt"],
+        "force": true
+    });
+
+    assert!(ArgumentCondition::equals("$.options.cwd", json!("/tmp")).matches(&arguments));
+    assert!(
+        ArgumentCondition::glob("files[1]", "*.txt")
+            .unwrap()
+            .matches(&arguments)
+    );
+    assert!(
+        ArgumentCondition::regex("force", "^true$")
+            .unwrap()
+            .matches(&arguments)
+    );
+    assert!(!ArgumentCondition::equals("files[2]", json!("c.txt")).matches(&arguments));
+    assert!(ArgumentCondition::regex("options.cwd", "(").is_err());
+    assert!(PermissionRule::new("run_[", RuleOutcome::Allow).is_err());
+}
+
+#[test]
+fn test_rules_persist_alongside_allowed_tools() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file_path = temp_dir.path().join("tool_permissions.json");
+
+    fs::write(
+        &test_file_path,
+        r#"{
+            "allowed_tools": ["read_file"],
+            "rules": [
+                {
+                    "tool": "write_file",
+                    "conditions": [{"path": "path", "glob": "./src/**"}],
+                    "outcome": "allow"
+                },
+                {"tool": "run_shell_command", "outcome": "deny"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let mut permissions = GlobalToolPermissions::load_from_path(&test_file_path).unwrap();
+    assert!(permissions.is_allowed("read_file"));
+    assert_eq!(permissions.rules().len(), 2);
+    assert_eq!(
+        permissions.rules()[0].conditions,
+        vec![ArgumentCondition::glob("path", "./src/**").unwrap()]
+    );
+
+    assert!(permissions.remove_rule(1).is_some());
+    assert!(permissions.remove_rule(5).is_none());
+    permissions.save_to_path(&test_file_path).unwrap();
+
+    let reloaded = GlobalToolPermissions::load_from_path(&test_file_path).unwrap();
+    assert_eq!(reloaded.rules(), permissions.rules());
+    assert_eq!(
+        reloaded.resolve(&call("run_shell_command", json!({"command": "ls"})), false),
+        RuleOutcome::Ask
+    );
+}
+
+#[test]
+fn test_invalid_rule_patterns_fail_to_load() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file_path = temp_dir.path().join("tool_permissions.json");
+
+    fs::write(
+        &test_file_path,
+        r#"{
+            "allowed_tools": ["read_file"],
+            "rules": [
+                {
+                    "tool": "run_shell_command",
+                    "conditions": [{"path": "command", "regex": "^(git"}],
+                    "outcome": "allow"
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let error = GlobalToolPermissions::load_from_path(&test_file_path).unwrap_err();
+    assert!(error.to_string().contains("Invalid regex '^(git'"));
+}
+
+#[test]
+fn test_permissions_that_failed_to_load_are_not_saved_over_the_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file_path = temp_dir.path().join("tool_permissions.json");
+    let content = r#"{
+        "allowed_tools": ["read_file"],
+        "rules": [{"tool": "write_file", "conditions": [{"path": "path", "glob": "[src"}], "outcome": "allow"}]
+    }"#;
+    fs::write(&test_file_path, content).unwrap();
+
+    let error = GlobalToolPermissions::load_from_path(&test_file_path).unwrap_err();
+    let mut permissions = GlobalToolPermissions::failed_to_load(&error);
+
+    // What "always allow" does
+    permissions.add_allowed("run_shell_command");
+    assert!(permissions.is_allowed("run_shell_command"));
+    let save_error = permissions.save_to_path(&test_file_path).unwrap_err();
+
+    assert!(save_error.to_string().contains(&error.to_string()));
+    assert_eq!(fs::read_to_string(&test_file_path).unwrap(), content);
+}
+
+#[test]
+fn test_denied_tools_override_rules_and_persist() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file_path = temp_dir.path().join("tool_permissions.json");
+
+    let mut permissions = GlobalToolPermissions::new();
+    permissions.add_rule(PermissionRule::new("run_shell_command", RuleOutcome::Allow).unwrap());
+    permissions.add_denied("run_shell_command");
+    permissions.save_to_path(&test_file_path).unwrap();
+
+    let loaded = GlobalToolPermissions::load_from_path(&test_file_path).unwrap();
+    assert!(loaded.is_denied("run_shell_command"));
+    assert_eq!(
+        loaded.resolve(&call("run_shell_command", json!({"command": "ls"})), true),
+        RuleOutcome::Deny
+    );
+}
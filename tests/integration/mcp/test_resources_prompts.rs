@@ -0,0 +1,92 @@
+//! Integration tests for MCP resources and prompts against the fake MCP server script.
+
+use OxideAgent::config::MCPToolConfig;
+use OxideAgent::core::mcp::manager::{McpConnectionRegistry, McpManager};
+use std::collections::HashMap;
+
+async fn registry_with_fake_server() -> McpConnectionRegistry {
+    let registry = McpConnectionRegistry::new();
+    let manager = McpManager::with_registry(registry.clone());
+    manager
+        .launch_servers(&[MCPToolConfig {
+            name: "fake".to_string(),
+            command: "python3".to_string(),
+            args: vec![format!(
+                "{}/tests/fixtures/fake_mcp_server.py",
+                env!("CARGO_MANIFEST_DIR")
+            )],
+            requires_approval: true,
+        }])
+        .await
+        .unwrap();
+    registry
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_resources_are_listed_across_pages_and_read_as_text() {
+    let registry = registry_with_fake_server().await;
+
+    let resources = registry.list_resources().await;
+    let uris: Vec<(&str, &str)> = resources
+        .iter()
+        .map(|(server, resource)| (server.as_str(), resource.uri.as_str()))
+        .collect();
+    assert_eq!(
+        uris,
+        vec![("fake", "file:///notes.txt"), ("fake", "file:///logo.png")]
+    );
+    assert_eq!(resources[0].1.description.as_deref(), Some("Meeting notes"));
+
+    assert_eq!(
+        registry.read_resource("file:///notes.txt").await.unwrap(),
+        "Ship on Friday"
+    );
+    assert_eq!(
+        registry.read_resource("file:///logo.png").await.unwrap(),
+        "[binary resource: image/png, 8 bytes base64]"
+    );
+
+    let error = registry
+        .read_resource("file:///missing.txt")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("No MCP server offers resource"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_prompts_are_listed_and_expanded_with_arguments() {
+    let registry = registry_with_fake_server().await;
+
+    let prompts = registry.list_prompts().await;
+    assert_eq!(prompts.len(), 1);
+    let (server, prompt) = &prompts[0];
+    assert_eq!(server, "fake");
+    assert_eq!(prompt.name, "review");
+    assert!(prompt.arguments[0].required);
+    assert!(!prompt.arguments[1].required);
+
+    let mut arguments = HashMap::new();
+    arguments.insert("file".to_string(), "main.rs".to_string());
+    arguments.insert("focus".to_string(), "errors".to_string());
+    assert_eq!(
+        registry.get_prompt("review", &arguments).await.unwrap(),
+        "You are a careful reviewer.\n\nReview main.rs with a focus on errors"
+    );
+
+    let error = registry
+        .get_prompt("review", &HashMap::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Prompt 'review' needs the argument(s): file"
+    );
+
+    let error = registry
+        .get_prompt("missing", &HashMap::new())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("No MCP server offers prompt"));
+}
@@ -0,0 +1,237 @@
+//! Integration tests for stdio MCP connections against a fake MCP server script.
+
+use OxideAgent::config::MCPToolConfig;
+use OxideAgent::core::llm::client::LlmClient;
+use OxideAgent::core::mcp::config::{McpServerConfig, McpServerType};
+use OxideAgent::core::mcp::connection::StdioMcpConnection;
+use OxideAgent::core::mcp::manager::{McpConnectionRegistry, McpManager};
+use OxideAgent::core::tools::Tool;
+use OxideAgent::types::{AppEvent, ChatMessage, McpElicitationAnswer};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+fn fake_server_script() -> String {
+    format!(
+        "{}/tests/fixtures/fake_mcp_server.py",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
+
+fn fake_server_tool_config(name: &str) -> MCPToolConfig {
+    MCPToolConfig {
+        name: name.to_string(),
+        command: "python3".to_string(),
+        args: vec![fake_server_script()],
+        requires_approval: true,
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stdio_connection_runs_initialize_handshake() {
+    let config = McpServerConfig {
+        name: "fake".to_string(),
+        description: None,
+        server_type: McpServerType::Command {
+            command: "python3".to_string(),
+            args: Some(vec![fake_server_script()]),
+            environment: None,
+            working_directory: None,
+        },
+        auto_start: Some(true),
+        environment: None,
+    };
+
+    let mut connection = StdioMcpConnection::new(&config).await.unwrap();
+    assert_eq!(
+        connection.initialize_result()["serverInfo"]["name"],
+        "fake-mcp-server"
+    );
+    assert!(connection.process_id().is_some());
+
+    // The server only lists tools after `notifications/initialized`, and logs
+    // a notification before its response
+    let tools = connection.discover_tools().await.unwrap();
+    let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+    assert_eq!(names, vec!["echo", "mixed", "fail", "pid"]);
+
+    let error = connection
+        .send_request("completion/complete", None)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Method not found"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stdio_tools_execute_through_one_long_lived_connection() {
+    let registry = McpConnectionRegistry::new();
+    let manager = McpManager::with_registry(registry.clone());
+
+    let adapters = manager
+        .launch_servers(&[fake_server_tool_config("fake")])
+        .await
+        .unwrap();
+    assert_eq!(adapters.len(), 4);
+    assert_eq!(registry.connections.read().await.len(), 1);
+
+    let tool = |name: &str| {
+        adapters
+            .iter()
+            .find(|adapter| adapter.name() == name)
+            .unwrap()
+    };
+
+    let output = tool("fake__echo")
+        .execute(&json!({ "text": "hello" }))
+        .await
+        .unwrap();
+    assert_eq!(output, "hello");
+
+    // Every call reaches the same server process
+    let first_pid = tool("fake__pid").execute(&json!({})).await.unwrap();
+    let second_pid = tool("fake__pid").execute(&json!({})).await.unwrap();
+    assert_eq!(first_pid, second_pid);
+    assert!(first_pid.parse::<u32>().is_ok());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stdio_tool_content_is_converted_to_text() {
+    let registry = McpConnectionRegistry::new();
+    let manager = McpManager::with_registry(registry.clone());
+    manager
+        .launch_servers(&[fake_server_tool_config("content")])
+        .await
+        .unwrap();
+    let connection_id = "content_connection".to_string();
+
+    let output = registry
+        .execute_tool_on_connection(&connection_id, "mixed", &json!({}))
+        .await
+        .unwrap();
+    assert_eq!(
+        output,
+        [
+            "Here is the report",
+            "[image content: image/png, 8 bytes base64]",
+            "[resource: file:///report.txt]",
+            "report body",
+            "[resource link: file:///data.csv (data.csv)]",
+        ]
+        .join("\n")
+    );
+
+    let error = registry
+        .execute_tool_on_connection(&connection_id, "fail", &json!({}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("disk is full"));
+
+    let error = registry
+        .execute_tool_on_connection(&connection_id, "missing", &json!({}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Unknown tool"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_failed_server_is_skipped() {
+    let manager = McpManager::with_registry(McpConnectionRegistry::new());
+    let broken = MCPToolConfig {
+        name: "broken".to_string(),
+        command: "python3".to_string(),
+        args: vec!["-c".to_string(), "import sys; sys.exit(1)".to_string()],
+        requires_approval: true,
+    };
+
+    let adapters = manager
+        .launch_servers(&[broken, fake_server_tool_config("working")])
+        .await
+        .unwrap();
+    assert_eq!(adapters.len(), 4);
+    assert_eq!(manager.registry.connections.read().await.len(), 1);
+}
+
+/// Model answering every sampling request with the same text
+#[derive(Debug)]
+struct CannedLlm;
+
+#[async_trait::async_trait]
+impl LlmClient for CannedLlm {
+    async fn chat(
+        &self,
+        _model: &str,
+        history: &[ChatMessage],
+        _tools: &[OxideAgent::types::Tool],
+        _stream: bool,
+        _tx: mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<Option<ChatMessage>> {
+        Ok(Some(ChatMessage::assistant(&format!(
+            "Summary of: {}",
+            history.last().unwrap().content
+        ))))
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stdio_server_requests_reach_the_user_and_the_model() {
+    let registry = McpConnectionRegistry::new();
+    let (event_tx, mut event_rx) = mpsc::channel(8);
+    registry.request_router().set_interface(event_tx);
+    registry
+        .request_router()
+        .set_sampler(Arc::new(CannedLlm), "canned".to_string());
+    let manager = McpManager::with_registry(registry.clone());
+    manager
+        .launch_servers(&[fake_server_tool_config("asking")])
+        .await
+        .unwrap();
+
+    // The user fills in the form and approves sampling
+    let router = registry.request_router().clone();
+    tokio::spawn(async move {
+        while let Some(event) = event_rx.recv().await {
+            match event {
+                AppEvent::McpElicitationRequest(request) => {
+                    assert_eq!(request.message, "Which city?");
+                    let mut values = serde_json::Map::new();
+                    values.insert("city".to_string(), json!("Paris"));
+                    router.answer_elicitation(request.id, McpElicitationAnswer::Accept(values));
+                }
+                AppEvent::McpSamplingRequest(request) => {
+                    assert_eq!(request.max_tokens, Some(50));
+                    router.approve_sampling(request.id, true);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    let connection_id = "asking_connection".to_string();
+    let output = registry
+        .execute_tool_on_connection(&connection_id, "ask", &json!({}))
+        .await
+        .unwrap();
+    let answer: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        answer,
+        json!({ "action": "accept", "content": { "city": "Paris" } })
+    );
+
+    let output = registry
+        .execute_tool_on_connection(
+            &connection_id,
+            "summarize",
+            &json!({ "text": "a long day" }),
+        )
+        .await
+        .unwrap();
+    let message: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(message["role"], "assistant");
+    assert_eq!(message["content"]["text"], "Summary of: a long day");
+    assert_eq!(message["model"], "canned");
+}
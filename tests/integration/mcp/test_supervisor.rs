@@ -0,0 +1,185 @@
+//! Integration tests for MCP server supervision against the fake MCP server script.
+
+use OxideAgent::core::mcp::config::{McpServerConfig, McpServerType};
+use OxideAgent::core::mcp::manager::{McpConnectionRegistry, McpToolSource, connection_id};
+use OxideAgent::core::mcp::supervisor::{McpServerStatus, McpSupervisor, RestartPolicy};
+use OxideAgent::core::tools::ToolRegistry;
+use OxideAgent::types::AppEvent;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+fn fake_server_config(name: &str) -> McpServerConfig {
+    McpServerConfig {
+        name: name.to_string(),
+        description: None,
+        server_type: McpServerType::Command {
+            command: "python3".to_string(),
+            args: Some(vec![format!(
+                "{}/tests/fixtures/fake_mcp_server.py",
+                env!("CARGO_MANIFEST_DIR")
+            )]),
+            environment: None,
+            working_directory: None,
+        },
+        auto_start: Some(true),
+        environment: None,
+    }
+}
+
+fn fast_restart_policy(max_restarts: Option<u32>) -> RestartPolicy {
+    RestartPolicy {
+        initial_delay: Duration::from_millis(50),
+        max_delay: Duration::from_millis(200),
+        max_restarts,
+        stable_after: Duration::from_secs(60),
+        check_interval: Duration::from_millis(20),
+    }
+}
+
+fn process_is_alive(pid: &str) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", pid])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+async fn wait_for_status(
+    supervisor: &McpSupervisor,
+    server_name: &str,
+    expected: impl Fn(&McpServerStatus) -> bool,
+) -> McpServerStatus {
+    for _ in 0..250 {
+        if let Some(status) = supervisor.status(server_name)
+            && expected(&status)
+        {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!(
+        "MCP server '{}' never reached the expected status, last: {:?}",
+        server_name,
+        supervisor.status(server_name)
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_crashed_server_is_restarted_and_its_tools_swapped() {
+    let registry = McpConnectionRegistry::new();
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.add_source(Box::new(McpToolSource::new(registry.clone())));
+    let (status_tx, mut status_rx) = mpsc::channel(100);
+    let mut supervisor = McpSupervisor::new(registry.clone())
+        .with_restart_policy(fast_restart_policy(None))
+        .with_status_sender(status_tx);
+
+    let tool_count = supervisor
+        .supervise(fake_server_config("fake"))
+        .await
+        .unwrap();
+    assert_eq!(tool_count, 4);
+    assert_eq!(supervisor.status("fake"), Some(McpServerStatus::Running(4)));
+    assert_eq!(tool_registry.definitions().len(), 4);
+
+    let first_pid = tool_registry
+        .get_tool("fake__pid")
+        .unwrap()
+        .execute(&json!({}))
+        .await
+        .unwrap();
+
+    // Crash the server
+    std::process::Command::new("kill")
+        .args(["-9", &first_pid])
+        .status()
+        .unwrap();
+    wait_for_status(&supervisor, "fake", |status| {
+        matches!(status, McpServerStatus::Restarting(..))
+    })
+    .await;
+    wait_for_status(&supervisor, "fake", |status| {
+        *status == McpServerStatus::Running(4)
+    })
+    .await;
+
+    // The registry hands out tools of the restarted server
+    let second_pid = tool_registry
+        .get_tool("fake__pid")
+        .unwrap()
+        .execute(&json!({}))
+        .await
+        .unwrap();
+    assert_ne!(first_pid, second_pid);
+
+    supervisor.shutdown().await;
+    assert_eq!(supervisor.status("fake"), Some(McpServerStatus::Stopped));
+    assert!(!process_is_alive(&second_pid));
+    assert!(
+        registry
+            .get_connection(&connection_id("fake"))
+            .await
+            .is_none()
+    );
+    assert!(tool_registry.definitions().is_empty());
+
+    let mut statuses = Vec::new();
+    while let Ok(AppEvent::McpServerStatus(server_name, status)) = status_rx.try_recv() {
+        assert_eq!(server_name, "fake");
+        statuses.push(status);
+    }
+    assert_eq!(statuses.first().map(String::as_str), Some("Starting"));
+    assert!(
+        statuses
+            .iter()
+            .any(|status| status.starts_with("Restarting"))
+    );
+    assert_eq!(statuses.last().map(String::as_str), Some("Stopped"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_server_that_keeps_failing_is_given_up() {
+    let mut config = fake_server_config("broken");
+    config.server_type = McpServerType::Command {
+        command: "python3".to_string(),
+        args: Some(vec![
+            "-c".to_string(),
+            "import sys; sys.exit(1)".to_string(),
+        ]),
+        environment: None,
+        working_directory: None,
+    };
+    let mut supervisor = McpSupervisor::new(McpConnectionRegistry::new())
+        .with_restart_policy(fast_restart_policy(Some(2)));
+
+    assert!(supervisor.supervise(config).await.is_err());
+    let status = wait_for_status(&supervisor, "broken", |status| {
+        matches!(status, McpServerStatus::Failed(_))
+    })
+    .await;
+    assert_eq!(
+        status,
+        McpServerStatus::Failed("gave up after 2 restarts".to_string())
+    );
+
+    supervisor.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_remote_servers_are_not_supervised() {
+    let mut config = fake_server_config("remote");
+    config.server_type = McpServerType::Remote {
+        url: "http://localhost:1".to_string(),
+        access_token: None,
+        api_key: None,
+    };
+    let mut supervisor = McpSupervisor::new(McpConnectionRegistry::new());
+
+    let error = supervisor.supervise(config).await.unwrap_err();
+    assert!(error.to_string().contains("no process to supervise"));
+    assert!(supervisor.statuses().is_empty());
+}
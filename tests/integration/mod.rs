@@ -5,10 +5,14 @@ mod test_cli;
 #[cfg(test)]
 mod mcp {
     mod test_config_integration;
+    mod test_resources_prompts;
+    mod test_stdio_connection;
+    mod test_supervisor;
 }
 
 #[cfg(test)]
 mod core {
+    mod test_agentic_loop;
     mod test_mocked_external_deps;
     mod test_orchestrator_agent_interactions;
     mod test_tool_approval_workflow;
@@ -8,3 +8,16 @@ fn test_help_output() {
     let assert = cmd.arg("--help").assert();
     assert.success();
 }
+
+#[test]
+fn test_exporting_a_missing_session_fails() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mut cmd = Command::cargo_bin("OxideAgent").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("--sessions-dir")
+        .arg(temp_dir.path().join("sessions"))
+        .arg("--export-session")
+        .arg("missing")
+        .assert()
+        .failure();
+}
@@ -1,10 +1,16 @@
+use OxideAgent::config::{FileAccessMode, ShellToolConfig};
 use OxideAgent::core::tools::{
     ReadFileTool, RunShellCommandTool, Tool, ToolProfile, ToolRegistry, WriteFileTool,
 };
+use OxideAgent::core::workspace::Workspace;
 use serde_json::json;
 use std::fs;
 use tempfile::TempDir;
 
+fn workspace(temp_dir: &TempDir) -> Workspace {
+    Workspace::new(temp_dir.path(), FileAccessMode::ReadWrite)
+}
+
 #[tokio::test]
 async fn test_write_file_tool_execute() {
     // Create a temporary directory for testing
@@ -12,7 +18,7 @@ async fn test_write_file_tool_execute() {
     let file_path = temp_dir.path().join("test.txt");
     let file_path_str = file_path.to_str().unwrap();
 
-    let tool = WriteFileTool;
+    let tool = WriteFileTool::new(workspace(&temp_dir));
     let args = json!({
         "path": file_path_str,
         "content": "Hello, World!"
@@ -29,7 +35,7 @@ async fn test_write_file_tool_execute() {
 
 #[tokio::test]
 async fn test_write_file_tool_execute_missing_path() {
-    let tool = WriteFileTool;
+    let tool = WriteFileTool::default();
     let args = json!({
         "content": "Hello, World!"
     });
@@ -47,7 +53,8 @@ async fn test_write_file_tool_execute_missing_path() {
 
 #[tokio::test]
 async fn test_write_file_tool_clone_box() {
-    let tool = WriteFileTool;
+    let temp_dir = TempDir::new().unwrap();
+    let tool = WriteFileTool::new(workspace(&temp_dir));
     let cloned_tool = tool.clone_box();
 
     // Check that the cloned tool has the same properties
@@ -57,7 +64,6 @@ async fn test_write_file_tool_clone_box() {
     assert_eq!(tool.profile(), cloned_tool.profile());
 
     // Test that the cloned tool works functionally
-    let temp_dir = TempDir::new().unwrap();
     let file_path = temp_dir.path().join("clone_test.txt");
     let file_path_str = file_path.to_str().unwrap();
 
@@ -83,7 +89,7 @@ async fn test_read_file_tool_execute() {
     // Write some content to the test file
     fs::write(&file_path, "Test content").unwrap();
 
-    let tool = ReadFileTool;
+    let tool = ReadFileTool::new(workspace(&temp_dir));
     let args = json!({
         "path": file_path_str
     });
@@ -96,7 +102,7 @@ async fn test_read_file_tool_execute() {
 
 #[tokio::test]
 async fn test_read_file_tool_execute_missing_path() {
-    let tool = ReadFileTool;
+    let tool = ReadFileTool::default();
     let args = json!({});
 
     // Execute the tool without path argument
@@ -112,7 +118,7 @@ async fn test_read_file_tool_execute_missing_path() {
 
 #[tokio::test]
 async fn test_read_file_tool_execute_nonexistent_file() {
-    let tool = ReadFileTool;
+    let tool = ReadFileTool::default();
     let args = json!({
         "path": "/nonexistent/file.txt"
     });
@@ -124,7 +130,8 @@ async fn test_read_file_tool_execute_nonexistent_file() {
 
 #[tokio::test]
 async fn test_read_file_tool_clone_box() {
-    let tool = ReadFileTool;
+    let temp_dir = TempDir::new().unwrap();
+    let tool = ReadFileTool::new(workspace(&temp_dir));
     let cloned_tool = tool.clone_box();
 
     // Check that the cloned tool has the same properties
@@ -134,7 +141,6 @@ async fn test_read_file_tool_clone_box() {
     assert_eq!(tool.profile(), cloned_tool.profile());
 
     // Test that the cloned tool works functionally
-    let temp_dir = TempDir::new().unwrap();
     let file_path = temp_dir.path().join("cloned_read_test.txt");
     let file_path_str = file_path.to_str().unwrap();
 
@@ -152,7 +158,7 @@ async fn test_read_file_tool_clone_box() {
 
 #[tokio::test]
 async fn test_run_shell_command_tool_execute() {
-    let tool = RunShellCommandTool;
+    let tool = RunShellCommandTool::default();
     let args = json!({
         "command": "echo 'Hello, World!'"
     });
@@ -167,7 +173,7 @@ async fn test_run_shell_command_tool_execute() {
 
 #[tokio::test]
 async fn test_run_shell_command_tool_execute_missing_command() {
-    let tool = RunShellCommandTool;
+    let tool = RunShellCommandTool::default();
     let args = json!({});
 
     // Execute the tool without command argument
@@ -183,7 +189,7 @@ async fn test_run_shell_command_tool_execute_missing_command() {
 
 #[tokio::test]
 async fn test_run_shell_command_tool_execute_failed_command() {
-    let tool = RunShellCommandTool;
+    let tool = RunShellCommandTool::default();
     let args = json!({
         "command": "nonexistent_command_xyz"
     });
@@ -195,7 +201,7 @@ async fn test_run_shell_command_tool_execute_failed_command() {
 
 #[tokio::test]
 async fn test_run_shell_command_tool_clone_box() {
-    let tool = RunShellCommandTool;
+    let tool = RunShellCommandTool::default();
     let cloned_tool = tool.clone_box();
 
     // Check that the cloned tool has the same properties
@@ -215,13 +221,94 @@ async fn test_run_shell_command_tool_clone_box() {
     assert!(output.contains("Cloned command test"));
 }
 
+#[tokio::test]
+async fn test_run_shell_command_tool_reports_exit_code_and_stderr() {
+    let tool = RunShellCommandTool::default();
+    let args = json!({
+        "command": "echo out; echo err >&2; exit 3"
+    });
+
+    let error = tool.execute(&args).await.unwrap_err().to_string();
+    assert_eq!(error, "Command failed.\nout\n[stderr]\nerr\n[exit code: 3]");
+}
+
+#[tokio::test]
+async fn test_run_shell_command_tool_kills_on_timeout() {
+    let tool = RunShellCommandTool::new(ShellToolConfig {
+        timeout_secs: 1,
+        ..Default::default()
+    });
+    let args = json!({
+        "command": "echo started; sleep 30"
+    });
+
+    let started = std::time::Instant::now();
+    let error = tool.execute(&args).await.unwrap_err().to_string();
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    assert!(error.starts_with("Command timed out after 1 seconds and was killed."));
+    assert!(error.contains("started\n[killed]"));
+}
+
+#[tokio::test]
+async fn test_run_shell_command_tool_uses_working_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("marker.txt"), "").unwrap();
+    let tool = RunShellCommandTool::default().with_working_dir(temp_dir.path());
+
+    let output = tool.execute(&json!({ "command": "ls" })).await.unwrap();
+    assert_eq!(output, "marker.txt\n[exit code: 0]");
+}
+
+#[tokio::test]
+async fn test_run_shell_command_tool_scrubs_environment() {
+    // Cargo sets CARGO_MANIFEST_DIR for test processes; it must not leak through
+    assert!(std::env::var_os("CARGO_MANIFEST_DIR").is_some());
+    let tool = RunShellCommandTool::new(ShellToolConfig {
+        env_allowlist: vec!["PATH".to_string()],
+        ..Default::default()
+    });
+
+    let output = tool.execute(&json!({ "command": "env" })).await.unwrap();
+    assert!(output.contains("PATH="));
+    assert!(!output.contains("CARGO_MANIFEST_DIR"));
+}
+
+#[tokio::test]
+async fn test_run_shell_command_tool_truncates_output() {
+    let tool = RunShellCommandTool::new(ShellToolConfig {
+        max_output_bytes: 10,
+        ..Default::default()
+    });
+    let args = json!({
+        "command": "printf '%0100d' 0"
+    });
+
+    let output = tool.execute(&args).await.unwrap();
+    assert_eq!(
+        output,
+        "0000000000\n[... output truncated, 90 more bytes]\n[exit code: 0]"
+    );
+}
+
+#[tokio::test]
+async fn test_run_shell_command_tool_keeps_output_when_pipe_is_held_open() {
+    let tool = RunShellCommandTool::new(ShellToolConfig::default());
+    let args = json!({
+        "command": "echo before; sleep 3 &"
+    });
+
+    let output = tool.execute(&args).await.unwrap();
+    assert!(output.starts_with("before\n[output truncated: pipe held open]"));
+    assert!(output.ends_with("[exit code: 0]"));
+}
+
 #[test]
 fn test_tool_registry() {
     let mut registry = ToolRegistry::new();
 
     // Add tools to the registry
-    registry.add_tool(Box::new(WriteFileTool));
-    registry.add_tool(Box::new(ReadFileTool));
+    registry.add_tool(Box::new(WriteFileTool::default()));
+    registry.add_tool(Box::new(ReadFileTool::default()));
 
     // Test getting a tool by name
     let write_tool = registry.get_tool("write_file");
@@ -250,8 +337,8 @@ fn test_tool_registry_clone() {
     let mut registry = ToolRegistry::new();
 
     // Add tools to the registry
-    registry.add_tool(Box::new(WriteFileTool));
-    registry.add_tool(Box::new(ReadFileTool));
+    registry.add_tool(Box::new(WriteFileTool::default()));
+    registry.add_tool(Box::new(ReadFileTool::default()));
 
     // Clone the registry
     let cloned_registry = registry.clone_registry();
@@ -47,12 +47,13 @@ mod tests {
 
         // Test that complex variants can be created
         let tool_calls = vec![ToolCall {
+            id: None,
             function: ToolFunction {
                 name: "test_tool".to_string(),
                 arguments: Value::Null,
             },
         }];
-        let _tool_request = AppEvent::ToolRequest(tool_calls);
+        let _tool_request = AppEvent::ToolRequest(tool_calls, vec![None]);
 
         // Test some other variants
         let _error = AppEvent::Error("test error".to_string());
@@ -95,6 +96,7 @@ mod tests {
 
         // Test ChatMessage::tool_call method
         let tool_calls = vec![ToolCall {
+            id: None,
             function: ToolFunction {
                 name: "test_tool".to_string(),
                 arguments: Value::Null,
@@ -105,6 +107,15 @@ mod tests {
         assert_eq!(tool_call_msg.content, "Tool call content");
         assert!(tool_call_msg.tool_calls.is_some());
 
+        // Test ChatMessage::tool method
+        let answered_call = ToolCall::new(Some("call_1".to_string()), "test_tool", Value::Null);
+        let tool_msg = ChatMessage::tool(&answered_call, "Tool output");
+        assert_eq!(tool_msg.role, "tool");
+        assert_eq!(tool_msg.content, "Tool output");
+        assert_eq!(tool_msg.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(tool_msg.tool_name.as_deref(), Some("test_tool"));
+        assert!(tool_msg.tool_calls.is_none());
+
         // Test clone functionality
         let original = ChatMessage::user("clone test");
         let cloned = original.clone();
@@ -121,6 +132,7 @@ mod tests {
         };
 
         let tool_call = ToolCall {
+            id: None,
             function: tool_function.clone(),
         };
 
@@ -182,6 +194,7 @@ mod tests {
 
         // Test ToolCall serialization/deserialization
         let original_tool_call = ToolCall {
+            id: None,
             function: ToolFunction {
                 name: "serialize_test".to_string(),
                 arguments: serde_json::json!({"arg1": "value1"}),
@@ -193,5 +206,18 @@ mod tests {
             original_tool_call.function.name,
             deserialized_tc.function.name
         );
+
+        // Messages saved before tool roles existed still deserialize
+        let legacy: ChatMessage = serde_json::from_str(
+            r#"{"role":"assistant","content":"","tool_calls":[{"function":{"name":"t","arguments":{}}}]}"#,
+        )
+        .unwrap();
+        assert!(legacy.tool_call_id.is_none());
+        assert!(legacy.tool_name.is_none());
+        assert!(legacy.tool_calls.unwrap()[0].id.is_none());
+
+        // Unset tool fields are omitted from the serialized form
+        assert!(!serialized.contains("tool_call_id"));
+        assert!(!serialized.contains("tool_name"));
     }
 }
@@ -1 +1,3 @@
+pub mod test_anthropic;
 pub mod test_ollama;
+pub mod test_openai;
@@ -0,0 +1,335 @@
+use OxideAgent::core::llm::anthropic::{AnthropicClient, list_models};
+use OxideAgent::core::llm::client::LlmClient;
+use OxideAgent::types::{AppEvent, ChatMessage, Tool, ToolCall};
+use httpmock::prelude::*;
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_list_models_success() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/v1/models")
+            .header("x-api-key", "secret")
+            .header("anthropic-version", "2023-06-01");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "data": [
+                    {"id": "claude-a", "type": "model"},
+                    {"id": "claude-b", "type": "model"}
+                ],
+                "has_more": false
+            }));
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let result = list_models(&client, &server.base_url(), Some("secret")).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap(), vec!["claude-a", "claude-b"]);
+}
+
+#[tokio::test]
+async fn test_send_chat_sends_system_prompt_separately() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/messages")
+            .header("x-api-key", "secret")
+            .json_body_includes(r#"{"system": "Be brief."}"#)
+            .json_body_includes(
+                r#"{"messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]}"#,
+            );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hello, world!"}],
+                "stop_reason": "end_turn"
+            }));
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), Some("secret".to_string()));
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![ChatMessage::system("Be brief."), ChatMessage::user("Hello")];
+    let result = client.chat("claude-a", &history, &[], false, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "Hello, world!");
+    assert!(response.tool_calls.is_none());
+}
+
+#[tokio::test]
+async fn test_send_chat_non_streaming_with_tools() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/messages")
+            .json_body_includes(r#"{"tools": [{"name": "test_tool", "description": "A test tool", "input_schema": {"type": "object", "properties": {}}}]}"#);
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "id": "msg_2",
+                "type": "message",
+                "role": "assistant",
+                "content": [
+                    {"type": "text", "text": "Let me check."},
+                    {"type": "tool_use", "id": "toolu_abc", "name": "test_tool", "input": {"arg1": "value1"}}
+                ],
+                "stop_reason": "tool_use"
+            }));
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![ChatMessage::user("Use the test tool")];
+    let tools = vec![Tool::new("test_tool", "A test tool", json!({}))];
+    let result = client.chat("claude-a", &history, &tools, false, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "Let me check.");
+    let tool_calls = response.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("toolu_abc"));
+    assert_eq!(tool_calls[0].function.name, "test_tool");
+    assert_eq!(tool_calls[0].function.arguments, json!({"arg1": "value1"}));
+}
+
+#[tokio::test]
+async fn test_send_chat_maps_tool_outputs_to_tool_results() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages").json_body_includes(
+            r#"{"messages": [
+                {"role": "user", "content": [{"type": "text", "text": "Read a.txt"}]},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_read", "name": "read_file", "input": {"path": "a.txt"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_read", "content": "file contents"}
+                ]}
+            ]}"#,
+        );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "content": [{"type": "text", "text": "The file says hi."}],
+                "stop_reason": "end_turn"
+            }));
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let tool_call = ToolCall::new(
+        Some("toolu_read".to_string()),
+        "read_file",
+        json!({"path": "a.txt"}),
+    );
+    let history = vec![
+        ChatMessage::user("Read a.txt"),
+        ChatMessage::tool_call("", vec![tool_call.clone()]),
+        ChatMessage::tool(&tool_call, "file contents"),
+    ];
+    let result = client.chat("claude-a", &history, &[], false, tx).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap().unwrap().content, "The file says hi.");
+}
+
+#[tokio::test]
+async fn test_send_chat_closes_unanswered_tool_uses() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages").json_body_includes(
+            r#"{"messages": [
+                {"role": "user", "content": [{"type": "text", "text": "List files"}]},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_1_0", "name": "list_files", "input": {}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1_0", "content": "No result was recorded for this tool call.", "is_error": true},
+                    {"type": "text", "text": "Never mind"}
+                ]}
+            ]}"#,
+        );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({ "content": [{"type": "text", "text": "Okay."}] }));
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![
+        ChatMessage::user("List files"),
+        ChatMessage::tool_call("", vec![ToolCall::new(None, "list_files", json!({}))]),
+        ChatMessage::user("Never mind"),
+    ];
+    let result = client.chat("claude-a", &history, &[], false, tx).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap().unwrap().content, "Okay.");
+}
+
+#[tokio::test]
+async fn test_send_chat_skips_blank_turns_and_unmatched_tool_results() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages").json_body_includes(
+            r#"{"messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "Read a.txt"},
+                    {"type": "text", "text": "Result of read_file: file contents"},
+                    {"type": "text", "text": "Go on"}
+                ]}
+            ]}"#,
+        );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({ "content": [{"type": "text", "text": "Okay."}] }));
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let orphan = ToolCall::new(None, "read_file", json!({"path": "a.txt"}));
+    let history = vec![
+        ChatMessage::user("Read a.txt"),
+        ChatMessage::tool(&orphan, "file contents"),
+        ChatMessage::assistant(" "),
+        ChatMessage::user("Go on"),
+    ];
+    let result = client.chat("claude-a", &history, &[], false, tx).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap().unwrap().content, "Okay.");
+}
+
+#[tokio::test]
+async fn test_send_chat_streaming_success() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"role\":\"assistant\",\"content\":[]}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: ping\n",
+            "data: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\", world!\"}}\n\n",
+            "event: content_block_stop\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(body);
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, mut rx) = mpsc::channel(10);
+    let history = vec![ChatMessage::user("Hello")];
+
+    let handle = tokio::spawn(async move {
+        let mut received_content = String::new();
+        let mut stream_ended = false;
+        while let Some(event) = rx.recv().await {
+            match event {
+                AppEvent::AgentStreamChunk(chunk) => received_content.push_str(&chunk),
+                AppEvent::AgentStreamEnd => {
+                    stream_ended = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        (received_content, stream_ended)
+    });
+
+    let result = client.chat("claude-a", &history, &[], true, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "Hello, world!");
+    assert!(response.tool_calls.is_none());
+
+    let (final_content, final_stream_ended) = handle.await.unwrap();
+    assert_eq!(final_content, "Hello, world!");
+    assert!(final_stream_ended);
+}
+
+#[tokio::test]
+async fn test_send_chat_streaming_tool_use() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        let body = concat!(
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Reading.\"}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"read_file\",\"input\":{}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"pa\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"th\\\": \\\"a.txt\\\"}\"}}\n\n",
+            "event: content_block_stop\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(body);
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, mut rx) = mpsc::channel(10);
+    let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    let history = vec![ChatMessage::user("Read a.txt")];
+
+    let result = client.chat("claude-a", &history, &[], true, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "Reading.");
+    let tool_calls = response.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("toolu_1"));
+    assert_eq!(tool_calls[0].function.name, "read_file");
+    assert_eq!(tool_calls[0].function.arguments, json!({"path": "a.txt"}));
+    drain.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_send_chat_error_status() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        then.status(400)
+            .body(r#"{"type":"error","error":{"type":"invalid_request_error"}}"#);
+    });
+
+    let client = AnthropicClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![ChatMessage::user("Hello")];
+    let result = client.chat("claude-a", &history, &[], false, tx).await;
+
+    mock.assert();
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("400"));
+    assert!(error.contains("invalid_request_error"));
+}
@@ -1,6 +1,8 @@
+use OxideAgent::config::ContextConfig;
+use OxideAgent::core::agents::context::ContextManager;
 use OxideAgent::core::llm::client::LlmClient;
-use OxideAgent::core::llm::ollama::{OllamaClient, list_models};
-use OxideAgent::types::{AppEvent, ChatMessage, Tool, ToolFunctionDefinition};
+use OxideAgent::core::llm::ollama::{OllamaClient, context_length, list_models};
+use OxideAgent::types::{AppEvent, ChatMessage, Tool, ToolCall, ToolFunctionDefinition};
 use httpmock::prelude::*;
 use reqwest::Client;
 use serde_json::json;
@@ -191,3 +193,170 @@ async fn test_send_chat_streaming_success() {
     assert_eq!(final_content, "Hello, world!");
     assert!(final_stream_ended);
 }
+
+#[tokio::test]
+async fn test_send_chat_serializes_tool_messages() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/api/chat").json_body_includes(
+            r#"{"messages": [
+                {"role": "user", "content": "Read a.txt"},
+                {"role": "assistant", "content": "", "tool_calls": [
+                    {"id": "call_read", "function": {"name": "read_file", "arguments": {"path": "a.txt"}}}
+                ]},
+                {"role": "tool", "content": "file contents", "tool_call_id": "call_read", "tool_name": "read_file"}
+            ]}"#,
+        );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "message": {
+                    "content": "The file says hi.",
+                    "tool_calls": []
+                }
+            }));
+    });
+
+    let client = OllamaClient::new(&server.base_url());
+    let (tx, _) = mpsc::channel(1);
+    let tool_call = ToolCall::new(
+        Some("call_read".to_string()),
+        "read_file",
+        json!({"path": "a.txt"}),
+    );
+    let history = vec![
+        ChatMessage::user("Read a.txt"),
+        ChatMessage::tool_call("", vec![tool_call.clone()]),
+        ChatMessage::tool(&tool_call, "file contents"),
+    ];
+    let result = client.chat("model1", &history, &[], false, tx).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap().unwrap().content, "The file says hi.");
+}
+
+#[tokio::test]
+async fn test_context_length_prefers_num_ctx() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/show")
+            .json_body(json!({"model": "qwen3:4b"}));
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "parameters": "num_ctx                        4096\nstop                           \"<|im_end|>\"",
+                "model_info": {
+                    "general.architecture": "qwen3",
+                    "qwen3.context_length": 40960
+                }
+            }));
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let result = context_length(&client, &server.base_url(), "qwen3:4b", 8192).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap(), Some(4096));
+}
+
+#[tokio::test]
+async fn test_context_length_from_model_info() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/api/show");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "model_info": {"llama.context_length": 131072}
+            }));
+    });
+
+    let client = OllamaClient::new(&server.base_url());
+    assert_eq!(client.context_length("llama3.2", 8192).await, Some(8192));
+    assert_eq!(
+        client.context_length("llama3.2", 200_000).await,
+        Some(131072)
+    );
+}
+
+#[tokio::test]
+async fn test_context_length_unknown_model() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/api/show");
+        then.status(404).body("model not found");
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    assert!(
+        context_length(&client, &server.base_url(), "missing", 8192)
+            .await
+            .is_err()
+    );
+    let client = OllamaClient::new(&server.base_url());
+    assert_eq!(client.context_length("missing", 8192).await, None);
+}
+
+#[tokio::test]
+async fn test_send_chat_requests_the_budgeted_context_length() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/api/show");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "model_info": {"qwen3.context_length": 40960}
+            }));
+    });
+    // Without a num_ctx of its own the model gets the default window, not its trained length
+    let trained = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/chat")
+            .json_body_includes(r#"{"options": {"num_ctx": 40960}}"#);
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({"message": {"content": "Too much"}}));
+    });
+    let chat = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/chat")
+            .json_body_includes(r#"{"options": {"num_ctx": 8192}}"#);
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({"message": {"content": "Hi"}}));
+    });
+
+    let client = OllamaClient::new(&server.base_url());
+    let history = vec![ChatMessage::user("Hello")];
+    let mut manager = ContextManager::default();
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+    let (tx, _) = mpsc::channel(1);
+    let result = client.chat("qwen3:4b", &view, &[], false, tx).await;
+
+    // The budget is taken from the window requested
+    assert_eq!(manager.context_length("qwen3:4b", &client).await, 8192);
+    chat.assert();
+    trained.assert_calls(0);
+    assert_eq!(result.unwrap().unwrap().content, "Hi");
+
+    // A configured context length is what gets requested
+    let configured = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/chat")
+            .json_body_includes(r#"{"options": {"num_ctx": 16384}}"#);
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({"message": {"content": "Hi again"}}));
+    });
+    let mut manager = ContextManager::new(ContextConfig {
+        context_length: Some(16_384),
+        ..ContextConfig::default()
+    });
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+    let (tx, _) = mpsc::channel(1);
+    let result = client.chat("qwen3:4b", &view, &[], false, tx).await;
+
+    configured.assert();
+    assert_eq!(result.unwrap().unwrap().content, "Hi again");
+}
@@ -0,0 +1,340 @@
+use OxideAgent::config::LLMConfig;
+use OxideAgent::core::llm::client::LlmClient;
+use OxideAgent::core::llm::llm_client_factory;
+use OxideAgent::core::llm::openai::{OpenAiClient, list_models};
+use OxideAgent::types::{AppEvent, ChatMessage, Tool, ToolCall};
+use httpmock::prelude::*;
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_list_models_success() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/v1/models")
+            .header("authorization", "Bearer secret");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "object": "list",
+                "data": [
+                    {"id": "model1", "object": "model"},
+                    {"id": "model2", "object": "model"}
+                ]
+            }));
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let result = list_models(&client, &server.base_url(), Some("secret")).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap(), vec!["model1", "model2"]);
+}
+
+#[tokio::test]
+async fn test_list_models_accepts_v1_base() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/v1/models");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({ "data": [] }));
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let base_url = format!("{}/v1/", server.base_url());
+    let result = list_models(&client, &base_url, None).await;
+
+    mock.assert();
+    assert!(result.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_send_chat_non_streaming_success() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .header("authorization", "Bearer secret");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hello, world!"},
+                    "finish_reason": "stop"
+                }]
+            }));
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), Some("secret".to_string()));
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![ChatMessage::user("Hello")];
+    let result = client.chat("model1", &history, &[], false, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "Hello, world!");
+    assert!(response.tool_calls.is_none());
+}
+
+#[tokio::test]
+async fn test_send_chat_non_streaming_with_tools() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .json_body_includes(r#"{"tools": [{"type": "function", "function": {"name": "test_tool", "description": "A test tool", "parameters": {}}}]}"#);
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_abc",
+                            "type": "function",
+                            "function": {
+                                "name": "test_tool",
+                                "arguments": "{\"arg1\": \"value1\"}"
+                            }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            }));
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![ChatMessage::user("Use the test tool")];
+    let tools = vec![Tool::new("test_tool", "A test tool", json!({}))];
+    let result = client.chat("model1", &history, &tools, false, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "");
+    let tool_calls = response.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("call_abc"));
+    assert_eq!(tool_calls[0].function.name, "test_tool");
+    assert_eq!(tool_calls[0].function.arguments, json!({"arg1": "value1"}));
+}
+
+#[tokio::test]
+async fn test_send_chat_serializes_tool_messages() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions").json_body_includes(
+            r#"{"messages": [
+                {"role": "user", "content": "Read a.txt"},
+                {"role": "assistant", "content": "", "tool_calls": [
+                    {"id": "call_read", "type": "function", "function": {"name": "read_file", "arguments": "{\"path\":\"a.txt\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_read", "content": "file contents"}
+            ]}"#,
+        );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "choices": [{"message": {"role": "assistant", "content": "The file says hi."}}]
+            }));
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let tool_call = ToolCall::new(
+        Some("call_read".to_string()),
+        "read_file",
+        json!({"path": "a.txt"}),
+    );
+    let history = vec![
+        ChatMessage::user("Read a.txt"),
+        ChatMessage::tool_call("", vec![tool_call.clone()]),
+        ChatMessage::tool(&tool_call, "file contents"),
+    ];
+    let result = client.chat("model1", &history, &[], false, tx).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap().unwrap().content, "The file says hi.");
+}
+
+#[tokio::test]
+async fn test_send_chat_sends_unmatched_tool_results_as_text() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions").json_body_includes(
+            r#"{"messages": [
+                {"role": "user", "content": "Read a.txt"},
+                {"role": "assistant", "content": "", "tool_calls": [
+                    {"id": "call_read", "type": "function", "function": {"name": "read_file", "arguments": "{\"path\":\"a.txt\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_read", "content": "file contents"},
+                {"role": "user", "content": "Result of read_file: stale contents"},
+                {"role": "user", "content": "Result of read_file: other contents"},
+                {"role": "user", "content": "Go on"}
+            ]}"#,
+        );
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "choices": [{"message": {"role": "assistant", "content": "Okay."}}]
+            }));
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), None);
+    let (tx, _) = mpsc::channel(1);
+    let tool_call = ToolCall::new(
+        Some("call_read".to_string()),
+        "read_file",
+        json!({"path": "a.txt"}),
+    );
+    let stale = ToolCall::new(
+        Some("call_stale".to_string()),
+        "read_file",
+        json!({"path": "a.txt"}),
+    );
+    let orphan = ToolCall::new(None, "read_file", json!({"path": "b.txt"}));
+    let history = vec![
+        ChatMessage::user("Read a.txt"),
+        ChatMessage::tool_call("", vec![tool_call.clone()]),
+        // An id of no pending call waits until the pending call is answered
+        ChatMessage::tool(&stale, "stale contents"),
+        ChatMessage::tool(&tool_call, "file contents"),
+        // Nothing is pending for a result without an id
+        ChatMessage::tool(&orphan, "other contents"),
+        ChatMessage::user("Go on"),
+    ];
+    let result = client.chat("model1", &history, &[], false, tx).await;
+
+    mock.assert();
+    assert_eq!(result.unwrap().unwrap().content, "Okay.");
+}
+
+#[tokio::test]
+async fn test_send_chat_streaming_success() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        let body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\", world!\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(body);
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), None);
+    let (tx, mut rx) = mpsc::channel(10);
+    let history = vec![ChatMessage::user("Hello")];
+
+    let handle = tokio::spawn(async move {
+        let mut received_content = String::new();
+        let mut stream_ended = false;
+        while let Some(event) = rx.recv().await {
+            match event {
+                AppEvent::AgentStreamChunk(chunk) => received_content.push_str(&chunk),
+                AppEvent::AgentStreamEnd => {
+                    stream_ended = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        (received_content, stream_ended)
+    });
+
+    let result = client.chat("model1", &history, &[], true, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    assert_eq!(response.content, "Hello, world!");
+    assert!(response.tool_calls.is_none());
+
+    let (final_content, final_stream_ended) = handle.await.unwrap();
+    assert_eq!(final_content, "Hello, world!");
+    assert!(final_stream_ended);
+}
+
+#[tokio::test]
+async fn test_send_chat_streaming_assembles_tool_calls() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        let body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"read_file\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"path\\\":\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":1,\"id\":\"call_2\",\"type\":\"function\",\"function\":{\"name\":\"run_shell_command\",\"arguments\":\"{\\\"command\\\":\\\"ls\\\"}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\" \\\"a.txt\\\"}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(body);
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), None);
+    let (tx, mut rx) = mpsc::channel(10);
+    let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    let history = vec![ChatMessage::user("Read a.txt and list files")];
+
+    let result = client.chat("model1", &history, &[], true, tx).await;
+
+    mock.assert();
+    let response = result.unwrap().unwrap();
+    let tool_calls = response.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 2);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("call_1"));
+    assert_eq!(tool_calls[1].id.as_deref(), Some("call_2"));
+    assert_eq!(tool_calls[0].function.name, "read_file");
+    assert_eq!(tool_calls[0].function.arguments, json!({"path": "a.txt"}));
+    assert_eq!(tool_calls[1].function.name, "run_shell_command");
+    assert_eq!(tool_calls[1].function.arguments, json!({"command": "ls"}));
+    drain.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_send_chat_error_status() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(401).body("invalid api key");
+    });
+
+    let client = OpenAiClient::new(&server.base_url(), Some("wrong".to_string()));
+    let (tx, _) = mpsc::channel(1);
+    let history = vec![ChatMessage::user("Hello")];
+    let result = client.chat("model1", &history, &[], false, tx).await;
+
+    mock.assert();
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("401"));
+    assert!(error.contains("invalid api key"));
+}
+
+#[test]
+fn test_llm_client_factory_providers() {
+    let mut config = LLMConfig {
+        provider: "openai".to_string(),
+        api_base: Some("http://localhost:8000".to_string()),
+        api_key: None,
+        model: None,
+    };
+    assert!(llm_client_factory(&config).is_ok());
+
+    config.provider = "ollama".to_string();
+    assert!(llm_client_factory(&config).is_ok());
+
+    config.provider = "unknown".to_string();
+    assert!(llm_client_factory(&config).is_err());
+}
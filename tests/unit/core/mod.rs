@@ -1,10 +1,16 @@
 pub mod llm;
 pub mod test_agents;
+pub mod test_checkpoints;
 pub mod test_container;
+pub mod test_context;
 pub mod test_events;
+pub mod test_file_tools;
 pub mod test_mocks;
 pub mod test_orchestrator;
 pub mod test_session;
 pub mod test_session_tool_permissions;
 pub mod test_tool_permissions;
 pub mod test_tools;
+pub mod test_transcript;
+pub mod test_undo;
+pub mod test_workspace;
@@ -2,7 +2,8 @@
 
 use OxideAgent::core::agents::{Agent, AgentId};
 use OxideAgent::core::mocks::MockOllamaClient;
-use OxideAgent::types::ChatMessage;
+use OxideAgent::types::{ChatMessage, ToolCall};
+use serde_json::json;
 
 #[test]
 fn test_agent_new() {
@@ -39,6 +40,22 @@ fn test_agent_add_assistant_message() {
     assert_eq!(agent.history[1].content, "Hello, user!");
 }
 
+#[test]
+fn test_agent_add_tool_message() {
+    let client = Box::new(MockOllamaClient::new());
+    let mut agent = Agent::new("You are a helpful assistant.", client);
+
+    let tool_call = ToolCall::new(Some("call_1".to_string()), "read_file", json!({}));
+    agent.add_assistant_message(ChatMessage::tool_call("", vec![tool_call.clone()]));
+    agent.add_tool_message(&tool_call, "file contents");
+
+    assert_eq!(agent.history.len(), 3);
+    assert_eq!(agent.history[2].role, "tool");
+    assert_eq!(agent.history[2].content, "file contents");
+    assert_eq!(agent.history[2].tool_call_id.as_deref(), Some("call_1"));
+    assert_eq!(agent.history[2].tool_name.as_deref(), Some("read_file"));
+}
+
 #[test]
 fn test_agent_update_system_prompt() {
     let client = Box::new(MockOllamaClient::new());
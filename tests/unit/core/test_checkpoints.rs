@@ -0,0 +1,170 @@
+//! Tests for workspace checkpoints taken before file and shell tools run.
+
+use OxideAgent::config::CheckpointMode;
+use OxideAgent::core::checkpoints::{CheckpointState, CheckpointStore};
+use OxideAgent::core::tools::ToolProfile;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+fn canonical_root(temp_dir: &TempDir) -> PathBuf {
+    temp_dir.path().canonicalize().unwrap()
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[tokio::test]
+async fn test_file_checkpoints_restore_every_call_from_the_same_message() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = canonical_root(&temp_dir);
+    let (a, b, c) = (root.join("a.txt"), root.join("b.txt"), root.join("c.txt"));
+    fs::write(&a, "a1").unwrap();
+    fs::write(&b, "b1").unwrap();
+    let mut store = CheckpointStore::new(CheckpointMode::Files, &root);
+
+    // First turn edits a.txt
+    let first = store
+        .record("write_file", ToolProfile::File, std::slice::from_ref(&a), 2)
+        .await
+        .unwrap()
+        .unwrap();
+    fs::write(&a, "a2").unwrap();
+
+    // Second turn edits b.txt and creates c.txt in one batch
+    let second = store
+        .record("edit_file", ToolProfile::File, std::slice::from_ref(&b), 5)
+        .await
+        .unwrap()
+        .unwrap();
+    fs::write(&b, "b2").unwrap();
+    let third = store
+        .record("write_file", ToolProfile::File, std::slice::from_ref(&c), 5)
+        .await
+        .unwrap()
+        .unwrap();
+    fs::write(&c, "c2").unwrap();
+    assert_eq!((first, second, third), (1, 2, 3));
+
+    let listed = store.list();
+    assert_eq!(listed.len(), 3);
+    assert_eq!(listed[1].tool_name, "edit_file");
+    assert_eq!(listed[1].message_index, 5);
+    assert_eq!(listed[1].description, b.display().to_string());
+
+    // Restoring the last call still rolls back the whole second turn
+    assert_eq!(store.restore(third).await.unwrap(), 5);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a2");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "b1");
+    assert!(!c.exists());
+    assert_eq!(store.list().len(), 1);
+
+    assert_eq!(store.restore(first).await.unwrap(), 2);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a1");
+    assert!(store.is_empty());
+
+    assert!(store.restore(first).await.is_err());
+}
+
+#[tokio::test]
+async fn test_checkpoints_skipped_when_not_needed() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = canonical_root(&temp_dir);
+    let file = root.join("a.txt");
+
+    let mut store = CheckpointStore::new(CheckpointMode::Off, &root);
+    let recorded = store
+        .record(
+            "write_file",
+            ToolProfile::File,
+            std::slice::from_ref(&file),
+            1,
+        )
+        .await
+        .unwrap();
+    assert!(recorded.is_none());
+
+    let mut store = CheckpointStore::new(CheckpointMode::Auto, &root);
+    // Read-only file tools report no paths
+    let recorded = store
+        .record("read_file", ToolProfile::File, &[], 1)
+        .await
+        .unwrap();
+    assert!(recorded.is_none());
+    // Shell commands outside a git repository can't be checkpointed
+    let recorded = store
+        .record("run_shell_command", ToolProfile::Shell, &[], 1)
+        .await
+        .unwrap();
+    assert!(recorded.is_none());
+    let recorded = store
+        .record("fetch", ToolProfile::Web, std::slice::from_ref(&file), 1)
+        .await
+        .unwrap();
+    assert!(recorded.is_none());
+
+    // Outside a git repository Auto falls back to file snapshots
+    store
+        .record("write_file", ToolProfile::File, &[file], 1)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        store.get(1).unwrap().state,
+        CheckpointState::Files(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_git_checkpoint_restores_working_tree_without_touching_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = canonical_root(&temp_dir);
+    git(&root, &["init", "-q"]);
+    fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+    fs::write(root.join("tracked.txt"), "original").unwrap();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/untracked.rs"), "fn main() {}").unwrap();
+    git(&root, &["add", "tracked.txt"]);
+    let status_before = git(&root, &["status", "--porcelain"]);
+
+    let mut store = CheckpointStore::new(CheckpointMode::Auto, &root);
+    let id = store
+        .record("run_shell_command", ToolProfile::Shell, &[], 3)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        store.get(id).unwrap().state,
+        CheckpointState::GitTree { .. }
+    ));
+    assert!(store.list()[0].description.starts_with("working tree "));
+
+    // A shell command rewrites, deletes and creates files
+    fs::write(root.join("tracked.txt"), "changed").unwrap();
+    fs::remove_file(root.join("src/untracked.rs")).unwrap();
+    fs::write(root.join("src/new.rs"), "// new").unwrap();
+    fs::write(root.join("build.log"), "ignored output").unwrap();
+
+    assert_eq!(store.restore(id).await.unwrap(), 3);
+    assert_eq!(
+        fs::read_to_string(root.join("tracked.txt")).unwrap(),
+        "original"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("src/untracked.rs")).unwrap(),
+        "fn main() {}"
+    );
+    assert!(!root.join("src/new.rs").exists());
+    // Ignored files are not part of the checkpoint
+    assert!(root.join("build.log").exists());
+    assert_eq!(git(&root, &["status", "--porcelain"]), status_before);
+}
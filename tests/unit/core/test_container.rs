@@ -25,8 +25,8 @@ async fn test_container_build_tool_registry() {
     let tool_registry = tool_registry.unwrap();
     let definitions = tool_registry.definitions();
 
-    // Should have 3 default tools: write_file, read_file, run_shell_command
-    assert_eq!(definitions.len(), 3);
+    // Should have 7 default tools: the file tools plus run_shell_command
+    assert_eq!(definitions.len(), 7);
 
     let names: Vec<String> = definitions
         .iter()
@@ -34,6 +34,10 @@ async fn test_container_build_tool_registry() {
         .collect();
     assert!(names.contains(&"write_file".to_string()));
     assert!(names.contains(&"read_file".to_string()));
+    assert!(names.contains(&"list_directory".to_string()));
+    assert!(names.contains(&"grep_files".to_string()));
+    assert!(names.contains(&"edit_file".to_string()));
+    assert!(names.contains(&"apply_unified_diff".to_string()));
     assert!(names.contains(&"run_shell_command".to_string()));
 }
 
@@ -93,22 +97,75 @@ fn create_test_config() -> Config {
             model: "qwen3:4b".to_string(), // Updated to match default
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: false,
         session: Some("test_session".to_string()),
         list_sessions: false,
+        sessions_dir: None,
         interface: InterfaceType::Tui,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11434".to_string(),
+            api_base: Some("http://localhost:11434".to_string()),
             api_key: None,
             model: Some("qwen3:4b".to_string()),
         },
         multi_agent: OxideAgent::config::MultiAgentConfig::default(),
+        tools: OxideAgent::config::ToolsConfig::default(),
     }
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_container_launches_servers_from_mcp_config_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mcp_file = temp_dir.path().join("mcp.json");
+    let mcp_servers = serde_json::json!({
+        "mcpServers": {
+            "fake": {
+                "command": "python3",
+                "args": [format!(
+                    "{}/tests/fixtures/fake_mcp_server.py",
+                    env!("CARGO_MANIFEST_DIR")
+                )]
+            }
+        }
+    });
+    std::fs::write(&mcp_file, mcp_servers.to_string()).unwrap();
+
+    let mut config = create_test_config();
+    config.mcp.config_file = Some(mcp_file);
+    let mut container = Container::new(config);
+
+    let names: Vec<String> = container
+        .build_tool_registry()
+        .await
+        .unwrap()
+        .definitions()
+        .iter()
+        .map(|t| t.function.name.clone())
+        .collect();
+    assert_eq!(names.len(), 11);
+    assert!(names.contains(&"fake__echo".to_string()));
+    assert!(names.contains(&"fake__pid".to_string()));
+
+    container.shutdown().await;
+    assert_eq!(
+        container
+            .build_tool_registry()
+            .await
+            .unwrap()
+            .definitions()
+            .len(),
+        7
+    );
+}
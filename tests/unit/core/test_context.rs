@@ -0,0 +1,273 @@
+//! Unit tests for context window management.
+
+use OxideAgent::config::{CompactionStrategy, ContextConfig};
+use OxideAgent::core::agents::Agent;
+use OxideAgent::core::agents::context::{
+    ContextManager, DEFAULT_CONTEXT_LENGTH, known_context_length, message_tokens,
+};
+use OxideAgent::core::llm::client::LlmClient;
+use OxideAgent::types::{AppEvent, ChatMessage, Tool, ToolCall};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Answers every request with `reply` (or fails) and records what it was sent
+#[derive(Debug, Clone)]
+struct RecordingClient {
+    reply: Option<String>,
+    context_length: Option<usize>,
+    requests: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+}
+
+impl RecordingClient {
+    fn new(reply: Option<&str>) -> Self {
+        Self {
+            reply: reply.map(str::to_string),
+            context_length: None,
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn requests(&self) -> Vec<Vec<ChatMessage>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LlmClient for RecordingClient {
+    async fn chat(
+        &self,
+        _model: &str,
+        history: &[ChatMessage],
+        _tools: &[Tool],
+        _stream: bool,
+        _tx: mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<Option<ChatMessage>> {
+        self.requests.lock().unwrap().push(history.to_vec());
+        match &self.reply {
+            Some(reply) => Ok(Some(ChatMessage::assistant(reply))),
+            None => Err(anyhow::anyhow!("model unavailable")),
+        }
+    }
+
+    async fn context_length(&self, _model: &str, _default_window: usize) -> Option<usize> {
+        self.context_length
+    }
+}
+
+fn config(strategy: CompactionStrategy) -> ContextConfig {
+    ContextConfig {
+        strategy,
+        threshold: 1.0,
+        context_length: Some(200),
+        ..ContextConfig::default()
+    }
+}
+
+/// A system prompt and `turns` turns of a question, a `read_file` call with
+/// a 200 character output (about 50 tokens), and an answer
+fn history(prefix: &str, turns: usize) -> Vec<ChatMessage> {
+    let mut history = vec![ChatMessage::system("sys")];
+    for turn in 0..turns {
+        let call = ToolCall::new(
+            Some(format!("call_{}", turn)),
+            "read_file",
+            json!({ "path": format!("f{}.rs", turn) }),
+        );
+        history.push(ChatMessage::user(&format!("{} {}", prefix, turn)));
+        history.push(ChatMessage::tool_call("", vec![call.clone()]));
+        history.push(ChatMessage::tool(&call, &"x".repeat(200)));
+        history.push(ChatMessage::assistant(&format!("Answer {}", turn)));
+    }
+    history
+}
+
+/// Every tool result in `view` follows the call it answers
+fn assert_no_orphaned_tool_results(view: &[ChatMessage]) {
+    for (index, message) in view.iter().enumerate() {
+        if message.role == "tool" {
+            let id = message.tool_call_id.as_deref();
+            assert!(
+                view[..index].iter().any(|earlier| earlier
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .any(|call| call.id.as_deref() == id)),
+                "tool result {:?} has no call",
+                id
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_context_length_lookup() {
+    let mut client = RecordingClient::new(Some("ok"));
+    let mut manager = ContextManager::default();
+    assert_eq!(
+        manager.context_length("qwen3:4b", &client).await,
+        DEFAULT_CONTEXT_LENGTH
+    );
+    assert_eq!(
+        manager.context_length("claude-sonnet-4-5", &client).await,
+        200_000
+    );
+    assert_eq!(known_context_length("gpt-4o-mini"), Some(128_000));
+    assert_eq!(known_context_length("gpt-4"), Some(8_192));
+
+    // Reported lengths win over known ones, and are looked up once per model
+    client.context_length = Some(32_768);
+    assert_eq!(manager.context_length("gpt-4o", &client).await, 32_768);
+    assert_eq!(
+        manager.context_length("qwen3:4b", &client).await,
+        DEFAULT_CONTEXT_LENGTH
+    );
+
+    let mut configured = ContextManager::new(ContextConfig {
+        context_length: Some(1_000),
+        ..ContextConfig::default()
+    });
+    assert_eq!(configured.context_length("gpt-4o", &client).await, 1_000);
+}
+
+#[test]
+fn test_message_tokens() {
+    let call = ToolCall::new(None, "read_file", json!({ "path": "a" }));
+    assert_eq!(message_tokens(&ChatMessage::user("12345678")), 2);
+    assert_eq!(
+        message_tokens(&ChatMessage::tool_call("", vec![call])),
+        (9 + r#"{"path":"a"}"#.len()).div_ceil(4)
+    );
+}
+
+#[tokio::test]
+async fn test_history_within_budget_is_sent_unchanged() {
+    let client = RecordingClient::new(Some("ok"));
+    let mut manager = ContextManager::new(config(CompactionStrategy::Summarize));
+    let history = history("Question", 3);
+
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+
+    assert_eq!(view.len(), history.len());
+    assert!(
+        view.iter()
+            .zip(&history)
+            .all(|(sent, kept)| sent.content == kept.content)
+    );
+    assert!(client.requests().is_empty());
+}
+
+#[tokio::test]
+async fn test_old_tool_outputs_are_left_out_first() {
+    let client = RecordingClient::new(Some("ok"));
+    let mut manager = ContextManager::new(config(CompactionStrategy::Summarize));
+    let history = history("Question", 4);
+
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+
+    assert_eq!(view.len(), history.len());
+    let tool_outputs: Vec<&str> = view
+        .iter()
+        .filter(|message| message.role == "tool")
+        .map(|message| message.content.as_str())
+        .collect();
+    assert!(
+        tool_outputs[..3]
+            .iter()
+            .all(|output| output.contains("left out"))
+    );
+    // The current turn is sent whole
+    assert_eq!(tool_outputs[3], "x".repeat(200));
+    assert_eq!(history[3].content, "x".repeat(200));
+    assert!(client.requests().is_empty());
+}
+
+#[tokio::test]
+async fn test_drop_strategy_drops_oldest_turns() {
+    let client = RecordingClient::new(Some("ok"));
+    let mut manager = ContextManager::new(config(CompactionStrategy::DropToolOutputs));
+    let history = history("Question", 10);
+
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+
+    assert!(view.iter().map(message_tokens).sum::<usize>() <= 200);
+    assert_eq!(view[0].content, "sys");
+    assert_eq!(view[1].role, "user");
+    assert_ne!(view[1].content, "Question 0");
+    assert_eq!(view.last().unwrap().content, "Answer 9");
+    assert_no_orphaned_tool_results(&view);
+    assert!(client.requests().is_empty());
+    assert!(manager.summary().is_none());
+}
+
+#[tokio::test]
+async fn test_summarize_strategy_pins_a_summary() {
+    let client = RecordingClient::new(Some("Read f0.rs to f7.rs."));
+    let mut manager = ContextManager::new(config(CompactionStrategy::Summarize));
+    let mut history = history("Question", 10);
+
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+
+    assert!(view.iter().map(message_tokens).sum::<usize>() <= 200);
+    assert_eq!(view[0].content, "sys");
+    assert_eq!(view[1].role, "system");
+    assert!(view[1].content.contains("Read f0.rs to f7.rs."));
+    assert_eq!(view[2].content, "Question 8");
+    assert_no_orphaned_tool_results(&view);
+
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0][1].content.contains("Question 0"));
+    assert!(!requests[0][1].content.contains("Question 8"));
+
+    // The summary is reused while the history grows
+    history.extend(self::history("Next", 1).into_iter().skip(1));
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+    assert_eq!(view[1].content, manager.summary().unwrap().content);
+    assert_eq!(view.last().unwrap().content, "Answer 0");
+    assert_eq!(client.requests().len(), 1);
+    assert_eq!(history.len(), 45);
+
+    // A different history (e.g. after switching sessions) gets its own summary
+    let other = self::history("Other", 10);
+    let view = manager.prepare("qwen3:4b", &other, &client).await;
+    assert_eq!(view[2].content, "Other 8");
+    let requests = client.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1][1].content.contains("Other 0"));
+    assert!(!requests[1][1].content.contains("Question"));
+}
+
+#[tokio::test]
+async fn test_failed_summary_falls_back_to_dropping() {
+    let client = RecordingClient::new(None);
+    let mut manager = ContextManager::new(config(CompactionStrategy::Summarize));
+    let history = history("Question", 10);
+
+    let view = manager.prepare("qwen3:4b", &history, &client).await;
+
+    assert_eq!(client.requests().len(), 1);
+    assert!(manager.summary().is_none());
+    assert!(view.iter().map(message_tokens).sum::<usize>() <= 200);
+    assert_eq!(view[1].role, "user");
+    assert_eq!(view.last().unwrap().content, "Answer 9");
+}
+
+#[tokio::test]
+async fn test_agent_keeps_full_history_when_compacting() {
+    let client = RecordingClient::new(Some("Done"));
+    let mut agent = Agent::new("sys", Box::new(client.clone()));
+    agent.set_context_config(config(CompactionStrategy::DropToolOutputs));
+    agent.history = history("Question", 10);
+    let (tx, _rx) = mpsc::channel(8);
+
+    agent.chat("qwen3:4b", &[], false, tx).await.unwrap();
+
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].len() < 41);
+    assert_eq!(agent.history.len(), 42);
+    assert_eq!(agent.history[1].content, "Question 0");
+    assert_eq!(agent.history.last().unwrap().content, "Done");
+}
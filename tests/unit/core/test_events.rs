@@ -102,12 +102,13 @@ fn test_event_bus_publish_app_event() {
 
     // Test tool call event
     let tool_calls = vec![ToolCall {
+        id: None,
         function: ToolFunction {
             name: "test_tool".to_string(),
             arguments: json!({}),
         },
     }];
-    let tool_request_event = AppEvent::ToolRequest(tool_calls);
+    let tool_request_event = AppEvent::ToolRequest(tool_calls, vec![None]);
     let result = bus.publish_app_event(tool_request_event, "test_source".to_string());
     assert!(result.is_ok());
 
@@ -207,7 +208,7 @@ fn test_event_type_variants() {
     let _tool_approval_req = EventType::ToolApprovalRequested(vec![]);
     let _agent_msg = EventType::AgentMessage("test".to_string());
     let _agent_chunk = EventType::AgentStreamChunk("chunk".to_string());
-    let _tool_req = EventType::ToolRequest(vec![]);
+    let _tool_req = EventType::ToolRequest(vec![], vec![]);
     let _tool_res = EventType::ToolResult("tool_name".to_string(), "result".to_string());
     let _error = EventType::Error("error".to_string());
     let _switch_session = EventType::SwitchSession("session_name".to_string());
@@ -155,9 +155,25 @@ async fn test_orchestrator_load_state_empty() {
 
 #[tokio::test]
 async fn test_orchestrator_list_sessions() {
-    // Test the list_sessions function directly
-    let result = Orchestrator::list_sessions();
-    assert!(result.is_ok());
+    let sessions_dir = tempfile::TempDir::new().unwrap();
+    let config = create_test_config();
+    let (tx, rx) = mpsc::channel::<AppEvent>(32);
+    let mut orchestrator = Orchestrator::new(
+        &config.agent.system_prompt,
+        ToolRegistry::new(),
+        config.session.clone(),
+        config.no_stream,
+        tx,
+        rx,
+        config.agent.model.clone(),
+        config.llm.clone(),
+    );
+    orchestrator.set_sessions_dir(sessions_dir.path());
+
+    // Sessions are listed from the configured directory
+    assert!(orchestrator.list_sessions().unwrap().is_empty());
+    std::fs::write(sessions_dir.path().join("session_saved.json"), "{}").unwrap();
+    assert_eq!(orchestrator.list_sessions().unwrap(), vec!["saved"]);
 }
 
 fn create_test_config() -> Config {
@@ -167,22 +183,29 @@ fn create_test_config() -> Config {
             model: "qwen3:4b".to_string(), // Updated to match default
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: false,
         session: Some("test_session".to_string()),
         list_sessions: false,
+        sessions_dir: None,
         interface: InterfaceType::Tui,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: String::new(),
+            api_base: None,
             api_key: None,
             model: Some("qwen3:4b".to_string()),
         },
         multi_agent: OxideAgent::config::MultiAgentConfig::default(),
+        tools: OxideAgent::config::ToolsConfig::default(),
     }
 }
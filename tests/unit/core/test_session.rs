@@ -1,8 +1,12 @@
 //! Unit tests for the session module.
 
-use OxideAgent::core::session::{SessionManager, SessionState};
+use OxideAgent::core::session::{
+    SessionManager, SessionParent, SessionState, validate_session_name,
+};
+use OxideAgent::core::transcript::ExportFormat;
 use OxideAgent::types::ChatMessage;
 use std::fs;
+use std::time::{Duration, SystemTime};
 use tempfile::TempDir;
 
 #[test]
@@ -71,6 +75,30 @@ fn test_session_state_tool_permissions_duplicate() {
     assert!(allowed_tools.contains(&"test_tool".to_string()));
 }
 
+#[test]
+fn test_session_state_denied_tools() {
+    let mut session_state = SessionState::new();
+    session_state.add_allowed_tool("test_tool".to_string());
+
+    // Denying a tool removes it from the allowed list
+    session_state.add_denied_tool("test_tool".to_string());
+    session_state.add_denied_tool("test_tool".to_string());
+    assert!(session_state.is_tool_denied("test_tool"));
+    assert!(!session_state.is_tool_allowed("test_tool"));
+    assert_eq!(session_state.list_denied_tools(), vec!["test_tool"]);
+
+    // Allowing it again lifts the denial
+    session_state.add_allowed_tool("test_tool".to_string());
+    assert!(!session_state.is_tool_denied("test_tool"));
+    assert!(!session_state.remove_denied_tool("test_tool"));
+
+    // Denied tools survive a serialization round trip
+    session_state.add_denied_tool("other_tool".to_string());
+    let json = serde_json::to_string(&session_state).unwrap();
+    let restored: SessionState = serde_json::from_str(&json).unwrap();
+    assert!(restored.is_tool_denied("other_tool"));
+}
+
 #[test]
 fn test_session_manager_save_and_load() {
     let temp_dir = TempDir::new().unwrap();
@@ -188,92 +216,36 @@ fn test_session_manager_load_invalid_json() {
     assert_eq!(session_state.list_allowed_tools().len(), 0);
 }
 
-use std::path::PathBuf;
-
-use crate::utils::CWD_MUTEX;
-
-struct CwdGuard {
-    original: PathBuf,
-}
-
-impl CwdGuard {
-    fn new() -> Self {
-        let original = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
-        Self { original }
-    }
-}
-
-impl Drop for CwdGuard {
-    fn drop(&mut self) {
-        let _ = std::env::set_current_dir(&self.original);
-    }
-}
-
 #[test]
 fn test_session_manager_list_sessions_default() {
-    let _lock = CWD_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
-    let _cwd_guard = CwdGuard::new();
     let temp_dir = TempDir::new().unwrap();
-    // let original_cwd = std::env::current_dir().unwrap(); // Handled by guard
+    let sessions = SessionManager::new(temp_dir.path());
 
-    // Change to temp directory for testing
-    std::env::set_current_dir(temp_dir.path()).unwrap();
+    // The unnamed session is saved as session.json
+    sessions
+        .save("default", &SessionState::new(), None)
+        .unwrap();
+    assert!(temp_dir.path().join("session.json").exists());
 
-    // Create a default session file
-    let default_session = "session.json";
-    let session_state = SessionState::new();
-    let save_result = SessionManager::save_state(default_session, &session_state);
-    assert!(
-        save_result.is_ok(),
-        "Failed to save default session: {:?}",
-        save_result.err()
-    );
-
-    // List sessions
-    let sessions_result = SessionManager::list_sessions();
-    assert!(
-        sessions_result.is_ok(),
-        "Failed to list sessions: {:?}",
-        sessions_result.err()
-    );
-
-    let sessions = sessions_result.unwrap();
-
-    // Check if default session is listed
-    assert!(sessions.contains(&"default".to_string()));
+    let listed = sessions.list_sessions().unwrap();
+    assert_eq!(listed, vec!["default".to_string()]);
 }
 
 #[test]
 fn test_session_manager_list_sessions_named() {
-    let _lock = CWD_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
-    let _cwd_guard = CwdGuard::new();
     let temp_dir = TempDir::new().unwrap();
-
-    // Change to temp directory for testing
-    std::env::set_current_dir(temp_dir.path()).unwrap();
-
-    // Create a named session file
-    let named_session = "session_test_named.json";
-    let session_state = SessionState::new();
-    let save_result = SessionManager::save_state(named_session, &session_state);
-    assert!(
-        save_result.is_ok(),
-        "Failed to save named session: {:?}",
-        save_result.err()
-    );
-
-    // List sessions
-    let sessions_result = SessionManager::list_sessions();
-    assert!(
-        sessions_result.is_ok(),
-        "Failed to list sessions: {:?}",
-        sessions_result.err()
-    );
-
-    let sessions = sessions_result.unwrap();
-
-    // Check if named session is listed
-    assert!(sessions.contains(&"test_named".to_string()));
+    let sessions = SessionManager::new(temp_dir.path());
+
+    // A session file the index doesn't know yet is picked up
+    SessionManager::save_state(
+        temp_dir.path().join("session_test_named.json"),
+        &SessionState::new(),
+    )
+    .unwrap();
+
+    let listed = sessions.list_sessions().unwrap();
+    assert!(listed.contains(&"test_named".to_string()));
+    assert!(temp_dir.path().join("index.json").exists());
 }
 
 #[test]
@@ -290,46 +262,234 @@ fn test_session_manager_get_session_filename() {
 
 #[test]
 fn test_session_manager_list_sessions_sorted_by_recency() {
-    let _lock = CWD_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
-    let _cwd_guard = CwdGuard::new();
     let temp_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(temp_dir.path());
+    let now = SystemTime::now();
+
+    // Files found outside the index are dated by their modification time
+    for (name, age) in [("old", 300), ("new", 0), ("middle", 100)] {
+        let path = temp_dir.path().join(format!("session_{}.json", name));
+        SessionManager::save_state(&path, &SessionState::new()).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - Duration::from_secs(age))
+            .unwrap();
+    }
 
-    // Change to temp directory for testing
-    std::env::set_current_dir(temp_dir.path()).unwrap();
+    let listed = sessions.list_sessions().unwrap();
+    assert_eq!(listed, vec!["new", "middle", "old"]);
+}
 
-    // Create sessions with delays to ensure different mtimes
-    let session_state = SessionState::new();
+#[test]
+fn test_session_manager_records_metadata_in_the_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(temp_dir.path());
+
+    let mut state = SessionState::new();
+    state.set_model("llama3.2".to_string());
+    state.set_history(vec![
+        ChatMessage::system("You are helpful."),
+        ChatMessage::user("\nFix the failing build\nIt breaks on CI"),
+        ChatMessage::assistant("Done"),
+    ]);
+    let saved = sessions.save("work", &state, Some("Llama")).unwrap();
+
+    assert_eq!(saved.name, "work");
+    assert_eq!(saved.title, "Fix the failing build");
+    assert_eq!(saved.agent.as_deref(), Some("Llama"));
+    assert_eq!(saved.model, "llama3.2");
+    assert_eq!(saved.message_count, 3);
+    // 16, 38 and 4 characters
+    assert_eq!(saved.token_estimate, 4 + 10 + 1);
+    assert_eq!(saved.created_at, saved.updated_at);
+
+    // Listing reads the index instead of the session file
+    fs::write(temp_dir.path().join("session_work.json"), "{ invalid json").unwrap();
+    assert_eq!(sessions.metadata("work").unwrap(), Some(saved.clone()));
+
+    // Saving again keeps the creation time
+    sessions.save("work", &state, Some("Qwen")).unwrap();
+    let updated = sessions.metadata("work").unwrap().unwrap();
+    assert_eq!(updated.created_at, saved.created_at);
+    assert_eq!(updated.agent.as_deref(), Some("Qwen"));
+
+    // Deleted session files drop out of the index
+    fs::remove_file(temp_dir.path().join("session_work.json")).unwrap();
+    assert!(sessions.list_sessions().unwrap().is_empty());
+}
 
-    // Create "old" session first
-    SessionManager::save_state("session_old.json", &session_state).unwrap();
-    std::thread::sleep(std::time::Duration::from_millis(50));
+#[test]
+fn test_session_manager_migrates_old_session_files_once() {
+    let old_dir = TempDir::new().unwrap();
+    let sessions_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(sessions_dir.path().join("sessions"));
+
+    let mut state = SessionState::new();
+    state.set_history(vec![ChatMessage::user("Old question")]);
+    SessionManager::save_state(old_dir.path().join("session.json"), &state).unwrap();
+    SessionManager::save_state(old_dir.path().join("session_notes.json"), &state).unwrap();
+    fs::write(old_dir.path().join("notes.json"), "{}").unwrap();
+
+    let mut imported = sessions.migrate_from(old_dir.path()).unwrap();
+    imported.sort();
+    assert_eq!(imported, vec!["default", "notes"]);
+    assert_eq!(
+        sessions.load("notes").unwrap().unwrap().history()[0].content,
+        "Old question"
+    );
+    assert_eq!(
+        sessions.metadata("default").unwrap().unwrap().title,
+        "Old question"
+    );
+    // The old files are kept
+    assert!(old_dir.path().join("session_notes.json").exists());
 
-    // Create "middle" session
-    SessionManager::save_state("session_middle.json", &session_state).unwrap();
-    std::thread::sleep(std::time::Duration::from_millis(50));
+    // A directory is only imported once
+    SessionManager::save_state(old_dir.path().join("session_later.json"), &state).unwrap();
+    assert!(sessions.migrate_from(old_dir.path()).unwrap().is_empty());
+    assert!(
+        !sessions
+            .list_sessions()
+            .unwrap()
+            .contains(&"later".to_string())
+    );
+}
 
-    // Create "new" session last (most recent)
-    SessionManager::save_state("session_new.json", &session_state).unwrap();
+#[test]
+fn test_session_manager_migration_keeps_existing_sessions() {
+    let old_dir = TempDir::new().unwrap();
+    let sessions_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(sessions_dir.path());
+
+    let mut current = SessionState::new();
+    current.set_history(vec![ChatMessage::user("Current")]);
+    sessions.save("notes", &current, None).unwrap();
+    let mut old = SessionState::new();
+    old.set_history(vec![ChatMessage::user("Old")]);
+    SessionManager::save_state(old_dir.path().join("session_notes.json"), &old).unwrap();
+
+    assert!(sessions.migrate_from(old_dir.path()).unwrap().is_empty());
+    assert_eq!(
+        sessions.load("notes").unwrap().unwrap().history()[0].content,
+        "Current"
+    );
+
+    // Migrating the sessions directory into itself does nothing
+    assert!(
+        sessions
+            .migrate_from(sessions_dir.path())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_session_manager_export_and_import() {
+    let temp_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(temp_dir.path());
+
+    let mut state = SessionState::new();
+    state.set_model("llama3.2".to_string());
+    state.add_allowed_tool("run_shell_command".to_string());
+    state.set_history(vec![
+        ChatMessage::user("Summarize the incident"),
+        ChatMessage::assistant("The deploy failed"),
+    ]);
+    sessions.save("incident", &state, Some("Llama")).unwrap();
+
+    let markdown = sessions.export("incident", ExportFormat::Markdown).unwrap();
+    assert!(markdown.starts_with("# Summarize the incident\n"));
+    assert!(markdown.contains("The deploy failed"));
+    assert!(sessions.export("missing", ExportFormat::Markdown).is_err());
+
+    // A JSON Lines transcript becomes a new session
+    let jsonl = sessions
+        .export("incident", ExportFormat::JsonLines)
+        .unwrap();
+    let imported = sessions.import("incident-copy", &jsonl).unwrap();
+    assert_eq!(imported.message_count, 2);
+    assert_eq!(imported.agent.as_deref(), Some("Llama"));
+    let copy = sessions.load("incident-copy").unwrap().unwrap();
+    assert_eq!(copy.model(), "llama3.2");
+    assert_eq!(copy.history()[1].content, "The deploy failed");
+    // Tool permissions are not part of a transcript
+    assert!(copy.list_allowed_tools().is_empty());
+
+    // Existing sessions are not overwritten
+    assert!(sessions.import("incident", &jsonl).is_err());
+}
 
-    // List sessions - should be sorted by recency (newest first)
-    let sessions = SessionManager::list_sessions().unwrap();
+#[test]
+fn test_session_manager_fork() {
+    let temp_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(temp_dir.path());
+
+    let mut state = SessionState::new();
+    state.set_model("granite3.3".to_string());
+    state.add_allowed_tool("read_file".to_string());
+    state.add_denied_tool("run_shell_command".to_string());
+    state.set_history(vec![
+        ChatMessage::user("First"),
+        ChatMessage::assistant("One"),
+        ChatMessage::user("Second"),
+        ChatMessage::assistant("Two"),
+    ]);
+    sessions.save("main", &state, Some("Granite")).unwrap();
+
+    let fork = sessions.fork("main", "retry", Some(2)).unwrap();
+    assert_eq!(fork.message_count, 2);
+    assert_eq!(fork.agent.as_deref(), Some("Granite"));
+    let parent = SessionParent {
+        session: "main".to_string(),
+        message_index: 2,
+    };
+    assert_eq!(fork.parent, Some(parent.clone()));
+
+    // The fork keeps the model, the permissions and its lineage
+    let forked = sessions.load("retry").unwrap().unwrap();
+    assert_eq!(forked.history().len(), 2);
+    assert_eq!(forked.history()[1].content, "One");
+    assert_eq!(forked.model(), "granite3.3");
+    assert!(forked.is_tool_allowed("read_file"));
+    assert!(forked.is_tool_denied("run_shell_command"));
+    assert_eq!(forked.parent(), Some(&parent));
+
+    // The parent is untouched
+    assert_eq!(sessions.load("main").unwrap().unwrap().history().len(), 4);
+
+    // Without an index, the whole history is kept
+    let whole = sessions.fork("main", "copy", None).unwrap();
+    assert_eq!(whole.message_count, 4);
+
+    assert!(sessions.fork("main", "retry", None).is_err());
+    assert!(sessions.fork("main", "too-long", Some(5)).is_err());
+    assert!(sessions.fork("missing", "other", None).is_err());
+    assert!(sessions.fork("main", "a/b", None).is_err());
+}
 
-    // Find positions
-    let new_pos = sessions.iter().position(|s| s == "new");
-    let middle_pos = sessions.iter().position(|s| s == "middle");
-    let old_pos = sessions.iter().position(|s| s == "old");
+#[test]
+fn test_session_manager_rejects_names_outside_its_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let sessions = SessionManager::new(temp_dir.path().join("sessions"));
+    fs::create_dir(sessions.dir()).unwrap();
 
-    // Verify ordering: new < middle < old (newer sessions have lower indices)
-    assert!(new_pos.is_some(), "new session not found");
-    assert!(middle_pos.is_some(), "middle session not found");
-    assert!(old_pos.is_some(), "old session not found");
+    assert!(validate_session_name("incident-2").is_ok());
+    for name in ["", "../escape", "a/b", "a\\b", "c:name", ".."] {
+        assert!(validate_session_name(name).is_err(), "{:?}", name);
+        assert!(sessions.session_path(name).is_err(), "{:?}", name);
+    }
 
+    let state = SessionState::new();
+    let jsonl = r#"{"role":"user","content":"Hi"}"#;
+    assert!(sessions.save("../escape", &state, None).is_err());
+    assert!(sessions.load("../escape").is_err());
+    assert!(sessions.import("../escape", jsonl).is_err());
     assert!(
-        new_pos.unwrap() < middle_pos.unwrap(),
-        "new session should appear before middle session"
-    );
-    assert!(
-        middle_pos.unwrap() < old_pos.unwrap(),
-        "middle session should appear before old session"
+        sessions
+            .export("../escape", ExportFormat::Markdown)
+            .is_err()
     );
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
 }
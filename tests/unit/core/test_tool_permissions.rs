@@ -47,3 +47,20 @@ fn test_global_tool_permissions_remove_nonexistent() {
     let mut permissions = GlobalToolPermissions::new();
     assert!(!permissions.remove_allowed("nonexistent_tool"));
 }
+
+#[test]
+fn test_global_tool_permissions_denied_tools() {
+    let mut permissions = GlobalToolPermissions::new();
+    permissions.add_allowed("test_tool");
+
+    // Denying a tool removes it from the allowed list
+    permissions.add_denied("test_tool");
+    assert!(permissions.is_denied("test_tool"));
+    assert!(!permissions.is_allowed("test_tool"));
+    assert_eq!(permissions.list_denied(), vec!["test_tool"]);
+
+    // Allowing it again lifts the denial
+    permissions.add_allowed("test_tool");
+    assert!(!permissions.is_denied("test_tool"));
+    assert!(!permissions.remove_denied("test_tool"));
+}
@@ -1,5 +1,6 @@
 //! Unit tests for the tools module using mock objects.
 
+use OxideAgent::config::ToolScope;
 use OxideAgent::core::mocks::{
     MockFileSystem, MockReadFileTool, MockRunShellCommandTool, MockShellExecutor, MockWriteFileTool,
 };
@@ -93,6 +94,68 @@ fn test_tool_registry_definitions_with_profiles() {
     assert!(shell_tool_names.contains(&"run_shell_command".to_string()));
 }
 
+#[test]
+fn test_tool_registry_filtered_by_scope() {
+    let mut registry = ToolRegistry::new();
+    let mock_fs = Arc::new(Mutex::new(MockFileSystem::new()));
+    let mock_shell = Arc::new(Mutex::new(MockShellExecutor::new()));
+
+    registry.add_tool(Box::new(MockWriteFileTool::new(mock_fs.clone())));
+    registry.add_tool(Box::new(MockReadFileTool::new(mock_fs)));
+    registry.add_tool(Box::new(MockRunShellCommandTool::new(mock_shell)));
+
+    let names = |registry: &ToolRegistry| -> Vec<String> {
+        registry
+            .definitions()
+            .iter()
+            .map(|t| t.function.name.clone())
+            .collect()
+    };
+
+    // An empty scope keeps every tool
+    assert_eq!(names(&registry.filtered(&ToolScope::default())).len(), 3);
+
+    // A read-only reviewer: file tools without write_file
+    let reviewer = ToolScope {
+        allowed_profiles: vec![ToolProfile::File],
+        include_tools: vec![],
+        exclude_tools: vec!["write_file".to_string()],
+    };
+    let filtered = registry.filtered(&reviewer);
+    assert_eq!(names(&filtered), vec!["read_file"]);
+    assert!(filtered.get_tool("run_shell_command").is_none());
+
+    // Included tools are added regardless of profile
+    let scope = ToolScope {
+        allowed_profiles: vec![],
+        include_tools: vec!["run_shell_command".to_string()],
+        exclude_tools: vec![],
+    };
+    assert_eq!(names(&registry.filtered(&scope)), vec!["run_shell_command"]);
+
+    // Excluding alone hides only the named tools
+    let scope = ToolScope {
+        exclude_tools: vec!["run_shell_command".to_string()],
+        ..ToolScope::default()
+    };
+    assert_eq!(
+        names(&registry.filtered(&scope)),
+        vec!["write_file", "read_file"]
+    );
+}
+
+#[test]
+fn test_tool_registry_ignores_duplicate_names() {
+    let mut registry = ToolRegistry::new();
+    let mock_fs = Arc::new(Mutex::new(MockFileSystem::new()));
+
+    registry.add_tool(Box::new(MockWriteFileTool::new(mock_fs.clone())));
+    registry.add_tool(Box::new(MockWriteFileTool::new(mock_fs)));
+
+    assert_eq!(registry.definitions().len(), 1);
+    assert_eq!(registry.tool_names(), vec!["write_file"]);
+}
+
 #[test]
 fn test_tool_definition() {
     let mock_fs = Arc::new(Mutex::new(MockFileSystem::new()));
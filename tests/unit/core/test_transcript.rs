@@ -0,0 +1,109 @@
+//! Unit tests for session transcripts.
+
+use OxideAgent::core::session::SessionMetadata;
+use OxideAgent::core::transcript::{ExportFormat, format_timestamp, parse_json_lines, render};
+use OxideAgent::types::{ChatMessage, ToolCall};
+use serde_json::json;
+
+fn metadata() -> SessionMetadata {
+    SessionMetadata {
+        name: "review".to_string(),
+        created_at: 1_760_000_000,
+        updated_at: 1_760_000_600,
+        title: "Fix the <build>".to_string(),
+        agent: Some("Qwen".to_string()),
+        model: "qwen3:4b".to_string(),
+        message_count: 4,
+        token_estimate: 20,
+        parent: None,
+    }
+}
+
+fn history() -> Vec<ChatMessage> {
+    let call = ToolCall::new(
+        Some("call_1".to_string()),
+        "read_file",
+        json!({ "path": "src/main.rs" }),
+    );
+    let mut history = vec![
+        ChatMessage::user("Fix the <build>"),
+        ChatMessage::tool_call("Reading the file", vec![call.clone()]),
+        ChatMessage::tool(&call, "fn main() {}\n```\nnested fence"),
+        ChatMessage::assistant("Done"),
+    ];
+    for (offset, message) in history.iter_mut().enumerate() {
+        message.timestamp = Some(1_760_000_000 + offset as u64);
+    }
+    history
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+    assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+    assert_eq!(format_timestamp(1_760_000_000), "2025-10-09 08:53:20 UTC");
+}
+
+#[test]
+fn test_markdown_transcript() {
+    let markdown = render(&metadata(), &history(), ExportFormat::Markdown).unwrap();
+
+    assert!(markdown.starts_with("# Fix the <build>\n"));
+    assert!(markdown.contains("- **Agent:** Qwen\n"));
+    assert!(markdown.contains("- **Messages:** 4 (~20 tokens)\n"));
+    assert!(markdown.contains("\n## User · 2025-10-09 08:53:20 UTC\n\nFix the <build>\n"));
+    assert!(markdown.contains(
+        "**Tool call** `read_file` (call_1)\n\n```json\n{\n  \"path\": \"src/main.rs\"\n}\n```\n"
+    ));
+    // A result containing a fence gets a longer one
+    assert!(markdown.contains(
+        "## Tool result `read_file` · 2025-10-09 08:53:22 UTC\n\n````\nfn main() {}\n```\nnested fence\n````\n"
+    ));
+    assert!(markdown.ends_with("## Assistant · 2025-10-09 08:53:23 UTC\n\nDone\n"));
+}
+
+#[test]
+fn test_html_transcript_escapes_content() {
+    let html = render(&metadata(), &history(), ExportFormat::Html).unwrap();
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Fix the &lt;build&gt;</title>"));
+    assert!(html.contains("<pre>Fix the &lt;build&gt;</pre>"));
+    assert!(html.contains("<section class=\"message tool\">"));
+    assert!(html.contains("Tool call <code>read_file</code>"));
+    assert!(html.contains("<time>2025-10-09 08:53:21 UTC</time>"));
+    assert!(!html.contains("<build>"));
+}
+
+#[test]
+fn test_json_lines_transcript_round_trip() {
+    let jsonl = render(&metadata(), &history(), ExportFormat::JsonLines).unwrap();
+    let lines: Vec<&str> = jsonl.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with(r#"{"type":"session","#));
+    assert!(lines[1].starts_with(r#"{"type":"message","#));
+
+    let transcript = parse_json_lines(&jsonl).unwrap();
+    assert_eq!(transcript.metadata, Some(metadata()));
+    assert_eq!(transcript.history.len(), 4);
+    assert_eq!(transcript.history[2].role, "tool");
+    assert_eq!(
+        transcript.history[2].tool_call_id.as_deref(),
+        Some("call_1")
+    );
+    assert_eq!(transcript.history[3].timestamp, Some(1_760_000_003));
+}
+
+#[test]
+fn test_json_lines_without_type_are_messages() {
+    let transcript = parse_json_lines(
+        "{\"role\":\"user\",\"content\":\"Hi\"}\n\n{\"role\":\"assistant\",\"content\":\"Hello\"}\n",
+    )
+    .unwrap();
+    assert!(transcript.metadata.is_none());
+    assert_eq!(transcript.history.len(), 2);
+    assert_eq!(transcript.history[1].content, "Hello");
+
+    let error = parse_json_lines("{\"role\":\"user\",\"content\":\"Hi\"}\nnot json").unwrap_err();
+    assert!(error.to_string().starts_with("Line 2"));
+}
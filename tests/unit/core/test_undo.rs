@@ -0,0 +1,71 @@
+//! Tests for tool effect snapshots and the undo stack.
+
+use OxideAgent::core::undo::{FileSnapshot, ToolEffect, UndoStack};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_snapshot_restores_previous_contents() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    fs::write(&path, "before").unwrap();
+
+    let snapshot = FileSnapshot::capture(&path).unwrap();
+    assert!(snapshot.existed());
+    fs::write(&path, "after").unwrap();
+
+    snapshot.restore().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "before");
+}
+
+#[test]
+fn test_snapshot_of_missing_file_removes_it_on_restore() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("created.txt");
+
+    let snapshot = FileSnapshot::capture(&path).unwrap();
+    assert!(!snapshot.existed());
+    fs::write(&path, "new file").unwrap();
+
+    snapshot.restore().unwrap();
+    assert!(!path.exists());
+    // Restoring again is harmless
+    snapshot.restore().unwrap();
+}
+
+#[test]
+fn test_undo_stack_undoes_newest_effect_first() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("file.txt");
+    let mut stack = UndoStack::default();
+    assert!(stack.undo_last().unwrap().is_none());
+
+    fs::write(&path, "v1").unwrap();
+    stack.push(ToolEffect::capture("write_file", std::slice::from_ref(&path)).unwrap());
+    fs::write(&path, "v2").unwrap();
+    stack.push(ToolEffect::capture("edit_file", std::slice::from_ref(&path)).unwrap());
+    fs::write(&path, "v3").unwrap();
+    assert_eq!(stack.len(), 2);
+
+    let undone = stack.undo_last().unwrap().unwrap();
+    assert_eq!(undone.tool_name, "edit_file");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "v2");
+
+    let undone = stack.undo_last().unwrap().unwrap();
+    assert_eq!(undone.tool_name, "write_file");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "v1");
+    assert!(stack.is_empty());
+}
+
+#[test]
+fn test_undo_stack_drops_oldest_effect_when_full() {
+    let mut stack = UndoStack::new(2);
+    for name in ["first", "second", "third"] {
+        stack.push(ToolEffect::capture(name, &[]).unwrap());
+    }
+    assert_eq!(stack.len(), 2);
+
+    assert_eq!(stack.undo_last().unwrap().unwrap().tool_name, "third");
+    assert_eq!(stack.undo_last().unwrap().unwrap().tool_name, "second");
+    assert!(stack.undo_last().unwrap().is_none());
+}
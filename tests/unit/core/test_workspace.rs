@@ -0,0 +1,193 @@
+//! Tests for workspace-root confinement of the file tools.
+
+use OxideAgent::config::{FileAccessMode, OxideConfig};
+use OxideAgent::core::tools::{ReadFileTool, Tool, WriteFileTool};
+use OxideAgent::core::workspace::Workspace;
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+fn read_write(temp_dir: &TempDir) -> Workspace {
+    Workspace::new(temp_dir.path(), FileAccessMode::ReadWrite)
+}
+
+#[test]
+fn test_workspace_resolves_paths_inside_root() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join("src")).unwrap();
+    fs::write(temp_dir.path().join("src/lib.rs"), "").unwrap();
+    let workspace = read_write(&temp_dir);
+    let root = temp_dir.path().canonicalize().unwrap();
+
+    assert_eq!(
+        workspace.resolve_read("src/lib.rs").unwrap(),
+        root.join("src/lib.rs")
+    );
+    assert_eq!(
+        workspace.resolve_read("./src/../src/lib.rs").unwrap(),
+        root.join("src/lib.rs")
+    );
+    let absolute = root.join("src/lib.rs");
+    assert_eq!(
+        workspace.resolve_read(absolute.to_str().unwrap()).unwrap(),
+        absolute
+    );
+
+    // Files that don't exist yet can be written, even in missing directories
+    assert_eq!(
+        workspace.resolve_write("src/new/mod.rs").unwrap(),
+        root.join("src/new/mod.rs")
+    );
+}
+
+#[test]
+fn test_workspace_rejects_paths_outside_root() {
+    let outer = TempDir::new().unwrap();
+    let root = outer.path().join("project");
+    fs::create_dir(&root).unwrap();
+    fs::write(outer.path().join("secret.txt"), "secret").unwrap();
+    let workspace = Workspace::new(&root, FileAccessMode::ReadWrite);
+
+    let error = workspace.resolve_read("../secret.txt").unwrap_err();
+    assert!(error.to_string().contains("outside the workspace root"));
+
+    let secret = outer.path().join("secret.txt");
+    assert!(workspace.resolve_read(secret.to_str().unwrap()).is_err());
+    assert!(workspace.resolve_write("../escaped.txt").is_err());
+    assert!(
+        workspace
+            .resolve_write("missing/../../escaped.txt")
+            .is_err()
+    );
+    assert!(!outer.path().join("escaped.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_workspace_rejects_symlink_escapes() {
+    let outer = TempDir::new().unwrap();
+    let root = outer.path().join("project");
+    let outside = outer.path().join("outside");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir(&outside).unwrap();
+    fs::write(outside.join("secret.txt"), "secret").unwrap();
+    std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+    std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("secret_link")).unwrap();
+    let workspace = Workspace::new(&root, FileAccessMode::ReadWrite);
+
+    assert!(workspace.resolve_read("link/secret.txt").is_err());
+    assert!(workspace.resolve_read("secret_link").is_err());
+    assert!(workspace.resolve_write("link/new.txt").is_err());
+    assert!(workspace.resolve_write("secret_link").is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_workspace_rejects_dangling_symlink_escapes() {
+    let outer = TempDir::new().unwrap();
+    let root = outer.path().join("project");
+    let outside = outer.path().join("outside");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir(&outside).unwrap();
+    std::os::unix::fs::symlink(outside.join("file"), root.join("dangling")).unwrap();
+    std::os::unix::fs::symlink(root.join("missing.txt"), root.join("inner")).unwrap();
+    std::os::unix::fs::symlink(root.join("loop"), root.join("loop")).unwrap();
+    let workspace = Workspace::new(&root, FileAccessMode::ReadWrite);
+
+    assert!(workspace.resolve_write("dangling").is_err());
+    let tool = WriteFileTool::new(workspace.clone());
+    let result = tool
+        .execute(&json!({"path": "dangling", "content": "escaped"}))
+        .await;
+    assert!(result.is_err());
+    assert!(!outside.join("file").exists());
+
+    // A dangling link inside the root resolves to its target
+    assert_eq!(
+        workspace.resolve_write("inner").unwrap(),
+        root.canonicalize().unwrap().join("missing.txt")
+    );
+    assert!(workspace.resolve_write("loop").is_err());
+}
+
+#[test]
+fn test_workspace_read_only_mode_and_extra_roots() {
+    let root = TempDir::new().unwrap();
+    let docs = TempDir::new().unwrap();
+    let scratch = TempDir::new().unwrap();
+    fs::write(root.path().join("a.txt"), "a").unwrap();
+    fs::write(docs.path().join("guide.md"), "guide").unwrap();
+
+    let workspace = Workspace::new(root.path(), FileAccessMode::ReadOnly)
+        .with_extra_root(docs.path(), FileAccessMode::ReadOnly)
+        .with_extra_root(scratch.path(), FileAccessMode::ReadWrite);
+
+    assert!(workspace.resolve_read("a.txt").is_ok());
+    let error = workspace.resolve_write("a.txt").unwrap_err();
+    assert!(error.to_string().contains("read-only workspace root"));
+
+    let guide = docs.path().join("guide.md");
+    assert!(workspace.resolve_read(guide.to_str().unwrap()).is_ok());
+    assert!(workspace.resolve_write(guide.to_str().unwrap()).is_err());
+
+    let note = scratch.path().join("note.txt");
+    assert!(workspace.resolve_write(note.to_str().unwrap()).is_ok());
+}
+
+#[test]
+fn test_workspace_from_config() {
+    let root = TempDir::new().unwrap();
+    let docs = TempDir::new().unwrap();
+    let toml_content = format!(
+        r#"
+        [tools]
+        workspace_root = "{}"
+        file_access = "read_only"
+
+        [[tools.extra_roots]]
+        path = "{}"
+        "#,
+        root.path().display(),
+        docs.path().display()
+    );
+    let config: OxideConfig = toml::from_str(&toml_content).unwrap();
+
+    let workspace = Workspace::from_config(&config.tools);
+    assert_eq!(workspace.root(), root.path());
+    assert_eq!(workspace.roots().len(), 2);
+    assert_eq!(workspace.roots()[0].mode, FileAccessMode::ReadOnly);
+    assert_eq!(workspace.roots()[1].mode, FileAccessMode::ReadOnly);
+}
+
+#[tokio::test]
+async fn test_file_tools_are_confined_to_workspace() {
+    let outer = TempDir::new().unwrap();
+    let root = outer.path().join("project");
+    fs::create_dir(&root).unwrap();
+    let workspace = Workspace::new(&root, FileAccessMode::ReadWrite);
+    let write_tool = WriteFileTool::new(workspace.clone());
+    let read_tool = ReadFileTool::new(workspace);
+
+    write_tool
+        .execute(&json!({"path": "notes.txt", "content": "inside"}))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join("notes.txt")).unwrap(),
+        "inside"
+    );
+    assert_eq!(
+        read_tool
+            .execute(&json!({"path": "notes.txt"}))
+            .await
+            .unwrap(),
+        "inside"
+    );
+
+    let error = write_tool
+        .execute(&json!({"path": "../escape.txt", "content": "outside"}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().starts_with("Access denied"));
+    assert!(!outer.path().join("escape.txt").exists());
+}
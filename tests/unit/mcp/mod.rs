@@ -3,3 +3,7 @@ pub mod test_connection;
 pub mod test_http;
 pub mod test_launcher;
 pub mod test_manager;
+pub mod test_oauth;
+pub mod test_router;
+pub mod test_sse;
+pub mod test_supervisor;
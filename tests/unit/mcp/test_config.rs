@@ -275,3 +275,68 @@ fn test_command_server_config() {
         _ => panic!("Expected command server type"),
     }
 }
+
+#[test]
+fn test_mcp_servers_format_loading() {
+    let json_content = r#"{
+        "mcpServers": {
+            "weather": {
+                "command": "npx",
+                "args": ["-y", "@h1deya/mcp-server-weather"],
+                "env": {"UNITS": "metric"}
+            },
+            "docs": {
+                "type": "http",
+                "url": "https://example.com/mcp",
+                "headers": {"Authorization": "Bearer docs-token"}
+            },
+            "old": {
+                "command": "old-server",
+                "disabled": true
+            }
+        }
+    }"#;
+
+    let mut temp_file = NamedTempFile::new().unwrap();
+    temp_file.write_all(json_content.as_bytes()).unwrap();
+
+    let config = McpConfigFile::load_from_file(temp_file.path()).unwrap();
+    assert_eq!(config.list_server_names(), vec!["docs", "old", "weather"]);
+
+    let weather = config.get_server("weather").unwrap();
+    assert_eq!(weather.auto_start, Some(true));
+    match &weather.server_type {
+        McpServerType::Command {
+            command,
+            args,
+            environment,
+            working_directory,
+        } => {
+            assert_eq!(command, "npx");
+            assert_eq!(args.as_ref().unwrap()[1], "@h1deya/mcp-server-weather");
+            assert_eq!(environment.as_ref().unwrap()["UNITS"], "metric");
+            assert!(working_directory.is_none());
+        }
+        _ => panic!("Expected command server type"),
+    }
+
+    match &config.get_server("docs").unwrap().server_type {
+        McpServerType::Remote {
+            url, access_token, ..
+        } => {
+            assert_eq!(url, "https://example.com/mcp");
+            assert_eq!(access_token.as_deref(), Some("docs-token"));
+        }
+        _ => panic!("Expected remote server type"),
+    }
+
+    assert_eq!(config.get_server("old").unwrap().auto_start, Some(false));
+}
+
+#[test]
+fn test_mcp_servers_entry_without_command_or_url_is_rejected() {
+    let value = serde_json::json!({ "mcpServers": { "empty": { "args": ["x"] } } });
+
+    let error = McpConfigFile::from_value(value).unwrap_err();
+    assert!(error.to_string().contains("neither a command nor a url"));
+}
@@ -1,6 +1,5 @@
 use OxideAgent::core::mcp::config::{McpServerConfig, McpServerType};
 use OxideAgent::core::mcp::connection::*;
-use OxideAgent::core::tools::{Tool, ToolProfile};
 use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value;
@@ -130,6 +129,11 @@ async fn test_mcp_connection_trait_implementation() {
             self.counter += 1;
             Ok(serde_json::json!("executed"))
         }
+
+        async fn request(&mut self, _method: &str, _params: Option<Value>) -> Result<Value> {
+            self.counter += 1;
+            Ok(serde_json::json!({}))
+        }
     }
 
     let mut conn = MockConnection { counter: 0 };
@@ -138,30 +142,54 @@ async fn test_mcp_connection_trait_implementation() {
     assert_eq!(conn.counter, 2);
 }
 
-#[tokio::test]
-async fn test_stdio_mcp_tool_adapter_creation() {
-    let adapter = StdioMcpToolAdapter {
-        name: "test_tool".to_string(),
-        description: "A test tool".to_string(),
-        parameters: serde_json::json!({}),
-    };
+#[test]
+fn test_tool_result_text_content() {
+    let result = serde_json::json!({
+        "content": [
+            { "type": "text", "text": "first" },
+            { "type": "text", "text": "second" }
+        ]
+    });
+    assert_eq!(tool_result_to_string(&result).unwrap(), "first\nsecond");
+}
 
-    assert_eq!(adapter.name(), "test_tool");
-    assert_eq!(adapter.description(), "A test tool");
-    assert_eq!(adapter.profile(), ToolProfile::Generic);
+#[test]
+fn test_tool_result_binary_content_is_summarized() {
+    let result = serde_json::json!({
+        "content": [
+            { "type": "audio", "data": "AAAA", "mimeType": "audio/wav" },
+            {
+                "type": "resource",
+                "resource": { "uri": "file:///logo.png", "mimeType": "image/png", "blob": "AAAA" }
+            }
+        ]
+    });
+    assert_eq!(
+        tool_result_to_string(&result).unwrap(),
+        "[audio content: audio/wav, 4 bytes base64]\n[resource: file:///logo.png (image/png, binary)]"
+    );
 }
 
-#[tokio::test]
-async fn test_stdio_mcp_tool_adapter_clone() {
-    let adapter = StdioMcpToolAdapter {
-        name: "test_tool".to_string(),
-        description: "A test tool".to_string(),
-        parameters: serde_json::json!({}),
-    };
+#[test]
+fn test_tool_result_without_content() {
+    let structured = serde_json::json!({ "structuredContent": { "temperature": 21 } });
+    assert_eq!(
+        tool_result_to_string(&structured).unwrap(),
+        "{\n  \"temperature\": 21\n}"
+    );
+
+    let raw = serde_json::json!({ "answer": 42 });
+    assert_eq!(tool_result_to_string(&raw).unwrap(), r#"{"answer":42}"#);
+}
 
-    let cloned_adapter = adapter.clone_box();
-    assert_eq!(cloned_adapter.name(), "test_tool");
-    assert_eq!(cloned_adapter.description(), "A test tool");
+#[test]
+fn test_tool_result_error_flag() {
+    let result = serde_json::json!({
+        "content": [{ "type": "text", "text": "permission denied" }],
+        "isError": true
+    });
+    let error = tool_result_to_string(&result).unwrap_err();
+    assert!(error.to_string().contains("permission denied"));
 }
 
 #[tokio::test]
@@ -1,6 +1,10 @@
 use OxideAgent::core::mcp::config::{McpServerConfig, McpServerType};
+use OxideAgent::core::mcp::connection::McpConnection;
 use OxideAgent::core::mcp::connection::McpToolDefinition;
-use OxideAgent::core::mcp::http::HttpMcpConnection;
+use OxideAgent::core::mcp::http::{HttpMcpConnection, HttpTransport};
+use OxideAgent::core::mcp::router::McpRequestRouter;
+use OxideAgent::types::{AppEvent, McpElicitationAnswer};
+use httpmock::prelude::*;
 use serde::{Deserialize, Serialize};
 use tokio;
 
@@ -81,3 +85,374 @@ fn truncate_description(description: &str) -> String {
         description.to_string()
     }
 }
+
+fn remote_connection(url: String) -> HttpMcpConnection {
+    let config = McpServerConfig {
+        name: "remote".to_string(),
+        description: None,
+        server_type: McpServerType::Remote {
+            url: url.clone(),
+            access_token: None,
+            api_key: None,
+        },
+        auto_start: Some(true),
+        environment: None,
+    };
+    HttpMcpConnection::new(&config, url, None, None)
+}
+
+fn sse_message(message: serde_json::Value) -> String {
+    format!("event: message\ndata: {}\n\n", message)
+}
+
+/// Mock `initialize` answering request `request_id` with `session_id`, and
+/// `notifications/initialized`
+fn mock_initialize<'a>(
+    server: &'a MockServer,
+    session_id: &str,
+    request_id: u64,
+) -> (httpmock::Mock<'a>, httpmock::Mock<'a>) {
+    let initialize = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .body_includes(r#""method":"initialize""#);
+        then.status(200)
+            .header("Mcp-Session-Id", session_id)
+            .json_body(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "result": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": { "tools": { "listChanged": true }, "resources": {}, "prompts": {} },
+                    "serverInfo": { "name": "mock", "version": "1.0.0" }
+                }
+            }));
+    });
+    let initialized = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("Mcp-Session-Id", session_id)
+            .body_includes(r#""method":"notifications/initialized""#);
+        then.status(202);
+    });
+    (initialize, initialized)
+}
+
+#[tokio::test]
+async fn test_http_resources_and_prompts_use_json_rpc() {
+    let server = MockServer::start();
+    mock_initialize(&server, "session-1", 1);
+    let resources_mock = server.mock(|when, then| {
+        when.method(POST)
+            .body_includes(r#""method":"resources/list""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(sse_message(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "result": { "resources": [{ "uri": "file:///a.txt", "name": "a.txt" }] }
+            })));
+    });
+    let prompt_mock = server.mock(|when, then| {
+        when.method(POST)
+            .body_includes(r#""method":"prompts/get""#)
+            .body_includes(r#""arguments":{"topic":"rust"}"#);
+        then.status(200).json_body(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "result": {
+                "messages": [{ "role": "user", "content": { "type": "text", "text": "Explain rust" } }]
+            }
+        }));
+    });
+
+    let mut connection = remote_connection(server.url("/mcp"));
+
+    let resources = connection.list_resources().await.unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].uri, "file:///a.txt");
+
+    let mut arguments = std::collections::HashMap::new();
+    arguments.insert("topic".to_string(), "rust".to_string());
+    let messages = connection.get_prompt("explain", &arguments).await.unwrap();
+    assert_eq!(messages[0].content["text"], "Explain rust");
+
+    resources_mock.assert_calls(1);
+    prompt_mock.assert_calls(1);
+}
+
+#[tokio::test]
+async fn test_streamable_http_keeps_session_and_handles_stream_messages() {
+    let server = MockServer::start();
+    let (initialize, initialized) = mock_initialize(&server, "session-1", 1);
+    let ping_answer = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .body_includes(r#""id":"server-ping""#)
+            .body_includes(r#""result":{}"#);
+        then.status(202);
+    });
+    let call = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("Mcp-Session-Id", "session-1")
+            .header("MCP-Protocol-Version", "2025-03-26")
+            .body_includes(r#""method":"tools/call""#)
+            .body_includes(r#""progressToken":2"#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(
+                [
+                    ": keep-alive\n\n".to_string(),
+                    sse_message(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/progress",
+                        "params": { "progressToken": 2, "progress": 1, "total": 2 }
+                    })),
+                    sse_message(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/tools/list_changed"
+                    })),
+                    sse_message(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": "server-ping",
+                        "method": "ping"
+                    })),
+                    sse_message(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": 2,
+                        "result": { "content": [{ "type": "text", "text": "done" }] }
+                    })),
+                ]
+                .concat(),
+            );
+    });
+
+    let mut connection = remote_connection(server.url("/mcp"));
+    let result = connection
+        .execute_tool("slow", &serde_json::json!({}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["content"][0]["text"], "done");
+    assert_eq!(connection.transport(), Some(&HttpTransport::Streamable));
+    assert_eq!(connection.session_id(), Some("session-1"));
+    assert_eq!(connection.initialize_result()["serverInfo"]["name"], "mock");
+    assert!(connection.take_tools_changed());
+    assert!(!connection.take_tools_changed());
+    initialize.assert_calls(1);
+    initialized.assert_calls(1);
+    ping_answer.assert_calls(1);
+    call.assert_calls(1);
+}
+
+#[tokio::test]
+async fn test_streamable_http_resumes_a_dropped_stream() {
+    let server = MockServer::start();
+    mock_initialize(&server, "session-1", 1);
+    let call = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .body_includes(r#""method":"tools/call""#);
+        // The stream ends after a progress event, before the response
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(format!(
+                "id: event-1\n{}",
+                sse_message(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/progress",
+                    "params": { "progressToken": 2, "progress": 1 }
+                }))
+            ));
+    });
+    let resume = server.mock(|when, then| {
+        when.method(GET)
+            .path("/mcp")
+            .header("Mcp-Session-Id", "session-1")
+            .header("Last-Event-ID", "event-1");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(format!(
+                "id: event-2\n{}",
+                sse_message(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "result": { "content": [{ "type": "text", "text": "resumed" }] }
+                }))
+            ));
+    });
+
+    let mut connection = remote_connection(server.url("/mcp"));
+    let result = connection
+        .execute_tool("slow", &serde_json::json!({}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["content"][0]["text"], "resumed");
+    call.assert_calls(1);
+    resume.assert_calls(1);
+}
+
+#[tokio::test]
+async fn test_streamable_http_starts_a_new_session_when_it_expired() {
+    let server = MockServer::start();
+    let (mut first_initialize, _) = mock_initialize(&server, "session-1", 1);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .body_includes(r#""method":"ping""#);
+        then.status(200)
+            .json_body(serde_json::json!({ "jsonrpc": "2.0", "id": 2, "result": {} }));
+    });
+
+    let mut connection = remote_connection(server.url("/mcp"));
+    connection.request("ping", None).await.unwrap();
+    assert_eq!(connection.session_id(), Some("session-1"));
+
+    // The server restarted and only knows the new session
+    first_initialize.delete();
+    mock_initialize(&server, "session-2", 4);
+    let expired = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("Mcp-Session-Id", "session-1")
+            .body_includes(r#""method":"tools/list""#);
+        then.status(404);
+    });
+    let list = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("Mcp-Session-Id", "session-2")
+            .body_includes(r#""method":"tools/list""#);
+        then.status(200).json_body(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "result": { "tools": [{ "name": "echo", "description": "Echo", "inputSchema": {} }] }
+        }));
+    });
+
+    let tools = connection.discover_tools().await.unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(connection.session_id(), Some("session-2"));
+    expired.assert_calls(1);
+    list.assert_calls(1);
+}
+
+#[tokio::test]
+async fn test_legacy_http_sse_transport_is_used_as_fallback() {
+    let server = MockServer::start();
+    let rejected = server.mock(|when, then| {
+        when.method(POST).path("/sse");
+        then.status(405);
+    });
+    // The responses arrive on the GET stream, after the endpoint event
+    let stream = server.mock(|when, then| {
+        when.method(GET).path("/sse");
+        then.status(200).header("content-type", "text/event-stream").body(
+            [
+                "event: endpoint\ndata: /messages?session_id=abc\n\n".to_string(),
+                sse_message(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "result": {
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": { "tools": {} },
+                        "serverInfo": { "name": "legacy", "version": "1.0.0" }
+                    }
+                })),
+                sse_message(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 3,
+                    "result": { "tools": [{ "name": "echo", "description": "Echo", "inputSchema": {} }] }
+                })),
+            ]
+            .concat(),
+        );
+    });
+    let messages = server.mock(|when, then| {
+        when.method(POST)
+            .path("/messages")
+            .query_param("session_id", "abc");
+        then.status(202);
+    });
+
+    let mut connection = remote_connection(server.url("/sse"));
+    let tools = connection.discover_tools().await.unwrap();
+
+    assert_eq!(tools[0].name, "echo");
+    assert_eq!(
+        connection.transport(),
+        Some(&HttpTransport::LegacySse {
+            endpoint: server.url("/messages?session_id=abc")
+        })
+    );
+    assert_eq!(
+        connection.initialize_result()["serverInfo"]["name"],
+        "legacy"
+    );
+    rejected.assert_calls(1);
+    stream.assert_calls(1);
+    // initialize, notifications/initialized and tools/list
+    messages.assert_calls(3);
+}
+
+#[tokio::test]
+async fn test_streamable_http_routes_elicitation_requests_to_the_user() {
+    let server = MockServer::start();
+    mock_initialize(&server, "session-1", 1);
+    let declined = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .body_includes(r#""id":"ask-1""#)
+            .body_includes(r#""action":"decline""#);
+        then.status(202);
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .body_includes(r#""method":"tools/call""#);
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(
+                [
+                    sse_message(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": "ask-1",
+                        "method": "elicitation/create",
+                        "params": {
+                            "message": "Delete the branch?",
+                            "requestedSchema": { "type": "object", "properties": {} }
+                        }
+                    })),
+                    sse_message(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": 2,
+                        "result": { "content": [{ "type": "text", "text": "kept" }] }
+                    })),
+                ]
+                .concat(),
+            );
+    });
+
+    let router = McpRequestRouter::new();
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(1);
+    router.set_interface(event_tx);
+    let user = router.clone();
+    tokio::spawn(async move {
+        if let Some(AppEvent::McpElicitationRequest(request)) = event_rx.recv().await {
+            assert_eq!(request.server, "remote");
+            assert_eq!(request.message, "Delete the branch?");
+            user.answer_elicitation(request.id, McpElicitationAnswer::Decline);
+        }
+    });
+
+    let mut connection = remote_connection(server.url("/mcp")).with_router(router);
+    let result = connection
+        .execute_tool("cleanup", &serde_json::json!({}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["content"][0]["text"], "kept");
+    declined.assert_calls(1);
+}
@@ -458,3 +458,62 @@ fn test_config_parsing_various_types() {
         _ => panic!("Expected Command config type"),
     }
 }
+
+/// Whether a process is gone: reaped, or a zombie waiting to be
+#[cfg(target_os = "linux")]
+fn process_is_gone(pid: &str) -> bool {
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => stat
+            .rsplit(')')
+            .next()
+            .is_some_and(|rest| rest.trim_start().starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_dropping_a_server_kills_the_processes_it_started() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let pid_file = temp_dir.path().join("grandchild.pid");
+    let config = McpServerConfig {
+        name: "wrapper".to_string(),
+        description: None,
+        server_type: McpServerType::Command {
+            command: "sh".to_string(),
+            // Like npx and uvx, start the actual server as a child
+            args: Some(vec![
+                "-c".to_string(),
+                format!("sleep 300 & echo $! > {}; wait", pid_file.display()),
+            ]),
+            environment: None,
+            working_directory: None,
+        },
+        auto_start: Some(true),
+        environment: None,
+    };
+
+    let process = McpLauncher::launch(&config).await.unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let grandchild = loop {
+        match std::fs::read_to_string(&pid_file) {
+            Ok(pid) if !pid.trim().is_empty() => break pid.trim().to_string(),
+            _ if std::time::Instant::now() < deadline => {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            _ => panic!("The server did not start its child"),
+        }
+    };
+    assert!(!process_is_gone(&grandchild));
+
+    drop(process);
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !process_is_gone(&grandchild) {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "The server's child outlived it"
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+}
@@ -26,7 +26,9 @@ async fn test_mcp_tool_adapter_creation() {
         "test_connection".to_string(),
     );
 
-    assert_eq!(adapter.name(), "test_tool");
+    assert_eq!(adapter.name(), "test__test_tool");
+    assert_eq!(adapter.qualified_name(), "test__test_tool");
+    assert_eq!(adapter.tool_name(), "test_tool");
     assert_eq!(adapter.description(), "A test tool");
     assert_eq!(adapter.profile(), ToolProfile::Generic);
 }
@@ -133,7 +135,7 @@ async fn test_mcp_tool_adapter_execute_method() {
         "test_connection".to_string(),
     );
 
-    assert_eq!(adapter.name(), "execute_test_tool");
+    assert_eq!(adapter.name(), "test__execute_test_tool");
     assert_eq!(
         adapter.description(),
         "A test tool for execute method testing"
@@ -161,6 +163,199 @@ async fn test_mcp_tool_adapter_clone() {
     );
 
     let cloned = original.clone_box();
-    assert_eq!(cloned.name(), "clone_test_tool");
+    assert_eq!(cloned.name(), "test__clone_test_tool");
     assert_eq!(cloned.description(), "A test tool for clone testing");
 }
+
+#[tokio::test]
+async fn test_tools_are_rediscovered_after_list_changed() {
+    use httpmock::prelude::*;
+
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).body_includes(r#""method":"initialize""#);
+        then.status(200).json_body(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "protocolVersion": "2025-03-26", "capabilities": { "tools": {} } }
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .body_includes(r#""method":"notifications/initialized""#);
+        then.status(202);
+    });
+    let tool =
+        |name: &str| serde_json::json!({ "name": name, "description": name, "inputSchema": {} });
+    let (install, installed) = (tool("install"), tool("installed"));
+    server.mock(|when, then| {
+        when.method(POST)
+            .body_includes(r#""method":"tools/list""#)
+            .body_includes(r#""id":2"#);
+        then.status(200).json_body(
+            serde_json::json!({ "jsonrpc": "2.0", "id": 2, "result": { "tools": [install] } }),
+        );
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .body_includes(r#""method":"tools/call""#);
+        then.status(200).header("content-type", "text/event-stream").body(format!(
+            "data: {}\n\ndata: {}\n\n",
+            serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" }),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 3, "result": { "content": [{ "type": "text", "text": "ok" }] } })
+        ));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .body_includes(r#""method":"tools/list""#)
+            .body_includes(r#""id":4"#);
+        then.status(200).json_body(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "result": { "tools": [tool("install"), installed] }
+        }));
+    });
+
+    let registry = McpConnectionRegistry::new();
+    let config = McpServerConfig {
+        name: "plugins".to_string(),
+        description: None,
+        server_type: McpServerType::Remote {
+            url: server.base_url(),
+            access_token: None,
+            api_key: None,
+        },
+        auto_start: Some(true),
+        environment: None,
+    };
+    let manager = McpManager::with_registry(registry.clone());
+    assert_eq!(manager.connect_server(&config).await.unwrap().len(), 1);
+
+    let output = registry
+        .execute_tool_on_connection(&connection_id("plugins"), "install", &serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(output, "ok");
+
+    let mut names: Vec<String> = registry
+        .tool_adapters()
+        .iter()
+        .map(|adapter| adapter.name().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["plugins__install", "plugins__installed"]);
+}
+
+#[test]
+fn test_qualified_tool_name_is_a_valid_function_name() {
+    assert_eq!(
+        qualified_tool_name("weather", "get_forecast"),
+        "weather__get_forecast"
+    );
+    assert_eq!(
+        qualified_tool_name("my server.v2", "search"),
+        "my_server_v2__search"
+    );
+    assert_eq!(
+        qualified_tool_name("files", "fs.read/text"),
+        "files__fs_read_text"
+    );
+
+    // Long names are cut to the limit and kept apart by a hash
+    let long_tool = "a".repeat(80);
+    let first = qualified_tool_name("server", &format!("{}1", long_tool));
+    let second = qualified_tool_name("server", &format!("{}2", long_tool));
+    assert_eq!(first.len(), MAX_TOOL_NAME_LEN);
+    assert_eq!(second.len(), MAX_TOOL_NAME_LEN);
+    assert!(first.starts_with("server__aaaa"));
+    assert_ne!(first, second);
+    assert_eq!(
+        first,
+        qualified_tool_name("server", &format!("{}1", long_tool))
+    );
+    assert!(
+        first
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    );
+}
+
+#[test]
+fn test_mcp_tool_collisions_are_skipped() {
+    let registry = McpConnectionRegistry::new();
+    registry.reserve_tool_names(["read_file".to_string()]);
+    let adapter = |server: &str, tool: &str| {
+        McpToolAdapter::new(
+            tool.to_string(),
+            String::new(),
+            serde_json::json!({}),
+            connection_id(server),
+        )
+    };
+
+    // Two servers offering `search` don't shadow each other
+    let accepted = registry.set_tools(
+        &connection_id("weather"),
+        vec![adapter("weather", "search")],
+    );
+    assert_eq!(accepted.len(), 1);
+    let accepted = registry.set_tools(
+        &connection_id("docs"),
+        vec![
+            adapter("docs", "search"),
+            adapter("docs", "read").with_alias("read_file".to_string()),
+        ],
+    );
+    assert_eq!(accepted.len(), 1);
+
+    // An alias taken by another server's tool is skipped
+    let accepted = registry.set_tools(
+        &connection_id("web"),
+        vec![adapter("web", "lookup").with_alias("weather__search".to_string())],
+    );
+    assert!(accepted.is_empty());
+
+    // Rediscovering a server replaces its own tools
+    let accepted = registry.set_tools(
+        &connection_id("weather"),
+        vec![adapter("weather", "search")],
+    );
+    assert_eq!(accepted.len(), 1);
+
+    let names: Vec<String> = registry
+        .tool_adapters()
+        .iter()
+        .map(|adapter| adapter.name())
+        .collect();
+    assert_eq!(names, vec!["docs__search", "weather__search"]);
+}
+
+#[test]
+fn test_aliased_mcp_tool_keeps_its_qualified_name() {
+    use OxideAgent::core::tools::ToolRegistry;
+
+    let registry = McpConnectionRegistry::new();
+    registry.set_tools(
+        &connection_id("weather"),
+        vec![
+            McpToolAdapter::new(
+                "get_forecast".to_string(),
+                "Forecast".to_string(),
+                serde_json::json!({}),
+                connection_id("weather"),
+            )
+            .with_alias("forecast".to_string()),
+        ],
+    );
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.add_source(Box::new(McpToolSource::new(registry)));
+
+    let tool = tool_registry.get_tool("forecast").unwrap();
+    assert_eq!(tool.qualified_name(), "weather__get_forecast");
+    assert_eq!(
+        tool_registry.qualified_name("forecast"),
+        "weather__get_forecast"
+    );
+    assert!(tool_registry.get_tool("weather__get_forecast").is_none());
+    assert_eq!(tool_registry.qualified_name("read_file"), "read_file");
+}
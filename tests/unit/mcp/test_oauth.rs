@@ -0,0 +1,303 @@
+use OxideAgent::config::McpOAuthConfig;
+use OxideAgent::core::mcp::config::{McpServerConfig, McpServerType};
+use OxideAgent::core::mcp::connection::McpConnection;
+use OxideAgent::core::mcp::http::HttpMcpConnection;
+use OxideAgent::core::mcp::oauth::*;
+use httpmock::prelude::*;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+fn oauth_config(token_cache: &Path) -> McpOAuthConfig {
+    McpOAuthConfig {
+        token_cache: token_cache.to_path_buf(),
+        open_browser: false,
+        ..Default::default()
+    }
+}
+
+fn oauth_connection(url: &str, oauth: OAuthClient) -> HttpMcpConnection {
+    let config = McpServerConfig {
+        name: "hosted".to_string(),
+        description: None,
+        server_type: McpServerType::Remote {
+            url: url.to_string(),
+            access_token: None,
+            api_key: None,
+        },
+        auto_start: Some(true),
+        environment: None,
+    };
+    HttpMcpConnection::new(&config, url.to_string(), None, None).with_oauth(oauth)
+}
+
+/// Form parameters of a request body
+fn form(body: &str) -> HashMap<String, String> {
+    Url::parse(&format!("http://form/?{}", body))
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+/// Mock an MCP server that only answers requests carrying `access_token`.
+/// The rejected `initialize` took request id 1.
+fn mock_authorized_server(server: &MockServer, access_token: &str) {
+    let authorization = format!("Bearer {}", access_token);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("authorization", &authorization)
+            .body_includes(r#""method":"initialize""#);
+        then.status(200).json_body(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "result": { "protocolVersion": "2025-03-26", "capabilities": { "tools": {} } }
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("authorization", &authorization)
+            .body_includes(r#""method":"notifications/initialized""#);
+        then.status(202);
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("authorization", &authorization)
+            .body_includes(r#""method":"tools/list""#);
+        then.status(200).json_body(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "result": { "tools": [{ "name": "search", "description": "Search", "inputSchema": {} }] }
+        }));
+    });
+}
+
+#[test]
+fn test_pkce_challenge_is_the_base64url_sha256_of_the_verifier() {
+    assert_eq!(
+        pkce_challenge("dBjftJeZ4CVP-mJ92K1qnDmLuEJjsKTaw2R2bm7VwV8"),
+        "EIUGz5kexNK4YkOcrOlzzPNU-9oBpNcIIAKjSchfHYo"
+    );
+}
+
+#[test]
+fn test_resource_metadata_url_is_read_from_the_challenge() {
+    assert_eq!(
+        resource_metadata_url(
+            r#"Bearer error="invalid_token", resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource""#
+        )
+        .as_deref(),
+        Some("https://mcp.example.com/.well-known/oauth-protected-resource")
+    );
+    assert_eq!(
+        resource_metadata_url("Bearer resource_metadata=https://example.com/meta, scope=tools")
+            .as_deref(),
+        Some("https://example.com/meta")
+    );
+    assert_eq!(resource_metadata_url(r#"Bearer realm="mcp""#), None);
+}
+
+#[test]
+fn test_token_cache_keeps_tokens_per_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = TokenCache::new(dir.path().join("tokens.json"));
+    let token = |access_token: &str| StoredToken {
+        client_id: "client".to_string(),
+        client_secret: None,
+        token_endpoint: "https://auth.example.com/token".to_string(),
+        access_token: access_token.to_string(),
+        refresh_token: Some("refresh".to_string()),
+        expires_at: None,
+    };
+
+    assert!(cache.load("https://a.example.com/mcp").is_none());
+    cache
+        .store("https://a.example.com/mcp", &token("a"))
+        .unwrap();
+    cache
+        .store("https://b.example.com/mcp", &token("b"))
+        .unwrap();
+
+    let reopened = TokenCache::new(dir.path().join("tokens.json"));
+    assert_eq!(reopened.load("https://a.example.com/mcp"), Some(token("a")));
+    assert_eq!(reopened.load("https://b.example.com/mcp"), Some(token("b")));
+}
+
+#[tokio::test]
+async fn test_oauth_authorization_code_flow_with_pkce() {
+    let server = MockServer::start();
+    let dir = tempfile::tempdir().unwrap();
+    let token_cache = dir.path().join("tokens.json");
+    let mcp_url = server.url("/mcp");
+
+    // Without a token the server points to its resource metadata
+    let challenge = format!(
+        r#"Bearer resource_metadata="{}""#,
+        server.url("/meta/resource")
+    );
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header_missing("authorization");
+        then.status(401).header("WWW-Authenticate", &challenge);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/meta/resource");
+        then.status(200).json_body(serde_json::json!({
+            "resource": mcp_url,
+            "authorization_servers": [server.url("/auth")],
+            "scopes_supported": ["tools", "offline_access"]
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/.well-known/oauth-authorization-server/auth");
+        then.status(200).json_body(serde_json::json!({
+            "issuer": server.url("/auth"),
+            "authorization_endpoint": server.url("/auth/authorize"),
+            "token_endpoint": server.url("/auth/token"),
+            "registration_endpoint": server.url("/auth/register"),
+            "code_challenge_methods_supported": ["S256"]
+        }));
+    });
+    let register_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/auth/register")
+            .body_includes(r#""token_endpoint_auth_method":"none""#);
+        then.status(201)
+            .json_body(serde_json::json!({ "client_id": "client-1" }));
+    });
+
+    // The browser is sent to the authorization URL; it redirects to the
+    // callback with a code
+    let code_challenge = Arc::new(Mutex::new(String::new()));
+    let seen_challenge = code_challenge.clone();
+    let prompt: AuthorizationPrompt = Arc::new(move |server_name, url| {
+        assert_eq!(server_name, "hosted");
+        let params: HashMap<String, String> = Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], "client-1");
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["scope"], "tools offline_access");
+        *seen_challenge.lock().unwrap() = params["code_challenge"].clone();
+        let callback = format!(
+            "{}?code=code-1&state={}",
+            params["redirect_uri"], params["state"]
+        );
+        let listener = Url::parse(&params["redirect_uri"]).unwrap();
+        let listener = format!(
+            "{}:{}",
+            listener.host_str().unwrap(),
+            listener.port().unwrap()
+        );
+        tokio::spawn(async move {
+            // Browsers open connections they never send a request on
+            let _idle = tokio::net::TcpStream::connect(listener).await.unwrap();
+            let response = reqwest::get(callback).await.unwrap();
+            assert!(response.status().is_success());
+        });
+    });
+
+    let mcp_resource = mcp_url.clone();
+    let token_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/auth/token")
+            .is_true(move |request| {
+                let params = form(&request.body_string());
+                params.get("grant_type").map(String::as_str) == Some("authorization_code")
+                    && params.get("code").map(String::as_str) == Some("code-1")
+                    && params.get("client_id").map(String::as_str) == Some("client-1")
+                    && params.get("resource") == Some(&mcp_resource)
+                    && params.get("code_verifier").is_some_and(|verifier| {
+                        pkce_challenge(verifier) == *code_challenge.lock().unwrap()
+                    })
+            });
+        then.status(200).json_body(serde_json::json!({
+            "access_token": "access-1",
+            "token_type": "Bearer",
+            "refresh_token": "refresh-1",
+            "expires_in": 3600
+        }));
+    });
+    mock_authorized_server(&server, "access-1");
+
+    let oauth =
+        OAuthClient::new("hosted", &mcp_url, &oauth_config(&token_cache)).with_prompt(prompt);
+    let mut connection = oauth_connection(&mcp_url, oauth);
+    let tools = connection.discover_tools().await.unwrap();
+
+    assert_eq!(tools.len(), 1);
+    register_mock.assert_calls(1);
+    token_mock.assert_calls(1);
+    let cached = TokenCache::new(&token_cache).load(&mcp_url).unwrap();
+    assert_eq!(cached.access_token, "access-1");
+    assert_eq!(cached.refresh_token.as_deref(), Some("refresh-1"));
+    assert_eq!(cached.client_id, "client-1");
+}
+
+#[tokio::test]
+async fn test_oauth_refreshes_a_rejected_token() {
+    let server = MockServer::start();
+    let dir = tempfile::tempdir().unwrap();
+    let token_cache = dir.path().join("tokens.json");
+    let mcp_url = server.url("/mcp");
+    TokenCache::new(&token_cache)
+        .store(
+            &mcp_url,
+            &StoredToken {
+                client_id: "client-1".to_string(),
+                client_secret: None,
+                token_endpoint: server.url("/auth/token"),
+                access_token: "expired".to_string(),
+                refresh_token: Some("refresh-1".to_string()),
+                expires_at: Some(0),
+            },
+        )
+        .unwrap();
+
+    let expired_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/mcp")
+            .header("authorization", "Bearer expired");
+        then.status(401)
+            .header("WWW-Authenticate", r#"Bearer error="invalid_token""#);
+    });
+    let refresh_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/auth/token")
+            .form_urlencoded_tuple("grant_type", "refresh_token")
+            .form_urlencoded_tuple("refresh_token", "refresh-1")
+            .form_urlencoded_tuple("client_id", "client-1");
+        then.status(200).json_body(serde_json::json!({
+            "access_token": "access-2",
+            "token_type": "Bearer",
+            "expires_in": 3600
+        }));
+    });
+    mock_authorized_server(&server, "access-2");
+
+    // Refreshing must not send the user to the browser
+    let prompt: AuthorizationPrompt =
+        Arc::new(|_, url| panic!("unexpected authorization prompt for {}", url));
+    let oauth =
+        OAuthClient::new("hosted", &mcp_url, &oauth_config(&token_cache)).with_prompt(prompt);
+    assert_eq!(oauth.access_token(), Some("expired"));
+    let mut connection = oauth_connection(&mcp_url, oauth);
+    let tools = connection.discover_tools().await.unwrap();
+
+    assert_eq!(tools.len(), 1);
+    expired_mock.assert_calls(1);
+    refresh_mock.assert_calls(1);
+    let cached = TokenCache::new(&token_cache).load(&mcp_url).unwrap();
+    assert_eq!(cached.access_token, "access-2");
+    // The refresh token is kept when the server doesn't rotate it
+    assert_eq!(cached.refresh_token.as_deref(), Some("refresh-1"));
+}
@@ -0,0 +1,213 @@
+use OxideAgent::core::llm::client::LlmClient;
+use OxideAgent::core::mcp::oauth::interface_prompt;
+use OxideAgent::core::mcp::router::{McpRequestRouter, elicitation_fields};
+use OxideAgent::types::{
+    AppEvent, ChatMessage, McpElicitationField, McpElicitationFieldKind, Tool,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Model that must not be asked
+#[derive(Debug)]
+struct UnusedLlm;
+
+#[async_trait::async_trait]
+impl LlmClient for UnusedLlm {
+    async fn chat(
+        &self,
+        _model: &str,
+        _history: &[ChatMessage],
+        _tools: &[Tool],
+        _stream: bool,
+        _tx: mpsc::Sender<AppEvent>,
+    ) -> anyhow::Result<Option<ChatMessage>> {
+        panic!("the model was asked without approval");
+    }
+}
+
+#[test]
+fn test_router_announces_the_capabilities_it_can_serve() {
+    let router = McpRequestRouter::new();
+    assert_eq!(router.capabilities(), json!({}));
+
+    // Sampling needs someone to approve it
+    router.set_sampler(Arc::new(UnusedLlm), "model".to_string());
+    assert_eq!(router.capabilities(), json!({}));
+
+    let (event_tx, _event_rx) = mpsc::channel(1);
+    router.set_interface(event_tx);
+    assert_eq!(
+        router.capabilities(),
+        json!({ "elicitation": {}, "sampling": {} })
+    );
+}
+
+#[test]
+fn test_authorization_prompts_reach_the_interface_only_while_it_runs() {
+    let router = McpRequestRouter::new();
+    let (event_tx, mut event_rx) = mpsc::channel(4);
+    router.set_interface(event_tx);
+    let prompt = interface_prompt(router.clone(), false);
+
+    // Before the interface is on screen the URL is printed instead
+    prompt("hosted", "https://auth.example/authorize");
+    assert!(event_rx.try_recv().is_err());
+
+    router.set_interface_running(true);
+    prompt("hosted", "https://auth.example/authorize");
+    match event_rx.try_recv() {
+        Ok(AppEvent::McpAuthorizationRequired(server, url)) => {
+            assert_eq!(server, "hosted");
+            assert_eq!(url, "https://auth.example/authorize");
+        }
+        other => panic!("Expected an authorization event, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_router_answers_ping_and_rejects_unknown_requests() {
+    let router = McpRequestRouter::new();
+
+    let response = router
+        .handle(
+            "server",
+            &json!({ "jsonrpc": "2.0", "id": 7, "method": "ping" }),
+        )
+        .await;
+    assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 7, "result": {} }));
+
+    let response = router
+        .handle(
+            "server",
+            &json!({ "jsonrpc": "2.0", "id": "roots", "method": "roots/list" }),
+        )
+        .await;
+    assert_eq!(response["id"], "roots");
+    assert_eq!(response["error"]["code"], -32601);
+
+    // Without an interface there is nobody to ask
+    let response = router
+        .handle(
+            "server",
+            &json!({ "jsonrpc": "2.0", "id": 8, "method": "elicitation/create", "params": {} }),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn test_rejected_sampling_request_never_reaches_the_model() {
+    let router = McpRequestRouter::new();
+    let (event_tx, mut event_rx) = mpsc::channel(1);
+    router.set_interface(event_tx);
+    router.set_sampler(Arc::new(UnusedLlm), "model".to_string());
+
+    let user = router.clone();
+    tokio::spawn(async move {
+        if let Some(AppEvent::McpSamplingRequest(request)) = event_rx.recv().await {
+            assert_eq!(request.server, "notes");
+            assert_eq!(request.system_prompt.as_deref(), Some("Be brief"));
+            assert_eq!(request.messages.len(), 1);
+            assert_eq!(request.messages[0].content, "Summarize my notes");
+            user.approve_sampling(request.id, false);
+        }
+    });
+
+    let response = router
+        .handle(
+            "notes",
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "sampling/createMessage",
+                "params": {
+                    "systemPrompt": "Be brief",
+                    "messages": [{ "role": "user", "content": { "type": "text", "text": "Summarize my notes" } }],
+                    "maxTokens": 100
+                }
+            }),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], -1);
+}
+
+#[test]
+fn test_elicitation_fields_are_read_from_the_schema() {
+    let fields = elicitation_fields(&json!({
+        "type": "object",
+        "properties": {
+            "age": { "type": "integer", "title": "Age" },
+            "color": { "type": "string", "enum": ["red", "green"] },
+            "name": { "type": "string", "description": "Your name" },
+            "subscribe": { "type": "boolean" }
+        },
+        "required": ["name"]
+    }));
+
+    assert_eq!(
+        fields,
+        vec![
+            McpElicitationField {
+                name: "age".to_string(),
+                title: Some("Age".to_string()),
+                description: None,
+                kind: McpElicitationFieldKind::Integer,
+                required: false,
+            },
+            McpElicitationField {
+                name: "color".to_string(),
+                title: None,
+                description: None,
+                kind: McpElicitationFieldKind::Choice(vec!["red".to_string(), "green".to_string()]),
+                required: false,
+            },
+            McpElicitationField {
+                name: "name".to_string(),
+                title: None,
+                description: Some("Your name".to_string()),
+                kind: McpElicitationFieldKind::Text,
+                required: true,
+            },
+            McpElicitationField {
+                name: "subscribe".to_string(),
+                title: None,
+                description: None,
+                kind: McpElicitationFieldKind::Boolean,
+                required: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_elicitation_field_parses_input_by_kind() {
+    let field = |kind, required| McpElicitationField {
+        name: "field".to_string(),
+        title: None,
+        description: None,
+        kind,
+        required,
+    };
+
+    let integer = field(McpElicitationFieldKind::Integer, true);
+    assert_eq!(integer.parse("42"), Ok(Some(json!(42))));
+    assert!(integer.parse("4.2").is_err());
+    assert!(integer.parse("").is_err());
+
+    let number = field(McpElicitationFieldKind::Number, false);
+    assert_eq!(number.parse("4.5"), Ok(Some(json!(4.5))));
+    assert_eq!(number.parse(" "), Ok(None));
+
+    let boolean = field(McpElicitationFieldKind::Boolean, false);
+    assert_eq!(boolean.parse("yes"), Ok(Some(json!(true))));
+    assert_eq!(boolean.parse("N"), Ok(Some(json!(false))));
+    assert!(boolean.parse("maybe").is_err());
+
+    let choice = field(
+        McpElicitationFieldKind::Choice(vec!["red".to_string(), "green".to_string()]),
+        true,
+    );
+    assert_eq!(choice.parse("green"), Ok(Some(json!("green"))));
+    assert!(choice.parse("blue").is_err());
+}
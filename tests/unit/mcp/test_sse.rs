@@ -0,0 +1,52 @@
+use OxideAgent::core::mcp::sse::{SseEvent, SseParser};
+
+#[test]
+fn test_events_split_across_chunks_are_reassembled() {
+    let mut parser = SseParser::new();
+    assert!(parser.feed(b"event: mess").is_empty());
+    assert!(parser.feed(b"age\ndata: {\"a\":").is_empty());
+    let events = parser.feed(b"1}\n\ndata: second\n\n");
+
+    assert_eq!(
+        events,
+        vec![
+            SseEvent {
+                event: Some("message".to_string()),
+                data: "{\"a\":1}".to_string(),
+                id: None,
+                retry: None,
+            },
+            SseEvent {
+                event: None,
+                data: "second".to_string(),
+                id: None,
+                retry: None,
+            },
+        ]
+    );
+    assert_eq!(events[1].event_type(), "message");
+}
+
+#[test]
+fn test_fields_comments_and_line_endings() {
+    let mut parser = SseParser::new();
+    let events = parser.feed(
+        b": keep-alive\r\n\r\nid: 7\r\nretry: 1500\r\ndata: line one\r\ndata:line two\r\nunknown: x\r\n\r\n",
+    );
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id.as_deref(), Some("7"));
+    assert_eq!(events[0].retry, Some(1500));
+    assert_eq!(events[0].data, "line one\nline two");
+}
+
+#[test]
+fn test_unterminated_event_is_returned_by_finish() {
+    let mut parser = SseParser::new();
+    assert!(parser.feed(b"event: endpoint\ndata: /messages").is_empty());
+
+    let event = parser.finish().unwrap();
+    assert_eq!(event.event_type(), "endpoint");
+    assert_eq!(event.data, "/messages");
+    assert_eq!(parser.finish(), None);
+}
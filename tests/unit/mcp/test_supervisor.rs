@@ -0,0 +1,30 @@
+use OxideAgent::core::mcp::supervisor::{McpServerStatus, RestartPolicy};
+use std::time::Duration;
+
+#[test]
+fn test_restart_delay_doubles_up_to_the_maximum() {
+    let policy = RestartPolicy {
+        initial_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(10),
+        ..RestartPolicy::default()
+    };
+
+    let delays: Vec<u64> = (1..=6)
+        .map(|attempt| policy.delay_for(attempt).as_secs())
+        .collect();
+    assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+    assert_eq!(policy.delay_for(200), Duration::from_secs(10));
+}
+
+#[test]
+fn test_server_status_display() {
+    assert_eq!(McpServerStatus::Running(3).to_string(), "Running (3 tools)");
+    assert_eq!(
+        McpServerStatus::Restarting(2, Duration::from_millis(1500)).to_string(),
+        "Restarting (attempt 2 in 1.5s)"
+    );
+    assert_eq!(
+        McpServerStatus::Failed("gave up".to_string()).to_string(),
+        "Failed: gave up"
+    );
+}
@@ -1,7 +1,10 @@
 use OxideAgent::config::{
-    AgentType, InterfaceType, OxideConfig, default_api_base, default_model, default_name,
-    default_provider, default_system_prompt,
+    AgentType, CheckpointMode, CompactionStrategy, InterfaceType, OxideConfig, ToolScope,
+    default_api_base, default_context_threshold, default_context_window,
+    default_max_tool_iterations, default_model, default_name, default_provider,
+    default_shell_max_output_bytes, default_shell_timeout_secs, default_system_prompt,
 };
+use OxideAgent::core::tools::ToolProfile;
 use std::fs;
 use std::io::Write;
 use tempfile::NamedTempFile;
@@ -49,6 +52,145 @@ fn test_config_from_json() {
     );
     assert_eq!(config.interface, InterfaceType::Tui);
     assert_eq!(config.llm.provider, "ollama");
+    assert_eq!(config.llm.base_url(), "http://localhost:11434");
+    assert_eq!(
+        config.multi_agent.max_tool_iterations,
+        default_max_tool_iterations()
+    );
+}
+
+#[test]
+fn test_config_multi_agent_max_tool_iterations() {
+    let toml_content = r#"
+        [multi_agent]
+        max_tool_iterations = 3
+    "#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let toml_path = temp_file.path().with_extension("toml");
+    std::fs::write(&toml_path, toml_content).unwrap();
+
+    let config = OxideConfig::from_file(&toml_path).unwrap();
+    assert_eq!(config.multi_agent.max_tool_iterations, 3);
+    assert_eq!(config.multi_agent.max_agents, 5);
+}
+
+#[test]
+fn test_config_multi_agent_context() {
+    let toml_content = r#"
+        [multi_agent.context]
+        strategy = "drop_tool_outputs"
+        context_length = 16384
+        default_window = 4096
+    "#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let toml_path = temp_file.path().with_extension("toml");
+    std::fs::write(&toml_path, toml_content).unwrap();
+
+    let config = OxideConfig::from_file(&toml_path).unwrap();
+    let context = &config.multi_agent.context;
+    assert_eq!(context.strategy, CompactionStrategy::DropToolOutputs);
+    assert_eq!(context.context_length, Some(16384));
+    assert_eq!(context.default_window, 4096);
+    assert_eq!(
+        OxideConfig::default().multi_agent.context.default_window,
+        default_context_window()
+    );
+    assert_eq!(context.threshold, default_context_threshold());
+    assert_eq!(
+        OxideConfig::default().multi_agent.context.strategy,
+        CompactionStrategy::Summarize
+    );
+}
+
+#[test]
+fn test_config_agent_tool_scopes() {
+    let toml_content = r#"
+        [agent]
+        exclude_tools = ["apply_unified_diff"]
+
+        [[multi_agent.default_agents]]
+        name = "reviewer"
+        model = "llama3.2"
+        allowed_profiles = ["file"]
+        exclude_tools = ["write_file", "edit_file"]
+
+        [[multi_agent.default_agents]]
+        name = "builder"
+        include_tools = ["run_shell_command"]
+    "#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let toml_path = temp_file.path().with_extension("toml");
+    std::fs::write(&toml_path, toml_content).unwrap();
+
+    let config = OxideConfig::from_file(&toml_path).unwrap();
+    assert_eq!(
+        config.agent.tool_scope.exclude_tools,
+        vec!["apply_unified_diff"]
+    );
+    assert!(config.agent.tool_scope.allowed_profiles.is_empty());
+
+    let agents = &config.multi_agent.default_agents;
+    assert_eq!(agents.len(), 2);
+    assert_eq!(agents[0].name, "reviewer");
+    assert_eq!(agents[0].model, "llama3.2");
+    let reviewer = &agents[0].tool_scope;
+    assert_eq!(reviewer.allowed_profiles, vec![ToolProfile::File]);
+    assert!(reviewer.allows("read_file", ToolProfile::File));
+    assert!(!reviewer.allows("write_file", ToolProfile::File));
+    assert!(!reviewer.allows("run_shell_command", ToolProfile::Shell));
+
+    let builder = &agents[1].tool_scope;
+    assert!(builder.allows("run_shell_command", ToolProfile::Shell));
+    assert!(!builder.allows("read_file", ToolProfile::File));
+    assert!(ToolScope::default().allows("read_file", ToolProfile::File));
+}
+
+#[test]
+fn test_config_tools_shell_section() {
+    let toml_content = r#"
+        [tools]
+        workspace_root = "/tmp/project"
+        checkpoints = "files"
+
+        [tools.shell]
+        timeout_secs = 5
+        env_allowlist = ["PATH"]
+    "#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let toml_path = temp_file.path().with_extension("toml");
+    std::fs::write(&toml_path, toml_content).unwrap();
+
+    let config = OxideConfig::from_file(&toml_path).unwrap();
+    assert_eq!(
+        config.tools.workspace_root.as_deref(),
+        Some(std::path::Path::new("/tmp/project"))
+    );
+    assert_eq!(config.tools.checkpoints, CheckpointMode::Files);
+    assert_eq!(config.tools.shell.timeout_secs, 5);
+    assert_eq!(config.tools.shell.env_allowlist, vec!["PATH"]);
+    assert_eq!(
+        config.tools.shell.max_output_bytes,
+        default_shell_max_output_bytes()
+    );
+
+    let defaults = OxideConfig::default();
+    assert!(defaults.tools.workspace_root.is_none());
+    assert_eq!(defaults.tools.checkpoints, CheckpointMode::Auto);
+    assert_eq!(
+        defaults.tools.shell.timeout_secs,
+        default_shell_timeout_secs()
+    );
+    assert!(
+        defaults
+            .tools
+            .shell
+            .env_allowlist
+            .contains(&"PATH".to_string())
+    );
 }
 
 #[test]
@@ -247,7 +389,36 @@ fn test_default_functions() {
         "You are a Rust programming expert."
     );
     assert_eq!(default_provider(), "ollama");
-    assert_eq!(default_api_base(), "http://localhost:11434");
+    assert_eq!(default_api_base("ollama"), "http://localhost:11434");
+    assert_eq!(default_max_tool_iterations(), 10);
+}
+
+#[test]
+fn test_llm_api_base_defaults_to_the_provider() {
+    let toml_content = r#"
+        [llm]
+        provider = "anthropic"
+    "#;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let toml_path = temp_file.path().with_extension("toml");
+    std::fs::write(&toml_path, toml_content).unwrap();
+
+    let mut config = OxideConfig::from_file(&toml_path).unwrap();
+    assert_eq!(config.llm.api_base, None);
+    assert_eq!(config.llm.base_url(), "https://api.anthropic.com");
+
+    config.llm.provider = "openai".to_string();
+    assert_eq!(config.llm.base_url(), "https://api.openai.com");
+    config.llm.provider = "openai-compatible".to_string();
+    assert_eq!(config.llm.base_url(), "https://api.openai.com");
+    config.llm.provider = "ollama".to_string();
+    assert_eq!(config.llm.base_url(), "http://localhost:11434");
+
+    // A configured base wins over the provider's
+    config.llm.provider = "openai".to_string();
+    config.llm.api_base = Some("http://localhost:8000/v1".to_string());
+    assert_eq!(config.llm.base_url(), "http://localhost:8000/v1");
 }
 
 #[test]
@@ -281,18 +452,12 @@ fn test_config_validation_mcp_with_token() {
 }
 
 #[test]
-fn test_config_validation_mcp_missing_token() {
+fn test_config_validation_mcp_without_token() {
+    // Servers without a token are authorized with OAuth
     let mut config = OxideConfig::default();
     config.mcp.server = Some("http://localhost:8080".to_string());
     config.mcp.auth_token = None;
-    assert!(config.validate().is_err());
-    assert!(
-        config
-            .validate()
-            .unwrap_err()
-            .to_string()
-            .contains("MCP server specified but no auth token provided")
-    );
+    assert!(config.validate().is_ok());
 }
 
 #[test]
@@ -314,6 +479,12 @@ fn test_config_validation_session_with_invalid_chars() {
         ..Default::default()
     };
     assert!(config.validate().is_err());
+
+    let config = OxideConfig {
+        session: Some("..".to_string()), // Refers to the parent directory
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
 }
 
 #[test]
@@ -345,3 +516,72 @@ fn test_config_interface_type_from_json_variants() {
     assert_eq!(telegram_config.interface, InterfaceType::Telegram);
     assert_eq!(discord_config.interface, InterfaceType::Discord);
 }
+
+#[test]
+fn test_config_mcp_servers_inline_and_from_file() {
+    let mut mcp_file = NamedTempFile::new().unwrap();
+    mcp_file
+        .write_all(br#"{"mcpServers": {"files": {"command": "mcp-files"}}}"#)
+        .unwrap();
+
+    let toml_content = format!(
+        r#"
+[mcp]
+config_file = "{}"
+
+[[mcp.tools]]
+name = "thinking"
+command = "npx"
+args = ["-y", "@modelcontextprotocol/server-sequential-thinking"]
+
+[[mcp.servers]]
+name = "fetch"
+type = "docker"
+image = "mcp/fetch"
+autoStart = false
+"#,
+        mcp_file.path().display()
+    );
+    let config: OxideConfig = toml::from_str(&toml_content).unwrap();
+
+    let servers = config.mcp.server_configs().unwrap();
+    let names: Vec<&str> = servers.iter().map(|server| server.name.as_str()).collect();
+    assert_eq!(names, vec!["thinking", "fetch", "files"]);
+    let kinds: Vec<&str> = servers
+        .iter()
+        .map(|server| server.server_type.kind())
+        .collect();
+    assert_eq!(kinds, vec!["command", "docker", "command"]);
+    assert_eq!(servers[1].auto_start, Some(false));
+}
+
+#[test]
+fn test_config_mcp_duplicate_server_names_are_rejected() {
+    let toml_content = r#"
+[[mcp.tools]]
+name = "fetch"
+command = "mcp-fetch"
+
+[[mcp.servers]]
+name = "fetch"
+type = "npm"
+package = "mcp-fetch"
+"#;
+    let config: OxideConfig = toml::from_str(toml_content).unwrap();
+
+    let error = config.mcp.server_configs().unwrap_err();
+    assert!(error.to_string().contains("configured more than once"));
+}
+
+#[test]
+fn test_config_sessions_dir() {
+    let config: OxideConfig = toml::from_str("sessions_dir = \"/srv/oxide/sessions\"").unwrap();
+    assert_eq!(
+        config.sessions_dir(),
+        std::path::PathBuf::from("/srv/oxide/sessions")
+    );
+
+    // Without one, sessions go to the data directory
+    let config = OxideConfig::default();
+    assert!(config.sessions_dir().ends_with("oxideagent/sessions"));
+}
@@ -127,23 +127,30 @@ fn test_create_test_config() {
             model: "qwen3:4b".to_string(), // Updated to match default
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: false,
         session: Some("test_session".to_string()),
         list_sessions: false,
+        sessions_dir: None,
         interface: InterfaceType::Tui,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11434".to_string(),
+            api_base: Some("http://localhost:11434".to_string()),
             api_key: None,
             model: Some("qwen3:4b".to_string()),
         },
         multi_agent: Default::default(),
+        tools: Default::default(),
     };
 
     assert_eq!(config.agent.name, "Qwen");
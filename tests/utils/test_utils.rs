@@ -194,22 +194,29 @@ pub fn create_test_config() -> OxideAgent::config::OxideConfig {
             model: "qwen:latest".to_string(),
             name: "Qwen".to_string(),
             system_prompt: "You are a test agent.".to_string(),
+            tool_scope: Default::default(),
         },
         no_stream: false,
         session: Some("test_session".to_string()),
         list_sessions: false,
+        sessions_dir: None,
         mcp: OxideAgent::config::MCPConfig {
             server: None,
             auth_token: None,
             tools: vec![],
+            servers: vec![],
+            config_file: None,
+            tool_aliases: Default::default(),
+            oauth: Default::default(),
         },
         interface: OxideAgent::config::InterfaceType::Tui,
         llm: OxideAgent::config::LLMConfig {
             provider: "ollama".to_string(),
-            api_base: "http://localhost:11343".to_string(),
+            api_base: Some("http://localhost:11343".to_string()),
             api_key: None,
             model: None,
         },
         multi_agent: OxideAgent::config::MultiAgentConfig::default(),
+        tools: OxideAgent::config::ToolsConfig::default(),
     }
 }